@@ -0,0 +1,246 @@
+use apkw_proto::apkw::v1::{LogLevel, LogRecord, Timestamp};
+
+/// Classifies Gradle console lines into `LogRecord`s. Remembers the last
+/// `> Task :path` header so following compiler output is attributed to it.
+#[derive(Default)]
+pub(crate) struct GradleLogParser {
+    current_task: Option<String>,
+}
+
+struct Diagnostic {
+    level: LogLevel,
+    source: &'static str,
+    file: String,
+    line: u32,
+    column: u32,
+    message: String,
+}
+
+impl GradleLogParser {
+    /// Returns `None` for blank lines.
+    pub(crate) fn parse(
+        &mut self,
+        stream: &str,
+        line: &str,
+        at_unix_millis: i64,
+    ) -> Option<LogRecord> {
+        let text = line.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let mut record = LogRecord {
+            level: LogLevel::Info as i32,
+            source: "gradle".into(),
+            message: text.to_string(),
+            at: Some(Timestamp {
+                unix_millis: at_unix_millis,
+            }),
+            ..Default::default()
+        };
+
+        if let Some((task, outcome)) = parse_task_header(text) {
+            self.current_task = Some(task.to_string());
+            record.task = task.to_string();
+            if outcome == Some("FAILED") {
+                record.level = LogLevel::Error as i32;
+            }
+            return Some(record);
+        }
+        if text.starts_with("> Configure project")
+            || text.starts_with("BUILD SUCCESSFUL")
+            || text.starts_with("BUILD FAILED")
+            || text.starts_with("FAILURE:")
+        {
+            self.current_task = None;
+        }
+
+        if let Some(diag) = parse_kotlin_diagnostic(text).or_else(|| parse_c_style_diagnostic(text))
+        {
+            record.level = diag.level as i32;
+            record.source = diag.source.into();
+            record.file = diag.file;
+            record.line = diag.line;
+            record.column = diag.column;
+            record.message = diag.message;
+        } else {
+            record.level = gradle_line_level(stream, text) as i32;
+        }
+        record.task = self.current_task.clone().unwrap_or_default();
+        Some(record)
+    }
+}
+
+/// `> Task :app:compileDebugKotlin UP-TO-DATE` -> (task, outcome).
+fn parse_task_header(text: &str) -> Option<(&str, Option<&str>)> {
+    let rest = text.strip_prefix("> Task ")?;
+    let mut parts = rest.split_whitespace();
+    let task = parts.next().filter(|task| task.starts_with(':'))?;
+    Some((task, parts.next()))
+}
+
+fn gradle_line_level(stream: &str, text: &str) -> LogLevel {
+    const ERROR_PREFIXES: &[&str] = &[
+        "FAILURE:",
+        "BUILD FAILED",
+        "* What went wrong:",
+        "Execution failed for task",
+        "ERROR:",
+        "error:",
+    ];
+    const WARN_PREFIXES: &[&str] = &[
+        "WARNING:",
+        "Warning:",
+        "warning:",
+        "Deprecated Gradle features",
+    ];
+    if ERROR_PREFIXES.iter().any(|prefix| text.starts_with(prefix)) {
+        LogLevel::Error
+    } else if stream == "stderr" || WARN_PREFIXES.iter().any(|prefix| text.starts_with(prefix)) {
+        LogLevel::Warn
+    } else {
+        LogLevel::Info
+    }
+}
+
+/// Kotlin compiler output: `e: file:///src/Main.kt:12:5 message` and the
+/// older `w: /src/Main.kt: (12, 5): message`.
+fn parse_kotlin_diagnostic(text: &str) -> Option<Diagnostic> {
+    let (level, rest) = if let Some(rest) = text.strip_prefix("e: ") {
+        (LogLevel::Error, rest)
+    } else if let Some(rest) = text.strip_prefix("w: ") {
+        (LogLevel::Warn, rest)
+    } else {
+        return None;
+    };
+    let rest = rest.strip_prefix("file://").unwrap_or(rest);
+    let (file, line, column, message) =
+        split_kotlin_location(rest).or_else(|| split_legacy_kotlin_location(rest))?;
+    Some(Diagnostic {
+        level,
+        source: "kotlin",
+        file: file.to_string(),
+        line,
+        column,
+        message: message.trim().to_string(),
+    })
+}
+
+fn split_kotlin_location(rest: &str) -> Option<(&str, u32, u32, &str)> {
+    let (location, message) = rest.split_once(' ')?;
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    Some((parts.next()?, line, column, message))
+}
+
+fn split_legacy_kotlin_location(rest: &str) -> Option<(&str, u32, u32, &str)> {
+    let (file, location) = rest.split_once(": (")?;
+    let (position, message) = location.split_once("): ")?;
+    let (line, column) = position.split_once(", ")?;
+    Some((
+        file,
+        line.trim().parse().ok()?,
+        column.trim().parse().ok()?,
+        message,
+    ))
+}
+
+/// javac (`Foo.java:12: error: msg`) and AAPT
+/// (`ERROR: res/layout/main.xml:7: AAPT: error: msg`) diagnostics.
+fn parse_c_style_diagnostic(text: &str) -> Option<Diagnostic> {
+    const MARKERS: &[(&str, LogLevel)] = &[
+        (": AAPT: error: ", LogLevel::Error),
+        (": error: ", LogLevel::Error),
+        (": warning: ", LogLevel::Warn),
+    ];
+    let (location, message, level, marker) = MARKERS.iter().find_map(|(marker, level)| {
+        text.split_once(marker)
+            .map(|(location, message)| (location, message, *level, *marker))
+    })?;
+    let location = location
+        .strip_prefix("ERROR:")
+        .unwrap_or(location)
+        .trim_start();
+
+    let mut parts = location.rsplitn(3, ':');
+    let last: u32 = parts.next()?.parse().ok()?;
+    let (file, line, column) = match parts.next() {
+        Some(value) => match value.parse::<u32>() {
+            Ok(line) => (parts.next()?.to_string(), line, last),
+            Err(_) => (location.rsplit_once(':')?.0.to_string(), last, 0),
+        },
+        None => return None,
+    };
+    let source = if marker.contains("AAPT") {
+        "aapt"
+    } else if file.ends_with(".java") {
+        "javac"
+    } else {
+        "compiler"
+    };
+    Some(Diagnostic {
+        level,
+        source,
+        file,
+        line,
+        column,
+        message: message.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradle_output_is_classified_and_attributed_to_tasks() {
+        let mut parser = GradleLogParser::default();
+        let header = parser
+            .parse("stdout", "> Task :app:compileDebugKotlin", 1)
+            .unwrap();
+        assert_eq!(header.task, ":app:compileDebugKotlin");
+
+        let kotlin = parser
+            .parse(
+                "stderr",
+                "e: file:///work/app/src/Main.kt:12:5 Unresolved reference: foo",
+                2,
+            )
+            .unwrap();
+        assert_eq!(kotlin.level, LogLevel::Error as i32);
+        assert_eq!(kotlin.source, "kotlin");
+        assert_eq!(kotlin.file, "/work/app/src/Main.kt");
+        assert_eq!((kotlin.line, kotlin.column), (12, 5));
+        assert_eq!(kotlin.message, "Unresolved reference: foo");
+        assert_eq!(kotlin.task, ":app:compileDebugKotlin");
+
+        let javac = parser
+            .parse(
+                "stderr",
+                "/work/app/src/Foo.java:7: warning: [deprecation] bar() is deprecated",
+                3,
+            )
+            .unwrap();
+        assert_eq!(javac.level, LogLevel::Warn as i32);
+        assert_eq!(javac.source, "javac");
+        assert_eq!(
+            (javac.file.as_str(), javac.line),
+            ("/work/app/src/Foo.java", 7)
+        );
+
+        let aapt = parser
+            .parse(
+                "stderr",
+                "ERROR: /work/app/src/main/res/layout/main.xml:9: AAPT: error: attribute not found.",
+                4,
+            )
+            .unwrap();
+        assert_eq!(aapt.source, "aapt");
+        assert_eq!(aapt.file, "/work/app/src/main/res/layout/main.xml");
+
+        let failed = parser.parse("stderr", "BUILD FAILED in 3s", 5).unwrap();
+        assert_eq!(failed.level, LogLevel::Error as i32);
+        assert!(failed.task.is_empty());
+        assert!(parser.parse("stdout", "   ", 6).is_none());
+    }
+}
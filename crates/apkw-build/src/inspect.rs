@@ -0,0 +1,686 @@
+//! Reads what an APK or AAB declares straight from its zip entries.
+//!
+//! APKs carry AndroidManifest.xml in Android's binary XML format; bundles
+//! carry `<module>/manifest/AndroidManifest.xml` as the protobuf `XmlNode`
+//! aapt2 writes. Native ABIs come from `lib/<abi>/*.so` entries and method
+//! counts from dex headers, so nothing is decompressed beyond the manifest
+//! and the first bytes of each dex file.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+
+use apkw_proto::apkw::v1::{ArtifactInspection, ArtifactType, DexFileStats, KeyValue};
+use prost::Message;
+use zip::ZipArchive;
+
+const APK_MANIFEST: &str = "AndroidManifest.xml";
+const BUNDLE_MANIFEST: &str = "base/manifest/AndroidManifest.xml";
+/// Manifests beyond this size are not read.
+const MAX_MANIFEST_BYTES: u64 = 8 * 1024 * 1024;
+const DEX_HEADER_LEN: usize = 0x70;
+
+const RES_XML_TYPE: u16 = 0x0003;
+const RES_STRING_POOL_TYPE: u16 = 0x0001;
+const RES_XML_RESOURCE_MAP_TYPE: u16 = 0x0180;
+const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+const UTF8_FLAG: u32 = 1 << 8;
+
+const TYPE_REFERENCE: u8 = 0x01;
+const TYPE_STRING: u8 = 0x03;
+const TYPE_INT_DEC: u8 = 0x10;
+const TYPE_INT_HEX: u8 = 0x11;
+const TYPE_INT_BOOLEAN: u8 = 0x12;
+
+/// `android:` attributes by resource id, for manifests whose attribute
+/// names were stripped or obfuscated.
+const ANDROID_ATTRS: &[(u32, &str)] = &[
+    (0x0101_0003, "name"),
+    (0x0101_020c, "minSdkVersion"),
+    (0x0101_021b, "versionCode"),
+    (0x0101_021c, "versionName"),
+    (0x0101_0270, "targetSdkVersion"),
+];
+
+/// Reads the manifest, native libraries and dex headers of an APK or AAB.
+pub(crate) fn inspect_artifact(path: &Path) -> io::Result<ArtifactInspection> {
+    let file = fs::File::open(path)?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(io::Error::other)?;
+    let names: Vec<String> = archive.file_names().map(String::from).collect();
+    let bundle = names.iter().any(|name| name == BUNDLE_MANIFEST);
+
+    let mut inspection = ArtifactInspection {
+        r#type: if bundle {
+            ArtifactType::Aab
+        } else {
+            ArtifactType::Apk
+        } as i32,
+        ..Default::default()
+    };
+    let manifest_name = if bundle {
+        BUNDLE_MANIFEST
+    } else {
+        APK_MANIFEST
+    };
+    let manifest = {
+        let entry = archive.by_name(manifest_name).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{manifest_name}: {err}"),
+            )
+        })?;
+        let mut data = Vec::new();
+        entry.take(MAX_MANIFEST_BYTES).read_to_end(&mut data)?;
+        data
+    };
+    let parsed = if bundle {
+        parse_proto_manifest(&manifest)
+    } else {
+        parse_binary_manifest(&manifest)
+    };
+    parsed
+        .map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{manifest_name}: {err}"),
+            )
+        })?
+        .apply(&mut inspection);
+
+    let mut abis = BTreeSet::new();
+    for name in &names {
+        // Bundle entries are prefixed with their module.
+        let entry = if bundle {
+            name.split_once('/').map(|(_, rest)| rest)
+        } else {
+            Some(name.as_str())
+        };
+        let Some(entry) = entry else {
+            continue;
+        };
+        if let Some((abi, file)) = entry
+            .strip_prefix("lib/")
+            .and_then(|rest| rest.split_once('/'))
+        {
+            if file.ends_with(".so") {
+                abis.insert(abi.to_string());
+            }
+        } else if is_dex_entry(entry, bundle) {
+            let mut header = Vec::with_capacity(DEX_HEADER_LEN);
+            archive
+                .by_name(name)
+                .map_err(io::Error::other)?
+                .take(DEX_HEADER_LEN as u64)
+                .read_to_end(&mut header)?;
+            if let Some(stats) = parse_dex_header(name, &header) {
+                inspection.method_count += u64::from(stats.method_count);
+                inspection.class_count += u64::from(stats.class_count);
+                inspection.dex_files.push(stats);
+            }
+        }
+    }
+    inspection.native_abis = abis.into_iter().collect();
+    Ok(inspection)
+}
+
+fn is_dex_entry(entry: &str, bundle: bool) -> bool {
+    let file = if bundle {
+        match entry.strip_prefix("dex/") {
+            Some(file) => file,
+            None => return false,
+        }
+    } else {
+        entry
+    };
+    !file.contains('/') && file.starts_with("classes") && file.ends_with(".dex")
+}
+
+/// Artifact metadata entries for an inspection; empty values are left out.
+pub(crate) fn inspection_metadata(inspection: &ArtifactInspection) -> Vec<KeyValue> {
+    let mut entries = vec![
+        ("application_id", inspection.application_id.clone()),
+        ("version_name", inspection.version_name.clone()),
+        ("min_sdk", inspection.min_sdk.clone()),
+        ("target_sdk", inspection.target_sdk.clone()),
+        ("permissions", inspection.permissions.join(",")),
+        ("native_abis", inspection.native_abis.join(",")),
+        ("split", inspection.split.clone()),
+    ];
+    if inspection.version_code != 0 {
+        entries.push(("version_code", inspection.version_code.to_string()));
+    }
+    if !inspection.dex_files.is_empty() {
+        entries.push(("dex_files", inspection.dex_files.len().to_string()));
+        entries.push(("dex_method_count", inspection.method_count.to_string()));
+        entries.push(("dex_class_count", inspection.class_count.to_string()));
+    }
+    entries
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| KeyValue {
+            key: key.into(),
+            value,
+        })
+        .collect()
+}
+
+/// The ABI (`arm64-v8a`) or density (`xxhdpi`) a `config.*` split is for.
+pub(crate) fn split_qualifier(split: &str) -> Option<&str> {
+    split.strip_prefix("config.")
+}
+
+/// What the manifest elements the inspection reads declared.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ManifestInfo {
+    package: String,
+    version_code: String,
+    version_name: String,
+    split: String,
+    min_sdk: String,
+    target_sdk: String,
+    permissions: Vec<String>,
+}
+
+impl ManifestInfo {
+    fn element(&mut self, name: &str, attrs: &[(String, String)]) {
+        let attr = |key: &str| {
+            attrs
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+        };
+        match name {
+            "manifest" => {
+                self.package = attr("package").unwrap_or_default();
+                self.version_code = attr("versionCode").unwrap_or_default();
+                self.version_name = attr("versionName").unwrap_or_default();
+                self.split = attr("split").unwrap_or_default();
+            }
+            "uses-sdk" => {
+                self.min_sdk = attr("minSdkVersion").unwrap_or_default();
+                self.target_sdk = attr("targetSdkVersion").unwrap_or_default();
+            }
+            "uses-permission" | "uses-permission-sdk-23" | "uses-permission-sdk-m" => {
+                if let Some(permission) = attr("name").filter(|value| !value.is_empty()) {
+                    if !self.permissions.contains(&permission) {
+                        self.permissions.push(permission);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn apply(self, inspection: &mut ArtifactInspection) {
+        inspection.application_id = self.package;
+        inspection.version_code = self.version_code.trim().parse().unwrap_or_default();
+        inspection.version_name = self.version_name;
+        inspection.split = self.split;
+        inspection.min_sdk = self.min_sdk;
+        inspection.target_sdk = self.target_sdk;
+        inspection.permissions = self.permissions;
+    }
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Parses Android's binary XML (`ResXMLTree`) manifest format.
+fn parse_binary_manifest(data: &[u8]) -> Result<ManifestInfo, String> {
+    if u16_at(data, 0) != Some(RES_XML_TYPE) {
+        return Err("not a binary XML document".into());
+    }
+    let end = u32_at(data, 4)
+        .map(|size| (size as usize).min(data.len()))
+        .unwrap_or_default();
+    let mut pos = u16_at(data, 2).unwrap_or_default() as usize;
+    let mut strings = Vec::new();
+    let mut resource_ids = Vec::new();
+    let mut info = ManifestInfo::default();
+    while pos + 8 <= end {
+        let (Some(kind), Some(header_size), Some(size)) = (
+            u16_at(data, pos),
+            u16_at(data, pos + 2),
+            u32_at(data, pos + 4),
+        ) else {
+            break;
+        };
+        let size = size as usize;
+        if size < 8 || pos + size > end {
+            break;
+        }
+        let chunk = &data[pos..pos + size];
+        match kind {
+            RES_STRING_POOL_TYPE => strings = parse_string_pool(chunk, header_size as usize)?,
+            RES_XML_RESOURCE_MAP_TYPE => {
+                resource_ids = (header_size as usize..size)
+                    .step_by(4)
+                    .filter_map(|offset| u32_at(chunk, offset))
+                    .collect();
+            }
+            RES_XML_START_ELEMENT_TYPE => {
+                let (name, attrs) =
+                    parse_start_element(chunk, header_size as usize, &strings, &resource_ids)
+                        .ok_or("truncated element")?;
+                info.element(&name, &attrs);
+            }
+            _ => {}
+        }
+        pos += size;
+    }
+    if info.package.is_empty() {
+        return Err("no manifest package".into());
+    }
+    Ok(info)
+}
+
+fn parse_string_pool(chunk: &[u8], header_size: usize) -> Result<Vec<String>, String> {
+    let count = u32_at(chunk, 8).ok_or("truncated string pool")? as usize;
+    let flags = u32_at(chunk, 16).ok_or("truncated string pool")?;
+    let strings_start = u32_at(chunk, 20).ok_or("truncated string pool")? as usize;
+    if count > chunk.len() / 4 {
+        return Err("string pool count exceeds its chunk".into());
+    }
+    let utf8 = flags & UTF8_FLAG != 0;
+    (0..count)
+        .map(|index| {
+            let offset = u32_at(chunk, header_size + index * 4)? as usize;
+            let pos = strings_start.checked_add(offset)?;
+            if utf8 {
+                decode_utf8_string(chunk, pos)
+            } else {
+                decode_utf16_string(chunk, pos)
+            }
+        })
+        .map(|value| value.ok_or_else(|| "truncated string pool".to_string()))
+        .collect()
+}
+
+fn decode_utf8_string(chunk: &[u8], pos: usize) -> Option<String> {
+    // UTF-16 length, then UTF-8 length; each one or two bytes.
+    let skip_len = |pos: usize| -> Option<(usize, usize)> {
+        let first = *chunk.get(pos)? as usize;
+        if first & 0x80 != 0 {
+            let second = *chunk.get(pos + 1)? as usize;
+            Some((((first & 0x7f) << 8) | second, pos + 2))
+        } else {
+            Some((first, pos + 1))
+        }
+    };
+    let (_, pos) = skip_len(pos)?;
+    let (len, pos) = skip_len(pos)?;
+    chunk
+        .get(pos..pos + len)
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+}
+
+fn decode_utf16_string(chunk: &[u8], pos: usize) -> Option<String> {
+    let first = u16_at(chunk, pos)? as usize;
+    let (len, pos) = if first & 0x8000 != 0 {
+        let second = u16_at(chunk, pos + 2)? as usize;
+        (((first & 0x7fff) << 16) | second, pos + 4)
+    } else {
+        (first, pos + 2)
+    };
+    let units = (0..len)
+        .map(|index| u16_at(chunk, pos + index * 2))
+        .collect::<Option<Vec<u16>>>()?;
+    Some(String::from_utf16_lossy(&units))
+}
+
+type Attributes = Vec<(String, String)>;
+
+fn parse_start_element(
+    chunk: &[u8],
+    header_size: usize,
+    strings: &[String],
+    resource_ids: &[u32],
+) -> Option<(String, Attributes)> {
+    let string = |index: u32| strings.get(index as usize).cloned();
+    let ext = header_size;
+    let name = string(u32_at(chunk, ext + 4)?)?;
+    let attr_start = u16_at(chunk, ext + 8)? as usize;
+    let attr_size = u16_at(chunk, ext + 10)? as usize;
+    let attr_count = u16_at(chunk, ext + 12)? as usize;
+    let mut attrs = Vec::with_capacity(attr_count);
+    for index in 0..attr_count {
+        let at = ext + attr_start + index * attr_size;
+        let name_index = u32_at(chunk, at + 4)?;
+        let raw_value = u32_at(chunk, at + 8)?;
+        let data_type = *chunk.get(at + 15)?;
+        let data = u32_at(chunk, at + 16)?;
+        let attr_name = resource_ids
+            .get(name_index as usize)
+            .and_then(|id| ANDROID_ATTRS.iter().find(|(known, _)| known == id))
+            .map(|(_, name)| name.to_string())
+            .or_else(|| string(name_index))
+            .unwrap_or_default();
+        let value = match data_type {
+            TYPE_STRING => string(data).unwrap_or_default(),
+            TYPE_INT_DEC => (data as i32).to_string(),
+            TYPE_INT_HEX => format!("0x{data:x}"),
+            TYPE_INT_BOOLEAN => (data != 0).to_string(),
+            TYPE_REFERENCE => format!("@0x{data:08x}"),
+            _ => string(raw_value).unwrap_or_else(|| data.to_string()),
+        };
+        attrs.push((attr_name, value));
+    }
+    Some((name, attrs))
+}
+
+/// The parts of aapt2's `XmlNode` (Resources.proto) the inspection reads.
+#[derive(Clone, PartialEq, Message)]
+struct XmlNode {
+    #[prost(message, optional, tag = "1")]
+    element: Option<XmlElement>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct XmlElement {
+    #[prost(string, tag = "3")]
+    name: String,
+    #[prost(message, repeated, tag = "4")]
+    attribute: Vec<XmlAttribute>,
+    #[prost(message, repeated, tag = "5")]
+    child: Vec<XmlNode>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct XmlAttribute {
+    #[prost(string, tag = "2")]
+    name: String,
+    #[prost(string, tag = "3")]
+    value: String,
+    #[prost(uint32, tag = "5")]
+    resource_id: u32,
+    #[prost(message, optional, tag = "6")]
+    compiled_item: Option<XmlItem>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct XmlItem {
+    #[prost(message, optional, tag = "7")]
+    prim: Option<XmlPrimitive>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct XmlPrimitive {
+    #[prost(int32, optional, tag = "6")]
+    int_decimal_value: Option<i32>,
+}
+
+fn parse_proto_manifest(data: &[u8]) -> Result<ManifestInfo, String> {
+    let root = XmlNode::decode(data).map_err(|err| err.to_string())?;
+    let mut info = ManifestInfo::default();
+    let mut pending: Vec<&XmlElement> = root.element.iter().collect();
+    while let Some(element) = pending.pop() {
+        let attrs: Attributes = element
+            .attribute
+            .iter()
+            .map(|attr| {
+                let name = ANDROID_ATTRS
+                    .iter()
+                    .find(|(id, _)| *id == attr.resource_id)
+                    .map(|(_, name)| name.to_string())
+                    .unwrap_or_else(|| attr.name.clone());
+                let value = match attr
+                    .compiled_item
+                    .as_ref()
+                    .and_then(|item| item.prim.as_ref())
+                    .and_then(|prim| prim.int_decimal_value)
+                {
+                    Some(number) if attr.value.is_empty() => number.to_string(),
+                    _ => attr.value.clone(),
+                };
+                (name, value)
+            })
+            .collect();
+        info.element(&element.name, &attrs);
+        pending.extend(
+            element
+                .child
+                .iter()
+                .rev()
+                .filter_map(|child| child.element.as_ref()),
+        );
+    }
+    if info.package.is_empty() {
+        return Err("no manifest package".into());
+    }
+    Ok(info)
+}
+
+fn parse_dex_header(name: &str, header: &[u8]) -> Option<DexFileStats> {
+    if header.len() < DEX_HEADER_LEN || !header.starts_with(b"dex\n") {
+        return None;
+    }
+    Some(DexFileStats {
+        name: name.to_string(),
+        version: String::from_utf8_lossy(&header[4..7]).to_string(),
+        string_count: u32_at(header, 0x38)?,
+        field_count: u32_at(header, 0x50)?,
+        method_count: u32_at(header, 0x58)?,
+        class_count: u32_at(header, 0x60)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn utf16_pool(strings: &[&str]) -> Vec<u8> {
+        let header_size = 28usize;
+        let mut body = Vec::new();
+        let mut offsets = Vec::new();
+        for value in strings {
+            offsets.push(body.len() as u32);
+            let units: Vec<u16> = value.encode_utf16().collect();
+            body.extend((units.len() as u16).to_le_bytes());
+            units
+                .iter()
+                .for_each(|unit| body.extend(unit.to_le_bytes()));
+            body.extend([0, 0]);
+        }
+        let strings_start = header_size + offsets.len() * 4;
+        let mut chunk = Vec::new();
+        chunk.extend(RES_STRING_POOL_TYPE.to_le_bytes());
+        chunk.extend((header_size as u16).to_le_bytes());
+        chunk.extend(((strings_start + body.len()) as u32).to_le_bytes());
+        chunk.extend((strings.len() as u32).to_le_bytes());
+        chunk.extend(0u32.to_le_bytes());
+        chunk.extend(0u32.to_le_bytes());
+        chunk.extend((strings_start as u32).to_le_bytes());
+        chunk.extend(0u32.to_le_bytes());
+        offsets
+            .iter()
+            .for_each(|offset| chunk.extend(offset.to_le_bytes()));
+        chunk.extend(body);
+        chunk
+    }
+
+    /// A start element with (name index, data type, data) attributes.
+    fn start_element(name: u32, attrs: &[(u32, u8, u32)]) -> Vec<u8> {
+        let mut chunk = Vec::new();
+        chunk.extend(RES_XML_START_ELEMENT_TYPE.to_le_bytes());
+        chunk.extend(16u16.to_le_bytes());
+        chunk.extend(((36 + attrs.len() * 20) as u32).to_le_bytes());
+        chunk.extend(1u32.to_le_bytes());
+        chunk.extend(u32::MAX.to_le_bytes());
+        chunk.extend(u32::MAX.to_le_bytes());
+        chunk.extend(name.to_le_bytes());
+        chunk.extend(20u16.to_le_bytes());
+        chunk.extend(20u16.to_le_bytes());
+        chunk.extend((attrs.len() as u16).to_le_bytes());
+        chunk.extend([0u8; 6]);
+        for (attr_name, data_type, data) in attrs {
+            chunk.extend(u32::MAX.to_le_bytes());
+            chunk.extend(attr_name.to_le_bytes());
+            let raw = if *data_type == TYPE_STRING {
+                *data
+            } else {
+                u32::MAX
+            };
+            chunk.extend(raw.to_le_bytes());
+            chunk.extend(8u16.to_le_bytes());
+            chunk.push(0);
+            chunk.push(*data_type);
+            chunk.extend(data.to_le_bytes());
+        }
+        chunk
+    }
+
+    #[test]
+    fn apk_manifest_abis_and_dex_counts_are_read() {
+        // 0..=4 are mapped to android attributes by the resource map.
+        let strings = [
+            "versionCode",
+            "versionName",
+            "minSdkVersion",
+            "targetSdkVersion",
+            "name",
+            "package",
+            "manifest",
+            "uses-sdk",
+            "uses-permission",
+            "com.example.app",
+            "1.4.2",
+            "android.permission.INTERNET",
+            "android.permission.CAMERA",
+        ];
+        let mut chunks = utf16_pool(&strings);
+        let resource_ids = [
+            0x0101_021b_u32,
+            0x0101_021c,
+            0x0101_020c,
+            0x0101_0270,
+            0x0101_0003,
+        ];
+        chunks.extend(RES_XML_RESOURCE_MAP_TYPE.to_le_bytes());
+        chunks.extend(8u16.to_le_bytes());
+        chunks.extend((8 + resource_ids.len() as u32 * 4).to_le_bytes());
+        resource_ids
+            .iter()
+            .for_each(|id| chunks.extend(id.to_le_bytes()));
+        chunks.extend(start_element(
+            6,
+            &[
+                (0, TYPE_INT_DEC, 42),
+                (1, TYPE_STRING, 10),
+                (5, TYPE_STRING, 9),
+            ],
+        ));
+        chunks.extend(start_element(
+            7,
+            &[(2, TYPE_INT_DEC, 24), (3, TYPE_INT_DEC, 35)],
+        ));
+        chunks.extend(start_element(8, &[(4, TYPE_STRING, 11)]));
+        chunks.extend(start_element(8, &[(4, TYPE_STRING, 12)]));
+        let mut manifest = Vec::new();
+        manifest.extend(RES_XML_TYPE.to_le_bytes());
+        manifest.extend(8u16.to_le_bytes());
+        manifest.extend((8 + chunks.len() as u32).to_le_bytes());
+        manifest.extend(chunks);
+
+        let mut dex = vec![0u8; DEX_HEADER_LEN];
+        dex[..8].copy_from_slice(b"dex\n039\0");
+        dex[0x58..0x5c].copy_from_slice(&1200u32.to_le_bytes());
+        dex[0x60..0x64].copy_from_slice(&80u32.to_le_bytes());
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        for (name, data) in [
+            ("AndroidManifest.xml", manifest.as_slice()),
+            ("classes.dex", dex.as_slice()),
+            ("classes2.dex", dex.as_slice()),
+            ("lib/arm64-v8a/libnative.so", b"elf".as_slice()),
+            ("lib/x86_64/libnative.so", b"elf".as_slice()),
+            ("assets/lib/notes.txt", b"x".as_slice()),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+        let dir = std::env::temp_dir().join(format!("apkw-inspect-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let apk = dir.join("app-debug.apk");
+        fs::write(&apk, bytes).unwrap();
+
+        let inspection = inspect_artifact(&apk).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(inspection.application_id, "com.example.app");
+        assert_eq!(inspection.version_code, 42);
+        assert_eq!(inspection.version_name, "1.4.2");
+        assert_eq!(
+            (inspection.min_sdk.as_str(), inspection.target_sdk.as_str()),
+            ("24", "35")
+        );
+        assert_eq!(
+            inspection.permissions,
+            ["android.permission.INTERNET", "android.permission.CAMERA"]
+        );
+        assert_eq!(inspection.native_abis, ["arm64-v8a", "x86_64"]);
+        assert_eq!(inspection.dex_files.len(), 2);
+        assert_eq!(inspection.dex_files[0].version, "039");
+        assert_eq!(
+            (inspection.method_count, inspection.class_count),
+            (2400, 160)
+        );
+        assert!(inspection_metadata(&inspection)
+            .iter()
+            .any(|kv| kv.key == "native_abis" && kv.value == "arm64-v8a,x86_64"));
+
+        let bundle_manifest = XmlNode {
+            element: Some(XmlElement {
+                name: "manifest".into(),
+                attribute: vec![
+                    XmlAttribute {
+                        name: "package".into(),
+                        value: "com.example.app".into(),
+                        ..Default::default()
+                    },
+                    XmlAttribute {
+                        name: "versionCode".into(),
+                        resource_id: 0x0101_021b,
+                        compiled_item: Some(XmlItem {
+                            prim: Some(XmlPrimitive {
+                                int_decimal_value: Some(7),
+                            }),
+                        }),
+                        ..Default::default()
+                    },
+                ],
+                child: vec![XmlNode {
+                    element: Some(XmlElement {
+                        name: "uses-sdk".into(),
+                        attribute: vec![XmlAttribute {
+                            name: "minSdkVersion".into(),
+                            value: "26".into(),
+                            ..Default::default()
+                        }],
+                        child: vec![],
+                    }),
+                }],
+            }),
+        };
+        let info = parse_proto_manifest(&bundle_manifest.encode_to_vec()).unwrap();
+        assert_eq!(
+            (
+                info.package.as_str(),
+                info.version_code.as_str(),
+                info.min_sdk.as_str()
+            ),
+            ("com.example.app", "7", "26")
+        );
+    }
+}
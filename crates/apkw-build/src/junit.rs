@@ -0,0 +1,143 @@
+/// At most this many failures are listed in job outputs and error details.
+pub(crate) const MAX_LISTED_FAILURES: usize = 20;
+
+pub(crate) struct TestFailure {
+    /// `Class.method`.
+    pub(crate) name: String,
+    /// First line of the failure message.
+    pub(crate) message: String,
+}
+
+/// Counts of one JUnit XML report.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct TestCounts {
+    pub(crate) total: u32,
+    pub(crate) failed: u32,
+    pub(crate) skipped: u32,
+}
+
+impl TestCounts {
+    pub(crate) fn passed(&self) -> u32 {
+        self.total - self.failed - self.skipped
+    }
+}
+
+/// Results across the JUnit XML reports of one test task (one file per test
+/// class for unit tests, per device for instrumented tests).
+#[derive(Default)]
+pub(crate) struct TestSummary {
+    pub(crate) counts: TestCounts,
+    pub(crate) failures: Vec<TestFailure>,
+}
+
+impl TestSummary {
+    /// Adds the test cases of one report and returns its own counts. Errors
+    /// count as failures.
+    pub(crate) fn add_report(&mut self, xml: &str) -> Result<TestCounts, String> {
+        let doc = roxmltree::Document::parse(xml).map_err(|err| err.to_string())?;
+        let mut counts = TestCounts::default();
+        for case in doc
+            .descendants()
+            .filter(|node| node.has_tag_name("testcase"))
+        {
+            counts.total += 1;
+            let failure = case
+                .children()
+                .find(|child| child.has_tag_name("failure") || child.has_tag_name("error"));
+            if let Some(failure) = failure {
+                counts.failed += 1;
+                let class = case.attribute("classname").unwrap_or_default();
+                let name = case.attribute("name").unwrap_or_default();
+                let message = failure
+                    .attribute("message")
+                    .filter(|message| !message.trim().is_empty())
+                    .or_else(|| failure.text())
+                    .unwrap_or_default();
+                self.failures.push(TestFailure {
+                    name: if class.is_empty() {
+                        name.to_string()
+                    } else {
+                        format!("{class}.{name}")
+                    },
+                    message: message
+                        .trim()
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                });
+            } else if case.children().any(|child| child.has_tag_name("skipped")) {
+                counts.skipped += 1;
+            }
+        }
+        self.counts.total += counts.total;
+        self.counts.failed += counts.failed;
+        self.counts.skipped += counts.skipped;
+        Ok(counts)
+    }
+
+    /// `Class.method: message` lines for the first failures.
+    pub(crate) fn failure_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .failures
+            .iter()
+            .take(MAX_LISTED_FAILURES)
+            .map(|failure| {
+                if failure.message.is_empty() {
+                    failure.name.clone()
+                } else {
+                    format!("{}: {}", failure.name, failure.message)
+                }
+            })
+            .collect();
+        if self.failures.len() > MAX_LISTED_FAILURES {
+            lines.push(format!(
+                "... and {} more",
+                self.failures.len() - MAX_LISTED_FAILURES
+            ));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_failures_errors_and_skips() {
+        let report = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuite name="com.example.CalcTest" tests="4" skipped="1" failures="1" errors="1">
+  <testcase name="adds" classname="com.example.CalcTest" time="0.001"/>
+  <testcase name="divides" classname="com.example.CalcTest" time="0.002">
+    <failure message="expected:&lt;2&gt; but was:&lt;3&gt;" type="java.lang.AssertionError">java.lang.AssertionError: expected:&lt;2&gt; but was:&lt;3&gt;
+	at com.example.CalcTest.divides(CalcTest.kt:12)</failure>
+  </testcase>
+  <testcase name="crashes" classname="com.example.CalcTest">
+    <error type="java.lang.IllegalStateException">java.lang.IllegalStateException: boom
+	at com.example.CalcTest.crashes(CalcTest.kt:20)</error>
+  </testcase>
+  <testcase name="slow" classname="com.example.CalcTest"><skipped/></testcase>
+  <system-out><![CDATA[]]></system-out>
+</testsuite>"#;
+        let mut summary = TestSummary::default();
+        let counts = summary.add_report(report).unwrap();
+        assert_eq!(
+            counts,
+            TestCounts {
+                total: 4,
+                failed: 2,
+                skipped: 1
+            }
+        );
+        assert_eq!(counts.passed(), 1);
+        assert_eq!(
+            summary.failure_lines(),
+            [
+                "com.example.CalcTest.divides: expected:<2> but was:<3>",
+                "com.example.CalcTest.crashes: java.lang.IllegalStateException: boom",
+            ]
+        );
+        assert!(summary.add_report("<testsuite>").is_err());
+    }
+}
@@ -1,39 +1,68 @@
-use std::io::Read;
 use std::{
     collections::{BTreeSet, HashMap, VecDeque},
     fs, io,
     path::{Path, PathBuf},
     process::Stdio,
     sync::Arc,
-    time::Instant,
+    time::{Instant, SystemTime},
 };
 
+mod gradle_log;
+mod inspect;
+mod junit;
+mod signing;
+mod task_report;
+mod toolchain_env;
+
 use apkw_proto::apkw::v1::{
     build_service_server::{BuildService, BuildServiceServer},
     job_event::Payload as JobPayload,
     job_service_client::JobServiceClient,
     observe_service_client::ObserveServiceClient,
     project_service_client::ProjectServiceClient,
-    Artifact, ArtifactFilter, ArtifactType, BuildRequest, BuildResponse, BuildVariant, ErrorCode,
-    ErrorDetail, GetJobRequest, GetProjectRequest, Id, JobCompleted, JobEvent, JobFailed,
-    JobLogAppended, JobProgress, JobProgressUpdated, JobState, JobStateChanged, KeyValue,
-    ListArtifactsRequest, ListArtifactsResponse, LogChunk, PublishJobEventRequest,
-    ReloadStateRequest, ReloadStateResponse, RunId, RunOutput, RunOutputKind, StartJobRequest,
-    StreamJobEventsRequest, Timestamp, UpsertRunOutputsRequest,
+    Artifact, ArtifactFilter, ArtifactType, BuildRequest, BuildResponse, BuildVariant,
+    CreateKeystoreRequest, CreateKeystoreResponse, DeleteKeystoreRequest, DeleteKeystoreResponse,
+    DeleteSecretRequest, ErrorCode, ErrorDetail, GetBuildReportRequest, GetBuildReportResponse,
+    GetProjectRequest, Id, ImportKeystoreRequest, ImportKeystoreResponse, InspectArtifactRequest,
+    InspectArtifactResponse, JobCompleted, JobEvent, JobFailed, JobLogAppended, JobPriority,
+    JobProgress, JobProgressUpdated, JobState, JobStateChanged, KeyValue, KeystoreInfo,
+    ListArtifactsRequest, ListArtifactsResponse, ListKeystoresRequest, ListKeystoresResponse,
+    LogChunk, LogRecord, PublishJobEventRequest, ReloadStateRequest, ReloadStateResponse, RunId,
+    RunOutput, RunOutputKind, RunTestsRequest, RunTestsResponse, SecretRef, SetSecretRequest,
+    SignArtifactRequest, SignArtifactResponse, StartJobRequest, TestKind, Timestamp,
+    UpsertRunOutputsRequest,
 };
 use apkw_util::{
     data_dir, expand_user, job_addr, now_millis, now_ts, observe_addr, project_addr,
-    serve_grpc_with_telemetry, write_json_atomic,
+    secrets::{format_secret_ref, validate_secret_ref, Redactor, SecretStore},
+    serve_grpc_with_telemetry, sha256_file, spawn_cancel_watcher, wait_for_job_dispatch,
+    write_json_atomic,
 };
+use gradle_log::GradleLogParser;
+use inspect::{inspect_artifact, inspection_metadata, split_qualifier};
+use junit::TestSummary;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use signing::{
+    apksigner_sign_args, jarsigner_sign_args, jarsigner_verify_args, jdk_tool, key_password_secret,
+    keystore_file_path, keytool_generate_args, keytool_list_args, keytool_printcert_args,
+    latest_build_tools, load_keystores, parse_apksigner_verify, parse_keytool_list,
+    parse_printcert_sha256, save_keystores, signed_output_path, store_password_secret,
+    ApkVerification, KeystorePasswords, KeystoreRecord, KeystoreState, DEFAULT_KEY_ALIAS,
+    DEFAULT_VALIDITY_DAYS, MIN_KEYSTORE_PASSWORD_LEN,
+};
+use task_report::{
+    build_report, parse_task_report, task_report_script, TaskRecord, TaskSummary,
+    DEFAULT_SLOWEST_LIMIT, TASK_REPORT_PROPERTY,
+};
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
     process::{Child, Command},
-    sync::{mpsc, watch, Mutex},
+    sync::{mpsc, Mutex},
 };
 use tonic::{transport::Channel, Request, Response, Status};
+use toolchain_env::{job_toolchain_set_id, local_sdk_dir, resolve_toolchain_env, ToolchainEnv};
 use tracing::warn;
+use uuid::Uuid;
 
 const LOG_CHANNEL_CAPACITY: usize = 1024;
 const RECENT_LOG_LIMIT: usize = 200;
@@ -60,6 +89,15 @@ struct BuildRecord {
     created_at_unix_millis: i64,
     project_path: String,
     artifacts: Vec<ArtifactRecord>,
+    /// The toolchain set the build ran with and the paths it exported.
+    toolchain_set_id: String,
+    android_home: String,
+    android_ndk_root: String,
+    java_home: String,
+    /// Per-task outcomes from the task report init script.
+    task_report: Vec<TaskRecord>,
+    /// Gradle failed; the record keeps the task report but no artifacts.
+    failed: bool,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize)]
@@ -116,12 +154,14 @@ struct BuildModelInfo {
 #[derive(Clone)]
 struct Svc {
     state: Arc<Mutex<BuildState>>,
+    keystores: Arc<Mutex<KeystoreState>>,
 }
 
 impl Default for Svc {
     fn default() -> Self {
         Self {
             state: Arc::new(Mutex::new(load_state())),
+            keystores: Arc::new(Mutex::new(load_keystores())),
         }
     }
 }
@@ -178,6 +218,7 @@ impl ArtifactRecord {
 struct LogLine {
     stream: &'static str,
     line: String,
+    at_unix_millis: i64,
 }
 
 struct TempFileGuard {
@@ -223,88 +264,36 @@ async fn connect_observe() -> Result<ObserveServiceClient<Channel>, Status> {
     Ok(ObserveServiceClient::new(channel))
 }
 
-async fn job_is_cancelled(client: &mut JobServiceClient<Channel>, job_id: &str) -> bool {
-    let resp = client
-        .get_job(GetJobRequest {
-            job_id: Some(Id {
-                value: job_id.to_string(),
-            }),
-        })
-        .await;
-    let job = match resp {
-        Ok(resp) => resp.into_inner().job,
-        Err(_) => return false,
-    };
-    matches!(
-        job.and_then(|job| JobState::try_from(job.state).ok()),
-        Some(JobState::Cancelled)
-    )
-}
-
-async fn spawn_cancel_watcher(job_id: String) -> watch::Receiver<bool> {
-    let (tx, rx) = watch::channel(false);
-    let mut client = match connect_job().await {
-        Ok(client) => client,
-        Err(err) => {
-            warn!("cancel watcher: failed to connect job service: {err}");
-            return rx;
-        }
-    };
-    let mut stream = match client
-        .stream_job_events(StreamJobEventsRequest {
-            job_id: Some(Id {
-                value: job_id.clone(),
-            }),
-            include_history: true,
-        })
-        .await
-    {
-        Ok(resp) => resp.into_inner(),
-        Err(err) => {
-            warn!("cancel watcher: stream failed for {job_id}: {err}");
-            return rx;
-        }
-    };
-
-    tokio::spawn(async move {
-        loop {
-            match stream.message().await {
-                Ok(Some(evt)) => {
-                    if let Some(JobPayload::StateChanged(state)) = evt.payload {
-                        if JobState::try_from(state.new_state).unwrap_or(JobState::Unspecified)
-                            == JobState::Cancelled
-                        {
-                            let _ = tx.send(true);
-                            break;
-                        }
-                    }
-                }
-                Ok(None) => break,
-                Err(_) => break,
-            }
-        }
-    });
-
-    rx
-}
-
 async fn start_job(
     client: &mut JobServiceClient<Channel>,
     job_type: &str,
     params: Vec<KeyValue>,
     correlation_id: &str,
     project_id: Option<Id>,
+    toolchain_set_id: Option<String>,
     run_id: Option<RunId>,
 ) -> Result<String, Status> {
+    // Builds of the same project share its build directory.
+    let concurrency_keys = project_id
+        .as_ref()
+        .filter(|id| !id.value.trim().is_empty())
+        .map(|id| vec![format!("project:{}", id.value.trim())])
+        .unwrap_or_default();
     let resp = client
         .start_job(StartJobRequest {
             job_type: job_type.into(),
             params,
             project_id,
             target_id: None,
-            toolchain_set_id: None,
+            toolchain_set_id: toolchain_set_id.map(|value| Id { value }),
             correlation_id: correlation_id.to_string(),
             run_id,
+            depends_on: Vec::new(),
+            concurrency_keys,
+            timeout_ms: 0,
+            retry_policy: None,
+            priority: JobPriority::Interactive as i32,
+            preemptible: false,
         })
         .await
         .map_err(|e| Status::unavailable(format!("job start failed: {e}")))?
@@ -334,6 +323,8 @@ async fn publish_job_event(
                 job_id: Some(Id {
                     value: job_id.to_string(),
                 }),
+                attempt: 0,
+                seq: 0,
                 payload: Some(payload),
             }),
         })
@@ -427,6 +418,11 @@ fn build_progress_metrics(
         metrics.push(metric("gradle_arg_count", args.len()));
     }
 
+    if !req.secrets.is_empty() {
+        let secrets: Vec<String> = req.secrets.iter().map(format_secret_ref).collect();
+        metrics.push(metric("secrets", secrets.join(" ")));
+    }
+
     if let Some(info) = model_info {
         if let Some(min_sdk) = info.min_sdk.as_ref() {
             metrics.push(metric("min_sdk", min_sdk));
@@ -458,6 +454,16 @@ async fn publish_log(
     client: &mut JobServiceClient<Channel>,
     job_id: &str,
     message: &str,
+) -> Result<(), Status> {
+    publish_log_records(client, job_id, message, Vec::new()).await
+}
+
+/// Publishes a raw log chunk together with its parsed records.
+async fn publish_log_records(
+    client: &mut JobServiceClient<Channel>,
+    job_id: &str,
+    message: &str,
+    records: Vec<LogRecord>,
 ) -> Result<(), Status> {
     publish_job_event(
         client,
@@ -468,6 +474,7 @@ async fn publish_log(
                 data: message.as_bytes().to_vec(),
                 truncated: false,
             }),
+            records,
         }),
     )
     .await
@@ -500,7 +507,10 @@ async fn publish_failed(
     publish_job_event(
         client,
         job_id,
-        JobPayload::Failed(JobFailed { error: Some(error) }),
+        JobPayload::Failed(JobFailed {
+            error: Some(error),
+            will_retry: false,
+        }),
     )
     .await
 }
@@ -543,6 +553,12 @@ async fn upsert_run_outputs_best_effort(run_id: &str, job_id: &str, artifacts: &
                 value: artifact.name.clone(),
             });
         }
+        if !artifact.sha256.is_empty() {
+            metadata.push(KeyValue {
+                key: "sha256".into(),
+                value: artifact.sha256.clone(),
+            });
+        }
         outputs.push(RunOutput {
             output_id: format!("artifact:{job_id}:{path}"),
             run_id: Some(RunId {
@@ -637,6 +653,8 @@ fn build_record(
     module: Option<&str>,
     tasks: &[String],
     artifacts: &[Artifact],
+    toolchain: &ToolchainEnv,
+    task_report: Vec<TaskRecord>,
 ) -> BuildRecord {
     BuildRecord {
         job_id: job_id.to_string(),
@@ -648,6 +666,20 @@ fn build_record(
         created_at_unix_millis: now_millis(),
         project_path: project_path.to_string_lossy().to_string(),
         artifacts: artifacts.iter().map(ArtifactRecord::from_proto).collect(),
+        toolchain_set_id: toolchain.toolchain_set_id.clone(),
+        android_home: toolchain.sdk_path.display().to_string(),
+        android_ndk_root: toolchain
+            .ndk_path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default(),
+        java_home: toolchain
+            .java_home
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default(),
+        task_report,
+        failed: false,
     }
 }
 
@@ -676,7 +708,7 @@ fn find_latest_record<'a>(
     query: &ArtifactQuery,
 ) -> Option<&'a BuildRecord> {
     state.records.iter().find(|record| {
-        if record.project_id != project_id {
+        if record.failed || record.project_id != project_id {
             return false;
         }
 
@@ -705,7 +737,8 @@ fn find_latest_record<'a>(
     })
 }
 
-async fn get_project_path(project_id: &str) -> Result<PathBuf, Status> {
+/// A project's path and toolchain set id.
+async fn get_project(project_id: &str) -> Result<(PathBuf, Option<String>), Status> {
     let mut client = connect_project().await?;
     let resp = client
         .get_project(GetProjectRequest {
@@ -732,7 +765,24 @@ async fn get_project_path(project_id: &str) -> Result<PathBuf, Status> {
             "project path missing in ProjectService response",
         ));
     }
-    Ok(PathBuf::from(project.path))
+    let toolchain_set_id = project
+        .toolchain_set_id
+        .map(|id| id.value.trim().to_string())
+        .filter(|id| !id.is_empty());
+    Ok((PathBuf::from(project.path), toolchain_set_id))
+}
+
+/// The toolchain set a build job of `project_id` is recorded with.
+async fn project_toolchain_set_id(project_id: &str) -> Option<String> {
+    let project_set_id = if looks_like_path(project_id) {
+        None
+    } else {
+        get_project(project_id)
+            .await
+            .ok()
+            .and_then(|(_, set_id)| set_id)
+    };
+    job_toolchain_set_id(project_set_id).await
 }
 
 fn looks_like_path(value: &str) -> bool {
@@ -753,6 +803,12 @@ fn looks_like_path(value: &str) -> bool {
 }
 
 async fn resolve_project_path(project_id: &str) -> Result<PathBuf, Status> {
+    resolve_project(project_id).await.map(|(path, _)| path)
+}
+
+/// The project directory and, for registered projects, their toolchain set
+/// id. A project given by path has no set of its own.
+async fn resolve_project(project_id: &str) -> Result<(PathBuf, Option<String>), Status> {
     let trimmed = project_id.trim();
     if trimmed.is_empty() {
         return Err(Status::invalid_argument("project_id is required"));
@@ -761,14 +817,24 @@ async fn resolve_project_path(project_id: &str) -> Result<PathBuf, Status> {
     if looks_like_path(trimmed) {
         let direct = expand_user(trimmed);
         if direct.is_dir() {
-            return Ok(direct);
+            return Ok((direct, None));
         }
         return Err(Status::not_found(format!(
             "project path not found: {trimmed}"
         )));
     }
 
-    get_project_path(trimmed).await
+    get_project(trimmed).await
+}
+
+fn project_error_code(err: &Status) -> ErrorCode {
+    match err.code() {
+        tonic::Code::NotFound => ErrorCode::NotFound,
+        tonic::Code::InvalidArgument => ErrorCode::InvalidArgument,
+        tonic::Code::Unavailable => ErrorCode::Unavailable,
+        tonic::Code::FailedPrecondition => ErrorCode::InvalidArgument,
+        _ => ErrorCode::Internal,
+    }
 }
 
 fn variant_label(variant: BuildVariant) -> &'static str {
@@ -869,6 +935,15 @@ struct BuildPlan {
     module: Option<String>,
     variant: VariantSelection,
     tasks: Vec<String>,
+    /// Set for `build.test` jobs, which report JUnit results instead of
+    /// artifacts.
+    tests: Option<TestRun>,
+}
+
+#[derive(Clone)]
+struct TestRun {
+    kind: TestKind,
+    device_serial: String,
 }
 
 #[derive(Default, Clone)]
@@ -946,8 +1021,17 @@ fn tasks_for_selection(
     tasks
 }
 
+#[allow(clippy::result_large_err)]
+fn validate_secret_refs(secrets: &[SecretRef]) -> Result<(), Status> {
+    for secret in secrets {
+        validate_secret_ref(secret).map_err(Status::invalid_argument)?;
+    }
+    Ok(())
+}
+
 #[allow(clippy::result_large_err)]
 fn build_plan_for_request(req: &BuildRequest) -> Result<BuildPlan, Status> {
+    validate_secret_refs(&req.secrets)?;
     let module = normalize_module_label(&req.module)?;
     let variant = resolve_variant_selection(req)?;
     let overrides = normalized_tasks(&req.tasks);
@@ -957,9 +1041,81 @@ fn build_plan_for_request(req: &BuildRequest) -> Result<BuildPlan, Status> {
         module,
         variant,
         tasks,
+        tests: None,
     })
 }
 
+/// The build request a test run validates against the Gradle model (its
+/// variant and module) and the plan that runs the test task instead of
+/// `assemble`. Filters become `--tests` for unit tests and the runner's
+/// `class` argument for instrumented tests.
+#[allow(clippy::result_large_err)]
+fn test_plan_for_request(req: &RunTestsRequest) -> Result<(BuildRequest, BuildPlan), Status> {
+    validate_secret_refs(&req.secrets)?;
+    let kind = match TestKind::try_from(req.kind).unwrap_or(TestKind::Unspecified) {
+        TestKind::Unspecified => TestKind::Unit,
+        kind => kind,
+    };
+    let mut gradle_args = req.gradle_args.clone();
+    let filters = normalized_tasks(&req.filters);
+    if kind == TestKind::Instrumented {
+        if !filters.is_empty() {
+            gradle_args.push(KeyValue {
+                key: "-P".into(),
+                value: format!(
+                    "android.testInstrumentationRunnerArguments.class={}",
+                    filters.join(",")
+                ),
+            });
+        }
+    } else {
+        gradle_args.extend(filters.into_iter().map(|filter| KeyValue {
+            key: "--tests".into(),
+            value: filter,
+        }));
+    }
+    let build_req = BuildRequest {
+        project_id: req.project_id.clone(),
+        variant: req.variant,
+        clean_first: false,
+        gradle_args,
+        job_id: req.job_id.clone(),
+        module: req.module.clone(),
+        variant_name: req.variant_name.clone(),
+        tasks: Vec::new(),
+        correlation_id: req.correlation_id.clone(),
+        run_id: req.run_id.clone(),
+        secrets: req.secrets.clone(),
+    };
+
+    let module = normalize_module_label(&req.module)?;
+    let variant = resolve_variant_selection(&build_req)?;
+    let task = match kind {
+        TestKind::Instrumented => format!("connected{}AndroidTest", variant.task_suffix),
+        _ => format!("test{}UnitTest", variant.task_suffix),
+    };
+    let tasks = tasks_for_selection(module.as_deref(), &variant, false, &[task]);
+    Ok((
+        build_req,
+        BuildPlan {
+            module,
+            variant,
+            tasks,
+            tests: Some(TestRun {
+                kind,
+                device_serial: req.device_serial.trim().to_string(),
+            }),
+        },
+    ))
+}
+
+fn test_kind_label(kind: TestKind) -> &'static str {
+    match kind {
+        TestKind::Instrumented => "instrumented",
+        TestKind::Unit | TestKind::Unspecified => "unit",
+    }
+}
+
 fn arg_is_flag(args: &[String], flag: &str) -> bool {
     args.iter().any(|arg| arg == flag)
 }
@@ -1290,7 +1446,12 @@ struct GradleSpawn {
 }
 
 #[allow(clippy::result_large_err)]
-fn spawn_gradle(project_dir: &Path, args: &[String]) -> Result<GradleSpawn, Status> {
+fn spawn_gradle(
+    project_dir: &Path,
+    args: &[String],
+    android_serial: Option<&str>,
+    env: &[(String, String)],
+) -> Result<GradleSpawn, Status> {
     let wrapper_props = project_dir
         .join("gradle")
         .join("wrapper")
@@ -1336,6 +1497,10 @@ fn spawn_gradle(project_dir: &Path, args: &[String]) -> Result<GradleSpawn, Stat
         }
         cmd.env("GRADLE_USER_HOME", home);
     }
+    if let Some(serial) = android_serial {
+        cmd.env("ANDROID_SERIAL", serial);
+    }
+    cmd.envs(env.iter().map(|(key, value)| (key, value)));
 
     let child = cmd.spawn().map_err(spawn_error)?;
     Ok(GradleSpawn { child, description })
@@ -1343,6 +1508,7 @@ fn spawn_gradle(project_dir: &Path, args: &[String]) -> Result<GradleSpawn, Stat
 
 async fn load_gradle_model(
     project_dir: &Path,
+    env: &[(String, String)],
     job_client: &mut JobServiceClient<Channel>,
     job_id: &str,
 ) -> Result<GradleModel, Status> {
@@ -1360,7 +1526,7 @@ async fn load_gradle_model(
         script_path.display().to_string(),
         "projects".to_string(),
     ];
-    let spawn = spawn_gradle(project_dir, &args)?;
+    let spawn = spawn_gradle(project_dir, &args, None, env)?;
     let _ = publish_log(
         job_client,
         job_id,
@@ -1489,7 +1655,11 @@ where
 {
     let mut lines = BufReader::new(reader).lines();
     while let Ok(Some(line)) = lines.next_line().await {
-        let payload = LogLine { stream, line };
+        let payload = LogLine {
+            stream,
+            line,
+            at_unix_millis: now_millis(),
+        };
         if tx.send(payload).await.is_err() {
             break;
         }
@@ -1630,37 +1800,6 @@ fn collect_artifact_paths(
     Ok(())
 }
 
-fn sha256_file(path: &Path) -> io::Result<String> {
-    let mut file = fs::File::open(path)?;
-    let mut hasher = Sha256::new();
-    let mut buf = [0u8; 8192];
-    loop {
-        let read = file.read(&mut buf)?;
-        if read == 0 {
-            break;
-        }
-        hasher.update(&buf[..read]);
-    }
-    Ok(hex_encode(&hasher.finalize()))
-}
-
-fn hex_encode(bytes: &[u8]) -> String {
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for b in bytes {
-        out.push(nibble_to_hex(b >> 4));
-        out.push(nibble_to_hex(b & 0x0f));
-    }
-    out
-}
-
-fn nibble_to_hex(n: u8) -> char {
-    match n {
-        0..=9 => (b'0' + n) as char,
-        10..=15 => (b'a' + (n - 10)) as char,
-        _ => '0',
-    }
-}
-
 fn artifact_type_label(artifact_type: ArtifactType) -> &'static str {
     match artifact_type {
         ArtifactType::Apk => "apk",
@@ -2012,6 +2151,23 @@ fn infer_density_from_name(name: &str) -> Option<String> {
     None
 }
 
+/// `collect_artifacts` off the async runtime, since it hashes and inspects
+/// every APK and AAB it finds.
+async fn scan_artifacts(
+    project_path: PathBuf,
+    query: ArtifactQuery,
+    primary_task: Option<String>,
+) -> Vec<Artifact> {
+    tokio::task::spawn_blocking(move || {
+        collect_artifacts(&project_path, &query, primary_task.as_deref())
+    })
+    .await
+    .unwrap_or_else(|err| {
+        warn!("artifact scan failed: {err}");
+        Vec::new()
+    })
+}
+
 fn collect_artifacts(
     project_path: &Path,
     query: &ArtifactQuery,
@@ -2097,11 +2253,39 @@ fn collect_artifacts(
                         });
                     }
                 }
-                if abi.is_none() {
-                    abi = infer_abi_from_name(&name);
-                }
-                if density.is_none() {
-                    density = infer_density_from_name(&name);
+                let inspection = match artifact_type {
+                    ArtifactType::Apk | ArtifactType::Aab => match inspect_artifact(&path) {
+                        Ok(inspection) => Some(inspection),
+                        Err(err) => {
+                            warn!("failed to inspect {}: {}", path.display(), err);
+                            None
+                        }
+                    },
+                    _ => None,
+                };
+                match inspection.as_ref() {
+                    Some(inspection) => {
+                        // Splits name their ABI or density (`config.arm64_v8a`);
+                        // otherwise an APK is per-ABI when it ships one ABI's
+                        // libraries.
+                        if let Some(qualifier) =
+                            split_qualifier(&inspection.split).map(|q| q.replace('_', "-"))
+                        {
+                            abi = abi.or_else(|| infer_abi_from_name(&qualifier));
+                            density = density.or_else(|| infer_density_from_name(&qualifier));
+                        }
+                        if abi.is_none() && inspection.native_abis.len() == 1 {
+                            abi = inspection.native_abis.first().cloned();
+                        }
+                    }
+                    None => {
+                        if abi.is_none() {
+                            abi = infer_abi_from_name(&name);
+                        }
+                        if density.is_none() {
+                            density = infer_density_from_name(&name);
+                        }
+                    }
                 }
                 if let Some(abi) = abi {
                     metadata.push(KeyValue {
@@ -2115,6 +2299,9 @@ fn collect_artifacts(
                         value: density,
                     });
                 }
+                if let Some(inspection) = inspection.as_ref() {
+                    metadata.extend(inspection_metadata(inspection));
+                }
                 metadata.push(KeyValue {
                     key: "artifact_type".into(),
                     value: artifact_type_label(artifact_type).into(),
@@ -2210,7 +2397,7 @@ async fn run_build_job(
     };
 
     let mut cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(&mut job_client, &job_id, "Build cancelled before start\n").await;
         return;
     }
@@ -2232,18 +2419,11 @@ async fn run_build_job(
         }
     };
 
-    let project_path = match resolve_project_path(&project_id).await {
-        Ok(path) => path,
+    let (project_path, project_set_id) = match resolve_project(&project_id).await {
+        Ok(project) => project,
         Err(err) => {
-            let code = match err.code() {
-                tonic::Code::NotFound => ErrorCode::NotFound,
-                tonic::Code::InvalidArgument => ErrorCode::InvalidArgument,
-                tonic::Code::Unavailable => ErrorCode::Unavailable,
-                tonic::Code::FailedPrecondition => ErrorCode::InvalidArgument,
-                _ => ErrorCode::Internal,
-            };
             let detail = job_error_detail(
-                code,
+                project_error_code(&err),
                 "project resolution failed",
                 err.message().to_string(),
                 &job_id,
@@ -2253,6 +2433,43 @@ async fn run_build_job(
         }
     };
 
+    // Resolved on every build so Gradle never runs against a set that was
+    // changed, unverified or uninstalled since the project was created.
+    let toolchain = match resolve_toolchain_env(project_set_id.as_deref()).await {
+        Ok(toolchain) => toolchain,
+        Err(err) => {
+            let _ = publish_failed(&mut job_client, &job_id, err.into_detail(&job_id)).await;
+            return;
+        }
+    };
+    let mut toolchain_log = format!(
+        "Toolchain set {} ({}): ANDROID_HOME={}",
+        toolchain.toolchain_set_id,
+        toolchain.source,
+        toolchain.sdk_path.display()
+    );
+    if let Some(ndk) = toolchain.ndk_path.as_ref() {
+        toolchain_log.push_str(&format!(" ANDROID_NDK_ROOT={}", ndk.display()));
+    }
+    match toolchain.java_home.as_ref() {
+        Some(java_home) => toolchain_log.push_str(&format!(" JAVA_HOME={}", java_home.display())),
+        None => toolchain_log.push_str(" (no JDK in set; using java from PATH)"),
+    }
+    toolchain_log.push('\n');
+    let _ = publish_log(&mut job_client, &job_id, &toolchain_log).await;
+    if let Some(sdk_dir) = local_sdk_dir(&project_path).filter(|dir| *dir != toolchain.sdk_path) {
+        let _ = publish_log(
+            &mut job_client,
+            &job_id,
+            &format!(
+                "WARN: local.properties sets sdk.dir={}, which the Android Gradle plugin prefers over ANDROID_HOME={}\n",
+                sdk_dir.display(),
+                toolchain.sdk_path.display()
+            ),
+        )
+        .await;
+    }
+
     if *cancel_rx.borrow() {
         let _ = publish_log(
             &mut job_client,
@@ -2263,19 +2480,45 @@ async fn run_build_job(
         return;
     }
 
-    let gradle_model = match load_gradle_model(&project_path, &mut job_client, &job_id).await {
-        Ok(model) => model,
-        Err(err) => {
-            let detail = job_error_detail(
-                ErrorCode::BuildFailed,
-                "gradle model failed",
-                err.message().to_string(),
-                &job_id,
-            );
-            let _ = publish_failed(&mut job_client, &job_id, detail).await;
-            return;
+    // Resolved once the job runs, so values never sit in the request or queue.
+    let secret_env = if req.secrets.is_empty() {
+        Vec::new()
+    } else {
+        match SecretStore::load()
+            .map_err(|err| err.to_string())
+            .and_then(|store| store.resolve_env(&req.secrets))
+        {
+            Ok(env) => env,
+            Err(err) => {
+                let detail = job_error_detail(
+                    ErrorCode::SecretUnavailable,
+                    "secret resolution failed",
+                    err,
+                    &job_id,
+                );
+                let _ = publish_failed(&mut job_client, &job_id, detail).await;
+                return;
+            }
         }
     };
+    let redactor = Redactor::new(secret_env.iter().map(|(_, value)| value.clone()));
+    let mut gradle_env = toolchain.vars();
+    gradle_env.extend(secret_env.iter().cloned());
+
+    let gradle_model =
+        match load_gradle_model(&project_path, &gradle_env, &mut job_client, &job_id).await {
+            Ok(model) => model,
+            Err(err) => {
+                let detail = job_error_detail(
+                    ErrorCode::BuildFailed,
+                    "gradle model failed",
+                    err.message().to_string(),
+                    &job_id,
+                );
+                let _ = publish_failed(&mut job_client, &job_id, detail).await;
+                return;
+            }
+        };
     let model_info = match build_model_info_for_plan(&plan, &req, &gradle_model) {
         Ok(info) => info,
         Err(err) => {
@@ -2302,21 +2545,16 @@ async fn run_build_job(
         args.push("--stacktrace".into());
     }
 
-    let _ = publish_progress(
-        &mut job_client,
-        &job_id,
-        10,
-        "preflight",
-        build_progress_metrics(
-            &project_id,
-            &project_path,
-            &plan,
-            &req,
-            &args,
-            Some(&model_info),
-        ),
-    )
-    .await;
+    let mut preflight = build_progress_metrics(
+        &project_id,
+        &project_path,
+        &plan,
+        &req,
+        &args,
+        Some(&model_info),
+    );
+    preflight.extend(toolchain.metadata());
+    let _ = publish_progress(&mut job_client, &job_id, 10, "preflight", preflight).await;
 
     let _ = publish_log(
         &mut job_client,
@@ -2341,7 +2579,50 @@ async fn run_build_job(
         .await;
     }
 
-    let spawn = match spawn_gradle(&project_path, &args) {
+    if !secret_env.is_empty() {
+        let names: Vec<&str> = secret_env.iter().map(|(name, _)| name.as_str()).collect();
+        let _ = publish_log(
+            &mut job_client,
+            &job_id,
+            &format!("Secrets injected as: {}\n", names.join(" ")),
+        )
+        .await;
+    }
+
+    // Task outcomes are collected for builds; the init script and report
+    // file stay out of the logged args and live only as long as the job.
+    let mut spawn_args = args.clone();
+    let mut report_files = Vec::new();
+    let mut task_report_path = None;
+    if plan.tests.is_none() {
+        let stamp = format!("{job_id}-{}", now_millis());
+        let script_path = std::env::temp_dir().join(format!("apkw-task-report-{stamp}.gradle"));
+        let report_path = std::env::temp_dir().join(format!("apkw-task-report-{stamp}.tsv"));
+        match fs::write(&script_path, task_report_script()) {
+            Ok(()) => {
+                spawn_args.push("-I".into());
+                spawn_args.push(script_path.display().to_string());
+                spawn_args.push(format!(
+                    "-P{TASK_REPORT_PROPERTY}={}",
+                    report_path.display()
+                ));
+                report_files.push(TempFileGuard { path: script_path });
+                report_files.push(TempFileGuard {
+                    path: report_path.clone(),
+                });
+                task_report_path = Some(report_path);
+            }
+            Err(err) => warn!("failed to write task report script: {err}"),
+        }
+    }
+
+    let android_serial = plan
+        .tests
+        .as_ref()
+        .map(|tests| tests.device_serial.as_str())
+        .filter(|serial| !serial.is_empty());
+    let started_at = SystemTime::now();
+    let spawn = match spawn_gradle(&project_path, &spawn_args, android_serial, &gradle_env) {
         Ok(child) => child,
         Err(err) => {
             let detail = job_error_detail(
@@ -2411,6 +2692,7 @@ async fn run_build_job(
     .await;
 
     let mut recent = VecDeque::with_capacity(RECENT_LOG_LIMIT);
+    let mut log_parser = GradleLogParser::default();
     let start = Instant::now();
     let mut status: Option<Result<std::process::ExitStatus, io::Error>> = None;
 
@@ -2427,12 +2709,17 @@ async fn run_build_job(
             line = line_rx.recv() => {
                 match line {
                     Some(line) => {
-                        let mut text = format!("[{}] {}", line.stream, line.line);
+                        let line_text = redactor.redact(&line.line);
+                        let mut text = format!("[{}] {}", line.stream, line_text);
                         if !text.ends_with('\n') {
                             text.push('\n');
                         }
                         append_recent(&mut recent, text.clone());
-                        let _ = publish_log(&mut job_client, &job_id, &text).await;
+                        let records = log_parser
+                            .parse(line.stream, &line_text, line.at_unix_millis)
+                            .into_iter()
+                            .collect();
+                        let _ = publish_log_records(&mut job_client, &job_id, &text, records).await;
                     }
                     None => {
                         if status.is_some() {
@@ -2483,21 +2770,66 @@ async fn run_build_job(
         return;
     }
 
-    if status.success() {
+    if let Some(tests) = plan.tests.as_ref() {
+        finish_test_job(
+            &mut job_client,
+            &job_id,
+            &req,
+            &plan,
+            tests,
+            &project_path,
+            TestExit {
+                status,
+                recent: &recent,
+                duration_ms,
+                started_at,
+            },
+        )
+        .await;
+        return;
+    }
+
+    // Failed builds keep their task report too: that is when the failed and
+    // slowest tasks matter most.
+    let task_report = task_report_path
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|raw| parse_task_report(&raw))
+        .unwrap_or_default();
+    drop(report_files);
+    let task_summary = TaskSummary::of(&task_report);
+    if !task_report.is_empty() {
+        let _ = publish_log(
+            &mut job_client,
+            &job_id,
+            &format!(
+                "Tasks: {} executed, {} up-to-date, {} from cache, {} skipped, {} failed (cache hit rate {:.0}%)\n",
+                task_summary.executed,
+                task_summary.up_to_date,
+                task_summary.from_cache,
+                task_summary.skipped,
+                task_summary.failed,
+                task_summary.cache_hit_rate() * 100.0
+            ),
+        )
+        .await;
+    }
+    let artifacts = if status.success() {
         let mut query = ArtifactQuery::default();
         if let Some(module) = plan.module.as_ref() {
             query.modules.push(module.clone());
         }
         query.variant = Some(plan.variant.label.clone());
-        let primary_task = plan
-            .tasks
-            .iter()
-            .find(|task| !is_clean_task(task))
-            .map(|task| task.as_str());
-        let artifacts = collect_artifacts(&project_path, &query, primary_task);
-        {
-            let mut st = state.lock().await;
-            let record = build_record(
+        let primary_task = plan.tasks.iter().find(|task| !is_clean_task(task)).cloned();
+        scan_artifacts(project_path.clone(), query, primary_task).await
+    } else {
+        Vec::new()
+    };
+    {
+        let mut st = state.lock().await;
+        let record = BuildRecord {
+            failed: !status.success(),
+            ..build_record(
                 &job_id,
                 &project_id,
                 &project_path,
@@ -2506,10 +2838,15 @@ async fn run_build_job(
                 plan.module.as_deref(),
                 &plan.tasks,
                 &artifacts,
-            );
-            upsert_build_record(&mut st, record);
-            save_state_best_effort(&st);
-        }
+                &toolchain,
+                task_report,
+            )
+        };
+        upsert_build_record(&mut st, record);
+        save_state_best_effort(&st);
+    }
+
+    if status.success() {
         if let Some(run_id) = req
             .run_id
             .as_ref()
@@ -2568,6 +2905,8 @@ async fn run_build_job(
                 value: plan.tasks.join(" "),
             });
         }
+        outputs.extend(toolchain.metadata());
+        outputs.extend(task_summary.outputs());
 
         for artifact in artifacts.iter().take(10) {
             let artifact_type =
@@ -2597,6 +2936,11 @@ async fn run_build_job(
             Some(&model_info),
         );
         metrics.push(metric("artifact_count", artifacts.len()));
+        metrics.push(metric("tasks_executed", task_summary.executed));
+        metrics.push(metric(
+            "cache_hit_rate",
+            format!("{:.2}", task_summary.cache_hit_rate()),
+        ));
         let type_summary = artifact_type_summary(&artifacts);
         if !type_summary.is_empty() {
             metrics.push(metric("artifact_types", type_summary));
@@ -2617,39 +2961,640 @@ async fn run_build_job(
     }
 }
 
-#[tonic::async_trait]
-impl BuildService for Svc {
-    async fn build(
-        &self,
-        request: Request<BuildRequest>,
-    ) -> Result<Response<BuildResponse>, Status> {
-        let mut req = request.into_inner();
-        let project_id = req
-            .project_id
-            .as_ref()
-            .map(|id| id.value.trim())
-            .filter(|value| !value.is_empty())
-            .ok_or_else(|| Status::invalid_argument("project_id is required"))?
-            .to_string();
-        req.project_id = Some(Id {
-            value: project_id.clone(),
-        });
+/// JUnit XML reports under the module's test result directories written
+/// since `since`, so reports of earlier runs are not counted again.
+fn collect_test_reports(
+    project_path: &Path,
+    module: Option<&str>,
+    since: SystemTime,
+) -> Vec<PathBuf> {
+    let modules: Vec<String> = module
+        .map(|module| vec![module.to_string()])
+        .unwrap_or_default();
+    let mut reports = Vec::new();
+    for (_, root) in output_roots_for_modules(project_path, &modules) {
+        for (artifact_type, dir) in output_dirs_for_root(&root) {
+            if artifact_type != ArtifactType::TestResult || !dir.is_dir() {
+                continue;
+            }
+            let mut paths = Vec::new();
+            if let Err(err) = collect_artifact_paths(&dir, artifact_type, &mut paths) {
+                warn!("failed to scan {}: {err}", dir.display());
+            }
+            reports.extend(paths.into_iter().filter(|path| {
+                fs::metadata(path)
+                    .and_then(|meta| meta.modified())
+                    .is_ok_and(|modified| modified >= since)
+            }));
+        }
+    }
+    reports.sort();
+    reports
+}
 
-        let plan = build_plan_for_request(&req)?;
-        let mut job_client = connect_job().await?;
-        let job_id = req
-            .job_id
-            .as_ref()
-            .map(|id| id.value.trim().to_string())
-            .filter(|value| !value.is_empty())
-            .unwrap_or_default();
-        let correlation_id = req.correlation_id.trim();
-        let job_id = if job_id.is_empty() {
-            let mut params = vec![
-                KeyValue {
-                    key: "variant".into(),
-                    value: plan.variant.label.clone(),
-                },
+/// How the Gradle process of a test job ended.
+struct TestExit<'a> {
+    status: std::process::ExitStatus,
+    recent: &'a VecDeque<String>,
+    duration_ms: u128,
+    started_at: SystemTime,
+}
+
+/// Parses the reports of a test job, attaches them to the run and finishes
+/// the job: failed when a test failed (or Gradle failed without failing
+/// tests), completed with the counts otherwise.
+async fn finish_test_job(
+    job_client: &mut JobServiceClient<Channel>,
+    job_id: &str,
+    req: &BuildRequest,
+    plan: &BuildPlan,
+    tests: &TestRun,
+    project_path: &Path,
+    exit: TestExit<'_>,
+) {
+    let reports = collect_test_reports(project_path, plan.module.as_deref(), exit.started_at);
+    let mut summary = TestSummary::default();
+    let mut artifacts = Vec::new();
+    for path in &reports {
+        let counts = match fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|xml| summary.add_report(&xml))
+        {
+            Ok(counts) => counts,
+            Err(err) => {
+                let _ = publish_log(
+                    job_client,
+                    job_id,
+                    &format!("WARN: skipping test report {}: {err}\n", path.display()),
+                )
+                .await;
+                continue;
+            }
+        };
+        artifacts.push(Artifact {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.display().to_string(),
+            size_bytes: fs::metadata(path).map(|meta| meta.len()).unwrap_or(0),
+            sha256: String::new(),
+            metadata: vec![
+                metric("test_kind", test_kind_label(tests.kind)),
+                metric("tests", counts.total),
+                metric("failures", counts.failed),
+                metric("skipped", counts.skipped),
+            ],
+            r#type: ArtifactType::TestResult as i32,
+        });
+    }
+    if let Some(run_id) = req
+        .run_id
+        .as_ref()
+        .map(|id| id.value.trim())
+        .filter(|value| !value.is_empty())
+    {
+        upsert_run_outputs_best_effort(run_id, job_id, &artifacts).await;
+    }
+
+    let counts = summary.counts;
+    let _ = publish_log(
+        job_client,
+        job_id,
+        &format!(
+            "Tests: {} passed, {} failed, {} skipped ({} reports)\n",
+            counts.passed(),
+            counts.failed,
+            counts.skipped,
+            artifacts.len()
+        ),
+    )
+    .await;
+    if counts.failed > 0 {
+        let detail = job_error_detail(
+            ErrorCode::TestsFailed,
+            &format!("{} of {} tests failed", counts.failed, counts.total),
+            summary.failure_lines().join("\n"),
+            job_id,
+        );
+        let _ = publish_failed(job_client, job_id, detail).await;
+        return;
+    }
+    if !exit.status.success() {
+        let code = exit.status.code().unwrap_or(-1);
+        let mut detail = format!("exit_code={code}\n");
+        detail.push_str(&collect_recent(exit.recent));
+        let detail = job_error_detail(
+            ErrorCode::BuildFailed,
+            "Gradle test run failed",
+            detail,
+            job_id,
+        );
+        let _ = publish_failed(job_client, job_id, detail).await;
+        return;
+    }
+    if reports.is_empty() {
+        let _ = publish_log(
+            job_client,
+            job_id,
+            "WARN: no JUnit reports found; the module may have no tests\n",
+        )
+        .await;
+    }
+
+    let mut outputs = vec![
+        metric("duration_ms", exit.duration_ms),
+        metric("test_kind", test_kind_label(tests.kind)),
+        metric("tests_total", counts.total),
+        metric("tests_passed", counts.passed()),
+        metric("tests_failed", counts.failed),
+        metric("tests_skipped", counts.skipped),
+        metric("test_report_count", artifacts.len()),
+        metric("variant", &plan.variant.label),
+    ];
+    if let Some(module) = plan.module.as_ref() {
+        outputs.push(metric("module", module));
+    }
+    outputs.push(metric("tasks", plan.tasks.join(" ")));
+    if let Some(dir) = reports.first().and_then(|path| path.parent()) {
+        outputs.push(metric("test_report_dir", dir.display()));
+    }
+    let _ = publish_completed(job_client, job_id, "Tests passed", outputs).await;
+}
+
+fn signable_artifact_type(path: &Path) -> Option<ArtifactType> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "apk" => Some(ArtifactType::Apk),
+        "aab" => Some(ArtifactType::Aab),
+        _ => None,
+    }
+}
+
+/// keytool from the active toolchain set's JDK, or from `PATH`.
+async fn keytool_path() -> PathBuf {
+    let java_home = resolve_toolchain_env(None)
+        .await
+        .ok()
+        .and_then(|toolchain| toolchain.java_home);
+    jdk_tool(java_home.as_deref(), "keytool")
+}
+
+/// Stores a new keystore's passwords in the vault through JobService, which
+/// owns it and masks the values in job logs, then records the keystore. When
+/// a step fails, the secrets stored so far and the keystore file are removed.
+async fn register_keystore(
+    keystores: &mut KeystoreState,
+    record: KeystoreRecord,
+    passwords: &KeystorePasswords,
+) -> Result<KeystoreInfo, Status> {
+    let mut client = match connect_job().await {
+        Ok(client) => client,
+        Err(err) => {
+            let _ = fs::remove_file(&record.path);
+            return Err(err);
+        }
+    };
+    let mut stored = Vec::new();
+    let mut result = Ok(());
+    for (name, value, what) in [
+        (&record.store_password_secret, &passwords.store, "Store"),
+        (&record.key_password_secret, &passwords.key, "Key"),
+    ] {
+        result = client
+            .set_secret(SetSecretRequest {
+                name: name.clone(),
+                value: value.clone(),
+                description: format!("{what} password of keystore {}", record.name),
+            })
+            .await
+            .map(|_| stored.push(name));
+        if result.is_err() {
+            break;
+        }
+    }
+    if result.is_ok() {
+        keystores.insert(record.clone());
+        result = save_keystores(keystores).map_err(|err| {
+            keystores.remove(&record.keystore_id);
+            Status::internal(format!("failed to persist keystores: {err}"))
+        });
+    }
+    if let Err(err) = result {
+        for name in stored {
+            if let Err(err) = client
+                .delete_secret(DeleteSecretRequest { name: name.clone() })
+                .await
+            {
+                warn!("failed to delete secret {name}: {err}");
+            }
+        }
+        let _ = fs::remove_file(&record.path);
+        return Err(err);
+    }
+    Ok(record.to_proto())
+}
+
+/// What a build.sign job signs and with which keystore.
+struct SignJob {
+    project_id: String,
+    input: PathBuf,
+    output: PathBuf,
+    artifact_type: ArtifactType,
+    keystore: KeystoreRecord,
+    run_id: String,
+}
+
+/// Runs a signing tool with its command line and output in the job log.
+/// Passwords only travel through `env`.
+async fn run_signing_tool(
+    client: &mut JobServiceClient<Channel>,
+    job_id: &str,
+    program: &Path,
+    args: &[String],
+    env: &[(String, String)],
+) -> Result<String, ErrorDetail> {
+    let _ = publish_log(
+        client,
+        job_id,
+        &format!("$ {} {}\n", program.display(), args.join(" ")),
+    )
+    .await;
+    let result = signing::run_tool(program, args, env).await;
+    let output = match &result {
+        Ok(output) => output,
+        Err(err) => err,
+    };
+    if !output.trim().is_empty() {
+        let _ = publish_log(client, job_id, &format!("{}\n", output.trim_end())).await;
+    }
+    let tool = program
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    result.map_err(|err| {
+        job_error_detail(
+            ErrorCode::SigningFailed,
+            &format!("{tool} failed"),
+            err,
+            job_id,
+        )
+    })
+}
+
+/// zipalign, apksigner sign with v2 and v3 enabled, then apksigner verify.
+async fn sign_apk(
+    client: &mut JobServiceClient<Channel>,
+    job_id: &str,
+    job: &SignJob,
+    toolchain: &ToolchainEnv,
+    env: &[(String, String)],
+) -> Result<ApkVerification, ErrorDetail> {
+    let Some(tools) = latest_build_tools(&toolchain.sdk_path) else {
+        return Err(job_error_detail(
+            ErrorCode::ToolchainNotConfigured,
+            "no build tools with zipalign and apksigner in the SDK",
+            format!(
+                "no build-tools/<version> under {} has zipalign and apksigner",
+                toolchain.sdk_path.display()
+            ),
+            job_id,
+        ));
+    };
+    let _ = publish_log(
+        client,
+        job_id,
+        &format!("Using build tools {}\n", tools.dir.display()),
+    )
+    .await;
+
+    let aligned = job.output.with_extension("aligned.apk");
+    let _aligned_guard = TempFileGuard {
+        path: aligned.clone(),
+    };
+    let zipalign_args = vec![
+        "-f".to_string(),
+        "-p".to_string(),
+        "4".to_string(),
+        job.input.display().to_string(),
+        aligned.display().to_string(),
+    ];
+    run_signing_tool(client, job_id, &tools.zipalign, &zipalign_args, env).await?;
+    let sign_args = apksigner_sign_args(&job.keystore, &aligned, &job.output);
+    run_signing_tool(client, job_id, &tools.apksigner, &sign_args, env).await?;
+    let verify_args = vec![
+        "verify".to_string(),
+        "--verbose".to_string(),
+        "--print-certs".to_string(),
+        job.output.display().to_string(),
+    ];
+    let output = run_signing_tool(client, job_id, &tools.apksigner, &verify_args, env).await?;
+    let verification = parse_apksigner_verify(&output);
+    if !verification.has_v2_or_v3() {
+        return Err(job_error_detail(
+            ErrorCode::SigningFailed,
+            "signed APK does not verify with the v2 or v3 signature scheme",
+            output,
+            job_id,
+        ));
+    }
+    Ok(verification)
+}
+
+/// jarsigner, then jarsigner -verify; bundles only carry a JAR signature.
+async fn sign_bundle(
+    client: &mut JobServiceClient<Channel>,
+    job_id: &str,
+    job: &SignJob,
+    toolchain: &ToolchainEnv,
+    env: &[(String, String)],
+) -> Result<ApkVerification, ErrorDetail> {
+    let jarsigner = jdk_tool(toolchain.java_home.as_deref(), "jarsigner");
+    let keytool = jdk_tool(toolchain.java_home.as_deref(), "keytool");
+    let sign_args = jarsigner_sign_args(&job.keystore, &job.input, &job.output);
+    run_signing_tool(client, job_id, &jarsigner, &sign_args, env).await?;
+    let output = run_signing_tool(
+        client,
+        job_id,
+        &jarsigner,
+        &jarsigner_verify_args(&job.output),
+        env,
+    )
+    .await?;
+    if !output.contains("jar verified") {
+        return Err(job_error_detail(
+            ErrorCode::SigningFailed,
+            "signed bundle does not verify",
+            output,
+            job_id,
+        ));
+    }
+    let printed = run_signing_tool(
+        client,
+        job_id,
+        &keytool,
+        &keytool_printcert_args(&job.output),
+        env,
+    )
+    .await?;
+    let digest = parse_printcert_sha256(&printed).ok_or_else(|| {
+        job_error_detail(
+            ErrorCode::SigningFailed,
+            "signer certificate of the bundle not found",
+            printed,
+            job_id,
+        )
+    })?;
+    Ok(ApkVerification {
+        schemes: vec!["jar".into()],
+        signer_cert_sha256: vec![digest],
+    })
+}
+
+fn set_metadata(metadata: &mut Vec<KeyValue>, key: &str, value: &str) {
+    metadata.retain(|item| item.key != key);
+    metadata.push(metric(key, value));
+}
+
+async fn run_sign_job(state: Arc<Mutex<BuildState>>, job_id: String, job: SignJob) {
+    let mut job_client = match connect_job().await {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("sign job {job_id}: failed to connect job service: {err}");
+            return;
+        }
+    };
+
+    let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
+        let _ = publish_log(&mut job_client, &job_id, "Signing cancelled before start\n").await;
+        return;
+    }
+
+    let _ = publish_state(&mut job_client, &job_id, JobState::Running).await;
+    let _ = publish_log(
+        &mut job_client,
+        &job_id,
+        &format!(
+            "Signing {} with keystore {} (alias {})\n",
+            job.input.display(),
+            job.keystore.name,
+            job.keystore.alias
+        ),
+    )
+    .await;
+
+    let project_set_id = if job.project_id.is_empty() {
+        None
+    } else {
+        match resolve_project(&job.project_id).await {
+            Ok((_, set_id)) => set_id,
+            Err(err) => {
+                let detail = job_error_detail(
+                    project_error_code(&err),
+                    "project resolution failed",
+                    err.message().to_string(),
+                    &job_id,
+                );
+                let _ = publish_failed(&mut job_client, &job_id, detail).await;
+                return;
+            }
+        }
+    };
+    let toolchain = match resolve_toolchain_env(project_set_id.as_deref()).await {
+        Ok(toolchain) => toolchain,
+        Err(err) => {
+            let _ = publish_failed(&mut job_client, &job_id, err.into_detail(&job_id)).await;
+            return;
+        }
+    };
+    if !Path::new(&job.keystore.path).is_file() {
+        let detail = job_error_detail(
+            ErrorCode::NotFound,
+            "keystore file is missing",
+            format!("{} does not exist", job.keystore.path),
+            &job_id,
+        );
+        let _ = publish_failed(&mut job_client, &job_id, detail).await;
+        return;
+    }
+    let passwords = match KeystorePasswords::resolve(&job.keystore) {
+        Ok(passwords) => passwords,
+        Err(err) => {
+            let detail = job_error_detail(
+                ErrorCode::SecretUnavailable,
+                "keystore passwords unavailable",
+                err,
+                &job_id,
+            );
+            let _ = publish_failed(&mut job_client, &job_id, detail).await;
+            return;
+        }
+    };
+
+    // apksigner is a script that runs the `java` on PATH.
+    let mut env = toolchain.vars();
+    if let Some(java_home) = toolchain.java_home.as_ref() {
+        let mut paths = vec![java_home.join("bin")];
+        if let Some(path) = std::env::var_os("PATH") {
+            paths.extend(std::env::split_paths(&path));
+        }
+        if let Ok(path) = std::env::join_paths(paths) {
+            env.push(("PATH".to_string(), path.to_string_lossy().to_string()));
+        }
+    }
+    env.extend(passwords.env());
+
+    if *cancel_rx.borrow() {
+        let _ = publish_log(&mut job_client, &job_id, "Signing cancelled\n").await;
+        return;
+    }
+
+    let signed = match job.artifact_type {
+        ArtifactType::Aab => sign_bundle(&mut job_client, &job_id, &job, &toolchain, &env).await,
+        _ => sign_apk(&mut job_client, &job_id, &job, &toolchain, &env).await,
+    };
+    let verification = match signed {
+        Ok(verification) => verification,
+        Err(detail) => {
+            let _ = publish_failed(&mut job_client, &job_id, detail).await;
+            return;
+        }
+    };
+    let signer_cert_sha256 = verification
+        .signer_cert_sha256
+        .first()
+        .cloned()
+        .unwrap_or_default();
+    if !job.keystore.cert_sha256.is_empty() && signer_cert_sha256 != job.keystore.cert_sha256 {
+        let detail = job_error_detail(
+            ErrorCode::SigningFailed,
+            "signer certificate does not match the keystore",
+            format!(
+                "signed with {signer_cert_sha256}, keystore {} has {}",
+                job.keystore.name, job.keystore.cert_sha256
+            ),
+            &job_id,
+        );
+        let _ = publish_failed(&mut job_client, &job_id, detail).await;
+        return;
+    }
+
+    let (size_bytes, sha256) = match fs::metadata(&job.output)
+        .and_then(|meta| sha256_file(&job.output).map(|sha256| (meta.len(), sha256)))
+    {
+        Ok(value) => value,
+        Err(err) => {
+            let detail = job_error_detail(
+                ErrorCode::Internal,
+                "signed artifact unreadable",
+                format!("{}: {err}", job.output.display()),
+                &job_id,
+            );
+            let _ = publish_failed(&mut job_client, &job_id, detail).await;
+            return;
+        }
+    };
+    let input_path = job.input.to_string_lossy().to_string();
+    let mut artifact = Artifact {
+        name: job
+            .output
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: job.output.to_string_lossy().to_string(),
+        size_bytes,
+        sha256,
+        metadata: Vec::new(),
+        r#type: job.artifact_type as i32,
+    };
+    let schemes = verification.schemes.join(",");
+
+    // The signed artifact joins the build that produced the input, keeping
+    // its variant and module metadata.
+    {
+        let mut st = state.lock().await;
+        let source = st.records.iter().enumerate().find_map(|(index, record)| {
+            record
+                .artifacts
+                .iter()
+                .find(|item| item.path == input_path)
+                .map(|item| (index, item.clone().into_proto()))
+        });
+        if let Some((_, source)) = source.as_ref() {
+            artifact.metadata = source.metadata.clone();
+        }
+        for (key, value) in [
+            ("signed_from", input_path.as_str()),
+            ("keystore_id", job.keystore.keystore_id.as_str()),
+            ("key_alias", job.keystore.alias.as_str()),
+            ("signer_cert_sha256", signer_cert_sha256.as_str()),
+            ("signature_schemes", schemes.as_str()),
+        ] {
+            set_metadata(&mut artifact.metadata, key, value);
+        }
+        if let Some((index, _)) = source {
+            let record = &mut st.records[index];
+            record.artifacts.retain(|item| item.path != artifact.path);
+            record.artifacts.push(ArtifactRecord::from_proto(&artifact));
+            save_state_best_effort(&st);
+        }
+    }
+
+    let mut outputs = vec![
+        metric("artifact_path", &artifact.path),
+        metric("artifact_sha256", &artifact.sha256),
+        metric("signer_cert_sha256", &signer_cert_sha256),
+        metric("signature_schemes", &schemes),
+        metric("keystore_id", &job.keystore.keystore_id),
+    ];
+    outputs.extend(toolchain.metadata());
+    let _ = publish_log(
+        &mut job_client,
+        &job_id,
+        &format!(
+            "Signed {} (schemes {schemes}; signer SHA-256 {signer_cert_sha256})\n",
+            artifact.path
+        ),
+    )
+    .await;
+    upsert_run_outputs_best_effort(&job.run_id, &job_id, std::slice::from_ref(&artifact)).await;
+    let _ = publish_completed(&mut job_client, &job_id, "Artifact signed", outputs).await;
+}
+
+#[tonic::async_trait]
+impl BuildService for Svc {
+    async fn build(
+        &self,
+        request: Request<BuildRequest>,
+    ) -> Result<Response<BuildResponse>, Status> {
+        let mut req = request.into_inner();
+        let project_id = req
+            .project_id
+            .as_ref()
+            .map(|id| id.value.trim())
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| Status::invalid_argument("project_id is required"))?
+            .to_string();
+        req.project_id = Some(Id {
+            value: project_id.clone(),
+        });
+
+        let plan = build_plan_for_request(&req)?;
+        let mut job_client = connect_job().await?;
+        let job_id = req
+            .job_id
+            .as_ref()
+            .map(|id| id.value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_default();
+        let correlation_id = req.correlation_id.trim();
+        let job_id = if job_id.is_empty() {
+            let mut params = vec![
+                KeyValue {
+                    key: "variant".into(),
+                    value: plan.variant.label.clone(),
+                },
                 KeyValue {
                     key: "clean_first".into(),
                     value: req.clean_first.to_string(),
@@ -2675,6 +3620,7 @@ impl BuildService for Svc {
                 Some(Id {
                     value: project_id.clone(),
                 }),
+                project_toolchain_set_id(&project_id).await,
                 req.run_id.clone(),
             )
             .await?
@@ -2690,6 +3636,70 @@ impl BuildService for Svc {
         }))
     }
 
+    async fn run_tests(
+        &self,
+        request: Request<RunTestsRequest>,
+    ) -> Result<Response<RunTestsResponse>, Status> {
+        let mut req = request.into_inner();
+        let project_id = req
+            .project_id
+            .as_ref()
+            .map(|id| id.value.trim())
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| Status::invalid_argument("project_id is required"))?
+            .to_string();
+        req.project_id = Some(Id {
+            value: project_id.clone(),
+        });
+
+        let (build_req, plan) = test_plan_for_request(&req)?;
+        let tests = plan.tests.clone().unwrap_or(TestRun {
+            kind: TestKind::Unit,
+            device_serial: String::new(),
+        });
+        let mut job_client = connect_job().await?;
+        let job_id = req
+            .job_id
+            .as_ref()
+            .map(|id| id.value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_default();
+        let job_id = if job_id.is_empty() {
+            let mut params = vec![
+                metric("variant", &plan.variant.label),
+                metric("test_kind", test_kind_label(tests.kind)),
+                metric("tasks", plan.tasks.join(" ")),
+            ];
+            if let Some(module) = plan.module.as_ref() {
+                params.push(metric("module", module));
+            }
+            if !tests.device_serial.is_empty() {
+                params.push(metric("device_serial", &tests.device_serial));
+            }
+            start_job(
+                &mut job_client,
+                "build.test",
+                params,
+                req.correlation_id.trim(),
+                Some(Id {
+                    value: project_id.clone(),
+                }),
+                project_toolchain_set_id(&project_id).await,
+                req.run_id.clone(),
+            )
+            .await?
+        } else {
+            job_id
+        };
+
+        let state = self.state.clone();
+        tokio::spawn(run_build_job(state, job_id.clone(), build_req, plan));
+
+        Ok(Response::new(RunTestsResponse {
+            job_id: Some(Id { value: job_id }),
+        }))
+    }
+
     async fn list_artifacts(
         &self,
         request: Request<ListArtifactsRequest>,
@@ -2706,6 +3716,8 @@ impl BuildService for Svc {
                 let st = self.state.lock().await;
                 find_latest_record(&st, &project_id, &query).cloned()
             };
+            // Recorded artifacts carry the inspection taken when the build
+            // finished, so listing them opens no file.
             if let Some(record) = record {
                 let artifacts = record
                     .artifacts
@@ -2718,11 +3730,458 @@ impl BuildService for Svc {
         }
 
         let project_path = resolve_project_path(&project_id).await?;
-        let artifacts = collect_artifacts(&project_path, &query, None);
+        let artifacts = scan_artifacts(project_path, query, None).await;
 
         Ok(Response::new(ListArtifactsResponse { artifacts }))
     }
 
+    async fn inspect_artifact(
+        &self,
+        request: Request<InspectArtifactRequest>,
+    ) -> Result<Response<InspectArtifactResponse>, Status> {
+        let req = request.into_inner();
+        if req.artifact_path.trim().is_empty() {
+            return Err(Status::invalid_argument("artifact_path is required"));
+        }
+        let path = expand_user(req.artifact_path.trim());
+        if !path.is_file() {
+            return Err(Status::not_found(format!(
+                "artifact not found: {}",
+                path.display()
+            )));
+        }
+        // Parsing and hashing read the whole file, so they run off the
+        // async runtime.
+        let scanned = {
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || {
+                inspect_artifact(&path).map(|inspection| {
+                    let size_bytes = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+                    (inspection, size_bytes, sha256_file(&path))
+                })
+            })
+            .await
+            .map_err(|err| Status::internal(format!("artifact inspection failed: {err}")))?
+        };
+        let (inspection, size_bytes, sha256) = scanned.map_err(|err| {
+            Status::invalid_argument(format!("cannot inspect {}: {err}", path.display()))
+        })?;
+        let sha256 = sha256
+            .map_err(|err| Status::internal(format!("failed to hash {}: {err}", path.display())))?;
+        let path_str = path.to_string_lossy().to_string();
+
+        // Recorded artifacts keep their build metadata (module, variant,
+        // signing); the inspection's entries are refreshed from the file.
+        let mut metadata = {
+            let st = self.state.lock().await;
+            st.records
+                .iter()
+                .flat_map(|record| record.artifacts.iter())
+                .find(|item| item.path == path_str)
+                .map(|item| item.clone().into_proto().metadata)
+                .unwrap_or_default()
+        };
+        for entry in inspection_metadata(&inspection) {
+            set_metadata(&mut metadata, &entry.key, &entry.value);
+        }
+        let artifact = Artifact {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path_str,
+            size_bytes,
+            sha256,
+            metadata,
+            r#type: inspection.r#type,
+        };
+        Ok(Response::new(InspectArtifactResponse {
+            artifact: Some(artifact),
+            inspection: Some(inspection),
+        }))
+    }
+
+    async fn get_build_report(
+        &self,
+        request: Request<GetBuildReportRequest>,
+    ) -> Result<Response<GetBuildReportResponse>, Status> {
+        let req = request.into_inner();
+        let job_id = req
+            .job_id
+            .as_ref()
+            .map(|id| id.value.trim().to_string())
+            .unwrap_or_default();
+        let project_id = req
+            .project_id
+            .as_ref()
+            .map(|id| id.value.trim().to_string())
+            .unwrap_or_default();
+        if job_id.is_empty() && project_id.is_empty() {
+            return Err(Status::invalid_argument("job_id or project_id is required"));
+        }
+        let record = {
+            let st = self.state.lock().await;
+            st.records
+                .iter()
+                .find(|record| {
+                    if job_id.is_empty() {
+                        record.project_id == project_id
+                    } else {
+                        record.job_id == job_id
+                    }
+                })
+                .cloned()
+        };
+        let Some(record) = record else {
+            return Err(Status::not_found(if job_id.is_empty() {
+                format!("no recorded build for project {project_id}")
+            } else {
+                format!("no recorded build for job {job_id}")
+            }));
+        };
+
+        let slowest_limit = match req.slowest_limit {
+            0 => DEFAULT_SLOWEST_LIMIT,
+            limit => limit as usize,
+        };
+        let mut report = build_report(&record.task_report, slowest_limit);
+        report.variant_name = record_variant_label(&record).unwrap_or_default();
+        report.job_id = Some(Id {
+            value: record.job_id,
+        });
+        report.project_id = Some(Id {
+            value: record.project_id,
+        });
+        report.module = record.module;
+        report.created_at_unix_millis = record.created_at_unix_millis;
+        report.failed = record.failed;
+        Ok(Response::new(GetBuildReportResponse {
+            report: Some(report),
+        }))
+    }
+
+    async fn create_keystore(
+        &self,
+        request: Request<CreateKeystoreRequest>,
+    ) -> Result<Response<CreateKeystoreResponse>, Status> {
+        let req = request.into_inner();
+        let name = req.name.trim();
+        if name.is_empty() {
+            return Err(Status::invalid_argument("name is required"));
+        }
+        let alias = match req.alias.trim() {
+            "" => DEFAULT_KEY_ALIAS,
+            alias => alias,
+        };
+        let distinguished_name = match req.distinguished_name.trim() {
+            "" => format!("CN={name}"),
+            dname => dname.to_string(),
+        };
+        let validity_days = match req.validity_days {
+            0 => DEFAULT_VALIDITY_DAYS,
+            days => days,
+        };
+        let password = if req.store_password.is_empty() {
+            Uuid::new_v4().simple().to_string()
+        } else {
+            req.store_password
+        };
+        if password.chars().count() < MIN_KEYSTORE_PASSWORD_LEN {
+            return Err(Status::invalid_argument(format!(
+                "store_password must be at least {MIN_KEYSTORE_PASSWORD_LEN} characters"
+            )));
+        }
+
+        let mut keystores = self.keystores.lock().await;
+        if keystores.contains_name(name) {
+            return Err(Status::already_exists(format!(
+                "keystore {name} already exists"
+            )));
+        }
+        let keystore_id = format!("ks-{}", Uuid::new_v4());
+        let path = keystore_file_path(&keystore_id, "p12");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| Status::internal(format!("keystore directory: {err}")))?;
+        }
+        // PKCS12 keys share the store password.
+        let passwords = KeystorePasswords {
+            store: password.clone(),
+            key: password,
+        };
+        let keytool = keytool_path().await;
+        let generated = async {
+            signing::run_tool(
+                &keytool,
+                &keytool_generate_args(&path, alias, &distinguished_name, validity_days),
+                &passwords.env(),
+            )
+            .await?;
+            let listing = signing::run_tool(
+                &keytool,
+                &keytool_list_args(&path, Some(alias)),
+                &passwords.env(),
+            )
+            .await?;
+            parse_keytool_list(&listing)
+                .ok_or_else(|| format!("no private key entry in {}", path.display()))
+        }
+        .await;
+        let entry = generated.map_err(|err| {
+            let _ = fs::remove_file(&path);
+            Status::failed_precondition(format!("keytool failed: {err}"))
+        })?;
+
+        let record = KeystoreRecord {
+            store_password_secret: store_password_secret(&keystore_id),
+            key_password_secret: key_password_secret(&keystore_id),
+            keystore_id,
+            name: name.to_string(),
+            alias: entry.alias,
+            path: path.to_string_lossy().to_string(),
+            cert_sha256: entry.cert_sha256,
+            subject: entry.subject,
+            imported: false,
+            created_at_unix_millis: now_millis(),
+        };
+        let keystore = register_keystore(&mut keystores, record, &passwords).await?;
+        Ok(Response::new(CreateKeystoreResponse {
+            keystore: Some(keystore),
+        }))
+    }
+
+    async fn import_keystore(
+        &self,
+        request: Request<ImportKeystoreRequest>,
+    ) -> Result<Response<ImportKeystoreResponse>, Status> {
+        let req = request.into_inner();
+        let name = req.name.trim();
+        if name.is_empty() {
+            return Err(Status::invalid_argument("name is required"));
+        }
+        if req.path.trim().is_empty() {
+            return Err(Status::invalid_argument("path is required"));
+        }
+        if req.store_password.is_empty() {
+            return Err(Status::invalid_argument("store_password is required"));
+        }
+        let source = expand_user(req.path.trim());
+        if !source.is_file() {
+            return Err(Status::not_found(format!(
+                "keystore not found: {}",
+                source.display()
+            )));
+        }
+        let passwords = KeystorePasswords {
+            key: if req.key_password.is_empty() {
+                req.store_password.clone()
+            } else {
+                req.key_password
+            },
+            store: req.store_password,
+        };
+        let alias = Some(req.alias.trim()).filter(|alias| !alias.is_empty());
+
+        let mut keystores = self.keystores.lock().await;
+        if keystores.contains_name(name) {
+            return Err(Status::already_exists(format!(
+                "keystore {name} already exists"
+            )));
+        }
+        let keystore_id = format!("ks-{}", Uuid::new_v4());
+        let extension = source
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .filter(|ext| !ext.is_empty() && ext.chars().all(|ch| ch.is_ascii_alphanumeric()))
+            .unwrap_or("keystore")
+            .to_ascii_lowercase();
+        let path = keystore_file_path(&keystore_id, &extension);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| Status::internal(format!("keystore directory: {err}")))?;
+        }
+        fs::copy(&source, &path)
+            .map_err(|err| Status::internal(format!("failed to copy keystore: {err}")))?;
+        let listing = signing::run_tool(
+            &keytool_path().await,
+            &keytool_list_args(&path, alias),
+            &passwords.env(),
+        )
+        .await;
+        let entry = listing
+            .and_then(|listing| {
+                parse_keytool_list(&listing)
+                    .ok_or_else(|| "the keystore has no private key entry".to_string())
+            })
+            .map_err(|err| {
+                let _ = fs::remove_file(&path);
+                Status::invalid_argument(format!("cannot read keystore: {err}"))
+            })?;
+
+        let record = KeystoreRecord {
+            store_password_secret: store_password_secret(&keystore_id),
+            key_password_secret: key_password_secret(&keystore_id),
+            keystore_id,
+            name: name.to_string(),
+            alias: entry.alias,
+            path: path.to_string_lossy().to_string(),
+            cert_sha256: entry.cert_sha256,
+            subject: entry.subject,
+            imported: true,
+            created_at_unix_millis: now_millis(),
+        };
+        let keystore = register_keystore(&mut keystores, record, &passwords).await?;
+        Ok(Response::new(ImportKeystoreResponse {
+            keystore: Some(keystore),
+        }))
+    }
+
+    async fn list_keystores(
+        &self,
+        _request: Request<ListKeystoresRequest>,
+    ) -> Result<Response<ListKeystoresResponse>, Status> {
+        let keystores = self.keystores.lock().await.list();
+        Ok(Response::new(ListKeystoresResponse { keystores }))
+    }
+
+    async fn delete_keystore(
+        &self,
+        request: Request<DeleteKeystoreRequest>,
+    ) -> Result<Response<DeleteKeystoreResponse>, Status> {
+        let keystore_id = request
+            .into_inner()
+            .keystore_id
+            .map(|id| id.value.trim().to_string())
+            .unwrap_or_default();
+        if keystore_id.is_empty() {
+            return Err(Status::invalid_argument("keystore_id is required"));
+        }
+        let mut keystores = self.keystores.lock().await;
+        let Some(record) = keystores.remove(&keystore_id) else {
+            return Ok(Response::new(DeleteKeystoreResponse { deleted: false }));
+        };
+        save_keystores(&keystores)
+            .map_err(|err| Status::internal(format!("failed to persist keystores: {err}")))?;
+        if let Err(err) = fs::remove_file(&record.path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("failed to remove keystore {}: {err}", record.path);
+            }
+        }
+        match connect_job().await {
+            Ok(mut client) => {
+                for name in [&record.store_password_secret, &record.key_password_secret] {
+                    if let Err(err) = client
+                        .delete_secret(DeleteSecretRequest { name: name.clone() })
+                        .await
+                    {
+                        warn!("failed to delete secret {name}: {err}");
+                    }
+                }
+            }
+            Err(err) => warn!("keystore secrets of {keystore_id} not deleted: {err}"),
+        }
+        Ok(Response::new(DeleteKeystoreResponse { deleted: true }))
+    }
+
+    async fn sign_artifact(
+        &self,
+        request: Request<SignArtifactRequest>,
+    ) -> Result<Response<SignArtifactResponse>, Status> {
+        let req = request.into_inner();
+        if req.artifact_path.trim().is_empty() {
+            return Err(Status::invalid_argument("artifact_path is required"));
+        }
+        let input = expand_user(req.artifact_path.trim());
+        if !input.is_file() {
+            return Err(Status::not_found(format!(
+                "artifact not found: {}",
+                input.display()
+            )));
+        }
+        let artifact_type = signable_artifact_type(&input)
+            .ok_or_else(|| Status::invalid_argument("only .apk and .aab files can be signed"))?;
+        let keystore_id = req
+            .keystore_id
+            .as_ref()
+            .map(|id| id.value.trim())
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| Status::invalid_argument("keystore_id is required"))?
+            .to_string();
+        let keystore = self
+            .keystores
+            .lock()
+            .await
+            .find(&keystore_id)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("keystore not found: {keystore_id}")))?;
+        let output = match req.output_path.trim() {
+            "" => signed_output_path(&input),
+            path => expand_user(path),
+        };
+        if output == input {
+            return Err(Status::invalid_argument(
+                "output_path must differ from artifact_path",
+            ));
+        }
+        let project_id = req
+            .project_id
+            .as_ref()
+            .map(|id| id.value.trim().to_string())
+            .unwrap_or_default();
+        let run_id = req
+            .run_id
+            .as_ref()
+            .map(|id| id.value.trim().to_string())
+            .unwrap_or_default();
+
+        let job_id = req
+            .job_id
+            .as_ref()
+            .map(|id| id.value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_default();
+        let job_id = if job_id.is_empty() {
+            let toolchain_set_id = if project_id.is_empty() {
+                job_toolchain_set_id(None).await
+            } else {
+                project_toolchain_set_id(&project_id).await
+            };
+            let mut job_client = connect_job().await?;
+            start_job(
+                &mut job_client,
+                "build.sign",
+                vec![
+                    metric("artifact_path", input.display()),
+                    metric("keystore_id", &keystore_id),
+                ],
+                req.correlation_id.trim(),
+                Some(Id {
+                    value: project_id.clone(),
+                })
+                .filter(|id| !id.value.is_empty()),
+                toolchain_set_id,
+                req.run_id.clone(),
+            )
+            .await?
+        } else {
+            job_id
+        };
+
+        let job = SignJob {
+            project_id,
+            input,
+            output,
+            artifact_type,
+            keystore,
+            run_id,
+        };
+        tokio::spawn(run_sign_job(self.state.clone(), job_id.clone(), job));
+
+        Ok(Response::new(SignArtifactResponse {
+            job_id: Some(Id { value: job_id }),
+        }))
+    }
+
     async fn reload_state(
         &self,
         _request: Request<ReloadStateRequest>,
@@ -2731,6 +4190,7 @@ impl BuildService for Svc {
         let count = state.records.len() as u32;
         let mut st = self.state.lock().await;
         *st = state;
+        *self.keystores.lock().await = load_keystores();
         Ok(Response::new(ReloadStateResponse {
             ok: true,
             item_count: count,
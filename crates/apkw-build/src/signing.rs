@@ -0,0 +1,569 @@
+//! Managed signing keystores and the SDK and JDK tools that sign with them.
+//!
+//! Keystore files live under `keys/keystores/`, which state archives never
+//! include, and their descriptions in `state/keystores.json`. Passwords are
+//! kept in the secrets vault and handed to keytool, apksigner and jarsigner
+//! through environment variables, so they never appear on a command line.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use apkw_proto::apkw::v1::{Id, KeystoreInfo, Timestamp};
+use apkw_util::{
+    secrets::{keys_dir, SecretStore},
+    state_file_path, write_json_atomic,
+};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::warn;
+
+const KEYSTORES_FILE_NAME: &str = "keystores.json";
+const KEYSTORES_DIR: &str = "keystores";
+pub(crate) const DEFAULT_KEY_ALIAS: &str = "upload";
+pub(crate) const DEFAULT_VALIDITY_DAYS: u32 = 10_000;
+/// keytool rejects shorter store passwords.
+pub(crate) const MIN_KEYSTORE_PASSWORD_LEN: usize = 6;
+const STORE_PASS_ENV: &str = "APKW_KEYSTORE_PASS";
+const KEY_PASS_ENV: &str = "APKW_KEY_PASS";
+/// Keeps keytool and jarsigner output in the English the parsers expect.
+const JVM_ENGLISH: &str = "-J-Duser.language=en";
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct KeystoreState {
+    keystores: Vec<KeystoreRecord>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct KeystoreRecord {
+    pub(crate) keystore_id: String,
+    pub(crate) name: String,
+    pub(crate) alias: String,
+    pub(crate) path: String,
+    pub(crate) cert_sha256: String,
+    pub(crate) subject: String,
+    pub(crate) imported: bool,
+    pub(crate) created_at_unix_millis: i64,
+    pub(crate) store_password_secret: String,
+    pub(crate) key_password_secret: String,
+}
+
+impl KeystoreRecord {
+    pub(crate) fn to_proto(&self) -> KeystoreInfo {
+        KeystoreInfo {
+            keystore_id: Some(Id {
+                value: self.keystore_id.clone(),
+            }),
+            name: self.name.clone(),
+            alias: self.alias.clone(),
+            path: self.path.clone(),
+            cert_sha256: self.cert_sha256.clone(),
+            subject: self.subject.clone(),
+            imported: self.imported,
+            created_at: Some(Timestamp {
+                unix_millis: self.created_at_unix_millis,
+            }),
+            store_password_secret: self.store_password_secret.clone(),
+            key_password_secret: self.key_password_secret.clone(),
+        }
+    }
+}
+
+impl KeystoreState {
+    pub(crate) fn find(&self, keystore_id: &str) -> Option<&KeystoreRecord> {
+        self.keystores
+            .iter()
+            .find(|record| record.keystore_id == keystore_id)
+    }
+
+    pub(crate) fn list(&self) -> Vec<KeystoreInfo> {
+        self.keystores
+            .iter()
+            .map(KeystoreRecord::to_proto)
+            .collect()
+    }
+
+    pub(crate) fn contains_name(&self, name: &str) -> bool {
+        self.keystores
+            .iter()
+            .any(|record| record.name.eq_ignore_ascii_case(name))
+    }
+
+    pub(crate) fn insert(&mut self, record: KeystoreRecord) {
+        self.keystores.push(record);
+    }
+
+    pub(crate) fn remove(&mut self, keystore_id: &str) -> Option<KeystoreRecord> {
+        let index = self
+            .keystores
+            .iter()
+            .position(|record| record.keystore_id == keystore_id)?;
+        Some(self.keystores.remove(index))
+    }
+}
+
+pub(crate) fn load_keystores() -> KeystoreState {
+    let path = state_file_path(KEYSTORES_FILE_NAME);
+    match fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data).unwrap_or_else(|err| {
+            warn!("Failed to parse {}: {}", path.display(), err);
+            KeystoreState::default()
+        }),
+        Err(err) => {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("Failed to read {}: {}", path.display(), err);
+            }
+            KeystoreState::default()
+        }
+    }
+}
+
+pub(crate) fn save_keystores(state: &KeystoreState) -> io::Result<()> {
+    write_json_atomic(&state_file_path(KEYSTORES_FILE_NAME), state)
+}
+
+/// Where a managed keystore file is kept; `extension` follows the format.
+pub(crate) fn keystore_file_path(keystore_id: &str, extension: &str) -> PathBuf {
+    keys_dir()
+        .join(KEYSTORES_DIR)
+        .join(format!("{keystore_id}.{extension}"))
+}
+
+pub(crate) fn store_password_secret(keystore_id: &str) -> String {
+    format!("keystore.{keystore_id}.store")
+}
+
+pub(crate) fn key_password_secret(keystore_id: &str) -> String {
+    format!("keystore.{keystore_id}.key")
+}
+
+/// A keystore's passwords, read from the vault when a job signs.
+pub(crate) struct KeystorePasswords {
+    pub(crate) store: String,
+    pub(crate) key: String,
+}
+
+impl KeystorePasswords {
+    /// Environment the signing tools read the passwords from.
+    pub(crate) fn env(&self) -> Vec<(String, String)> {
+        vec![
+            (STORE_PASS_ENV.to_string(), self.store.clone()),
+            (KEY_PASS_ENV.to_string(), self.key.clone()),
+        ]
+    }
+
+    pub(crate) fn resolve(record: &KeystoreRecord) -> Result<Self, String> {
+        let store = SecretStore::load().map_err(|err| format!("secrets store: {err}"))?;
+        let get = |name: &str| -> Result<String, String> {
+            store
+                .get(name)
+                .map_err(|err| format!("secret {name}: {err}"))?
+                .ok_or_else(|| format!("secret {name} of keystore {} is missing", record.name))
+        };
+        Ok(Self {
+            store: get(&record.store_password_secret)?,
+            key: get(&record.key_password_secret)?,
+        })
+    }
+}
+
+/// The JDK tool `name` from `java_home`, or from `PATH` without one.
+pub(crate) fn jdk_tool(java_home: Option<&Path>, name: &str) -> PathBuf {
+    java_home
+        .map(|home| home.join("bin"))
+        .and_then(|bin| find_tool(&bin, name))
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// `name` in `dir`, with the host's executable suffix when it has one.
+fn find_tool(dir: &Path, name: &str) -> Option<PathBuf> {
+    [
+        name.to_string(),
+        format!("{name}.exe"),
+        format!("{name}.bat"),
+    ]
+    .into_iter()
+    .map(|file| dir.join(file))
+    .find(|path| path.is_file())
+}
+
+/// The newest stable `build-tools/<version>` of the SDK with both zipalign
+/// and apksigner, or the newest prerelease (`-rc1`) when no stable one has
+/// them.
+pub(crate) fn latest_build_tools(sdk: &Path) -> Option<BuildTools> {
+    let mut candidates: Vec<(bool, Vec<u32>, PathBuf)> = fs::read_dir(sdk.join("build-tools"))
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (numbers, suffix) = name.split_once('-').unwrap_or((&name, ""));
+            let version = numbers
+                .split('.')
+                .map(|part| part.parse::<u32>().ok())
+                .collect::<Option<Vec<_>>>()?;
+            Some((suffix.is_empty(), version, entry.path()))
+        })
+        .collect();
+    candidates.sort();
+    candidates.into_iter().rev().find_map(|(_, _, dir)| {
+        Some(BuildTools {
+            zipalign: find_tool(&dir, "zipalign")?,
+            apksigner: find_tool(&dir, "apksigner")?,
+            dir,
+        })
+    })
+}
+
+pub(crate) struct BuildTools {
+    pub(crate) dir: PathBuf,
+    pub(crate) zipalign: PathBuf,
+    pub(crate) apksigner: PathBuf,
+}
+
+/// `<name>-signed.<ext>` next to `input`, replacing an `-unsigned` suffix.
+pub(crate) fn signed_output_path(input: &Path) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem = stem.strip_suffix("-unsigned").unwrap_or(&stem);
+    let name = match input.extension() {
+        Some(ext) => format!("{stem}-signed.{}", ext.to_string_lossy()),
+        None => format!("{stem}-signed"),
+    };
+    input.with_file_name(name)
+}
+
+/// Runs a signing tool with `env` and returns its combined output, or the
+/// output with the exit status when it fails.
+pub(crate) async fn run_tool(
+    program: &Path,
+    args: &[String],
+    env: &[(String, String)],
+) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|err| format!("failed to run {}: {err}", program.display()))?;
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if output.status.success() {
+        Ok(text)
+    } else {
+        Err(format!(
+            "{} exited with {}\n{}",
+            program.display(),
+            output.status,
+            text.trim_end()
+        ))
+    }
+}
+
+pub(crate) fn keytool_generate_args(
+    path: &Path,
+    alias: &str,
+    distinguished_name: &str,
+    validity_days: u32,
+) -> Vec<String> {
+    [
+        JVM_ENGLISH,
+        "-genkeypair",
+        "-noprompt",
+        "-storetype",
+        "PKCS12",
+        "-keystore",
+        &path.display().to_string(),
+        "-alias",
+        alias,
+        "-keyalg",
+        "RSA",
+        "-keysize",
+        "2048",
+        "-validity",
+        &validity_days.to_string(),
+        "-dname",
+        distinguished_name,
+        "-storepass:env",
+        STORE_PASS_ENV,
+        "-keypass:env",
+        KEY_PASS_ENV,
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+pub(crate) fn keytool_list_args(path: &Path, alias: Option<&str>) -> Vec<String> {
+    let mut args: Vec<String> = [
+        JVM_ENGLISH,
+        "-list",
+        "-v",
+        "-keystore",
+        &path.display().to_string(),
+        "-storepass:env",
+        STORE_PASS_ENV,
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    if let Some(alias) = alias {
+        args.extend(["-alias".to_string(), alias.to_string()]);
+    }
+    args
+}
+
+pub(crate) fn keytool_printcert_args(jar: &Path) -> Vec<String> {
+    vec![
+        JVM_ENGLISH.into(),
+        "-printcert".into(),
+        "-jarfile".into(),
+        jar.display().to_string(),
+    ]
+}
+
+pub(crate) fn apksigner_sign_args(
+    keystore: &KeystoreRecord,
+    input: &Path,
+    output: &Path,
+) -> Vec<String> {
+    vec![
+        "sign".into(),
+        "--ks".into(),
+        keystore.path.clone(),
+        "--ks-key-alias".into(),
+        keystore.alias.clone(),
+        "--ks-pass".into(),
+        format!("env:{STORE_PASS_ENV}"),
+        "--key-pass".into(),
+        format!("env:{KEY_PASS_ENV}"),
+        "--v2-signing-enabled".into(),
+        "true".into(),
+        "--v3-signing-enabled".into(),
+        "true".into(),
+        "--out".into(),
+        output.display().to_string(),
+        input.display().to_string(),
+    ]
+}
+
+pub(crate) fn jarsigner_sign_args(
+    keystore: &KeystoreRecord,
+    input: &Path,
+    output: &Path,
+) -> Vec<String> {
+    vec![
+        JVM_ENGLISH.into(),
+        "-keystore".into(),
+        keystore.path.clone(),
+        "-storepass:env".into(),
+        STORE_PASS_ENV.into(),
+        "-keypass:env".into(),
+        KEY_PASS_ENV.into(),
+        "-digestalg".into(),
+        "SHA-256".into(),
+        "-signedjar".into(),
+        output.display().to_string(),
+        input.display().to_string(),
+        keystore.alias.clone(),
+    ]
+}
+
+pub(crate) fn jarsigner_verify_args(jar: &Path) -> Vec<String> {
+    vec![
+        JVM_ENGLISH.into(),
+        "-verify".into(),
+        jar.display().to_string(),
+    ]
+}
+
+/// The key entry keytool describes: its alias, owner and certificate digest.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct KeyEntry {
+    pub(crate) alias: String,
+    pub(crate) subject: String,
+    pub(crate) cert_sha256: String,
+}
+
+/// The first private key entry of `keytool -list -v` output.
+pub(crate) fn parse_keytool_list(output: &str) -> Option<KeyEntry> {
+    let mut entries: Vec<(KeyEntry, bool)> = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if let Some(alias) = line.strip_prefix("Alias name:") {
+            entries.push((
+                KeyEntry {
+                    alias: alias.trim().to_string(),
+                    ..Default::default()
+                },
+                false,
+            ));
+            continue;
+        }
+        let Some((entry, is_key)) = entries.last_mut() else {
+            continue;
+        };
+        if let Some(kind) = line.strip_prefix("Entry type:") {
+            *is_key = kind.trim() == "PrivateKeyEntry";
+        } else if let Some(owner) = line.strip_prefix("Owner:") {
+            if entry.subject.is_empty() {
+                entry.subject = owner.trim().to_string();
+            }
+        } else if let Some(digest) = line.strip_prefix("SHA256:") {
+            if entry.cert_sha256.is_empty() {
+                entry.cert_sha256 = normalize_fingerprint(digest);
+            }
+        }
+    }
+    entries
+        .into_iter()
+        .find(|(entry, is_key)| *is_key && !entry.cert_sha256.is_empty())
+        .map(|(entry, _)| entry)
+}
+
+/// The first certificate digest of `keytool -printcert` output.
+pub(crate) fn parse_printcert_sha256(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("SHA256:"))
+        .map(normalize_fingerprint)
+}
+
+/// `AB:CD:..` as lowercase hex without separators, as apksigner prints it.
+pub(crate) fn normalize_fingerprint(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .filter(char::is_ascii_hexdigit)
+        .map(|ch| ch.to_ascii_lowercase())
+        .collect()
+}
+
+/// What `apksigner verify --verbose --print-certs` reported.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ApkVerification {
+    /// Schemes that verified, e.g. `v2`, `v3`.
+    pub(crate) schemes: Vec<String>,
+    pub(crate) signer_cert_sha256: Vec<String>,
+}
+
+impl ApkVerification {
+    pub(crate) fn has_v2_or_v3(&self) -> bool {
+        self.schemes
+            .iter()
+            .any(|scheme| scheme == "v2" || scheme == "v3")
+    }
+}
+
+pub(crate) fn parse_apksigner_verify(output: &str) -> ApkVerification {
+    let mut verification = ApkVerification::default();
+    for line in output.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("Verified using ") {
+            let Some((scheme, result)) = rest.split_once(':') else {
+                continue;
+            };
+            let scheme = scheme.split_whitespace().next().unwrap_or_default();
+            if result.trim() == "true" && !scheme.is_empty() {
+                verification.schemes.push(scheme.to_string());
+            }
+        } else if let Some((label, digest)) = line.split_once(':') {
+            if label.starts_with("Signer #") && label.ends_with("certificate SHA-256 digest") {
+                let digest = normalize_fingerprint(digest);
+                if !verification.signer_cert_sha256.contains(&digest) {
+                    verification.signer_cert_sha256.push(digest);
+                }
+            }
+        }
+    }
+    verification
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_output_yields_schemes_and_certificate_digests() {
+        let verify = "\
+Verifies
+Verified using v1 scheme (JAR signing): false
+Verified using v2 scheme (APK Signature Scheme v2): true
+Verified using v3 scheme (APK Signature Scheme v3): true
+Verified using v3.1 scheme (APK Signature Scheme v3.1): false
+Verified using v4 scheme (APK Signature Scheme v4): false
+Number of signers: 1
+Signer #1 certificate DN: CN=Example
+Signer #1 certificate SHA-256 digest: 0A1B2C3D
+Signer #1 certificate SHA-1 digest: ffff
+";
+        let verification = parse_apksigner_verify(verify);
+        assert_eq!(verification.schemes, ["v2", "v3"]);
+        assert_eq!(verification.signer_cert_sha256, ["0a1b2c3d"]);
+        assert!(verification.has_v2_or_v3());
+        assert!(
+            !parse_apksigner_verify("Verified using v1 scheme (JAR signing): true").has_v2_or_v3()
+        );
+
+        let list = "\
+Keystore type: PKCS12
+
+Your keystore contains 2 entries
+
+Alias name: ca
+Entry type: trustedCertEntry
+Owner: CN=Root
+Certificate fingerprints:
+\t SHA256: 11:22
+
+Alias name: upload
+Entry type: PrivateKeyEntry
+Certificate chain length: 1
+Certificate[1]:
+Owner: CN=Example, O=Example Inc
+Issuer: CN=Example, O=Example Inc
+Certificate fingerprints:
+\t SHA1: AA:BB
+\t SHA256: 0A:1B:2C:3D
+";
+        assert_eq!(
+            parse_keytool_list(list),
+            Some(KeyEntry {
+                alias: "upload".into(),
+                subject: "CN=Example, O=Example Inc".into(),
+                cert_sha256: "0a1b2c3d".into(),
+            })
+        );
+        assert_eq!(
+            signed_output_path(Path::new("/out/app-release-unsigned.apk")),
+            Path::new("/out/app-release-signed.apk")
+        );
+    }
+
+    #[test]
+    fn build_tools_prefer_the_newest_stable_release() {
+        let sdk = std::env::temp_dir().join(format!("apkw-build-sdk-{}", uuid::Uuid::new_v4()));
+        for version in ["33.0.2", "34.0.0", "35.0.0-rc1", "tools"] {
+            let dir = sdk.join("build-tools").join(version);
+            fs::create_dir_all(&dir).unwrap();
+            for tool in ["zipalign", "apksigner"] {
+                fs::write(dir.join(tool), b"").unwrap();
+            }
+        }
+        let dir = |tools: BuildTools| tools.dir.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(latest_build_tools(&sdk).map(dir).as_deref(), Some("34.0.0"));
+
+        for version in ["33.0.2", "34.0.0"] {
+            fs::remove_file(sdk.join("build-tools").join(version).join("apksigner")).unwrap();
+        }
+        assert_eq!(
+            latest_build_tools(&sdk).map(dir).as_deref(),
+            Some("35.0.0-rc1")
+        );
+        let _ = fs::remove_dir_all(&sdk);
+    }
+}
@@ -0,0 +1,257 @@
+use apkw_proto::apkw::v1::{BuildReport, KeyValue, TaskExecution, TaskOutcome};
+use serde::{Deserialize, Serialize};
+
+/// Gradle property the init script reads the report path from.
+pub(crate) const TASK_REPORT_PROPERTY: &str = "apkw.taskReport";
+
+/// Slowest tasks returned when a request does not ask for a number.
+pub(crate) const DEFAULT_SLOWEST_LIMIT: usize = 10;
+
+/// Init script that appends one `PATH\tOUTCOME\tSTART_MS\tEND_MS` line per
+/// finished task to the file named by `-Papkw.taskReport`. It listens through
+/// a shared build service, which stays compatible with the configuration
+/// cache, and never fails the build when the listener cannot be registered.
+pub(crate) fn task_report_script() -> &'static str {
+    r#"
+import org.gradle.api.provider.Property
+import org.gradle.api.services.BuildService
+import org.gradle.api.services.BuildServiceParameters
+import org.gradle.build.event.BuildEventsListenerRegistry
+import org.gradle.tooling.events.FinishEvent
+import org.gradle.tooling.events.OperationCompletionListener
+import org.gradle.tooling.events.task.TaskFailureResult
+import org.gradle.tooling.events.task.TaskFinishEvent
+import org.gradle.tooling.events.task.TaskSkippedResult
+import org.gradle.tooling.events.task.TaskSuccessResult
+
+abstract class ApkwTaskReport implements BuildService<ApkwTaskReport.Params>, OperationCompletionListener {
+    interface Params extends BuildServiceParameters {
+        Property<String> getOutput()
+    }
+
+    @Override
+    void onFinish(FinishEvent event) {
+        if (!(event instanceof TaskFinishEvent)) {
+            return
+        }
+        def result = event.result
+        def outcome = "EXECUTED"
+        if (result instanceof TaskFailureResult) {
+            outcome = "FAILED"
+        } else if (result instanceof TaskSkippedResult) {
+            outcome = result.skipMessage ?: "SKIPPED"
+        } else if (result instanceof TaskSuccessResult) {
+            if (result.fromCache) {
+                outcome = "FROM-CACHE"
+            } else if (result.upToDate) {
+                outcome = "UP-TO-DATE"
+            }
+        }
+        def line = "${event.descriptor.taskPath}\t${outcome}\t${result.startTime}\t${result.endTime}\n"
+        synchronized (ApkwTaskReport) {
+            new File(parameters.output.get()).append(line)
+        }
+    }
+}
+
+def apkwTaskReport = gradle.startParameter.projectProperties["apkw.taskReport"]
+if (apkwTaskReport) {
+    try {
+        def service = gradle.sharedServices.registerIfAbsent("apkwTaskReport", ApkwTaskReport) {
+            parameters.output.set(apkwTaskReport)
+        }
+        gradle.services.get(BuildEventsListenerRegistry).onTaskCompletion(service)
+    } catch (Throwable err) {
+        println("APKW task report unavailable: ${err.message}")
+    }
+}
+"#
+}
+
+/// One finished task, as stored with the build record.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct TaskRecord {
+    pub(crate) path: String,
+    /// Gradle's label: `EXECUTED`, `UP-TO-DATE`, `FROM-CACHE`, `SKIPPED`,
+    /// `NO-SOURCE` or `FAILED`.
+    pub(crate) outcome: String,
+    pub(crate) started_at_unix_millis: i64,
+    pub(crate) duration_ms: u64,
+}
+
+impl TaskRecord {
+    fn outcome(&self) -> TaskOutcome {
+        match self.outcome.as_str() {
+            "EXECUTED" => TaskOutcome::Executed,
+            "UP-TO-DATE" => TaskOutcome::UpToDate,
+            "FROM-CACHE" => TaskOutcome::FromCache,
+            "NO-SOURCE" => TaskOutcome::NoSource,
+            "FAILED" => TaskOutcome::Failed,
+            "SKIPPED" => TaskOutcome::Skipped,
+            // Other skip messages (`ON-ERROR`, custom `onlyIf` reasons).
+            _ => TaskOutcome::Skipped,
+        }
+    }
+
+    fn to_proto(&self) -> TaskExecution {
+        TaskExecution {
+            path: self.path.clone(),
+            outcome: self.outcome() as i32,
+            duration_ms: self.duration_ms,
+            started_at_unix_millis: self.started_at_unix_millis,
+        }
+    }
+}
+
+/// Parses the init script's report; malformed lines are skipped.
+pub(crate) fn parse_task_report(raw: &str) -> Vec<TaskRecord> {
+    raw.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let path = fields.next()?.trim();
+            let outcome = fields.next()?.trim();
+            let start = fields.next()?.trim().parse::<i64>().ok()?;
+            let end = fields.next()?.trim().parse::<i64>().ok()?;
+            if path.is_empty() || outcome.is_empty() {
+                return None;
+            }
+            Some(TaskRecord {
+                path: path.to_string(),
+                outcome: outcome.to_string(),
+                started_at_unix_millis: start,
+                duration_ms: end.saturating_sub(start).max(0) as u64,
+            })
+        })
+        .collect()
+}
+
+/// Outcome counts of a build's tasks.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub(crate) struct TaskSummary {
+    pub(crate) executed: u32,
+    pub(crate) up_to_date: u32,
+    pub(crate) from_cache: u32,
+    /// Skipped for any reason, including `NO-SOURCE`.
+    pub(crate) skipped: u32,
+    pub(crate) failed: u32,
+    pub(crate) duration_ms: u64,
+}
+
+impl TaskSummary {
+    pub(crate) fn of(tasks: &[TaskRecord]) -> Self {
+        let mut summary = Self::default();
+        for task in tasks {
+            match task.outcome() {
+                TaskOutcome::Executed => summary.executed += 1,
+                TaskOutcome::UpToDate => summary.up_to_date += 1,
+                TaskOutcome::FromCache => summary.from_cache += 1,
+                TaskOutcome::Failed => summary.failed += 1,
+                _ => summary.skipped += 1,
+            }
+            summary.duration_ms += task.duration_ms;
+        }
+        summary
+    }
+
+    /// Share of the tasks that had work to do which Gradle avoided, either
+    /// UP-TO-DATE or FROM-CACHE; 0 when no task had work.
+    pub(crate) fn cache_hit_rate(&self) -> f64 {
+        let avoided = self.up_to_date + self.from_cache;
+        let work = avoided + self.executed + self.failed;
+        if work == 0 {
+            0.0
+        } else {
+            f64::from(avoided) / f64::from(work)
+        }
+    }
+
+    /// Job outputs summarizing the report.
+    pub(crate) fn outputs(&self) -> Vec<KeyValue> {
+        [
+            ("tasks_executed", self.executed.to_string()),
+            ("tasks_up_to_date", self.up_to_date.to_string()),
+            ("tasks_from_cache", self.from_cache.to_string()),
+            ("tasks_skipped", self.skipped.to_string()),
+            ("cache_hit_rate", format!("{:.2}", self.cache_hit_rate())),
+        ]
+        .into_iter()
+        .map(|(key, value)| KeyValue {
+            key: key.into(),
+            value,
+        })
+        .collect()
+    }
+}
+
+/// The tasks of a build with their summary and the `slowest_limit` slowest
+/// ones; identity fields are left for the caller.
+pub(crate) fn build_report(tasks: &[TaskRecord], slowest_limit: usize) -> BuildReport {
+    let summary = TaskSummary::of(tasks);
+    let mut slowest: Vec<&TaskRecord> = tasks.iter().collect();
+    slowest.sort_by_key(|task| std::cmp::Reverse(task.duration_ms));
+    slowest.truncate(slowest_limit);
+    BuildReport {
+        tasks: tasks.iter().map(TaskRecord::to_proto).collect(),
+        slowest_tasks: slowest.into_iter().map(TaskRecord::to_proto).collect(),
+        tasks_executed: summary.executed,
+        tasks_up_to_date: summary.up_to_date,
+        tasks_from_cache: summary.from_cache,
+        tasks_skipped: summary.skipped,
+        tasks_failed: summary.failed,
+        cache_hit_rate: summary.cache_hit_rate(),
+        task_duration_ms: summary.duration_ms,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_summarize_outcomes_and_slowest_tasks() {
+        let raw = "\
+:app:preBuild\tUP-TO-DATE\t1000\t1001
+:app:compileDebugKotlin\tEXECUTED\t1001\t4001
+:app:compileDebugJavaWithJavac\tFROM-CACHE\t4001\t4201
+:app:processDebugResources\tEXECUTED\t1001\t2501
+:app:compileDebugAidl\tNO-SOURCE\t1001\t1001
+garbage line
+:app:mergeDebugAssets\tUP-TO-DATE\t2501\t2511
+";
+        let tasks = parse_task_report(raw);
+        assert_eq!(tasks.len(), 6);
+        assert_eq!(tasks[1].duration_ms, 3000);
+
+        let summary = TaskSummary::of(&tasks);
+        assert_eq!(
+            (
+                summary.executed,
+                summary.up_to_date,
+                summary.from_cache,
+                summary.skipped
+            ),
+            (2, 2, 1, 1)
+        );
+        assert!((summary.cache_hit_rate() - 0.6).abs() < 1e-9);
+        assert!(summary
+            .outputs()
+            .iter()
+            .any(|kv| kv.key == "cache_hit_rate" && kv.value == "0.60"));
+
+        let report = build_report(&tasks, 2);
+        assert_eq!(report.tasks.len(), 6);
+        let slowest: Vec<&str> = report
+            .slowest_tasks
+            .iter()
+            .map(|task| task.path.as_str())
+            .collect();
+        assert_eq!(
+            slowest,
+            [":app:compileDebugKotlin", ":app:processDebugResources"]
+        );
+        assert_eq!(report.task_duration_ms, 4711);
+        assert_eq!(TaskSummary::of(&[]).cache_hit_rate(), 0.0);
+    }
+}
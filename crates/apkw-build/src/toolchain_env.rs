@@ -0,0 +1,474 @@
+use std::path::{Path, PathBuf};
+
+use apkw_proto::apkw::v1::{
+    toolchain_service_client::ToolchainServiceClient, ErrorCode, ErrorDetail,
+    GetActiveToolchainSetRequest, InstalledToolchain, KeyValue, ListInstalledRequest,
+    ListToolchainSetsRequest, Pagination, Remediation, ToolchainKind, ToolchainSet,
+};
+use apkw_util::{expand_user, toolchain_addr};
+use tonic::transport::Channel;
+
+/// The SDK (and NDK and JDK, when the set has them) a build runs with,
+/// resolved from the project's toolchain set or, without one, the active set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ToolchainEnv {
+    pub(crate) toolchain_set_id: String,
+    /// `project` or `active`.
+    pub(crate) source: &'static str,
+    pub(crate) sdk_toolchain_id: String,
+    pub(crate) sdk_path: PathBuf,
+    pub(crate) ndk_toolchain_id: String,
+    pub(crate) ndk_path: Option<PathBuf>,
+    pub(crate) jdk_toolchain_id: String,
+    /// Without one Gradle falls back to the `java` on `PATH`.
+    pub(crate) java_home: Option<PathBuf>,
+}
+
+/// Why a build cannot get its toolchain environment, with what to do
+/// about it.
+#[derive(Debug)]
+pub(crate) struct ToolchainEnvError {
+    code: ErrorCode,
+    message: String,
+    technical: String,
+    remedy: Remediation,
+}
+
+fn remedy(title: &str, description: String, action_id: &str, params: Vec<KeyValue>) -> Remediation {
+    Remediation {
+        title: title.into(),
+        description,
+        action_id: action_id.into(),
+        params,
+    }
+}
+
+fn param(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        key: key.into(),
+        value: value.into(),
+    }
+}
+
+impl ToolchainEnvError {
+    fn not_configured(message: String, remedy: Remediation) -> Self {
+        Self {
+            code: ErrorCode::ToolchainNotConfigured,
+            technical: message.clone(),
+            message,
+            remedy,
+        }
+    }
+
+    fn unavailable(technical: String) -> Self {
+        Self {
+            code: ErrorCode::Unavailable,
+            message: "toolchain service unavailable".into(),
+            technical,
+            remedy: remedy(
+                "Start ToolchainService",
+                "Builds resolve their SDK and NDK through apkw-toolchain; start it and re-run the build.".into(),
+                "",
+                vec![],
+            ),
+        }
+    }
+
+    pub(crate) fn into_detail(self, correlation_id: &str) -> ErrorDetail {
+        ErrorDetail {
+            code: self.code as i32,
+            message: self.message,
+            technical_details: self.technical,
+            remedies: vec![self.remedy],
+            correlation_id: correlation_id.into(),
+        }
+    }
+}
+
+impl ToolchainEnv {
+    /// Environment Gradle and the Android Gradle plugin read the JDK, SDK
+    /// and NDK locations from.
+    pub(crate) fn vars(&self) -> Vec<(String, String)> {
+        let sdk = self.sdk_path.display().to_string();
+        let mut vars = vec![
+            ("ANDROID_HOME".to_string(), sdk.clone()),
+            ("ANDROID_SDK_ROOT".to_string(), sdk),
+        ];
+        if let Some(ndk) = self.ndk_path.as_ref() {
+            let ndk = ndk.display().to_string();
+            vars.push(("ANDROID_NDK_ROOT".to_string(), ndk.clone()));
+            vars.push(("ANDROID_NDK_HOME".to_string(), ndk));
+        }
+        if let Some(java_home) = self.java_home.as_ref() {
+            vars.push(("JAVA_HOME".to_string(), java_home.display().to_string()));
+        }
+        vars
+    }
+
+    /// What gets recorded with the build: the set and the paths it used.
+    pub(crate) fn metadata(&self) -> Vec<KeyValue> {
+        let mut metadata = vec![
+            param("toolchain_set_id", &self.toolchain_set_id),
+            param("toolchain_set_source", self.source),
+            param("sdk_toolchain_id", &self.sdk_toolchain_id),
+            param("android_home", &self.sdk_path.display().to_string()),
+        ];
+        if let Some(ndk) = self.ndk_path.as_ref() {
+            metadata.push(param("ndk_toolchain_id", &self.ndk_toolchain_id));
+            metadata.push(param("android_ndk_root", &ndk.display().to_string()));
+        }
+        if let Some(java_home) = self.java_home.as_ref() {
+            metadata.push(param("jdk_toolchain_id", &self.jdk_toolchain_id));
+            metadata.push(param("java_home", &java_home.display().to_string()));
+        }
+        metadata
+    }
+}
+
+/// The install path of one toolchain of `set`, which must be installed,
+/// verified and still on disk.
+#[allow(clippy::result_large_err)]
+fn installed_path(
+    set_id: &str,
+    kind: &str,
+    toolchain_id: &str,
+    installed: &[InstalledToolchain],
+) -> Result<PathBuf, ToolchainEnvError> {
+    let Some(toolchain) = installed.iter().find(|item| {
+        item.toolchain_id
+            .as_ref()
+            .is_some_and(|id| id.value == toolchain_id)
+    }) else {
+        return Err(ToolchainEnvError::not_configured(
+            format!("{kind} toolchain {toolchain_id} of toolchain set {set_id} is not installed"),
+            remedy(
+                "Install the toolchain",
+                format!("Install {kind} toolchain {toolchain_id} again, or switch to a toolchain set whose toolchains are installed."),
+                "toolchain.install",
+                vec![param("toolchain_id", toolchain_id)],
+            ),
+        ));
+    };
+    if !toolchain.verified {
+        return Err(ToolchainEnvError {
+            code: ErrorCode::ToolchainVerifyFailed,
+            message: format!("{kind} toolchain {toolchain_id} is not verified"),
+            technical: format!(
+                "toolchain set {set_id} uses {kind} {toolchain_id} at {}, which has not passed verification",
+                toolchain.install_path
+            ),
+            remedy: remedy(
+                "Verify the toolchain",
+                format!("Run toolchain verification for {toolchain_id}, or reinstall it if verification fails."),
+                "toolchain.verify",
+                vec![param("toolchain_id", toolchain_id)],
+            ),
+        });
+    }
+    let path = expand_user(toolchain.install_path.trim());
+    if toolchain.install_path.trim().is_empty() || !path.is_dir() {
+        return Err(ToolchainEnvError::not_configured(
+            format!(
+                "{kind} toolchain {toolchain_id} is missing from {}",
+                toolchain.install_path
+            ),
+            remedy(
+                "Reinstall the toolchain",
+                format!("The install directory of {toolchain_id} is gone; reinstall it."),
+                "toolchain.install",
+                vec![param("toolchain_id", toolchain_id)],
+            ),
+        ));
+    }
+    Ok(path)
+}
+
+/// Checks `set` against the installed toolchains. The SDK is required; the
+/// NDK and JDK only when the set names them.
+#[allow(clippy::result_large_err)]
+fn select_toolchain_env(
+    set: &ToolchainSet,
+    source: &'static str,
+    installed: &[InstalledToolchain],
+) -> Result<ToolchainEnv, ToolchainEnvError> {
+    let id = |id: &Option<apkw_proto::apkw::v1::Id>| {
+        id.as_ref()
+            .map(|id| id.value.trim().to_string())
+            .unwrap_or_default()
+    };
+    let set_id = id(&set.toolchain_set_id);
+    let sdk_toolchain_id = id(&set.sdk_toolchain_id);
+    if sdk_toolchain_id.is_empty() {
+        return Err(ToolchainEnvError::not_configured(
+            format!("toolchain set {set_id} has no SDK"),
+            remedy(
+                "Add an SDK to the toolchain set",
+                "Create a toolchain set with an installed SDK (apkw-cli toolchain create-set) and use it for the project.".into(),
+                "toolchain.create_set",
+                vec![],
+            ),
+        ));
+    }
+    let sdk_path = installed_path(&set_id, "SDK", &sdk_toolchain_id, installed)?;
+    let ndk_toolchain_id = id(&set.ndk_toolchain_id);
+    let ndk_path = if ndk_toolchain_id.is_empty() {
+        None
+    } else {
+        Some(installed_path(
+            &set_id,
+            "NDK",
+            &ndk_toolchain_id,
+            installed,
+        )?)
+    };
+    let jdk_toolchain_id = id(&set.jdk_toolchain_id);
+    let java_home = if jdk_toolchain_id.is_empty() {
+        None
+    } else {
+        Some(installed_path(
+            &set_id,
+            "JDK",
+            &jdk_toolchain_id,
+            installed,
+        )?)
+    };
+    Ok(ToolchainEnv {
+        toolchain_set_id: set_id,
+        source,
+        sdk_toolchain_id,
+        sdk_path,
+        ndk_toolchain_id,
+        ndk_path,
+        jdk_toolchain_id,
+        java_home,
+    })
+}
+
+async fn find_set(
+    client: &mut ToolchainServiceClient<Channel>,
+    set_id: &str,
+) -> Result<Option<ToolchainSet>, tonic::Status> {
+    let mut page_token = String::new();
+    loop {
+        let resp = client
+            .list_toolchain_sets(ListToolchainSetsRequest {
+                page: Some(Pagination {
+                    page_size: 200,
+                    page_token: page_token.clone(),
+                }),
+            })
+            .await?
+            .into_inner();
+        if let Some(set) = resp.sets.into_iter().find(|set| {
+            set.toolchain_set_id
+                .as_ref()
+                .is_some_and(|id| id.value == set_id)
+        }) {
+            return Ok(Some(set));
+        }
+        page_token = resp
+            .page_info
+            .map(|info| info.next_page_token)
+            .unwrap_or_default();
+        if page_token.is_empty() {
+            return Ok(None);
+        }
+    }
+}
+
+async fn connect_toolchain() -> Result<ToolchainServiceClient<Channel>, ToolchainEnvError> {
+    let endpoint = format!("http://{}", toolchain_addr());
+    let channel = Channel::from_shared(endpoint)
+        .map_err(|err| {
+            ToolchainEnvError::unavailable(format!("invalid toolchain endpoint: {err}"))
+        })?
+        .connect()
+        .await
+        .map_err(|err| ToolchainEnvError::unavailable(err.to_string()))?;
+    Ok(ToolchainServiceClient::new(channel))
+}
+
+/// The set id recorded on a build job when it starts: the project's set, or
+/// the active one. Best effort; the job itself checks the set before Gradle
+/// runs.
+pub(crate) async fn job_toolchain_set_id(project_set_id: Option<String>) -> Option<String> {
+    if let Some(set_id) = project_set_id.filter(|id| !id.trim().is_empty()) {
+        return Some(set_id);
+    }
+    let mut client = connect_toolchain().await.ok()?;
+    client
+        .get_active_toolchain_set(GetActiveToolchainSetRequest {})
+        .await
+        .ok()?
+        .into_inner()
+        .set
+        .and_then(|set| set.toolchain_set_id)
+        .map(|id| id.value)
+        .filter(|id| !id.is_empty())
+}
+
+/// Resolves the toolchain set a build of a project with `project_set_id`
+/// uses, on every build, so a changed set or a removed toolchain is noticed
+/// before Gradle starts.
+pub(crate) async fn resolve_toolchain_env(
+    project_set_id: Option<&str>,
+) -> Result<ToolchainEnv, ToolchainEnvError> {
+    let mut client = connect_toolchain().await?;
+    let failed = |err: tonic::Status| ToolchainEnvError::unavailable(err.message().to_string());
+
+    let project_set_id = project_set_id.map(str::trim).filter(|id| !id.is_empty());
+    let (set, source) = match project_set_id {
+        Some(set_id) => match find_set(&mut client, set_id).await.map_err(failed)? {
+            Some(set) => (set, "project"),
+            None => {
+                return Err(ToolchainEnvError::not_configured(
+                    format!("toolchain set {set_id} of the project no longer exists"),
+                    remedy(
+                        "Choose a toolchain set for the project",
+                        "Create a toolchain set (apkw-cli toolchain create-set) and assign it to the project.".into(),
+                        "toolchain.create_set",
+                        vec![param("toolchain_set_id", set_id)],
+                    ),
+                ))
+            }
+        },
+        None => {
+            let active = client
+                .get_active_toolchain_set(GetActiveToolchainSetRequest {})
+                .await
+                .map_err(failed)?
+                .into_inner()
+                .set;
+            match active {
+                Some(set) => (set, "active"),
+                None => {
+                    return Err(ToolchainEnvError::not_configured(
+                        "the project has no toolchain set and none is active".into(),
+                        remedy(
+                            "Activate a toolchain set",
+                            "Create a toolchain set from an installed SDK (apkw-cli toolchain create-set) and activate it (apkw-cli toolchain set-active).".into(),
+                            "toolchain.set_active",
+                            vec![],
+                        ),
+                    ))
+                }
+            }
+        }
+    };
+    let installed = client
+        .list_installed(ListInstalledRequest {
+            kind: ToolchainKind::Unspecified as i32,
+        })
+        .await
+        .map_err(failed)?
+        .into_inner()
+        .items;
+    select_toolchain_env(&set, source, &installed)
+}
+
+/// `sdk.dir` from the project's `local.properties`, which the Android
+/// Gradle plugin prefers over `ANDROID_HOME`.
+pub(crate) fn local_sdk_dir(project_dir: &Path) -> Option<PathBuf> {
+    let data = std::fs::read_to_string(project_dir.join("local.properties")).ok()?;
+    data.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "sdk.dir")
+            .then(|| PathBuf::from(value.trim().replace("\\:", ":").replace("\\\\", "\\")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apkw_proto::apkw::v1::Id;
+
+    fn id(value: &str) -> Option<Id> {
+        Some(Id {
+            value: value.into(),
+        })
+    }
+
+    #[test]
+    fn sets_resolve_to_verified_install_paths() {
+        let root =
+            std::env::temp_dir().join(format!("apkw-build-toolchain-{}", apkw_util::now_millis()));
+        let sdk_dir = root.join("sdk");
+        std::fs::create_dir_all(&sdk_dir).unwrap();
+        let set = ToolchainSet {
+            toolchain_set_id: id("set-1"),
+            sdk_toolchain_id: id("sdk-1"),
+            ndk_toolchain_id: id("ndk-1"),
+            display_name: String::new(),
+            jdk_toolchain_id: id("jdk-1"),
+        };
+        let mut installed = vec![
+            InstalledToolchain {
+                toolchain_id: id("sdk-1"),
+                install_path: sdk_dir.display().to_string(),
+                verified: true,
+                ..Default::default()
+            },
+            InstalledToolchain {
+                toolchain_id: id("ndk-1"),
+                install_path: root.join("ndk").display().to_string(),
+                verified: false,
+                ..Default::default()
+            },
+            InstalledToolchain {
+                toolchain_id: id("jdk-1"),
+                install_path: root.join("jdk").display().to_string(),
+                verified: true,
+                ..Default::default()
+            },
+        ];
+
+        let err = select_toolchain_env(&set, "project", &installed).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ToolchainVerifyFailed);
+        assert_eq!(err.remedy.action_id, "toolchain.verify");
+
+        installed[1].verified = true;
+        let err = select_toolchain_env(&set, "project", &installed).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ToolchainNotConfigured);
+
+        std::fs::create_dir_all(root.join("ndk")).unwrap();
+        std::fs::create_dir_all(root.join("jdk")).unwrap();
+        let env = select_toolchain_env(&set, "project", &installed).unwrap();
+        let vars = env.vars();
+        assert_eq!(
+            vars[0],
+            ("ANDROID_HOME".into(), sdk_dir.display().to_string())
+        );
+        assert!(vars.iter().any(|(key, _)| key == "ANDROID_NDK_ROOT"));
+        assert!(vars
+            .iter()
+            .any(|(key, value)| key == "JAVA_HOME" && value.ends_with("jdk")));
+        assert!(env
+            .metadata()
+            .iter()
+            .any(|kv| kv.key == "toolchain_set_id" && kv.value == "set-1"));
+
+        let sdk_only = ToolchainSet {
+            ndk_toolchain_id: None,
+            jdk_toolchain_id: None,
+            ..set.clone()
+        };
+        let env = select_toolchain_env(&sdk_only, "active", &installed[..1]).unwrap();
+        assert_eq!(env.vars().len(), 2);
+        assert!(select_toolchain_env(
+            &ToolchainSet {
+                sdk_toolchain_id: None,
+                ..set
+            },
+            "active",
+            &installed
+        )
+        .is_err());
+
+        std::fs::write(root.join("local.properties"), "sdk.dir=/opt/android-sdk\n").unwrap();
+        assert_eq!(
+            local_sdk_dir(&root),
+            Some(PathBuf::from("/opt/android-sdk"))
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -7,24 +7,34 @@ use apkw_proto::apkw::v1::{
     toolchain_service_client::ToolchainServiceClient,
     workflow_service_client::WorkflowServiceClient, Artifact, ArtifactFilter, ArtifactType,
     BuildRequest, BuildVariant, CancelJobRequest, CleanupToolchainCacheRequest,
-    CreateProjectRequest, CreateToolchainSetRequest, ExportEvidenceBundleRequest,
-    ExportSupportBundleRequest, GetActiveToolchainSetRequest, GetCuttlefishStatusRequest,
-    GetDefaultTargetRequest, GetJobRequest, Id, InstallCuttlefishRequest, Job, JobEvent,
-    JobEventKind, JobFilter, JobHistoryFilter, JobState, KeyValue, ListArtifactsRequest,
-    ListJobHistoryRequest, ListJobsRequest, ListProvidersRequest, ListRecentProjectsRequest,
-    ListRunOutputsRequest, ListRunsRequest, ListTargetsRequest, ListTemplatesRequest,
-    ListToolchainSetsRequest, OpenProjectRequest, Pagination, ReloadStateRequest, RunFilter, RunId,
-    RunOutputFilter, RunOutputKind, SetActiveToolchainSetRequest, SetDefaultTargetRequest,
-    SetProjectConfigRequest, StartCuttlefishRequest, StartJobRequest, StopCuttlefishRequest,
-    StreamJobEventsRequest, StreamRunEventsRequest, UninstallToolchainRequest,
-    UpdateToolchainRequest, WorkflowPipelineOptions, WorkflowPipelineRequest,
+    CreateKeystoreRequest, CreateNotificationRuleRequest, CreateProjectRequest,
+    CreateToolchainSetRequest, CreateTriggerRequest, DeleteKeystoreRequest,
+    DeleteNotificationRuleRequest, DeleteSecretRequest, DeleteTriggerRequest,
+    ExportEvidenceBundleRequest, ExportSupportBundleRequest, GetActiveToolchainSetRequest,
+    GetBuildReportRequest, GetCuttlefishStatusRequest, GetDefaultTargetRequest, GetJobRequest, Id,
+    ImportKeystoreRequest, InspectArtifactRequest, InstallCuttlefishRequest, Job, JobEvent,
+    JobEventCursor, JobEventKind, JobFilter, JobHistoryFilter, JobOutputSpec, JobPriority,
+    JobState, JobTypeInfo, KeyValue, KeystoreInfo, ListArtifactsRequest, ListJobHistoryRequest,
+    ListJobTypesRequest, ListJobsRequest, ListKeystoresRequest, ListNotificationRulesRequest,
+    ListProvidersRequest, ListRecentProjectsRequest, ListRunOutputsRequest, ListRunsRequest,
+    ListSecretsRequest, ListTargetsRequest, ListTemplatesRequest, ListToolchainSetsRequest,
+    ListTriggersRequest, ListWorkflowsRequest, LogLevel, LogRecord, NotificationRule,
+    OpenProjectRequest, Pagination, PlanPipelineResponse, RegisterJobTypeRequest,
+    ReloadStateRequest, ResumePipelineRequest, RetryPolicy, RunFilter, RunId, RunOutputFilter,
+    RunOutputKind, RunTestsRequest, SearchJobLogsRequest, SecretInfo, SecretRef,
+    SetActiveToolchainSetRequest, SetDefaultTargetRequest, SetProjectConfigRequest,
+    SetSecretRequest, SignArtifactRequest, StartCuttlefishRequest, StartJobRequest,
+    StopCuttlefishRequest, StreamJobEventsRequest, StreamRunEventsRequest, TaskOutcome, TestKind,
+    UninstallToolchainRequest, UpdateToolchainRequest, WorkflowInfo, WorkflowPipelineOptions,
+    WorkflowPipelineRequest, WorkflowTrigger,
 };
 use apkw_telemetry as telemetry;
 use apkw_util::{
     collect_job_history, data_dir, default_export_path, expand_user, now_millis,
-    open_state_archive, save_state_archive_to, state_export_path, write_json_atomic,
-    StateArchiveOptions, StateOpGuard, DEFAULT_BUILD_ADDR, DEFAULT_JOB_ADDR, DEFAULT_OBSERVE_ADDR,
-    DEFAULT_PROJECT_ADDR, DEFAULT_TARGETS_ADDR, DEFAULT_TOOLCHAIN_ADDR, DEFAULT_WORKFLOW_ADDR,
+    open_state_archive, save_state_archive_to, secrets::parse_secret_ref, state_export_path,
+    write_json_atomic, StateArchiveOptions, StateOpGuard, DEFAULT_BUILD_ADDR, DEFAULT_JOB_ADDR,
+    DEFAULT_OBSERVE_ADDR, DEFAULT_PROJECT_ADDR, DEFAULT_TARGETS_ADDR, DEFAULT_TOOLCHAIN_ADDR,
+    DEFAULT_WORKFLOW_ADDR,
 };
 use clap::{Parser, Subcommand};
 use futures_util::StreamExt;
@@ -103,6 +113,24 @@ enum JobCmd {
         #[arg(long)]
         run_id: Option<String>,
         #[arg(long)]
+        depends_on: Vec<String>,
+        #[arg(long)]
+        concurrency_key: Vec<String>,
+        #[arg(long)]
+        timeout_ms: Option<u64>,
+        /// Total attempts, including the first, before the job fails
+        #[arg(long)]
+        max_attempts: Option<u32>,
+        /// Delay before the first retry; doubles on each further retry
+        #[arg(long, default_value_t = 1000)]
+        retry_backoff_ms: u64,
+        /// Dispatch priority: background, normal, or interactive
+        #[arg(long)]
+        priority: Option<String>,
+        /// Allow interactive jobs to stop this job and re-run it later
+        #[arg(long)]
+        preemptible: bool,
+        #[arg(long)]
         no_stream: bool,
     },
     /// List jobs with optional filters
@@ -130,6 +158,32 @@ enum JobCmd {
         #[arg(long, default_value = "")]
         page_token: String,
     },
+    /// Search job logs, failure messages and summaries for text
+    Search {
+        #[arg(long, default_value_t = default_job_addr())]
+        addr: String,
+        /// Case-insensitive text to find
+        query: String,
+        #[arg(long)]
+        job_type: Vec<String>,
+        #[arg(long)]
+        state: Vec<String>,
+        #[arg(long)]
+        created_after: Option<i64>,
+        #[arg(long)]
+        created_before: Option<i64>,
+        #[arg(long)]
+        correlation_id: Option<String>,
+        #[arg(long)]
+        run_id: Option<String>,
+        /// Matches shown per job (server default when 0)
+        #[arg(long, default_value_t = 0)]
+        max_matches: u32,
+        #[arg(long, default_value_t = 20)]
+        page_size: u32,
+        #[arg(long, default_value = "")]
+        page_token: String,
+    },
     /// Watch a job stream by id
     Watch {
         #[arg(long, default_value_t = default_job_addr())]
@@ -137,6 +191,13 @@ enum JobCmd {
         job_id: String,
         #[arg(long)]
         include_history: bool,
+        /// Resume after this event sequence number (replays retained events after it)
+        #[arg(long, default_value_t = 0)]
+        after_seq: u64,
+        /// Show structured log records at or above this level (debug, info, warn, error)
+        /// instead of raw log output
+        #[arg(long)]
+        level: Option<String>,
     },
     /// Watch job events by run or correlation id (aggregated stream)
     WatchRun {
@@ -154,6 +215,9 @@ enum JobCmd {
         max_delay_ms: Option<u64>,
         #[arg(long)]
         discovery_interval_ms: Option<u64>,
+        /// Resume a job from a cursor (JOB_ID:SEQ); may be repeated
+        #[arg(long = "cursor")]
+        cursors: Vec<String>,
     },
     /// List job event history by id
     History {
@@ -166,6 +230,13 @@ enum JobCmd {
         after: Option<i64>,
         #[arg(long)]
         before: Option<i64>,
+        /// Only show events from this attempt
+        #[arg(long)]
+        attempt: Option<u32>,
+        /// Show structured log records at or above this level (debug, info, warn, error)
+        /// instead of raw log output
+        #[arg(long)]
+        level: Option<String>,
         #[arg(long, default_value_t = 200)]
         page_size: u32,
         #[arg(long, default_value = "")]
@@ -185,6 +256,86 @@ enum JobCmd {
         addr: String,
         job_id: String,
     },
+    /// List job types accepted by JobService
+    Types {
+        #[arg(long, default_value_t = default_job_addr())]
+        addr: String,
+    },
+    /// Register (or update) a job type run by an external service
+    RegisterType {
+        #[arg(long, default_value_t = default_job_addr())]
+        addr: String,
+        job_type: String,
+        #[arg(long, default_value = "")]
+        display_name: String,
+        /// Address of the service that runs jobs of this type
+        #[arg(long, default_value = "")]
+        service_addr: String,
+        /// Reject CancelJob for jobs of this type
+        #[arg(long)]
+        not_cancellable: bool,
+        /// Expected output as KEY or KEY=DESCRIPTION
+        #[arg(long)]
+        output: Vec<String>,
+        /// Required output as KEY or KEY=DESCRIPTION
+        #[arg(long)]
+        required_output: Vec<String>,
+    },
+    /// Add a notification rule that fires when matching jobs finish
+    NotifyAdd {
+        #[arg(long, default_value_t = default_job_addr())]
+        addr: String,
+        #[arg(long, default_value = "")]
+        name: String,
+        /// Replace the rule with this id instead of adding one
+        #[arg(long, default_value = "")]
+        rule_id: String,
+        #[arg(long)]
+        job_type: Vec<String>,
+        /// Terminal states to fire on (success, failed, cancelled); all when omitted
+        #[arg(long)]
+        state: Vec<String>,
+        #[arg(long)]
+        run_id: Option<String>,
+        /// POST the JSON payload to this URL
+        #[arg(long)]
+        webhook: Option<String>,
+        /// Run this command with the JSON payload on stdin (after `--`)
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+    /// List notification rules
+    NotifyList {
+        #[arg(long, default_value_t = default_job_addr())]
+        addr: String,
+    },
+    /// Remove a notification rule by id
+    NotifyRemove {
+        #[arg(long, default_value_t = default_job_addr())]
+        addr: String,
+        rule_id: String,
+    },
+    /// Store a secret for builds; the value is read from stdin unless --value is given
+    SecretSet {
+        #[arg(long, default_value_t = default_job_addr())]
+        addr: String,
+        name: String,
+        #[arg(long)]
+        value: Option<String>,
+        #[arg(long, default_value = "")]
+        description: String,
+    },
+    /// List stored secrets (names only)
+    SecretList {
+        #[arg(long, default_value_t = default_job_addr())]
+        addr: String,
+    },
+    /// Remove a stored secret by name
+    SecretRemove {
+        #[arg(long, default_value_t = default_job_addr())]
+        addr: String,
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -203,7 +354,7 @@ enum ToolchainCmd {
         #[arg(long, default_value = "")]
         page_token: String,
     },
-    /// Create a toolchain set from installed SDK/NDK ids
+    /// Create a toolchain set from installed SDK/NDK/JDK ids
     CreateSet {
         #[arg(long, default_value_t = default_toolchain_addr())]
         addr: String,
@@ -212,6 +363,8 @@ enum ToolchainCmd {
         #[arg(long)]
         ndk_toolchain_id: Option<String>,
         #[arg(long)]
+        jdk_toolchain_id: Option<String>,
+        #[arg(long)]
         display_name: Option<String>,
     },
     /// Set the active toolchain set
@@ -600,6 +753,9 @@ enum BuildCmd {
         gradle_arg: Vec<String>,
         #[arg(long)]
         clean_first: bool,
+        /// Inject a stored secret as NAME, ENV=NAME or -Pprop=NAME (repeatable)
+        #[arg(long = "secret")]
+        secrets: Vec<String>,
         #[arg(long)]
         job_id: Option<String>,
         #[arg(long)]
@@ -609,6 +765,40 @@ enum BuildCmd {
         #[arg(long)]
         no_stream: bool,
     },
+    /// Run unit or instrumented tests and report JUnit results
+    Test {
+        #[arg(long, default_value_t = default_build_addr())]
+        addr: String,
+        #[arg(long, default_value_t = default_job_addr())]
+        job_addr: String,
+        project_ref: String,
+        /// unit or instrumented
+        #[arg(long, default_value = "unit")]
+        kind: String,
+        #[arg(long, default_value = "debug")]
+        variant: String,
+        #[arg(long)]
+        variant_name: Option<String>,
+        #[arg(long)]
+        module: Option<String>,
+        /// adb serial to run instrumented tests on
+        #[arg(long)]
+        device: Option<String>,
+        /// Test class or method pattern (repeatable)
+        #[arg(long = "filter", action = clap::ArgAction::Append)]
+        filters: Vec<String>,
+        #[arg(long, action = clap::ArgAction::Append)]
+        gradle_arg: Vec<String>,
+        /// Inject a stored secret as NAME, ENV=NAME or -Pprop=NAME (repeatable)
+        #[arg(long = "secret")]
+        secrets: Vec<String>,
+        #[arg(long)]
+        correlation_id: Option<String>,
+        #[arg(long)]
+        run_id: Option<String>,
+        #[arg(long)]
+        no_stream: bool,
+    },
     /// List build artifacts
     ListArtifacts {
         #[arg(long, default_value_t = default_build_addr())]
@@ -627,9 +817,92 @@ enum BuildCmd {
         #[arg(long, default_value = "")]
         path_contains: String,
     },
+    /// Show an APK or AAB's manifest, permissions, native ABIs and dex counts
+    Inspect {
+        #[arg(long, default_value_t = default_build_addr())]
+        addr: String,
+        artifact_path: String,
+    },
+    /// Show task outcomes, cache hit rate and the slowest tasks of a build
+    Report {
+        #[arg(long, default_value_t = default_build_addr())]
+        addr: String,
+        /// build.run job id (the project's latest build when omitted)
+        #[arg(long)]
+        job_id: Option<String>,
+        #[arg(long)]
+        project_ref: Option<String>,
+        #[arg(long, default_value_t = 10)]
+        slowest: u32,
+        /// List every task in completion order
+        #[arg(long)]
+        all: bool,
+    },
+    /// Generate a signing keystore; its password is generated unless --store-password-stdin is given
+    KeystoreCreate {
+        #[arg(long, default_value_t = default_build_addr())]
+        addr: String,
+        name: String,
+        #[arg(long)]
+        alias: Option<String>,
+        /// Certificate owner, e.g. "CN=Example, O=Example Inc, C=US"
+        #[arg(long)]
+        dname: Option<String>,
+        #[arg(long, default_value_t = 0)]
+        validity_days: u32,
+        /// Read the store password from stdin
+        #[arg(long)]
+        store_password_stdin: bool,
+    },
+    /// Import a JKS or PKCS12 keystore; the store password is read from stdin
+    KeystoreImport {
+        #[arg(long, default_value_t = default_build_addr())]
+        addr: String,
+        name: String,
+        path: String,
+        #[arg(long)]
+        alias: Option<String>,
+        /// Read a key password from a second stdin line
+        #[arg(long)]
+        key_password_stdin: bool,
+    },
+    /// List managed keystores
+    KeystoreList {
+        #[arg(long, default_value_t = default_build_addr())]
+        addr: String,
+    },
+    /// Remove a managed keystore and its stored passwords
+    KeystoreRemove {
+        #[arg(long, default_value_t = default_build_addr())]
+        addr: String,
+        keystore_id: String,
+    },
+    /// Sign an APK or AAB with a managed keystore
+    Sign {
+        #[arg(long, default_value_t = default_build_addr())]
+        addr: String,
+        #[arg(long, default_value_t = default_job_addr())]
+        job_addr: String,
+        artifact_path: String,
+        #[arg(long)]
+        keystore_id: String,
+        /// Project whose toolchain set and build record are used
+        #[arg(long)]
+        project_ref: Option<String>,
+        /// Where the signed file is written (<name>-signed.<ext> when omitted)
+        #[arg(long)]
+        output: Option<String>,
+        #[arg(long)]
+        correlation_id: Option<String>,
+        #[arg(long)]
+        run_id: Option<String>,
+        #[arg(long)]
+        no_stream: bool,
+    },
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum WorkflowCmd {
     /// Run the workflow pipeline
     RunPipeline {
@@ -657,6 +930,12 @@ enum WorkflowCmd {
         toolchain_set_id: Option<String>,
         #[arg(long)]
         target_id: Option<String>,
+        /// More targets to install/launch on in parallel (comma separated)
+        #[arg(long, value_delimiter = ',')]
+        target_ids: Vec<String>,
+        /// Add online targets matching key=value terms (kind, provider, state, api_level), or `*`
+        #[arg(long)]
+        target_selector: Option<String>,
         #[arg(long, default_value = "debug")]
         variant: String,
         #[arg(long)]
@@ -667,17 +946,128 @@ enum WorkflowCmd {
         task: Vec<String>,
         #[arg(long)]
         apk_path: Option<String>,
+        /// Expected sha256 of --apk-path; install steps refuse a different file
+        #[arg(long)]
+        apk_sha256: Option<String>,
+        /// Install the APK an earlier run recorded instead of building one
+        #[arg(long, conflicts_with = "apk_path")]
+        artifact_run_id: Option<String>,
         #[arg(long)]
         application_id: Option<String>,
         #[arg(long)]
         activity: Option<String>,
         #[arg(long, value_delimiter = ',')]
         step: Vec<String>,
+        /// Run `.apkw/workflows/<NAME>` from the project instead of the steps
+        #[arg(long, conflicts_with = "step")]
+        workflow: Option<String>,
+        /// Workflow input as KEY=VALUE (repeatable)
+        #[arg(long = "input", requires = "workflow")]
+        inputs: Vec<String>,
+        /// Inject a stored secret as NAME, ENV=NAME or -Pprop=NAME (repeatable)
+        #[arg(long = "secret")]
+        secrets: Vec<String>,
+        /// Print the steps the pipeline would run, with resolved inputs, without running it
+        #[arg(long)]
+        plan: bool,
         #[arg(long)]
         stream_run: bool,
         #[arg(long)]
         no_stream: bool,
     },
+    /// Resume a failed pipeline run from its first failed or skipped step
+    Resume {
+        #[arg(long, default_value_t = default_workflow_addr())]
+        addr: String,
+        #[arg(long, default_value_t = default_job_addr())]
+        job_addr: String,
+        #[arg(long)]
+        run_id: String,
+        #[arg(long)]
+        job_id: Option<String>,
+        /// Override an input of the run as KEY=VALUE (repeatable)
+        #[arg(long = "input")]
+        inputs: Vec<String>,
+        #[arg(long)]
+        no_stream: bool,
+    },
+    /// List the workflows defined in a project
+    List {
+        #[arg(long, default_value_t = default_workflow_addr())]
+        addr: String,
+        #[arg(long)]
+        project_id: Option<String>,
+        #[arg(long)]
+        project_path: Option<String>,
+    },
+    /// Add a trigger that runs a pipeline on a cron schedule or when project files change
+    TriggerAdd {
+        #[arg(long, default_value_t = default_workflow_addr())]
+        addr: String,
+        #[arg(long, default_value = "")]
+        name: String,
+        /// Replace the trigger with this id instead of adding one
+        #[arg(long, default_value = "")]
+        trigger_id: String,
+        /// Five-field cron expression, or @hourly/@daily/@weekly/@monthly/@yearly
+        #[arg(
+            long,
+            conflicts_with = "watch_path",
+            required_unless_present = "watch_path"
+        )]
+        cron: Option<String>,
+        /// Time zone of --cron: UTC or a fixed offset such as +02:00
+        #[arg(long, default_value = "", requires = "cron")]
+        timezone: String,
+        /// Run when files under this directory change
+        #[arg(long)]
+        watch_path: Option<String>,
+        /// File or directory name patterns the watch ignores (comma separated, `*` and `?`)
+        #[arg(long, value_delimiter = ',', requires = "watch_path")]
+        ignore: Vec<String>,
+        /// Quiet period after the last change before the run starts
+        #[arg(long, default_value_t = 0, requires = "watch_path")]
+        debounce_ms: u32,
+        #[arg(long)]
+        disabled: bool,
+        #[arg(long)]
+        project_id: Option<String>,
+        #[arg(long)]
+        project_path: Option<String>,
+        #[arg(long)]
+        toolchain_set_id: Option<String>,
+        #[arg(long)]
+        target_id: Option<String>,
+        #[arg(long, value_delimiter = ',')]
+        target_ids: Vec<String>,
+        #[arg(long)]
+        target_selector: Option<String>,
+        #[arg(long, default_value = "debug")]
+        variant: String,
+        #[arg(long)]
+        module: Option<String>,
+        #[arg(long)]
+        application_id: Option<String>,
+        #[arg(long)]
+        activity: Option<String>,
+        #[arg(long, value_delimiter = ',')]
+        step: Vec<String>,
+        #[arg(long, conflicts_with = "step")]
+        workflow: Option<String>,
+        #[arg(long = "input", requires = "workflow")]
+        inputs: Vec<String>,
+    },
+    /// List workflow triggers
+    TriggerList {
+        #[arg(long, default_value_t = default_workflow_addr())]
+        addr: String,
+    },
+    /// Remove a workflow trigger by id
+    TriggerRemove {
+        #[arg(long, default_value_t = default_workflow_addr())]
+        addr: String,
+        trigger_id: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -820,11 +1210,20 @@ fn command_name(cmd: &Cmd) -> &'static str {
         Cmd::Job { cmd } => match cmd {
             JobCmd::Run { .. } => "job.run",
             JobCmd::List { .. } => "job.list",
+            JobCmd::Search { .. } => "job.search",
             JobCmd::Watch { .. } => "job.watch",
             JobCmd::WatchRun { .. } => "job.watch_run",
             JobCmd::History { .. } => "job.history",
             JobCmd::Export { .. } => "job.export",
             JobCmd::Cancel { .. } => "job.cancel",
+            JobCmd::Types { .. } => "job.types",
+            JobCmd::RegisterType { .. } => "job.register_type",
+            JobCmd::NotifyAdd { .. } => "job.notify_add",
+            JobCmd::NotifyList { .. } => "job.notify_list",
+            JobCmd::NotifyRemove { .. } => "job.notify_remove",
+            JobCmd::SecretSet { .. } => "job.secret_set",
+            JobCmd::SecretList { .. } => "job.secret_list",
+            JobCmd::SecretRemove { .. } => "job.secret_remove",
         },
         Cmd::Toolchain { cmd } => match cmd {
             ToolchainCmd::ListProviders { .. } => "toolchain.list_providers",
@@ -861,10 +1260,23 @@ fn command_name(cmd: &Cmd) -> &'static str {
         },
         Cmd::Build { cmd } => match cmd {
             BuildCmd::Run { .. } => "build.run",
+            BuildCmd::Test { .. } => "build.test",
             BuildCmd::ListArtifacts { .. } => "build.list_artifacts",
+            BuildCmd::Inspect { .. } => "build.inspect",
+            BuildCmd::Report { .. } => "build.report",
+            BuildCmd::KeystoreCreate { .. } => "build.keystore_create",
+            BuildCmd::KeystoreImport { .. } => "build.keystore_import",
+            BuildCmd::KeystoreList { .. } => "build.keystore_list",
+            BuildCmd::KeystoreRemove { .. } => "build.keystore_remove",
+            BuildCmd::Sign { .. } => "build.sign",
         },
         Cmd::Workflow { cmd } => match cmd {
             WorkflowCmd::RunPipeline { .. } => "workflow.run_pipeline",
+            WorkflowCmd::Resume { .. } => "workflow.resume",
+            WorkflowCmd::List { .. } => "workflow.list",
+            WorkflowCmd::TriggerAdd { .. } => "workflow.trigger_add",
+            WorkflowCmd::TriggerList { .. } => "workflow.trigger_list",
+            WorkflowCmd::TriggerRemove { .. } => "workflow.trigger_remove",
         },
         Cmd::State { cmd } => match cmd {
             StateCmd::Save { .. } => "state.save",
@@ -886,12 +1298,29 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 toolchain_set_id,
                 correlation_id,
                 run_id,
+                depends_on,
+                concurrency_key,
+                timeout_ms,
+                max_attempts,
+                retry_backoff_ms,
+                priority,
+                preemptible,
                 no_stream,
             } => {
                 if job_type.trim().is_empty() {
                     eprintln!("job_type is required");
                     return Ok(());
                 }
+                let priority = match priority {
+                    Some(priority) => {
+                        let Some(parsed) = parse_job_priority(&priority) else {
+                            eprintln!("unsupported priority: {priority}");
+                            return Ok(());
+                        };
+                        parsed
+                    }
+                    None => JobPriority::Unspecified,
+                };
                 update_cli_config(|cfg| {
                     cfg.job_addr = addr.clone();
                     cfg.last_job_type = job_type.clone();
@@ -913,6 +1342,18 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                         }),
                         correlation_id: correlation_id.unwrap_or_default(),
                         run_id: run_id.map(|value| RunId { value }),
+                        depends_on: depends_on.into_iter().map(|value| Id { value }).collect(),
+                        concurrency_keys: concurrency_key,
+                        timeout_ms: timeout_ms.unwrap_or_default(),
+                        retry_policy: max_attempts.map(|max_attempts| RetryPolicy {
+                            max_attempts,
+                            initial_backoff_ms: retry_backoff_ms,
+                            backoff_multiplier: 2.0,
+                            max_backoff_ms: 0,
+                            retryable_codes: Vec::new(),
+                        }),
+                        priority: priority as i32,
+                        preemptible,
                     })
                     .await?
                     .into_inner();
@@ -986,16 +1427,100 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
             }
+            JobCmd::Search {
+                addr,
+                query,
+                job_type,
+                state,
+                created_after,
+                created_before,
+                correlation_id,
+                run_id,
+                max_matches,
+                page_size,
+                page_token,
+            } => {
+                update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let (states, unknown_states) = parse_job_states(&state);
+                if !unknown_states.is_empty() {
+                    eprintln!("Unknown states: {}", unknown_states.join(", "));
+                }
+                let filter = JobFilter {
+                    job_types: split_tokens(&job_type),
+                    states,
+                    created_after: created_after
+                        .map(|ms| apkw_proto::apkw::v1::Timestamp { unix_millis: ms }),
+                    created_before: created_before
+                        .map(|ms| apkw_proto::apkw::v1::Timestamp { unix_millis: ms }),
+                    finished_after: None,
+                    finished_before: None,
+                    correlation_id: correlation_id.unwrap_or_default(),
+                    run_id: run_id.map(|value| RunId { value }),
+                };
+                let mut client = JobServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .search_job_logs(SearchJobLogsRequest {
+                        query,
+                        filter: Some(filter),
+                        page: Some(Pagination {
+                            page_size: page_size.max(1),
+                            page_token,
+                        }),
+                        max_matches_per_job: max_matches,
+                    })
+                    .await?
+                    .into_inner();
+                if resp.results.is_empty() {
+                    println!("no matches found");
+                } else {
+                    println!("jobs={}", resp.results.len());
+                    for result in &resp.results {
+                        if let Some(job) = result.job.as_ref() {
+                            render_job_summary(job);
+                        }
+                        println!("  matches={}", result.total_matches);
+                        for m in &result.matches {
+                            println!(
+                                "  offset={} seq={} attempt={} at={} [{}] {}",
+                                m.event_offset,
+                                m.event_seq,
+                                m.attempt,
+                                m.at.as_ref().map(|ts| ts.unix_millis).unwrap_or_default(),
+                                m.stream,
+                                m.snippet
+                            );
+                        }
+                    }
+                }
+                if let Some(page_info) = resp.page_info {
+                    if !page_info.next_page_token.is_empty() {
+                        println!("next_page_token={}", page_info.next_page_token);
+                    }
+                }
+            }
             JobCmd::Watch {
                 addr,
                 job_id,
                 include_history,
+                after_seq,
+                level,
             } => {
                 update_cli_config(|cfg| {
                     cfg.job_addr = addr.clone();
                     cfg.last_job_id = job_id.clone();
                 });
-                stream_job_events(&addr, &job_id, include_history, true).await?;
+                let min_level = match level {
+                    Some(level) => {
+                        let Some(parsed) = parse_log_level(&level) else {
+                            eprintln!("unsupported level: {level}");
+                            return Ok(());
+                        };
+                        Some(parsed)
+                    }
+                    None => None,
+                };
+                stream_job_events(&addr, &job_id, include_history, after_seq, true, min_level)
+                    .await?;
             }
             JobCmd::WatchRun {
                 addr,
@@ -1005,13 +1530,23 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 buffer_max_events,
                 max_delay_ms,
                 discovery_interval_ms,
+                cursors,
             } => {
                 update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let mut parsed = Vec::with_capacity(cursors.len());
+                for cursor in &cursors {
+                    let Some(cursor) = parse_job_event_cursor(cursor) else {
+                        eprintln!("invalid cursor (expected JOB_ID:SEQ): {cursor}");
+                        return Ok(());
+                    };
+                    parsed.push(cursor);
+                }
                 stream_run_events(
                     &addr,
                     run_id,
                     correlation_id,
                     include_history,
+                    parsed,
                     buffer_max_events,
                     max_delay_ms,
                     discovery_interval_ms,
@@ -1024,6 +1559,8 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 kind,
                 after,
                 before,
+                attempt,
+                level,
                 page_size,
                 page_token,
             } => {
@@ -1031,14 +1568,25 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     cfg.job_addr = addr.clone();
                     cfg.last_job_id = job_id.clone();
                 });
-                let (kinds, unknown_kinds) = parse_job_event_kinds(&kind);
-                if !unknown_kinds.is_empty() {
+                let min_level = match level {
+                    Some(level) => {
+                        let Some(parsed) = parse_log_level(&level) else {
+                            eprintln!("unsupported level: {level}");
+                            return Ok(());
+                        };
+                        Some(parsed)
+                    }
+                    None => None,
+                };
+                let (kinds, unknown_kinds) = parse_job_event_kinds(&kind);
+                if !unknown_kinds.is_empty() {
                     eprintln!("Unknown kinds: {}", unknown_kinds.join(", "));
                 }
                 let filter = JobHistoryFilter {
                     kinds,
                     after: after.map(|ms| apkw_proto::apkw::v1::Timestamp { unix_millis: ms }),
                     before: before.map(|ms| apkw_proto::apkw::v1::Timestamp { unix_millis: ms }),
+                    attempt: attempt.unwrap_or_default(),
                 };
                 let mut client = JobServiceClient::new(connect(&addr).await?);
                 let resp = client
@@ -1059,7 +1607,7 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 } else {
                     println!("events={}", resp.events.len());
                     for event in &resp.events {
-                        render_job_event(event);
+                        render_job_event(event, min_level);
                     }
                 }
                 if let Some(page_info) = resp.page_info {
@@ -1121,6 +1669,159 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     .into_inner();
                 println!("accepted={}", resp.accepted);
             }
+            JobCmd::Types { addr } => {
+                update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let mut client = JobServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .list_job_types(ListJobTypesRequest {})
+                    .await?
+                    .into_inner();
+                for info in resp.job_types {
+                    print_job_type(&info);
+                }
+            }
+            JobCmd::RegisterType {
+                addr,
+                job_type,
+                display_name,
+                service_addr,
+                not_cancellable,
+                output,
+                required_output,
+            } => {
+                update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let outputs = output
+                    .iter()
+                    .map(|spec| parse_job_output_spec(spec, false))
+                    .chain(
+                        required_output
+                            .iter()
+                            .map(|spec| parse_job_output_spec(spec, true)),
+                    )
+                    .collect();
+                let mut client = JobServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .register_job_type(RegisterJobTypeRequest {
+                        job_type: Some(JobTypeInfo {
+                            job_type,
+                            display_name,
+                            service_addr,
+                            cancellable: !not_cancellable,
+                            outputs,
+                            builtin: false,
+                        }),
+                    })
+                    .await?
+                    .into_inner();
+                println!("created={}", resp.created);
+                if let Some(info) = resp.job_type.as_ref() {
+                    print_job_type(info);
+                }
+            }
+            JobCmd::NotifyAdd {
+                addr,
+                name,
+                rule_id,
+                job_type,
+                state,
+                run_id,
+                webhook,
+                command,
+            } => {
+                update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let (states, unknown_states) = parse_job_states(&state);
+                if !unknown_states.is_empty() {
+                    eprintln!("Unknown states: {}", unknown_states.join(", "));
+                }
+                let mut client = JobServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .create_notification_rule(CreateNotificationRuleRequest {
+                        rule: Some(NotificationRule {
+                            rule_id,
+                            name,
+                            job_types: split_tokens(&job_type),
+                            states,
+                            run_id: run_id.map(|value| RunId { value }),
+                            webhook_url: webhook.unwrap_or_default(),
+                            command,
+                            disabled: false,
+                        }),
+                    })
+                    .await?
+                    .into_inner();
+                if let Some(rule) = resp.rule.as_ref() {
+                    print_notification_rule(rule);
+                }
+            }
+            JobCmd::NotifyList { addr } => {
+                update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let mut client = JobServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .list_notification_rules(ListNotificationRulesRequest {})
+                    .await?
+                    .into_inner();
+                for rule in resp.rules {
+                    print_notification_rule(&rule);
+                }
+            }
+            JobCmd::NotifyRemove { addr, rule_id } => {
+                update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let mut client = JobServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .delete_notification_rule(DeleteNotificationRuleRequest { rule_id })
+                    .await?
+                    .into_inner();
+                println!("deleted={}", resp.deleted);
+            }
+            JobCmd::SecretSet {
+                addr,
+                name,
+                value,
+                description,
+            } => {
+                update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let value = match value {
+                    Some(value) => value,
+                    None => {
+                        let mut line = String::new();
+                        io::stdin().read_line(&mut line)?;
+                        line.trim_end_matches(['\r', '\n']).to_string()
+                    }
+                };
+                let mut client = JobServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .set_secret(SetSecretRequest {
+                        name,
+                        value,
+                        description,
+                    })
+                    .await?
+                    .into_inner();
+                println!("created={}", resp.created);
+                if let Some(secret) = resp.secret.as_ref() {
+                    print_secret(secret);
+                }
+            }
+            JobCmd::SecretList { addr } => {
+                update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let mut client = JobServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .list_secrets(ListSecretsRequest {})
+                    .await?
+                    .into_inner();
+                for secret in resp.secrets {
+                    print_secret(&secret);
+                }
+            }
+            JobCmd::SecretRemove { addr, name } => {
+                update_cli_config(|cfg| cfg.job_addr = addr.clone());
+                let mut client = JobServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .delete_secret(DeleteSecretRequest { name })
+                    .await?
+                    .into_inner();
+                println!("deleted={}", resp.deleted);
+            }
         },
 
         Cmd::Toolchain { cmd } => match cmd {
@@ -1156,7 +1857,11 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     let set_id = set.toolchain_set_id.map(|i| i.value).unwrap_or_default();
                     let sdk = set.sdk_toolchain_id.map(|i| i.value).unwrap_or_default();
                     let ndk = set.ndk_toolchain_id.map(|i| i.value).unwrap_or_default();
-                    println!("{}\t{}\tsdk={}\tndk={}", set_id, set.display_name, sdk, ndk);
+                    let jdk = set.jdk_toolchain_id.map(|i| i.value).unwrap_or_default();
+                    println!(
+                        "{}\t{}\tsdk={}\tndk={}\tjdk={}",
+                        set_id, set.display_name, sdk, ndk, jdk
+                    );
                 }
                 if let Some(page_info) = resp.page_info {
                     if !page_info.next_page_token.is_empty() {
@@ -1168,6 +1873,7 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 addr,
                 sdk_toolchain_id,
                 ndk_toolchain_id,
+                jdk_toolchain_id,
                 display_name,
             } => {
                 update_cli_config(|cfg| cfg.toolchain_addr = addr.clone());
@@ -1179,8 +1885,14 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     .as_ref()
                     .map(|s| s.trim())
                     .filter(|s| !s.is_empty());
-                if sdk_id.is_none() && ndk_id.is_none() {
-                    eprintln!("Provide --sdk-toolchain-id and/or --ndk-toolchain-id");
+                let jdk_id = jdk_toolchain_id
+                    .as_ref()
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty());
+                if sdk_id.is_none() && ndk_id.is_none() && jdk_id.is_none() {
+                    eprintln!(
+                        "Provide --sdk-toolchain-id, --ndk-toolchain-id and/or --jdk-toolchain-id"
+                    );
                     return Ok(());
                 }
                 let mut client = ToolchainServiceClient::new(connect(&addr).await?);
@@ -1193,6 +1905,9 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                             value: value.to_string(),
                         }),
                         display_name: display_name.unwrap_or_default(),
+                        jdk_toolchain_id: jdk_id.map(|value| Id {
+                            value: value.to_string(),
+                        }),
                     })
                     .await?
                     .into_inner();
@@ -1200,8 +1915,9 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     let set_id = set.toolchain_set_id.map(|i| i.value).unwrap_or_default();
                     let sdk = set.sdk_toolchain_id.map(|i| i.value).unwrap_or_default();
                     let ndk = set.ndk_toolchain_id.map(|i| i.value).unwrap_or_default();
+                    let jdk = set.jdk_toolchain_id.map(|i| i.value).unwrap_or_default();
                     println!(
-                        "set_id={set_id}\tdisplay_name={}\tsdk={sdk}\tndk={ndk}",
+                        "set_id={set_id}\tdisplay_name={}\tsdk={sdk}\tndk={ndk}\tjdk={jdk}",
                         set.display_name
                     );
                 } else {
@@ -1235,8 +1951,9 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     let set_id = set.toolchain_set_id.map(|i| i.value).unwrap_or_default();
                     let sdk = set.sdk_toolchain_id.map(|i| i.value).unwrap_or_default();
                     let ndk = set.ndk_toolchain_id.map(|i| i.value).unwrap_or_default();
+                    let jdk = set.jdk_toolchain_id.map(|i| i.value).unwrap_or_default();
                     println!(
-                        "set_id={set_id}\tdisplay_name={}\tsdk={sdk}\tndk={ndk}",
+                        "set_id={set_id}\tdisplay_name={}\tsdk={sdk}\tndk={ndk}\tjdk={jdk}",
                         set.display_name
                     );
                 } else {
@@ -1981,6 +2698,7 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 task,
                 gradle_arg,
                 clean_first,
+                secrets,
                 job_id,
                 correlation_id,
                 run_id,
@@ -1998,6 +2716,9 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     eprintln!("unsupported variant: {variant}");
                     return Ok(());
                 };
+                let Some(secrets) = parse_secret_specs(&secrets) else {
+                    return Ok(());
+                };
 
                 let tasks = task
                     .into_iter()
@@ -2034,6 +2755,83 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                         tasks,
                         correlation_id: correlation_id.unwrap_or_default(),
                         run_id: run_id.map(|value| RunId { value }),
+                        secrets,
+                    })
+                    .await?
+                    .into_inner();
+
+                let job_id = resp.job_id.map(|i| i.value).unwrap_or_default();
+                println!("job_id={job_id}");
+                if !job_id.is_empty() && !no_stream {
+                    stream_job_events_until_done(&job_addr, &job_id).await?;
+                }
+            }
+            BuildCmd::Test {
+                addr,
+                job_addr,
+                project_ref,
+                kind,
+                variant,
+                variant_name,
+                module,
+                device,
+                filters,
+                gradle_arg,
+                secrets,
+                correlation_id,
+                run_id,
+                no_stream,
+            } => {
+                update_cli_config(|cfg| {
+                    cfg.build_addr = addr.clone();
+                    cfg.job_addr = job_addr.clone();
+                });
+                if project_ref.trim().is_empty() {
+                    eprintln!("project_ref is required");
+                    return Ok(());
+                }
+                let kind = match kind.trim().to_ascii_lowercase().as_str() {
+                    "unit" => TestKind::Unit,
+                    "instrumented" | "connected" => TestKind::Instrumented,
+                    other => {
+                        eprintln!("unsupported test kind: {other} (expected unit or instrumented)");
+                        return Ok(());
+                    }
+                };
+                let Some(variant) = parse_build_variant(&variant) else {
+                    eprintln!("unsupported variant: {variant}");
+                    return Ok(());
+                };
+                let Some(secrets) = parse_secret_specs(&secrets) else {
+                    return Ok(());
+                };
+                let gradle_args = gradle_arg
+                    .into_iter()
+                    .map(|arg| arg.trim().to_string())
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| KeyValue {
+                        key: arg,
+                        value: String::new(),
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut client = BuildServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .run_tests(RunTestsRequest {
+                        project_id: Some(Id {
+                            value: project_ref.trim().to_string(),
+                        }),
+                        variant: variant as i32,
+                        variant_name: variant_name.unwrap_or_default().trim().to_string(),
+                        module: module.unwrap_or_default().trim().to_string(),
+                        kind: kind as i32,
+                        device_serial: device.unwrap_or_default().trim().to_string(),
+                        filters,
+                        gradle_args,
+                        job_id: None,
+                        correlation_id: correlation_id.unwrap_or_default(),
+                        run_id: run_id.map(|value| RunId { value }),
+                        secrets,
                     })
                     .await?
                     .into_inner();
@@ -2118,124 +2916,392 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
             }
-        },
-
-        Cmd::Workflow { cmd } => match cmd {
-            WorkflowCmd::RunPipeline {
+            BuildCmd::Report {
                 addr,
-                job_addr,
-                run_id,
-                correlation_id,
                 job_id,
-                project_id,
-                project_path,
-                project_name,
-                template_id,
-                toolchain_id,
-                toolchain_set_id,
-                target_id,
-                variant,
-                module,
-                variant_name,
-                task,
-                apk_path,
-                application_id,
-                activity,
-                step,
-                stream_run,
-                no_stream,
+                project_ref,
+                slowest,
+                all,
             } => {
-                update_cli_config(|cfg| {
-                    cfg.workflow_addr = addr.clone();
-                    cfg.job_addr = job_addr.clone();
-                    cfg.last_job_type = "workflow.pipeline".into();
-                });
-
-                let (options, unknown_steps) = parse_workflow_steps(&step);
-                if !unknown_steps.is_empty() {
-                    eprintln!("Unknown workflow steps: {}", unknown_steps.join(", "));
+                update_cli_config(|cfg| cfg.build_addr = addr.clone());
+                let job_id = job_id
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty());
+                let project_ref = project_ref
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty());
+                if job_id.is_none() && project_ref.is_none() {
+                    eprintln!("Provide --job-id or --project-ref");
+                    return Ok(());
                 }
-                if let Some(opts) = options.as_ref() {
-                    let any = opts.verify_toolchain
-                        || opts.create_project
-                        || opts.open_project
-                        || opts.build
-                        || opts.install_apk
-                        || opts.launch_app
-                        || opts.export_support_bundle
-                        || opts.export_evidence_bundle;
-                    if !any {
-                        eprintln!("No workflow steps enabled; use --step all or omit --step for inference.");
-                        return Ok(());
-                    }
+                let mut client = BuildServiceClient::new(connect(&addr).await?);
+                let report = client
+                    .get_build_report(GetBuildReportRequest {
+                        job_id: job_id.map(|value| Id { value }),
+                        project_id: project_ref.map(|value| Id { value }),
+                        slowest_limit: slowest,
+                    })
+                    .await?
+                    .into_inner()
+                    .report
+                    .unwrap_or_default();
+                println!(
+                    "job_id={}\tproject_id={}\tmodule={}\tvariant={}\tresult={}",
+                    report.job_id.map(|i| i.value).unwrap_or_default(),
+                    report.project_id.map(|i| i.value).unwrap_or_default(),
+                    report.module,
+                    report.variant_name,
+                    if report.failed { "failed" } else { "success" }
+                );
+                println!(
+                    "tasks={}\texecuted={}\tup_to_date={}\tfrom_cache={}\tskipped={}\tfailed={}\tcache_hit_rate={:.0}%\ttask_time_ms={}",
+                    report.tasks.len(),
+                    report.tasks_executed,
+                    report.tasks_up_to_date,
+                    report.tasks_from_cache,
+                    report.tasks_skipped,
+                    report.tasks_failed,
+                    report.cache_hit_rate * 100.0,
+                    report.task_duration_ms
+                );
+                if report.tasks.is_empty() {
+                    println!("no task outcomes recorded for this build");
                 }
-
-                let Some(build_variant) = parse_build_variant(&variant) else {
-                    eprintln!("unsupported variant: {variant}");
-                    return Ok(());
+                let (title, tasks) = if all {
+                    ("tasks", &report.tasks)
+                } else {
+                    ("slowest", &report.slowest_tasks)
                 };
-
-                let tasks = task
-                    .into_iter()
-                    .map(|t| t.trim().to_string())
-                    .filter(|t| !t.is_empty())
-                    .collect::<Vec<_>>();
-
-                let mut client = WorkflowServiceClient::new(connect(&addr).await?);
-                let correlation_id = correlation_id.unwrap_or_default();
+                if !tasks.is_empty() {
+                    println!("{title}:");
+                }
+                for task in tasks {
+                    let outcome = TaskOutcome::try_from(task.outcome)
+                        .unwrap_or(TaskOutcome::Unspecified)
+                        .as_str_name()
+                        .trim_start_matches("TASK_OUTCOME_");
+                    println!(
+                        "  {:>8}ms  {:<10}  {}",
+                        task.duration_ms, outcome, task.path
+                    );
+                }
+            }
+            BuildCmd::Inspect {
+                addr,
+                artifact_path,
+            } => {
+                update_cli_config(|cfg| cfg.build_addr = addr.clone());
+                let mut client = BuildServiceClient::new(connect(&addr).await?);
                 let resp = client
-                    .run_pipeline(WorkflowPipelineRequest {
-                        run_id: run_id.map(|value| RunId { value }),
-                        correlation_id: correlation_id.clone(),
-                        job_id: job_id
-                            .as_ref()
-                            .filter(|value| !value.trim().is_empty())
-                            .map(|value| Id {
-                                value: value.clone(),
-                            }),
-                        project_id: project_id
-                            .as_ref()
-                            .filter(|value| !value.trim().is_empty())
-                            .map(|value| Id {
-                                value: value.clone(),
-                            }),
-                        project_path: project_path.unwrap_or_default().trim().to_string(),
-                        project_name: project_name.unwrap_or_default().trim().to_string(),
-                        template_id: template_id
-                            .as_ref()
-                            .filter(|value| !value.trim().is_empty())
-                            .map(|value| Id {
-                                value: value.clone(),
-                            }),
-                        toolchain_id: toolchain_id
-                            .as_ref()
-                            .filter(|value| !value.trim().is_empty())
-                            .map(|value| Id {
-                                value: value.clone(),
-                            }),
-                        toolchain_set_id: toolchain_set_id
-                            .as_ref()
-                            .filter(|value| !value.trim().is_empty())
-                            .map(|value| Id {
-                                value: value.clone(),
-                            }),
-                        target_id: target_id
-                            .as_ref()
-                            .filter(|value| !value.trim().is_empty())
-                            .map(|value| Id {
-                                value: value.clone(),
-                            }),
-                        build_variant: build_variant as i32,
-                        module: module.unwrap_or_default().trim().to_string(),
-                        variant_name: variant_name.unwrap_or_default().trim().to_string(),
-                        tasks,
-                        apk_path: apk_path.unwrap_or_default().trim().to_string(),
-                        application_id: application_id.unwrap_or_default().trim().to_string(),
-                        activity: activity.unwrap_or_default().trim().to_string(),
-                        options,
+                    .inspect_artifact(InspectArtifactRequest {
+                        artifact_path: expand_user(&artifact_path).to_string_lossy().to_string(),
                     })
                     .await?
                     .into_inner();
+                if let Some(artifact) = resp.artifact.as_ref() {
+                    println!(
+                        "{}\tsize={}\tsha256={}",
+                        artifact.path, artifact.size_bytes, artifact.sha256
+                    );
+                }
+                let inspection = resp.inspection.unwrap_or_default();
+                println!(
+                    "application_id={}\tversion_code={}\tversion_name={}\tmin_sdk={}\ttarget_sdk={}",
+                    inspection.application_id,
+                    inspection.version_code,
+                    inspection.version_name,
+                    inspection.min_sdk,
+                    inspection.target_sdk
+                );
+                if !inspection.split.is_empty() {
+                    println!("split={}", inspection.split);
+                }
+                println!("native_abis={}", inspection.native_abis.join(","));
+                println!(
+                    "dex_files={}\tmethods={}\tclasses={}",
+                    inspection.dex_files.len(),
+                    inspection.method_count,
+                    inspection.class_count
+                );
+                for dex in &inspection.dex_files {
+                    println!(
+                        "  {}\tversion={}\tmethods={}\tclasses={}\tfields={}",
+                        dex.name, dex.version, dex.method_count, dex.class_count, dex.field_count
+                    );
+                }
+                println!("permissions:");
+                for permission in &inspection.permissions {
+                    println!("  {permission}");
+                }
+            }
+            BuildCmd::KeystoreCreate {
+                addr,
+                name,
+                alias,
+                dname,
+                validity_days,
+                store_password_stdin,
+            } => {
+                update_cli_config(|cfg| cfg.build_addr = addr.clone());
+                let store_password = if store_password_stdin {
+                    read_stdin_line()?
+                } else {
+                    String::new()
+                };
+                let mut client = BuildServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .create_keystore(CreateKeystoreRequest {
+                        name,
+                        alias: alias.unwrap_or_default(),
+                        distinguished_name: dname.unwrap_or_default(),
+                        validity_days,
+                        store_password,
+                    })
+                    .await?
+                    .into_inner();
+                if let Some(keystore) = resp.keystore.as_ref() {
+                    print_keystore(keystore);
+                }
+            }
+            BuildCmd::KeystoreImport {
+                addr,
+                name,
+                path,
+                alias,
+                key_password_stdin,
+            } => {
+                update_cli_config(|cfg| cfg.build_addr = addr.clone());
+                let store_password = read_stdin_line()?;
+                let key_password = if key_password_stdin {
+                    read_stdin_line()?
+                } else {
+                    String::new()
+                };
+                let mut client = BuildServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .import_keystore(ImportKeystoreRequest {
+                        name,
+                        path: expand_user(&path).to_string_lossy().to_string(),
+                        alias: alias.unwrap_or_default(),
+                        store_password,
+                        key_password,
+                    })
+                    .await?
+                    .into_inner();
+                if let Some(keystore) = resp.keystore.as_ref() {
+                    print_keystore(keystore);
+                }
+            }
+            BuildCmd::KeystoreList { addr } => {
+                update_cli_config(|cfg| cfg.build_addr = addr.clone());
+                let mut client = BuildServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .list_keystores(ListKeystoresRequest {})
+                    .await?
+                    .into_inner();
+                for keystore in resp.keystores {
+                    print_keystore(&keystore);
+                }
+            }
+            BuildCmd::KeystoreRemove { addr, keystore_id } => {
+                update_cli_config(|cfg| cfg.build_addr = addr.clone());
+                let mut client = BuildServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .delete_keystore(DeleteKeystoreRequest {
+                        keystore_id: Some(Id { value: keystore_id }),
+                    })
+                    .await?
+                    .into_inner();
+                println!("deleted={}", resp.deleted);
+            }
+            BuildCmd::Sign {
+                addr,
+                job_addr,
+                artifact_path,
+                keystore_id,
+                project_ref,
+                output,
+                correlation_id,
+                run_id,
+                no_stream,
+            } => {
+                update_cli_config(|cfg| {
+                    cfg.build_addr = addr.clone();
+                    cfg.job_addr = job_addr.clone();
+                });
+                let mut client = BuildServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .sign_artifact(SignArtifactRequest {
+                        project_id: project_ref
+                            .map(|v| v.trim().to_string())
+                            .filter(|v| !v.is_empty())
+                            .map(|value| Id { value }),
+                        artifact_path: expand_user(&artifact_path).to_string_lossy().to_string(),
+                        keystore_id: Some(Id { value: keystore_id }),
+                        output_path: output
+                            .map(|path| expand_user(&path).to_string_lossy().to_string())
+                            .unwrap_or_default(),
+                        job_id: None,
+                        correlation_id: correlation_id.unwrap_or_default(),
+                        run_id: run_id.map(|value| RunId { value }),
+                    })
+                    .await?
+                    .into_inner();
+                let job_id = resp.job_id.map(|i| i.value).unwrap_or_default();
+                println!("job_id={job_id}");
+                if !job_id.is_empty() && !no_stream {
+                    stream_job_events_until_done(&job_addr, &job_id).await?;
+                }
+            }
+        },
+
+        Cmd::Workflow { cmd } => match cmd {
+            WorkflowCmd::RunPipeline {
+                addr,
+                job_addr,
+                run_id,
+                correlation_id,
+                job_id,
+                project_id,
+                project_path,
+                project_name,
+                template_id,
+                toolchain_id,
+                toolchain_set_id,
+                target_id,
+                target_ids,
+                target_selector,
+                variant,
+                module,
+                variant_name,
+                task,
+                apk_path,
+                apk_sha256,
+                artifact_run_id,
+                application_id,
+                activity,
+                step,
+                workflow,
+                inputs,
+                secrets,
+                plan,
+                stream_run,
+                no_stream,
+            } => {
+                update_cli_config(|cfg| {
+                    cfg.workflow_addr = addr.clone();
+                    cfg.job_addr = job_addr.clone();
+                    cfg.last_job_type = "workflow.pipeline".into();
+                });
+
+                let (options, unknown_steps) = parse_workflow_steps(&step);
+                if !unknown_steps.is_empty() {
+                    eprintln!("Unknown workflow steps: {}", unknown_steps.join(", "));
+                }
+                if let Some(opts) = options.as_ref() {
+                    let any = opts.verify_toolchain
+                        || opts.create_project
+                        || opts.open_project
+                        || opts.build
+                        || opts.run_tests
+                        || opts.install_apk
+                        || opts.launch_app
+                        || opts.export_support_bundle
+                        || opts.support_bundle_on_failure
+                        || opts.export_evidence_bundle;
+                    if !any {
+                        eprintln!("No workflow steps enabled; use --step all or omit --step for inference.");
+                        return Ok(());
+                    }
+                }
+
+                let Some(build_variant) = parse_build_variant(&variant) else {
+                    eprintln!("unsupported variant: {variant}");
+                    return Ok(());
+                };
+                let Some(secrets) = parse_secret_specs(&secrets) else {
+                    return Ok(());
+                };
+
+                let tasks = task
+                    .into_iter()
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect::<Vec<_>>();
+
+                let mut client = WorkflowServiceClient::new(connect(&addr).await?);
+                let correlation_id = correlation_id.unwrap_or_default();
+                let request = WorkflowPipelineRequest {
+                    run_id: run_id.map(|value| RunId { value }),
+                    correlation_id: correlation_id.clone(),
+                    job_id: job_id
+                        .as_ref()
+                        .filter(|value| !value.trim().is_empty())
+                        .map(|value| Id {
+                            value: value.clone(),
+                        }),
+                    project_id: project_id
+                        .as_ref()
+                        .filter(|value| !value.trim().is_empty())
+                        .map(|value| Id {
+                            value: value.clone(),
+                        }),
+                    project_path: project_path.unwrap_or_default().trim().to_string(),
+                    project_name: project_name.unwrap_or_default().trim().to_string(),
+                    template_id: template_id
+                        .as_ref()
+                        .filter(|value| !value.trim().is_empty())
+                        .map(|value| Id {
+                            value: value.clone(),
+                        }),
+                    toolchain_id: toolchain_id
+                        .as_ref()
+                        .filter(|value| !value.trim().is_empty())
+                        .map(|value| Id {
+                            value: value.clone(),
+                        }),
+                    toolchain_set_id: toolchain_set_id
+                        .as_ref()
+                        .filter(|value| !value.trim().is_empty())
+                        .map(|value| Id {
+                            value: value.clone(),
+                        }),
+                    target_id: target_id
+                        .as_ref()
+                        .filter(|value| !value.trim().is_empty())
+                        .map(|value| Id {
+                            value: value.clone(),
+                        }),
+                    build_variant: build_variant as i32,
+                    module: module.unwrap_or_default().trim().to_string(),
+                    variant_name: variant_name.unwrap_or_default().trim().to_string(),
+                    tasks,
+                    apk_path: apk_path.unwrap_or_default().trim().to_string(),
+                    application_id: application_id.unwrap_or_default().trim().to_string(),
+                    activity: activity.unwrap_or_default().trim().to_string(),
+                    options,
+                    workflow: workflow.unwrap_or_default().trim().to_string(),
+                    inputs: parse_kv_params(&inputs),
+                    target_ids: target_ids
+                        .into_iter()
+                        .map(|value| value.trim().to_string())
+                        .filter(|value| !value.is_empty())
+                        .map(|value| Id { value })
+                        .collect(),
+                    target_selector: target_selector.unwrap_or_default().trim().to_string(),
+                    secrets,
+                    apk_sha256: apk_sha256.unwrap_or_default().trim().to_string(),
+                    artifact_run_id: artifact_run_id
+                        .map(|value| value.trim().to_string())
+                        .filter(|value| !value.is_empty())
+                        .map(|value| RunId { value }),
+                };
+                if plan {
+                    let plan = client.plan_pipeline(request).await?.into_inner();
+                    print_pipeline_plan(&plan);
+                    return Ok(());
+                }
+                let resp = client.run_pipeline(request).await?.into_inner();
 
                 let run_id = resp
                     .run_id
@@ -2278,15 +3344,189 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                         run_id_opt,
                         correlation_opt,
                         true,
+                        Vec::new(),
                         None,
                         None,
                         None,
                     )
                     .await?;
                 } else {
-                    stream_job_events(&job_addr, &job_id, true, true).await?;
+                    stream_job_events(&job_addr, &job_id, true, 0, true, None).await?;
                 }
             }
+            WorkflowCmd::Resume {
+                addr,
+                job_addr,
+                run_id,
+                job_id,
+                inputs,
+                no_stream,
+            } => {
+                update_cli_config(|cfg| {
+                    cfg.workflow_addr = addr.clone();
+                    cfg.job_addr = job_addr.clone();
+                    cfg.last_job_type = "workflow.pipeline".into();
+                });
+                let mut client = WorkflowServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .resume_pipeline(ResumePipelineRequest {
+                        run_id: Some(RunId {
+                            value: run_id.trim().to_string(),
+                        }),
+                        job_id: job_id
+                            .filter(|value| !value.trim().is_empty())
+                            .map(|value| Id { value }),
+                        inputs: parse_kv_params(&inputs),
+                    })
+                    .await?
+                    .into_inner();
+
+                let job_id = resp
+                    .job_id
+                    .as_ref()
+                    .map(|id| id.value.clone())
+                    .unwrap_or_default();
+                println!("run_id={}\njob_id={job_id}", run_id.trim());
+                for kv in &resp.outputs {
+                    println!("{}={}", kv.key, kv.value);
+                }
+
+                if job_id.is_empty() {
+                    return Ok(());
+                }
+                update_cli_config(|cfg| cfg.last_job_id = job_id.clone());
+                if !no_stream {
+                    stream_job_events(&job_addr, &job_id, true, 0, true, None).await?;
+                }
+            }
+            WorkflowCmd::List {
+                addr,
+                project_id,
+                project_path,
+            } => {
+                update_cli_config(|cfg| cfg.workflow_addr = addr.clone());
+                let mut client = WorkflowServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .list_workflows(ListWorkflowsRequest {
+                        project_id: project_id
+                            .filter(|value| !value.trim().is_empty())
+                            .map(|value| Id { value }),
+                        project_path: project_path.unwrap_or_default().trim().to_string(),
+                    })
+                    .await?
+                    .into_inner();
+                for workflow in resp.workflows {
+                    print_workflow_info(&workflow);
+                }
+            }
+            WorkflowCmd::TriggerAdd {
+                addr,
+                name,
+                trigger_id,
+                cron,
+                timezone,
+                watch_path,
+                ignore,
+                debounce_ms,
+                disabled,
+                project_id,
+                project_path,
+                toolchain_set_id,
+                target_id,
+                target_ids,
+                target_selector,
+                variant,
+                module,
+                application_id,
+                activity,
+                step,
+                workflow,
+                inputs,
+            } => {
+                update_cli_config(|cfg| cfg.workflow_addr = addr.clone());
+                let (options, unknown_steps) = parse_workflow_steps(&step);
+                if !unknown_steps.is_empty() {
+                    eprintln!("Unknown workflow steps: {}", unknown_steps.join(", "));
+                }
+                let Some(build_variant) = parse_build_variant(&variant) else {
+                    eprintln!("unsupported variant: {variant}");
+                    return Ok(());
+                };
+                let id = |value: Option<String>| {
+                    value
+                        .map(|value| value.trim().to_string())
+                        .filter(|value| !value.is_empty())
+                        .map(|value| Id { value })
+                };
+                let pipeline = WorkflowPipelineRequest {
+                    project_id: id(project_id),
+                    project_path: project_path.unwrap_or_default().trim().to_string(),
+                    toolchain_set_id: id(toolchain_set_id),
+                    target_id: id(target_id),
+                    target_ids: target_ids
+                        .into_iter()
+                        .filter_map(|value| id(Some(value)))
+                        .collect(),
+                    target_selector: target_selector.unwrap_or_default().trim().to_string(),
+                    build_variant: build_variant as i32,
+                    module: module.unwrap_or_default().trim().to_string(),
+                    application_id: application_id.unwrap_or_default().trim().to_string(),
+                    activity: activity.unwrap_or_default().trim().to_string(),
+                    options,
+                    workflow: workflow.unwrap_or_default().trim().to_string(),
+                    inputs: parse_kv_params(&inputs),
+                    ..Default::default()
+                };
+                let mut client = WorkflowServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .create_trigger(CreateTriggerRequest {
+                        trigger: Some(WorkflowTrigger {
+                            trigger_id,
+                            name,
+                            pipeline: Some(pipeline),
+                            cron: cron.unwrap_or_default(),
+                            timezone,
+                            watch_path: watch_path
+                                .map(|path| {
+                                    let path = expand_user(&path);
+                                    fs::canonicalize(&path)
+                                        .unwrap_or(path)
+                                        .display()
+                                        .to_string()
+                                })
+                                .unwrap_or_default(),
+                            ignore,
+                            debounce_ms,
+                            disabled,
+                            ..Default::default()
+                        }),
+                    })
+                    .await?
+                    .into_inner();
+                if let Some(trigger) = resp.trigger.as_ref() {
+                    print_workflow_trigger(trigger);
+                }
+            }
+            WorkflowCmd::TriggerList { addr } => {
+                update_cli_config(|cfg| cfg.workflow_addr = addr.clone());
+                let mut client = WorkflowServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .list_triggers(ListTriggersRequest {})
+                    .await?
+                    .into_inner();
+                for trigger in resp.triggers {
+                    print_workflow_trigger(&trigger);
+                }
+            }
+            WorkflowCmd::TriggerRemove { addr, trigger_id } => {
+                update_cli_config(|cfg| cfg.workflow_addr = addr.clone());
+                let mut client = WorkflowServiceClient::new(connect(&addr).await?);
+                let resp = client
+                    .delete_trigger(DeleteTriggerRequest { trigger_id })
+                    .await?
+                    .into_inner();
+                println!("deleted={}", resp.deleted);
+            }
         },
         Cmd::State { cmd } => match cmd {
             StateCmd::Save {
@@ -2420,14 +3660,16 @@ async fn stream_job_events_until_done(
     addr: &str,
     job_id: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    stream_job_events(addr, job_id, true, true).await
+    stream_job_events(addr, job_id, true, 0, true, None).await
 }
 
 async fn stream_job_events(
     addr: &str,
     job_id: &str,
     include_history: bool,
+    after_seq: u64,
     stop_on_done: bool,
+    min_level: Option<LogLevel>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut client = JobServiceClient::new(connect(addr).await?);
     let mut stream = client
@@ -2436,19 +3678,23 @@ async fn stream_job_events(
                 value: job_id.to_string(),
             }),
             include_history,
+            after_seq,
         })
         .await?
         .into_inner();
 
+    let mut last_seq = after_seq;
     while let Some(item) = stream.next().await {
         match item {
             Ok(evt) => {
-                if render_job_event(&evt) && stop_on_done {
+                last_seq = last_seq.max(evt.seq);
+                if render_job_event(&evt, min_level) && stop_on_done {
                     break;
                 }
             }
             Err(err) => {
                 eprintln!("stream error: {err}");
+                eprintln!("resume with: --after-seq {last_seq}");
                 break;
             }
         }
@@ -2457,15 +3703,39 @@ async fn stream_job_events(
     Ok(())
 }
 
+/// Parses a `JOB_ID:SEQ` resume cursor.
+fn parse_job_event_cursor(value: &str) -> Option<JobEventCursor> {
+    let (job_id, seq) = value.trim().rsplit_once(':')?;
+    let job_id = job_id.trim();
+    if job_id.is_empty() {
+        return None;
+    }
+    Some(JobEventCursor {
+        job_id: Some(Id {
+            value: job_id.to_string(),
+        }),
+        after_seq: seq.trim().parse().ok()?,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn stream_run_events(
     addr: &str,
     run_id: Option<String>,
     correlation_id: Option<String>,
     include_history: bool,
+    cursors: Vec<JobEventCursor>,
     buffer_max_events: Option<u32>,
     max_delay_ms: Option<u64>,
     discovery_interval_ms: Option<u64>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let mut last_seqs: BTreeMap<String, u64> = cursors
+        .iter()
+        .filter_map(|cursor| {
+            let job_id = cursor.job_id.as_ref()?.value.clone();
+            Some((job_id, cursor.after_seq))
+        })
+        .collect();
     let mut client = JobServiceClient::new(connect(addr).await?);
     let mut stream = client
         .stream_run_events(StreamRunEventsRequest {
@@ -2475,6 +3745,7 @@ async fn stream_run_events(
             buffer_max_events: buffer_max_events.unwrap_or(0),
             max_delay_ms: max_delay_ms.unwrap_or(0),
             discovery_interval_ms: discovery_interval_ms.unwrap_or(0),
+            cursors,
         })
         .await?
         .into_inner();
@@ -2482,10 +3753,22 @@ async fn stream_run_events(
     while let Some(item) = stream.next().await {
         match item {
             Ok(evt) => {
-                render_job_event(&evt);
+                if let Some(job_id) = evt.job_id.as_ref().filter(|_| evt.seq > 0) {
+                    let last = last_seqs.entry(job_id.value.clone()).or_default();
+                    *last = (*last).max(evt.seq);
+                }
+                render_job_event(&evt, None);
             }
             Err(err) => {
                 eprintln!("stream error: {err}");
+                if !last_seqs.is_empty() {
+                    let resume = last_seqs
+                        .iter()
+                        .map(|(job_id, seq)| format!("--cursor {job_id}:{seq}"))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    eprintln!("resume with: {resume}");
+                }
                 break;
             }
         }
@@ -2494,7 +3777,10 @@ async fn stream_run_events(
     Ok(())
 }
 
-fn render_job_event(event: &JobEvent) -> bool {
+/// Prints one event; returns true once the job is finished. With
+/// `min_level`, log events print their structured records at or above that
+/// level and raw chunks are skipped.
+fn render_job_event(event: &JobEvent, min_level: Option<LogLevel>) -> bool {
     let ts = event
         .at
         .as_ref()
@@ -2519,7 +3805,13 @@ fn render_job_event(event: &JobEvent) -> bool {
             false
         }
         Some(JobPayload::Log(log)) => {
-            if let Some(chunk) = log.chunk.as_ref() {
+            if let Some(min_level) = min_level {
+                for record in &log.records {
+                    if record.level >= min_level as i32 {
+                        println!("{}", format_log_record(record));
+                    }
+                }
+            } else if let Some(chunk) = log.chunk.as_ref() {
                 print!("{}", String::from_utf8_lossy(&chunk.data));
                 let _ = std::io::stdout().flush();
             }
@@ -2533,15 +3825,20 @@ fn render_job_event(event: &JobEvent) -> bool {
             true
         }
         Some(JobPayload::Failed(failed)) => {
+            let label = if failed.will_retry {
+                format!("attempt {} failed, retrying", event.attempt)
+            } else {
+                "failed".to_string()
+            };
             if let Some(err) = failed.error.as_ref() {
-                println!("{ts} failed: {} ({})", err.message, err.code);
+                println!("{ts} {label}: {} ({})", err.message, err.code);
                 if !err.technical_details.is_empty() {
                     println!("details: {}", err.technical_details);
                 }
             } else {
-                println!("{ts} failed");
+                println!("{ts} {label}");
             }
-            true
+            !failed.will_retry
         }
         None => {
             println!("{ts} event=unknown");
@@ -2550,6 +3847,240 @@ fn render_job_event(event: &JobEvent) -> bool {
     }
 }
 
+fn print_job_type(info: &JobTypeInfo) {
+    let origin = if info.builtin {
+        "builtin"
+    } else {
+        "registered"
+    };
+    let service = if info.service_addr.is_empty() {
+        "-"
+    } else {
+        info.service_addr.as_str()
+    };
+    println!(
+        "{}\t{}\t{origin}\tservice={service}\tcancellable={}",
+        info.job_type, info.display_name, info.cancellable
+    );
+    for output in &info.outputs {
+        let required = if output.required { " (required)" } else { "" };
+        if output.description.is_empty() {
+            println!("  output {}{required}", output.key);
+        } else {
+            println!("  output {}{required}: {}", output.key, output.description);
+        }
+    }
+}
+
+fn print_notification_rule(rule: &NotificationRule) {
+    let join_or_any = |items: Vec<String>| {
+        if items.is_empty() {
+            "*".to_string()
+        } else {
+            items.join(",")
+        }
+    };
+    let states = rule
+        .states
+        .iter()
+        .map(|state| {
+            let state = JobState::try_from(*state).unwrap_or(JobState::Unspecified);
+            format!("{state:?}")
+        })
+        .collect();
+    let run_id = rule
+        .run_id
+        .as_ref()
+        .map(|id| id.value.as_str())
+        .unwrap_or("*");
+    let action = if rule.webhook_url.is_empty() {
+        format!("command={}", rule.command.join(" "))
+    } else {
+        format!("webhook={}", rule.webhook_url)
+    };
+    let disabled = if rule.disabled { "\tdisabled" } else { "" };
+    println!(
+        "{}\t{}\tjob_types={}\tstates={}\trun_id={run_id}\t{action}{disabled}",
+        rule.rule_id,
+        rule.name,
+        join_or_any(rule.job_types.clone()),
+        join_or_any(states),
+    );
+}
+
+fn read_stdin_line() -> io::Result<String> {
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn print_keystore(keystore: &KeystoreInfo) {
+    println!(
+        "{}\t{}\talias={}\tcert_sha256={}\timported={}\t{}",
+        keystore
+            .keystore_id
+            .as_ref()
+            .map(|id| id.value.as_str())
+            .unwrap_or_default(),
+        keystore.name,
+        keystore.alias,
+        keystore.cert_sha256,
+        keystore.imported,
+        keystore.subject
+    );
+}
+
+fn print_secret(secret: &SecretInfo) {
+    let updated = secret
+        .updated_at
+        .as_ref()
+        .map(|ts| ts.unix_millis)
+        .unwrap_or_default();
+    println!(
+        "{}\tupdated_at={updated}\t{}",
+        secret.name, secret.description
+    );
+}
+
+fn print_pipeline_plan(plan: &PlanPipelineResponse) {
+    println!("workflow={}", plan.workflow);
+    for value in &plan.resolved {
+        println!("  {}={} ({})", value.key, value.value, value.source);
+    }
+    if !plan.targets.is_empty() {
+        let targets: Vec<&str> = plan.targets.iter().map(|id| id.value.as_str()).collect();
+        println!("  targets={}", targets.join(","));
+    }
+    for step in &plan.steps {
+        let target = step
+            .target_id
+            .as_ref()
+            .map(|id| format!(" @{}", id.value))
+            .unwrap_or_default();
+        let status = if step.skipped {
+            " [skipped]"
+        } else if !step.missing_inputs.is_empty() {
+            " [missing inputs]"
+        } else {
+            ""
+        };
+        let on_failure = if step.on_failure { " (on_failure)" } else { "" };
+        println!(
+            "wave {} step {}{target}: {}{on_failure}{status}",
+            step.wave, step.id, step.action
+        );
+        if !step.condition.is_empty() {
+            println!("    if: {}", step.condition);
+        }
+        if !step.skipped_reason.is_empty() {
+            println!("    reason: {}", step.skipped_reason);
+        }
+        for kv in step.inputs.iter().filter(|kv| !kv.value.is_empty()) {
+            println!("    {}={}", kv.key, kv.value);
+        }
+        if !step.missing_inputs.is_empty() {
+            println!("    missing: {}", step.missing_inputs.join(", "));
+        }
+    }
+    for warning in &plan.warnings {
+        println!("warning: {warning}");
+    }
+    for error in &plan.errors {
+        println!("error: {error}");
+    }
+}
+
+fn print_workflow_trigger(trigger: &WorkflowTrigger) {
+    let source = if trigger.cron.is_empty() {
+        format!(
+            "watch={}\tignore={}\tdebounce_ms={}",
+            trigger.watch_path,
+            trigger.ignore.join(","),
+            trigger.debounce_ms
+        )
+    } else {
+        format!("cron={}\ttimezone={}", trigger.cron, trigger.timezone)
+    };
+    let pipeline = trigger
+        .pipeline
+        .as_ref()
+        .map(|req| {
+            if req.workflow.is_empty() {
+                format!("project={}", req.project_path)
+            } else {
+                format!("workflow={}\tproject={}", req.workflow, req.project_path)
+            }
+        })
+        .unwrap_or_default();
+    let disabled = if trigger.disabled { "\tdisabled" } else { "" };
+    println!(
+        "{}\t{}\t{source}\t{pipeline}{disabled}",
+        trigger.trigger_id, trigger.name
+    );
+    if trigger.last_fired_unix_millis > 0 {
+        let run_id = trigger
+            .last_run_id
+            .as_ref()
+            .map(|id| id.value.as_str())
+            .unwrap_or_default();
+        println!(
+            "  last_fired_unix_millis={}\tlast_run_id={run_id}",
+            trigger.last_fired_unix_millis
+        );
+    }
+    if !trigger.last_error.is_empty() {
+        println!("  last_error={}", trigger.last_error);
+    }
+}
+
+fn print_workflow_info(workflow: &WorkflowInfo) {
+    if !workflow.errors.is_empty() {
+        println!("{}\tinvalid\t{}", workflow.name, workflow.path);
+        for error in &workflow.errors {
+            println!("  error: {error}");
+        }
+        return;
+    }
+    println!(
+        "{}\tsteps={}\t{}",
+        workflow.name,
+        workflow.steps.len(),
+        workflow.path
+    );
+    if !workflow.description.is_empty() {
+        println!("  {}", workflow.description);
+    }
+    for step in &workflow.steps {
+        let mut notes = Vec::new();
+        if !step.needs.is_empty() {
+            notes.push(format!("needs {}", step.needs.join(",")));
+        }
+        if !step.condition.is_empty() {
+            notes.push(format!("if {}", step.condition));
+        }
+        if step.on_failure {
+            notes.push("on_failure".to_string());
+        }
+        if step.continue_on_failure {
+            notes.push("continue_on_failure".to_string());
+        }
+        if notes.is_empty() {
+            println!("  step {}: {}", step.id, step.action);
+        } else {
+            println!("  step {}: {} ({})", step.id, step.action, notes.join("; "));
+        }
+    }
+}
+
+fn parse_job_output_spec(spec: &str, required: bool) -> JobOutputSpec {
+    let (key, description) = spec.split_once('=').unwrap_or((spec, ""));
+    JobOutputSpec {
+        key: key.trim().to_string(),
+        description: description.trim().to_string(),
+        required,
+    }
+}
+
 fn split_tokens(values: &[String]) -> Vec<String> {
     values
         .iter()
@@ -2648,11 +4179,15 @@ fn parse_workflow_steps(values: &[String]) -> (Option<WorkflowPipelineOptions>,
             "create" | "create_project" | "project.create" => opts.create_project = true,
             "open" | "open_project" | "project.open" => opts.open_project = true,
             "build" | "build.run" => opts.build = true,
+            "test" | "tests" | "build.test" => opts.run_tests = true,
             "install" | "install_apk" | "targets.install" => opts.install_apk = true,
             "launch" | "launch_app" | "targets.launch" => opts.launch_app = true,
             "support" | "support_bundle" | "observe.support_bundle" => {
                 opts.export_support_bundle = true
             }
+            "support_on_failure" | "failure_bundle" | "support_bundle_on_failure" => {
+                opts.support_bundle_on_failure = true
+            }
             "evidence" | "evidence_bundle" | "observe.evidence_bundle" => {
                 opts.export_evidence_bundle = true
             }
@@ -2674,6 +4209,11 @@ fn render_job_summary(job: &Job) {
         .map(|id| id.value.as_str())
         .unwrap_or("-");
     let state = JobState::try_from(job.state).unwrap_or(JobState::Unspecified);
+    let priority = match JobPriority::try_from(job.priority).unwrap_or(JobPriority::Unspecified) {
+        JobPriority::Background => "background",
+        JobPriority::Interactive => "interactive",
+        _ => "normal",
+    };
     let created = job
         .created_at
         .as_ref()
@@ -2685,7 +4225,7 @@ fn render_job_summary(job: &Job) {
         .map(|ts| ts.unix_millis)
         .unwrap_or_default();
     println!(
-        "{}\t{}\tstate={state:?}\trun_id={run_id}\tcreated={created}\tfinished={finished}",
+        "{}\t{}\tstate={state:?}\tpriority={priority}\trun_id={run_id}\tcreated={created}\tfinished={finished}",
         job_id, job.job_type
     );
 }
@@ -2821,6 +4361,11 @@ impl LogExportEvent {
                     .as_ref()
                     .map(|err| format!("{} ({})", err.message, err.code))
                     .unwrap_or_else(|| "failed".into());
+                let summary = if failed.will_retry {
+                    format!("attempt {} failed, retrying: {summary}", event.attempt)
+                } else {
+                    summary
+                };
                 Self {
                     at_unix_millis,
                     kind: "failed".into(),
@@ -2838,6 +4383,20 @@ impl LogExportEvent {
     }
 }
 
+fn parse_secret_specs(specs: &[String]) -> Option<Vec<SecretRef>> {
+    let mut refs = Vec::new();
+    for spec in specs.iter().filter(|spec| !spec.trim().is_empty()) {
+        match parse_secret_ref(spec) {
+            Ok(secret) => refs.push(secret),
+            Err(err) => {
+                eprintln!("{err}");
+                return None;
+            }
+        }
+    }
+    Some(refs)
+}
+
 fn parse_build_variant(value: &str) -> Option<BuildVariant> {
     match value.trim().to_ascii_lowercase().as_str() {
         "" | "unspecified" => Some(BuildVariant::Unspecified),
@@ -2847,6 +4406,50 @@ fn parse_build_variant(value: &str) -> Option<BuildVariant> {
     }
 }
 
+fn parse_job_priority(value: &str) -> Option<JobPriority> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "background" | "low" => Some(JobPriority::Background),
+        "normal" => Some(JobPriority::Normal),
+        "interactive" | "high" => Some(JobPriority::Interactive),
+        _ => None,
+    }
+}
+
+fn parse_log_level(value: &str) -> Option<LogLevel> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "debug" => Some(LogLevel::Debug),
+        "info" => Some(LogLevel::Info),
+        "warn" | "warning" => Some(LogLevel::Warn),
+        "error" => Some(LogLevel::Error),
+        _ => None,
+    }
+}
+
+fn format_log_record(record: &LogRecord) -> String {
+    let level = match LogLevel::try_from(record.level).unwrap_or(LogLevel::Unspecified) {
+        LogLevel::Debug => "debug",
+        LogLevel::Info => "info",
+        LogLevel::Warn => "warn",
+        LogLevel::Error => "error",
+        LogLevel::Unspecified => "-",
+    };
+    let mut out = format!("{level:<5} {}", record.source);
+    if !record.task.is_empty() {
+        out.push_str(&format!(" [{}]", record.task));
+    }
+    if !record.file.is_empty() {
+        out.push_str(&format!(" {}", record.file));
+        if record.line > 0 {
+            out.push_str(&format!(":{}", record.line));
+            if record.column > 0 {
+                out.push_str(&format!(":{}", record.column));
+            }
+        }
+    }
+    out.push_str(&format!(": {}", record.message));
+    out
+}
+
 fn parse_run_output_kind(value: &str) -> Option<RunOutputKind> {
     match value.trim().to_ascii_lowercase().as_str() {
         "" | "unspecified" => Some(RunOutputKind::Unspecified),
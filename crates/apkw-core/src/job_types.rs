@@ -0,0 +1,355 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use apkw_proto::apkw::v1::{JobOutputSpec, JobTypeInfo};
+use apkw_util::{
+    build_addr, observe_addr, project_addr, state_file_path, targets_addr, toolchain_addr,
+    workflow_addr, write_json_atomic,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tonic::Status;
+use tracing::warn;
+
+const JOB_TYPES_FILE_NAME: &str = "job-types.json";
+const MAX_JOB_TYPE_LEN: usize = 128;
+
+type ServiceAddr = fn() -> String;
+
+/// Job types run by the bundled services: (job type, display name, owner).
+/// Jobs have always been labelled with their type, so only the pipeline job
+/// carries a display name of its own.
+const BUILTIN_JOB_TYPES: &[(&str, &str, ServiceAddr)] = &[
+    ("workflow.pipeline", "Workflow Pipeline", workflow_addr),
+    ("project.create", "", project_addr),
+    ("build.run", "", build_addr),
+    ("build.test", "", build_addr),
+    ("build.sign", "", build_addr),
+    ("toolchain.install", "", toolchain_addr),
+    ("toolchain.verify", "", toolchain_addr),
+    ("toolchain.update", "", toolchain_addr),
+    ("toolchain.uninstall", "", toolchain_addr),
+    ("toolchain.cleanup_cache", "", toolchain_addr),
+    ("targets.install", "", targets_addr),
+    ("targets.launch", "", targets_addr),
+    ("targets.stop", "", targets_addr),
+    ("targets.cuttlefish.install", "", targets_addr),
+    ("targets.cuttlefish.start", "", targets_addr),
+    ("targets.cuttlefish.stop", "", targets_addr),
+    ("observe.support_bundle", "", observe_addr),
+    ("observe.evidence_bundle", "", observe_addr),
+];
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct JobTypesFile {
+    job_types: Vec<JobTypeRecord>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct JobTypeRecord {
+    job_type: String,
+    display_name: String,
+    service_addr: String,
+    cancellable: bool,
+    outputs: Vec<JobOutputRecord>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct JobOutputRecord {
+    key: String,
+    description: String,
+    required: bool,
+}
+
+impl JobTypeRecord {
+    fn from_proto(item: &JobTypeInfo) -> Self {
+        Self {
+            job_type: item.job_type.clone(),
+            display_name: item.display_name.clone(),
+            service_addr: item.service_addr.clone(),
+            cancellable: item.cancellable,
+            outputs: item
+                .outputs
+                .iter()
+                .map(|output| JobOutputRecord {
+                    key: output.key.clone(),
+                    description: output.description.clone(),
+                    required: output.required,
+                })
+                .collect(),
+        }
+    }
+
+    fn into_proto(self) -> JobTypeInfo {
+        JobTypeInfo {
+            job_type: self.job_type,
+            display_name: self.display_name,
+            service_addr: self.service_addr,
+            cancellable: self.cancellable,
+            outputs: self
+                .outputs
+                .into_iter()
+                .map(|output| JobOutputSpec {
+                    key: output.key,
+                    description: output.description,
+                    required: output.required,
+                })
+                .collect(),
+            builtin: false,
+        }
+    }
+}
+
+fn builtin_job_types() -> BTreeMap<String, JobTypeInfo> {
+    BUILTIN_JOB_TYPES
+        .iter()
+        .map(|(job_type, display_name, service_addr)| {
+            (
+                job_type.to_string(),
+                JobTypeInfo {
+                    job_type: job_type.to_string(),
+                    display_name: if display_name.is_empty() {
+                        job_type.to_string()
+                    } else {
+                        display_name.to_string()
+                    },
+                    service_addr: service_addr(),
+                    cancellable: true,
+                    outputs: Vec::new(),
+                    builtin: true,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Validates a registration and fills defaults.
+#[allow(clippy::result_large_err)]
+fn normalize_job_type_info(mut info: JobTypeInfo) -> Result<JobTypeInfo, Status> {
+    info.job_type = info.job_type.trim().to_string();
+    let job_type = info.job_type.as_str();
+    if job_type.is_empty() {
+        return Err(Status::invalid_argument("job_type is required"));
+    }
+    if job_type.len() > MAX_JOB_TYPE_LEN {
+        return Err(Status::invalid_argument(format!(
+            "job_type must be at most {MAX_JOB_TYPE_LEN} characters"
+        )));
+    }
+    let valid_chars = job_type
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-'));
+    if !valid_chars || !job_type.contains('.') || job_type.split('.').any(str::is_empty) {
+        return Err(Status::invalid_argument(format!(
+            "job_type must be dotted lowercase segments (e.g. plugin.lint): {job_type}"
+        )));
+    }
+
+    info.display_name = info.display_name.trim().to_string();
+    if info.display_name.is_empty() {
+        info.display_name = info.job_type.clone();
+    }
+    info.service_addr = info.service_addr.trim().to_string();
+    info.builtin = false;
+
+    let mut keys = HashSet::new();
+    for output in &mut info.outputs {
+        output.key = output.key.trim().to_string();
+        if output.key.is_empty() {
+            return Err(Status::invalid_argument("output keys must not be empty"));
+        }
+        if !keys.insert(output.key.clone()) {
+            return Err(Status::invalid_argument(format!(
+                "duplicate output key: {}",
+                output.key
+            )));
+        }
+    }
+    Ok(info)
+}
+
+/// Job types accepted by `StartJob`: the built-in types plus registrations
+/// persisted in `job-types.json`.
+#[derive(Clone)]
+pub(crate) struct JobTypeRegistry {
+    types: Arc<RwLock<BTreeMap<String, JobTypeInfo>>>,
+    path: PathBuf,
+}
+
+impl JobTypeRegistry {
+    pub(crate) fn load() -> Self {
+        let path = state_file_path(JOB_TYPES_FILE_NAME);
+        let types = Self::read(&path);
+        Self {
+            types: Arc::new(RwLock::new(types)),
+            path,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn at(path: PathBuf) -> Self {
+        let types = Self::read(&path);
+        Self {
+            types: Arc::new(RwLock::new(types)),
+            path,
+        }
+    }
+
+    fn read(path: &Path) -> BTreeMap<String, JobTypeInfo> {
+        let mut types = builtin_job_types();
+        let file = match fs::read(path) {
+            Ok(data) => match serde_json::from_slice::<JobTypesFile>(&data) {
+                Ok(file) => file,
+                Err(err) => {
+                    warn!("ignoring unreadable {}: {err}", path.display());
+                    return types;
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return types,
+            Err(err) => {
+                warn!("failed to read {}: {err}", path.display());
+                return types;
+            }
+        };
+        for record in file.job_types {
+            match normalize_job_type_info(record.into_proto()) {
+                Ok(info) if !types.contains_key(&info.job_type) => {
+                    types.insert(info.job_type.clone(), info);
+                }
+                Ok(info) => warn!(
+                    "skipping registration of built-in job type {}",
+                    info.job_type
+                ),
+                Err(err) => warn!("skipping invalid job type registration: {}", err.message()),
+            }
+        }
+        types
+    }
+
+    /// Re-reads registrations from disk (used by `ReloadState`).
+    pub(crate) async fn reload(&self) -> usize {
+        let types = Self::read(&self.path);
+        let count = types.len();
+        *self.types.write().await = types;
+        count
+    }
+
+    pub(crate) async fn get(&self, job_type: &str) -> Option<JobTypeInfo> {
+        self.types.read().await.get(job_type).cloned()
+    }
+
+    pub(crate) async fn list(&self) -> Vec<JobTypeInfo> {
+        self.types.read().await.values().cloned().collect()
+    }
+
+    /// Adds or updates a registration. Returns the stored entry and whether
+    /// it was newly created.
+    #[allow(clippy::result_large_err)]
+    pub(crate) async fn register(&self, info: JobTypeInfo) -> Result<(JobTypeInfo, bool), Status> {
+        let info = normalize_job_type_info(info)?;
+        let mut types = self.types.write().await;
+        let created = match types.get(&info.job_type) {
+            Some(existing) if existing.builtin => {
+                return Err(Status::failed_precondition(format!(
+                    "{} is a built-in job type",
+                    info.job_type
+                )));
+            }
+            Some(_) => false,
+            None => true,
+        };
+        let mut next = types.clone();
+        next.insert(info.job_type.clone(), info.clone());
+        let file = JobTypesFile {
+            job_types: next
+                .values()
+                .filter(|entry| !entry.builtin)
+                .map(JobTypeRecord::from_proto)
+                .collect(),
+        };
+        write_json_atomic(&self.path, &file).map_err(|err| {
+            Status::internal(format!("failed to persist job type registry: {err}"))
+        })?;
+        *types = next;
+        Ok((info, created))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registrations_are_validated_and_defaulted() {
+        let info = normalize_job_type_info(JobTypeInfo {
+            job_type: " plugin.lint ".into(),
+            cancellable: true,
+            outputs: vec![JobOutputSpec {
+                key: " report ".into(),
+                ..Default::default()
+            }],
+            builtin: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(info.job_type, "plugin.lint");
+        assert_eq!(info.display_name, "plugin.lint");
+        assert_eq!(info.outputs[0].key, "report");
+        assert!(!info.builtin);
+
+        let builtins = builtin_job_types();
+        assert_eq!(builtins["build.run"].display_name, "build.run");
+        assert_eq!(
+            builtins["workflow.pipeline"].display_name,
+            "Workflow Pipeline"
+        );
+
+        for bad in ["", "lint", "Plugin.Lint", "plugin..lint", "plugin lint"] {
+            let result = normalize_job_type_info(JobTypeInfo {
+                job_type: bad.into(),
+                ..Default::default()
+            });
+            assert!(result.is_err(), "{bad:?} should be rejected");
+        }
+    }
+
+    #[tokio::test]
+    async fn registrations_survive_a_reload() {
+        let path = std::env::temp_dir().join(format!(
+            "apkw-core-job-types-{}-{}.json",
+            std::process::id(),
+            apkw_util::now_millis()
+        ));
+        let registry = JobTypeRegistry::at(path.clone());
+        let (info, created) = registry
+            .register(JobTypeInfo {
+                job_type: "plugin.lint".into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(created);
+        let builtin = registry
+            .register(JobTypeInfo {
+                job_type: "build.run".into(),
+                ..Default::default()
+            })
+            .await;
+        assert!(builtin.is_err());
+
+        let reloaded = JobTypeRegistry::at(path.clone());
+        assert_eq!(reloaded.get("plugin.lint").await, Some(info));
+        assert!(reloaded
+            .get("build.run")
+            .await
+            .is_some_and(|info| info.builtin));
+        let _ = fs::remove_file(&path);
+    }
+}
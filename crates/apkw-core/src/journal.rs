@@ -0,0 +1,575 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use apkw_util::write_json_atomic;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+
+use crate::{
+    apply_retention, load_store, read_env_u64, JobStateFile, JobStore, PersistedEvent,
+    PersistedJob, RetentionPolicy, HISTORY_CAPACITY,
+};
+
+const JOURNAL_DIR_NAME: &str = "jobs-journal";
+const SNAPSHOT_FILE_NAME: &str = "snapshot.json";
+const SEGMENT_PREFIX: &str = "segment-";
+const SEGMENT_SUFFIX: &str = ".jsonl";
+const JOURNAL_SCHEMA_VERSION: u32 = 2;
+const JOURNAL_DEBOUNCE_MS: u64 = 250;
+const DEFAULT_SEGMENT_MAX_BYTES: u64 = 4 * 1024 * 1024;
+const DEFAULT_COMPACT_AFTER_BYTES: u64 = 32 * 1024 * 1024;
+
+/// One line of a journal segment. Records are replayed in order on top of the
+/// latest snapshot; replay is idempotent so records that race a compaction
+/// may safely appear in both.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(crate) enum JournalRecord {
+    JobCreated {
+        job: PersistedJob,
+    },
+    JobUpdated {
+        job: PersistedJob,
+    },
+    Event {
+        job_id: String,
+        event: PersistedEvent,
+    },
+}
+
+enum JournalCommand {
+    Append(Box<JournalRecord>),
+    Compact,
+    Reload(oneshot::Sender<usize>),
+}
+
+/// Handle used by JobService to append records to the journal writer task.
+#[derive(Clone)]
+pub(crate) struct Journal {
+    tx: mpsc::UnboundedSender<JournalCommand>,
+}
+
+impl Journal {
+    pub(crate) fn append(&self, record: JournalRecord) {
+        let _ = self.tx.send(JournalCommand::Append(Box::new(record)));
+    }
+
+    /// Requests a snapshot + retention pass; skipped when nothing changed.
+    pub(crate) fn compact(&self) {
+        let _ = self.tx.send(JournalCommand::Compact);
+    }
+
+    /// Flushes pending records, reloads the store from disk and returns the
+    /// number of jobs loaded.
+    pub(crate) async fn reload(&self) -> Option<usize> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.tx.send(JournalCommand::Reload(reply_tx)).ok()?;
+        reply_rx.await.ok()
+    }
+
+    /// A journal without a writer; appended records are dropped.
+    #[cfg(test)]
+    pub(crate) fn detached() -> Self {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        Self { tx }
+    }
+}
+
+pub(crate) struct Recovered {
+    pub(crate) jobs: Vec<PersistedJob>,
+    /// Segment the writer should continue appending to.
+    pub(crate) segment: u64,
+}
+
+pub(crate) fn journal_dir() -> PathBuf {
+    apkw_util::state_file_path(JOURNAL_DIR_NAME)
+}
+
+fn snapshot_path(dir: &Path) -> PathBuf {
+    dir.join(SNAPSHOT_FILE_NAME)
+}
+
+fn segment_path(dir: &Path, segment: u64) -> PathBuf {
+    dir.join(format!("{SEGMENT_PREFIX}{segment:08}{SEGMENT_SUFFIX}"))
+}
+
+fn parse_segment_name(name: &str) -> Option<u64> {
+    name.strip_prefix(SEGMENT_PREFIX)?
+        .strip_suffix(SEGMENT_SUFFIX)?
+        .parse::<u64>()
+        .ok()
+}
+
+fn list_segments(dir: &Path) -> Vec<u64> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("Failed to read {}: {}", dir.display(), err);
+            }
+            return Vec::new();
+        }
+    };
+    let mut segments = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| parse_segment_name(&entry.file_name().to_string_lossy()))
+        .collect::<Vec<_>>();
+    segments.sort_unstable();
+    segments
+}
+
+fn read_state_file(path: &Path) -> Option<JobStateFile> {
+    match fs::read_to_string(path) {
+        Ok(data) => match serde_json::from_str::<JobStateFile>(&data) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                warn!("Failed to parse {}: {}", path.display(), err);
+                None
+            }
+        },
+        Err(err) => {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("Failed to read {}: {}", path.display(), err);
+            }
+            None
+        }
+    }
+}
+
+fn apply_record(
+    jobs: &mut HashMap<String, PersistedJob>,
+    order: &mut Vec<String>,
+    record: JournalRecord,
+) {
+    match record {
+        JournalRecord::JobCreated { job } => {
+            if !jobs.contains_key(&job.job_id) {
+                order.push(job.job_id.clone());
+                jobs.insert(job.job_id.clone(), job);
+            }
+        }
+        JournalRecord::JobUpdated { job } => {
+            // Updates for jobs dropped by retention are ignored rather than
+            // resurrecting an empty record.
+            if let Some(existing) = jobs.get_mut(&job.job_id) {
+                let history = std::mem::take(&mut existing.history);
+                *existing = job;
+                existing.history = history;
+            }
+        }
+        JournalRecord::Event { job_id, event } => {
+            let Some(existing) = jobs.get_mut(&job_id) else {
+                return;
+            };
+            if event.seq != 0 && event.seq <= existing.last_seq() {
+                return;
+            }
+            existing.history.push(event);
+            if existing.history.len() > HISTORY_CAPACITY * 2 {
+                let trim = existing.history.len() - HISTORY_CAPACITY;
+                existing.history.drain(0..trim);
+            }
+        }
+    }
+}
+
+/// Replays one segment. A torn final line (crash mid-append) is truncated so
+/// later appends start on a clean record boundary.
+fn replay_segment(
+    path: &Path,
+    truncate_tail: bool,
+    jobs: &mut HashMap<String, PersistedJob>,
+    order: &mut Vec<String>,
+) {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            warn!("Failed to read {}: {}", path.display(), err);
+            return;
+        }
+    };
+
+    let mut offset = 0usize;
+    while offset < data.len() {
+        let (line, next, complete) = match data[offset..].iter().position(|b| *b == b'\n') {
+            Some(pos) => (&data[offset..offset + pos], offset + pos + 1, true),
+            None => (&data[offset..], data.len(), false),
+        };
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            offset = next;
+            continue;
+        }
+        match serde_json::from_slice::<JournalRecord>(line) {
+            Ok(record) if complete || !truncate_tail => apply_record(jobs, order, record),
+            result => {
+                let is_tail = !complete || next >= data.len();
+                if is_tail && truncate_tail {
+                    warn!(
+                        "Truncating torn record at byte {} of {}",
+                        offset,
+                        path.display()
+                    );
+                    if let Err(err) = truncate_file(path, offset as u64) {
+                        warn!("Failed to truncate {}: {}", path.display(), err);
+                    }
+                    return;
+                }
+                if let Err(err) = result {
+                    warn!(
+                        "Skipping unreadable record at byte {} of {}: {}",
+                        offset,
+                        path.display(),
+                        err
+                    );
+                }
+            }
+        }
+        offset = next;
+    }
+}
+
+fn truncate_file(path: &Path, len: u64) -> io::Result<()> {
+    let file = fs::OpenOptions::new().write(true).open(path)?;
+    file.set_len(len)?;
+    file.sync_all()
+}
+
+/// Rebuilds job state from the latest snapshot (or the legacy `jobs.json`)
+/// plus every journal segment written after it.
+pub(crate) fn recover(dir: &Path, legacy_path: &Path) -> Recovered {
+    let (base, base_segment) = match read_state_file(&snapshot_path(dir)) {
+        Some(file) => {
+            let segment = file.journal_segment;
+            (file, segment)
+        }
+        None => (read_state_file(legacy_path).unwrap_or_default(), 0),
+    };
+
+    let mut order = Vec::with_capacity(base.jobs.len());
+    let mut jobs = HashMap::with_capacity(base.jobs.len());
+    for job in base.jobs {
+        order.push(job.job_id.clone());
+        jobs.insert(job.job_id.clone(), job);
+    }
+
+    let segments = list_segments(dir)
+        .into_iter()
+        .filter(|segment| *segment >= base_segment)
+        .collect::<Vec<_>>();
+    let last_segment = segments.last().copied();
+    for segment in &segments {
+        replay_segment(
+            &segment_path(dir, *segment),
+            Some(*segment) == last_segment,
+            &mut jobs,
+            &mut order,
+        );
+    }
+
+    let jobs = order
+        .into_iter()
+        .filter_map(|job_id| jobs.remove(&job_id))
+        .collect();
+    Recovered {
+        jobs,
+        segment: last_segment.unwrap_or(base_segment).max(1),
+    }
+}
+
+struct JournalWriter {
+    dir: PathBuf,
+    store: JobStore,
+    policy: RetentionPolicy,
+    segment: u64,
+    file: Option<fs::File>,
+    segment_bytes: u64,
+    bytes_since_snapshot: u64,
+    snapshot_stale: bool,
+    segment_max_bytes: u64,
+    compact_after_bytes: u64,
+}
+
+impl JournalWriter {
+    fn append(&mut self, records: &[JournalRecord]) -> io::Result<()> {
+        let mut buf = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut buf, record).map_err(io::Error::other)?;
+            buf.push(b'\n');
+        }
+        if self.file.is_none() {
+            fs::create_dir_all(&self.dir)?;
+            let path = segment_path(&self.dir, self.segment);
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?;
+            self.segment_bytes = file.metadata().map(|meta| meta.len()).unwrap_or_default();
+            self.file = Some(file);
+        }
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(&buf)?;
+            file.sync_data()?;
+        }
+        self.segment_bytes += buf.len() as u64;
+        self.bytes_since_snapshot += buf.len() as u64;
+        if self.segment_bytes >= self.segment_max_bytes {
+            self.segment += 1;
+            self.file = None;
+            self.segment_bytes = 0;
+        }
+        Ok(())
+    }
+
+    async fn compact(&mut self) -> io::Result<()> {
+        if !self.snapshot_stale && self.bytes_since_snapshot == 0 {
+            return Ok(());
+        }
+        let mut jobs = self.store.snapshot().await;
+        apply_retention(&mut jobs, &self.policy);
+        let keep_ids: HashSet<String> = jobs.iter().map(|job| job.job_id.clone()).collect();
+        self.store.prune_to(&keep_ids).await;
+
+        // Records still queued behind this snapshot land in the next segment
+        // and are deduplicated on replay.
+        let next_segment = self.segment + 1;
+        let file = JobStateFile {
+            schema_version: JOURNAL_SCHEMA_VERSION,
+            journal_segment: next_segment,
+            jobs,
+        };
+        write_json_atomic(&snapshot_path(&self.dir), &file)?;
+
+        self.segment = next_segment;
+        self.file = None;
+        self.segment_bytes = 0;
+        self.bytes_since_snapshot = 0;
+        self.snapshot_stale = false;
+
+        for segment in list_segments(&self.dir) {
+            if segment < next_segment {
+                let path = segment_path(&self.dir, segment);
+                if let Err(err) = fs::remove_file(&path) {
+                    warn!("Failed to remove {}: {}", path.display(), err);
+                }
+            }
+        }
+        let legacy = crate::state_file_path();
+        if legacy.exists() {
+            match fs::remove_file(&legacy) {
+                Ok(()) => info!("Migrated {} into the job journal", legacy.display()),
+                Err(err) => warn!("Failed to remove {}: {}", legacy.display(), err),
+            }
+        }
+        Ok(())
+    }
+
+    async fn reload(&mut self) -> usize {
+        self.file = None;
+        let (store, segment) = load_store(&self.policy).await;
+        let count = store.list_jobs().await.len();
+        self.store.replace_with(store).await;
+        self.segment = segment;
+        self.segment_bytes = 0;
+        self.snapshot_stale = true;
+        count
+    }
+
+    async fn run(mut self, mut rx: mpsc::UnboundedReceiver<JournalCommand>) {
+        while let Some(first) = rx.recv().await {
+            tokio::time::sleep(Duration::from_millis(JOURNAL_DEBOUNCE_MS)).await;
+
+            let mut pending = Vec::new();
+            let mut compact = false;
+            let mut reloads = Vec::new();
+            let mut next = Some(first);
+            while let Some(cmd) = next.take().or_else(|| rx.try_recv().ok()) {
+                match cmd {
+                    JournalCommand::Append(record) => pending.push(*record),
+                    JournalCommand::Compact => compact = true,
+                    JournalCommand::Reload(reply) => reloads.push(reply),
+                }
+            }
+
+            if !pending.is_empty() {
+                if let Err(err) = self.append(&pending) {
+                    warn!("Failed to append to job journal: {}", err);
+                    // Fall back to a full snapshot so the records are not lost.
+                    self.snapshot_stale = true;
+                    compact = true;
+                }
+            }
+            if !reloads.is_empty() {
+                let count = self.reload().await;
+                for reply in reloads {
+                    let _ = reply.send(count);
+                }
+                compact = true;
+            }
+            if compact || self.bytes_since_snapshot >= self.compact_after_bytes {
+                if let Err(err) = self.compact().await {
+                    warn!("Failed to compact job journal: {}", err);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn spawn_writer(store: JobStore, policy: RetentionPolicy, segment: u64) -> Journal {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let writer = JournalWriter {
+        dir: journal_dir(),
+        store,
+        policy,
+        segment,
+        file: None,
+        segment_bytes: 0,
+        bytes_since_snapshot: 0,
+        snapshot_stale: true,
+        segment_max_bytes: read_env_u64(
+            "APKW_JOB_JOURNAL_SEGMENT_BYTES",
+            DEFAULT_SEGMENT_MAX_BYTES,
+        )
+        .max(1),
+        compact_after_bytes: read_env_u64(
+            "APKW_JOB_JOURNAL_COMPACT_BYTES",
+            DEFAULT_COMPACT_AFTER_BYTES,
+        )
+        .max(1),
+    };
+    tokio::spawn(writer.run(rx));
+    Journal { tx }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PersistedEventPayload, PersistedJobProgress};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "apkw-core-journal-{name}-{}-{}",
+            std::process::id(),
+            apkw_util::now_millis()
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn job(job_id: &str) -> PersistedJob {
+        PersistedJob {
+            job_id: job_id.into(),
+            job_type: "build.run".into(),
+            ..Default::default()
+        }
+    }
+
+    fn progress(seq: u64, percent: u32) -> PersistedEvent {
+        PersistedEvent {
+            seq,
+            attempt: 1,
+            at_unix_millis: seq as i64,
+            payload: PersistedEventPayload::Progress {
+                progress: Some(PersistedJobProgress {
+                    percent,
+                    ..Default::default()
+                }),
+            },
+        }
+    }
+
+    fn write_records(path: &Path, records: &[JournalRecord], tail: &[u8]) {
+        let mut data = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut data, record).unwrap();
+            data.push(b'\n');
+        }
+        data.extend_from_slice(tail);
+        fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn recover_deduplicates_events_already_in_snapshot() {
+        let dir = test_dir("dedupe");
+        let mut snap_job = job("a");
+        snap_job.history = vec![progress(1, 10), progress(2, 20)];
+        write_json_atomic(
+            &snapshot_path(&dir),
+            &JobStateFile {
+                schema_version: JOURNAL_SCHEMA_VERSION,
+                journal_segment: 3,
+                jobs: vec![snap_job],
+            },
+        )
+        .unwrap();
+        // Segment 2 predates the snapshot and must be ignored entirely.
+        write_records(
+            &segment_path(&dir, 2),
+            &[JournalRecord::JobCreated { job: job("stale") }],
+            b"",
+        );
+        write_records(
+            &segment_path(&dir, 3),
+            &[
+                JournalRecord::Event {
+                    job_id: "a".into(),
+                    event: progress(2, 20),
+                },
+                JournalRecord::Event {
+                    job_id: "a".into(),
+                    event: progress(3, 30),
+                },
+                JournalRecord::JobCreated { job: job("b") },
+            ],
+            b"",
+        );
+
+        let recovered = recover(&dir, &dir.join("jobs.json"));
+        let ids = recovered
+            .jobs
+            .iter()
+            .map(|job| job.job_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["a", "b"]);
+        let seqs = recovered.jobs[0]
+            .history
+            .iter()
+            .map(|evt| evt.seq)
+            .collect::<Vec<_>>();
+        assert_eq!(seqs, vec![1, 2, 3]);
+        assert_eq!(recovered.segment, 3);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn recover_truncates_torn_tail_of_last_segment() {
+        let dir = test_dir("torn");
+        let path = segment_path(&dir, 1);
+        write_records(
+            &path,
+            &[
+                JournalRecord::JobCreated { job: job("a") },
+                JournalRecord::Event {
+                    job_id: "a".into(),
+                    event: progress(1, 50),
+                },
+            ],
+            br#"{"op":"event","job_id":"a","ev"#,
+        );
+        let intact_len = fs::metadata(&path).unwrap().len() - 30;
+
+        let recovered = recover(&dir, &dir.join("jobs.json"));
+        assert_eq!(recovered.jobs.len(), 1);
+        assert_eq!(recovered.jobs[0].history.len(), 1);
+        assert_eq!(fs::metadata(&path).unwrap().len(), intact_len);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use apkw_proto::apkw::v1::{job_event::Payload, JobEvent};
+
+/// Longest line kept in the index; longer lines are cut at a char boundary.
+const MAX_LINE_BYTES: usize = 2048;
+/// Snippets longer than this are windowed around the first match.
+const MAX_SNIPPET_BYTES: usize = 200;
+
+/// One indexed line: a log line, a failure message, or a completion summary.
+struct LogDoc {
+    job_id: String,
+    seq: u64,
+    attempt: u32,
+    at_unix_millis: i64,
+    stream: String,
+    text: String,
+}
+
+pub(crate) struct LogHit {
+    pub(crate) seq: u64,
+    pub(crate) attempt: u32,
+    pub(crate) at_unix_millis: i64,
+    pub(crate) stream: String,
+    pub(crate) snippet: String,
+}
+
+/// Inverted index from the byte trigrams of each lowercased line to the lines
+/// containing them. Queries are matched as case-insensitive substrings: the
+/// postings of the query's own trigrams give the candidate lines, which are
+/// then checked. Queries shorter than a trigram check every line.
+#[derive(Default)]
+pub(crate) struct LogIndex {
+    next_doc: u64,
+    docs: HashMap<u64, LogDoc>,
+    postings: HashMap<[u8; 3], BTreeSet<u64>>,
+    job_docs: HashMap<String, VecDeque<u64>>,
+}
+
+fn trigrams(lower: &str) -> HashSet<[u8; 3]> {
+    lower
+        .as_bytes()
+        .windows(3)
+        .map(|window| [window[0], window[1], window[2]])
+        .collect()
+}
+
+fn truncate_at_boundary(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+fn snippet(text: &str, match_at: usize, match_len: usize) -> String {
+    if text.len() <= MAX_SNIPPET_BYTES {
+        return text.to_string();
+    }
+    let context = MAX_SNIPPET_BYTES.saturating_sub(match_len) / 2;
+    let mut start = match_at.saturating_sub(context);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (start + MAX_SNIPPET_BYTES).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    out.push_str(&text[start..end]);
+    if end < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+fn event_lines(event: &JobEvent) -> Vec<(String, String)> {
+    match event.payload.as_ref() {
+        Some(Payload::Log(log)) => log
+            .chunk
+            .as_ref()
+            .map(|chunk| {
+                String::from_utf8_lossy(&chunk.data)
+                    .lines()
+                    .map(str::trim_end)
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| (chunk.stream.clone(), line.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Some(Payload::Failed(failed)) => failed
+            .error
+            .as_ref()
+            .map(|err| {
+                std::iter::once(err.message.as_str())
+                    .chain(err.technical_details.lines())
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| ("error".to_string(), line.trim_end().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Some(Payload::Completed(done)) if !done.summary.trim().is_empty() => {
+            vec![("summary".to_string(), done.summary.clone())]
+        }
+        _ => Vec::new(),
+    }
+}
+
+impl LogIndex {
+    pub(crate) fn add_event(&mut self, job_id: &str, seq: u64, event: &JobEvent) {
+        let at_unix_millis = event
+            .at
+            .as_ref()
+            .map(|ts| ts.unix_millis)
+            .unwrap_or_default();
+        for (stream, line) in event_lines(event) {
+            let doc_id = self.next_doc;
+            self.next_doc += 1;
+            let text = truncate_at_boundary(&line, MAX_LINE_BYTES).to_string();
+            for trigram in trigrams(&text.to_lowercase()) {
+                self.postings.entry(trigram).or_default().insert(doc_id);
+            }
+            self.job_docs
+                .entry(job_id.to_string())
+                .or_default()
+                .push_back(doc_id);
+            self.docs.insert(
+                doc_id,
+                LogDoc {
+                    job_id: job_id.to_string(),
+                    seq,
+                    attempt: event.attempt,
+                    at_unix_millis,
+                    stream,
+                    text,
+                },
+            );
+        }
+    }
+
+    fn remove_doc(&mut self, doc_id: u64) {
+        let Some(doc) = self.docs.remove(&doc_id) else {
+            return;
+        };
+        for trigram in trigrams(&doc.text.to_lowercase()) {
+            let empty = match self.postings.get_mut(&trigram) {
+                Some(ids) => {
+                    ids.remove(&doc_id);
+                    ids.is_empty()
+                }
+                None => false,
+            };
+            if empty {
+                self.postings.remove(&trigram);
+            }
+        }
+    }
+
+    pub(crate) fn remove_job(&mut self, job_id: &str) {
+        for doc_id in self.job_docs.remove(job_id).unwrap_or_default() {
+            self.remove_doc(doc_id);
+        }
+    }
+
+    /// Drops lines from events the job's bounded history no longer holds.
+    pub(crate) fn evict_before(&mut self, job_id: &str, min_seq: u64) {
+        let mut evicted = Vec::new();
+        if let Some(ids) = self.job_docs.get_mut(job_id) {
+            while let Some(doc_id) = ids.front().copied() {
+                if self.docs.get(&doc_id).is_some_and(|doc| doc.seq >= min_seq) {
+                    break;
+                }
+                ids.pop_front();
+                evicted.push(doc_id);
+            }
+        }
+        for doc_id in evicted {
+            self.remove_doc(doc_id);
+        }
+    }
+
+    /// Returns matching lines grouped by job, in publish order.
+    pub(crate) fn search(&self, query: &str) -> HashMap<String, Vec<LogHit>> {
+        let needle = query.trim().to_lowercase();
+        let mut out: HashMap<String, Vec<LogHit>> = HashMap::new();
+        if needle.is_empty() {
+            return out;
+        }
+
+        // Every line containing the query contains all of its trigrams, so
+        // intersecting their postings (smallest first) leaves the candidates.
+        let mut lists = Vec::new();
+        for trigram in trigrams(&needle) {
+            match self.postings.get(&trigram) {
+                Some(ids) => lists.push(ids),
+                None => return out,
+            }
+        }
+        lists.sort_by_key(|ids| ids.len());
+        let candidates: BTreeSet<u64> = match lists.split_first() {
+            Some((first, rest)) => first
+                .iter()
+                .copied()
+                .filter(|doc_id| rest.iter().all(|ids| ids.contains(doc_id)))
+                .collect(),
+            None => self.docs.keys().copied().collect(),
+        };
+
+        for doc_id in candidates {
+            let Some(doc) = self.docs.get(&doc_id) else {
+                continue;
+            };
+            let lower = doc.text.to_lowercase();
+            let Some(found) = lower.find(&needle) else {
+                continue;
+            };
+            // Lowercasing can change byte offsets outside ASCII; fall back to
+            // the start of the line rather than slicing mid-character.
+            let match_at = if lower.len() == doc.text.len() {
+                found
+            } else {
+                0
+            };
+            out.entry(doc.job_id.clone()).or_default().push(LogHit {
+                seq: doc.seq,
+                attempt: doc.attempt,
+                at_unix_millis: doc.at_unix_millis,
+                stream: doc.stream.clone(),
+                snippet: snippet(&doc.text, match_at, needle.len()),
+            });
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apkw_proto::apkw::v1::{JobLogAppended, LogChunk};
+
+    fn log_event(text: &str) -> JobEvent {
+        JobEvent {
+            attempt: 1,
+            payload: Some(Payload::Log(JobLogAppended {
+                chunk: Some(LogChunk {
+                    stream: "targets".into(),
+                    data: text.as_bytes().to_vec(),
+                    truncated: false,
+                }),
+                records: Vec::new(),
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn search_matches_fragments_and_tracks_eviction() {
+        let mut index = LogIndex::default();
+        index.add_event("job-1", 1, &log_event("Performing Streamed Install\n"));
+        index.add_event(
+            "job-1",
+            2,
+            &log_event(
+                "adb: failed to install app.apk: Failure [INSTALL_FAILED_UPDATE_INCOMPATIBLE]\n",
+            ),
+        );
+        index.add_event("job-2", 1, &log_event("Success\n"));
+
+        let hits = index.search("failed_update_incompat");
+        assert_eq!(hits.len(), 1);
+        let job_hits = &hits["job-1"];
+        assert_eq!(job_hits.len(), 1);
+        assert_eq!(job_hits[0].seq, 2);
+        assert!(job_hits[0]
+            .snippet
+            .contains("INSTALL_FAILED_UPDATE_INCOMPATIBLE"));
+
+        assert_eq!(index.search("streamed install").len(), 1);
+        assert!(index.search("install streamed").is_empty());
+
+        index.evict_before("job-1", 2);
+        assert!(index.search("streamed").is_empty());
+        index.remove_job("job-1");
+        assert!(index.search("INSTALL_FAILED").is_empty());
+        assert_eq!(
+            index.postings.keys().copied().collect::<HashSet<_>>(),
+            trigrams("success")
+        );
+        assert_eq!(index.search("success").len(), 1);
+        assert_eq!(index.search("su").len(), 1);
+        assert!(index.search("successful").is_empty());
+    }
+}
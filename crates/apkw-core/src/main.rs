@@ -1,7 +1,14 @@
+mod job_types;
+mod journal;
+mod log_index;
+mod notify;
+mod queue;
+mod retry;
+mod secrets;
+
 use std::{
     cmp::{Ordering, Reverse},
     collections::{BinaryHeap, HashMap, HashSet, VecDeque},
-    fs, io,
     path::PathBuf,
     sync::Arc,
     time::Duration,
@@ -9,22 +16,36 @@ use std::{
 
 use apkw_proto::apkw::v1::{
     job_service_server::{JobService, JobServiceServer},
-    CancelJobRequest, CancelJobResponse, ErrorCode, ErrorDetail, GetJobRequest, GetJobResponse, Id,
-    Job, JobCompleted, JobEvent, JobEventKind, JobFailed, JobFilter, JobHistoryFilter,
-    JobLogAppended, JobProgress, JobProgressUpdated, JobRef, JobState, JobStateChanged, KeyValue,
-    ListJobHistoryRequest, ListJobHistoryResponse, ListJobsRequest, ListJobsResponse, LogChunk,
-    PageInfo, Pagination, PublishJobEventRequest, PublishJobEventResponse, ReloadStateRequest,
-    ReloadStateResponse, Remediation, RunId, StartJobRequest, StartJobResponse,
-    StreamJobEventsRequest, StreamRunEventsRequest, Timestamp,
+    AwaitJobDispatchRequest, AwaitJobDispatchResponse, CancelJobRequest, CancelJobResponse,
+    CreateNotificationRuleRequest, CreateNotificationRuleResponse, DeleteNotificationRuleRequest,
+    DeleteNotificationRuleResponse, DeleteSecretRequest, DeleteSecretResponse, ErrorCode,
+    ErrorDetail, GetJobRequest, GetJobResponse, Id, Job, JobAttempt, JobCancellation, JobCompleted,
+    JobEvent, JobEventKind, JobFailed, JobFilter, JobHistoryFilter, JobLogAppended, JobLogMatch,
+    JobLogSearchResult, JobProgress, JobProgressUpdated, JobRef, JobState, JobStateChanged,
+    KeyValue, ListJobHistoryRequest, ListJobHistoryResponse, ListJobTypesRequest,
+    ListJobTypesResponse, ListJobsRequest, ListJobsResponse, ListNotificationRulesRequest,
+    ListNotificationRulesResponse, ListSecretsRequest, ListSecretsResponse, LogChunk, LogRecord,
+    PageInfo, Pagination, PublishJobEventRequest, PublishJobEventResponse, RegisterJobTypeRequest,
+    RegisterJobTypeResponse, ReloadStateRequest, ReloadStateResponse, Remediation,
+    RenewJobLeaseRequest, RenewJobLeaseResponse, RetryPolicy, RunId, SearchJobLogsRequest,
+    SearchJobLogsResponse, SetSecretRequest, SetSecretResponse, StartJobRequest, StartJobResponse,
+    StreamJobEventsRequest, StreamRunEventsRequest, Timestamp, WatchCancellationRequest,
 };
-use apkw_util::{
-    init_service_telemetry, init_tracing, now_millis, now_ts, serve_grpc, write_json_atomic,
+use apkw_util::{init_service_telemetry, init_tracing, now_millis, now_ts, serve_grpc};
+use job_types::JobTypeRegistry;
+use journal::{Journal, JournalRecord};
+use log_index::{LogHit, LogIndex};
+use notify::{finished_state, NotificationRegistry};
+use queue::{
+    normalize_concurrency_keys, normalize_priority, plan_dispatch, QueueDecision, QueueEntry,
 };
+use retry::{attempts_used, is_retryable, normalize_retry_policy, retry_backoff_ms};
+use secrets::SecretRegistry;
 use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, mpsc, watch, Mutex};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
-use tracing::{info, warn};
+use tracing::info;
 use uuid::Uuid;
 
 const BROADCAST_CAPACITY: usize = 1024;
@@ -32,7 +53,6 @@ const HISTORY_CAPACITY: usize = 2048;
 const STATE_FILE_NAME: &str = "jobs.json";
 const DEFAULT_JOB_HISTORY_RETENTION_DAYS: u64 = 30;
 const DEFAULT_JOB_HISTORY_MAX: usize = 200;
-const PERSIST_DEBOUNCE_MS: u64 = 250;
 const RETENTION_TICK_SECS: u64 = 300;
 const DEFAULT_PAGE_SIZE: usize = 50;
 const MAX_PAGE_SIZE: usize = 200;
@@ -40,6 +60,17 @@ const DEFAULT_RUN_STREAM_BUFFER_MAX: usize = 512;
 const DEFAULT_RUN_STREAM_MAX_DELAY_MS: u64 = 1500;
 const DEFAULT_RUN_STREAM_DISCOVERY_MS: u64 = 750;
 const DEFAULT_RUN_STREAM_FLUSH_MS: u64 = 200;
+const DISPATCH_RECHECK_SECS: u64 = 1;
+const DEFAULT_JOB_LEASE_MS: u64 = 30_000;
+const MIN_JOB_LEASE_MS: u64 = 5_000;
+const MAX_JOB_LEASE_MS: u64 = 600_000;
+const STARTUP_LEASE_GRACE_MS: u64 = 60_000;
+const LIVENESS_TICK_SECS: u64 = 5;
+/// How long a withheld `Failed` state waits for the worker's `JobFailed`
+/// before the job is failed without a retry.
+const FAILURE_SETTLE_MS: i64 = 5_000;
+const DEFAULT_SEARCH_MATCHES_PER_JOB: usize = 20;
+const MAX_SEARCH_MATCHES_PER_JOB: usize = 200;
 
 #[derive(Clone, Copy)]
 struct RetentionPolicy {
@@ -52,6 +83,8 @@ struct RetentionPolicy {
 struct JobStateFile {
     #[serde(default = "default_schema_version")]
     schema_version: u32,
+    /// First journal segment that must be replayed on top of `jobs`.
+    journal_segment: u64,
     jobs: Vec<PersistedJob>,
 }
 
@@ -70,11 +103,47 @@ struct PersistedJob {
     project_id: Option<String>,
     target_id: Option<String>,
     toolchain_set_id: Option<String>,
+    depends_on: Vec<String>,
+    concurrency_keys: Vec<String>,
+    dispatched: bool,
+    timeout_ms: u64,
+    retry_policy: Option<RetryPolicyRecord>,
+    attempt: u32,
+    attempts: Vec<JobAttemptRecord>,
+    retry_not_before_unix_millis: Option<i64>,
+    priority: i32,
+    preemptible: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     history: Vec<PersistedEvent>,
 }
 
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RetryPolicyRecord {
+    max_attempts: u32,
+    initial_backoff_ms: u64,
+    backoff_multiplier: f64,
+    max_backoff_ms: u64,
+    retryable_codes: Vec<i32>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct JobAttemptRecord {
+    attempt: u32,
+    state: i32,
+    started_at_unix_millis: Option<i64>,
+    finished_at_unix_millis: Option<i64>,
+    error: Option<ErrorDetailRecord>,
+    preempted: bool,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 struct PersistedEvent {
+    #[serde(default)]
+    seq: u64,
+    #[serde(default)]
+    attempt: u32,
     at_unix_millis: i64,
     payload: PersistedEventPayload,
 }
@@ -90,6 +159,8 @@ enum PersistedEventPayload {
     },
     Log {
         chunk: Option<PersistedLogChunk>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        records: Vec<PersistedLogRecord>,
     },
     Completed {
         summary: String,
@@ -116,6 +187,19 @@ struct PersistedLogChunk {
     truncated: bool,
 }
 
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PersistedLogRecord {
+    level: i32,
+    source: String,
+    message: String,
+    file: String,
+    line: u32,
+    column: u32,
+    task: String,
+    at_unix_millis: Option<i64>,
+}
+
 #[derive(Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 struct KeyValueRecord {
@@ -216,6 +300,56 @@ impl ErrorDetailRecord {
     }
 }
 
+impl RetryPolicyRecord {
+    fn from_proto(item: &RetryPolicy) -> Self {
+        Self {
+            max_attempts: item.max_attempts,
+            initial_backoff_ms: item.initial_backoff_ms,
+            backoff_multiplier: item.backoff_multiplier,
+            max_backoff_ms: item.max_backoff_ms,
+            retryable_codes: item.retryable_codes.clone(),
+        }
+    }
+
+    fn into_proto(self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            initial_backoff_ms: self.initial_backoff_ms,
+            backoff_multiplier: self.backoff_multiplier,
+            max_backoff_ms: self.max_backoff_ms,
+            retryable_codes: self.retryable_codes,
+        }
+    }
+}
+
+impl JobAttemptRecord {
+    fn from_proto(item: &JobAttempt) -> Self {
+        Self {
+            attempt: item.attempt,
+            state: item.state,
+            started_at_unix_millis: millis_from_ts(&item.started_at),
+            finished_at_unix_millis: millis_from_ts(&item.finished_at),
+            error: item.error.as_ref().map(ErrorDetailRecord::from_proto),
+            preempted: item.preempted,
+        }
+    }
+
+    fn into_proto(self) -> JobAttempt {
+        JobAttempt {
+            attempt: self.attempt,
+            state: self.state,
+            started_at: self
+                .started_at_unix_millis
+                .map(|ms| Timestamp { unix_millis: ms }),
+            finished_at: self
+                .finished_at_unix_millis
+                .map(|ms| Timestamp { unix_millis: ms }),
+            error: self.error.map(ErrorDetailRecord::into_proto),
+            preempted: self.preempted,
+        }
+    }
+}
+
 impl PersistedJobProgress {
     fn from_proto(item: &JobProgress) -> Self {
         Self {
@@ -260,6 +394,36 @@ impl PersistedLogChunk {
     }
 }
 
+impl PersistedLogRecord {
+    fn from_proto(item: &LogRecord) -> Self {
+        Self {
+            level: item.level,
+            source: item.source.clone(),
+            message: item.message.clone(),
+            file: item.file.clone(),
+            line: item.line,
+            column: item.column,
+            task: item.task.clone(),
+            at_unix_millis: item.at.as_ref().map(|ts| ts.unix_millis),
+        }
+    }
+
+    fn into_proto(self) -> LogRecord {
+        LogRecord {
+            level: self.level,
+            source: self.source,
+            message: self.message,
+            file: self.file,
+            line: self.line,
+            column: self.column,
+            task: self.task,
+            at: self
+                .at_unix_millis
+                .map(|unix_millis| Timestamp { unix_millis }),
+        }
+    }
+}
+
 impl PersistedEventPayload {
     fn from_proto(payload: &apkw_proto::apkw::v1::job_event::Payload) -> Self {
         match payload {
@@ -278,6 +442,11 @@ impl PersistedEventPayload {
             }
             apkw_proto::apkw::v1::job_event::Payload::Log(log) => PersistedEventPayload::Log {
                 chunk: log.chunk.as_ref().map(PersistedLogChunk::from_proto),
+                records: log
+                    .records
+                    .iter()
+                    .map(PersistedLogRecord::from_proto)
+                    .collect(),
             },
             apkw_proto::apkw::v1::job_event::Payload::Completed(done) => {
                 PersistedEventPayload::Completed {
@@ -309,9 +478,13 @@ impl PersistedEventPayload {
                     progress: progress.map(PersistedJobProgress::into_proto),
                 })
             }
-            PersistedEventPayload::Log { chunk } => {
+            PersistedEventPayload::Log { chunk, records } => {
                 apkw_proto::apkw::v1::job_event::Payload::Log(JobLogAppended {
                     chunk: chunk.map(PersistedLogChunk::into_proto),
+                    records: records
+                        .into_iter()
+                        .map(PersistedLogRecord::into_proto)
+                        .collect(),
                 })
             }
             PersistedEventPayload::Completed { summary, outputs } => {
@@ -326,6 +499,7 @@ impl PersistedEventPayload {
             PersistedEventPayload::Failed { error } => {
                 apkw_proto::apkw::v1::job_event::Payload::Failed(JobFailed {
                     error: error.map(ErrorDetailRecord::into_proto),
+                    will_retry: false,
                 })
             }
         }
@@ -333,7 +507,7 @@ impl PersistedEventPayload {
 }
 
 impl PersistedEvent {
-    fn from_proto(event: &JobEvent) -> Option<Self> {
+    fn from_proto(event: &JobEvent, seq: u64) -> Option<Self> {
         let payload = event.payload.as_ref()?;
         let at_unix_millis = event
             .at
@@ -341,6 +515,8 @@ impl PersistedEvent {
             .map(|ts| ts.unix_millis)
             .unwrap_or_default();
         Some(Self {
+            seq,
+            attempt: event.attempt,
             at_unix_millis,
             payload: PersistedEventPayload::from_proto(payload),
         })
@@ -354,6 +530,9 @@ impl PersistedEvent {
             job_id: Some(Id {
                 value: job_id.to_string(),
             }),
+            // Events journaled before retries existed belong to attempt 1.
+            attempt: self.attempt.max(1),
+            seq: self.seq,
             payload: Some(self.payload.into_proto()),
         }
     }
@@ -361,6 +540,18 @@ impl PersistedEvent {
 
 impl PersistedJob {
     fn from_inner(job_id: &str, inner: &JobRecordInner) -> Self {
+        let first_seq = inner.first_history_seq();
+        let mut persisted = Self::meta_from_inner(job_id, inner);
+        persisted.history = inner
+            .history
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, evt)| PersistedEvent::from_proto(evt, first_seq + idx as u64))
+            .collect();
+        persisted
+    }
+
+    fn meta_from_inner(job_id: &str, inner: &JobRecordInner) -> Self {
         let job = &inner.job;
         Self {
             job_id: job_id.to_string(),
@@ -383,14 +574,28 @@ impl PersistedJob {
             project_id: job.project_id.as_ref().map(|id| id.value.clone()),
             target_id: job.target_id.as_ref().map(|id| id.value.clone()),
             toolchain_set_id: job.toolchain_set_id.as_ref().map(|id| id.value.clone()),
-            history: inner
-                .history
+            depends_on: job.depends_on.iter().map(|id| id.value.clone()).collect(),
+            concurrency_keys: job.concurrency_keys.clone(),
+            dispatched: inner.dispatched,
+            timeout_ms: job.timeout_ms,
+            retry_policy: job.retry_policy.as_ref().map(RetryPolicyRecord::from_proto),
+            attempt: job.attempt,
+            attempts: job
+                .attempts
                 .iter()
-                .filter_map(PersistedEvent::from_proto)
+                .map(JobAttemptRecord::from_proto)
                 .collect(),
+            retry_not_before_unix_millis: inner.retry_not_before,
+            priority: job.priority,
+            preemptible: job.preemptible,
+            history: Vec::new(),
         }
     }
 
+    fn last_seq(&self) -> u64 {
+        self.history.last().map(|evt| evt.seq).unwrap_or_default()
+    }
+
     fn into_inner(self) -> JobRecordInner {
         let PersistedJob {
             job_id,
@@ -405,21 +610,64 @@ impl PersistedJob {
             project_id,
             target_id,
             toolchain_set_id,
+            depends_on,
+            concurrency_keys,
+            dispatched,
+            timeout_ms,
+            retry_policy,
+            attempt,
+            attempts,
+            retry_not_before_unix_millis,
+            priority,
+            preemptible,
             mut history,
         } = self;
 
+        let mut attempts = attempts
+            .into_iter()
+            .map(JobAttemptRecord::into_proto)
+            .collect::<Vec<_>>();
+        if attempts.is_empty() {
+            // Jobs persisted before retries existed ran exactly once.
+            attempts.push(JobAttempt {
+                attempt: 1,
+                state,
+                started_at: started_at_unix_millis.map(|ms| Timestamp { unix_millis: ms }),
+                finished_at: finished_at_unix_millis.map(|ms| Timestamp { unix_millis: ms }),
+                error: None,
+                preempted: false,
+            });
+        }
+        let attempt = attempt.max(1);
+        let attempt_started_at = attempts
+            .last()
+            .and_then(|current| millis_from_ts(&current.started_at))
+            .or(started_at_unix_millis);
+
+        // Events written before sequence numbers existed carry seq 0; number
+        // them after the retained history so new events sort after them.
+        let next_seq = match history.last().map(|evt| evt.seq) {
+            Some(seq) if seq > 0 => seq + 1,
+            _ => history.len() as u64 + 1,
+        };
+
         if history.len() > HISTORY_CAPACITY {
             let trim = history.len() - HISTORY_CAPACITY;
             history.drain(0..trim);
         }
 
+        let first_seq = next_seq - history.len() as u64;
         let mut deque = VecDeque::with_capacity(HISTORY_CAPACITY);
-        for evt in history {
+        for (idx, mut evt) in history.into_iter().enumerate() {
+            if evt.seq == 0 {
+                evt.seq = first_seq + idx as u64;
+            }
             deque.push_back(evt.into_proto(&job_id));
         }
 
         let (btx, _brx) = broadcast::channel::<JobEvent>(BROADCAST_CAPACITY);
         let (cancel_tx, _cancel_rx) = watch::channel(false);
+        let (dispatch_tx, _dispatch_rx) = watch::channel(dispatched);
 
         let correlation = if correlation_id.is_empty() {
             job_id.clone()
@@ -449,13 +697,35 @@ impl PersistedJob {
             project_id: project_id.map(|value| Id { value }),
             target_id: target_id.map(|value| Id { value }),
             toolchain_set_id: toolchain_set_id.map(|value| Id { value }),
+            depends_on: depends_on.into_iter().map(|value| Id { value }).collect(),
+            concurrency_keys,
+            timeout_ms,
+            deadline: attempt_started_at
+                .filter(|_| timeout_ms > 0)
+                .map(|ms| Timestamp {
+                    unix_millis: ms.saturating_add(timeout_ms as i64),
+                }),
+            // Leases are not persisted; owners re-establish them after a
+            // restart (see `JobSvc::grant_restart_leases`).
+            lease_expires_at: None,
+            retry_policy: retry_policy.map(RetryPolicyRecord::into_proto),
+            attempt,
+            attempts,
+            priority: normalize_priority(priority) as i32,
+            preemptible,
         };
 
         JobRecordInner {
             job,
             broadcaster: btx,
             history: deque,
+            next_seq,
             cancel_tx,
+            dispatched,
+            dispatch_tx,
+            blocked_reason: String::new(),
+            failure_pending_since: None,
+            retry_not_before: retry_not_before_unix_millis,
         }
     }
 
@@ -472,25 +742,6 @@ impl PersistedJob {
     }
 }
 
-fn is_known_job_type(job_type: &str) -> bool {
-    matches!(
-        job_type,
-        "workflow.pipeline"
-            | "project.create"
-            | "build.run"
-            | "toolchain.install"
-            | "toolchain.verify"
-            | "targets.install"
-            | "targets.launch"
-            | "targets.stop"
-            | "targets.cuttlefish.install"
-            | "targets.cuttlefish.start"
-            | "targets.cuttlefish.stop"
-            | "observe.support_bundle"
-            | "observe.evidence_bundle"
-    )
-}
-
 #[allow(clippy::result_large_err)]
 fn parse_page(page: Option<Pagination>) -> Result<(usize, usize), Status> {
     let page = page.unwrap_or_default();
@@ -516,6 +767,118 @@ fn millis_from_ts(ts: &Option<Timestamp>) -> Option<i64> {
     ts.as_ref().map(|ts| ts.unix_millis)
 }
 
+/// Stamps `started_at` and arms the job's deadline, if it has a timeout. The
+/// deadline is counted from the start of the current attempt.
+fn start_job_clock(job: &mut Job) {
+    let now = now_ts();
+    let started = match job.attempts.last_mut() {
+        Some(current) => *current.started_at.get_or_insert(now),
+        None => now,
+    };
+    job.deadline = (job.timeout_ms > 0).then(|| Timestamp {
+        unix_millis: started.unix_millis.saturating_add(job.timeout_ms as i64),
+    });
+    job.started_at.get_or_insert(started);
+}
+
+/// Opens a new queued attempt after the current one ended and returns its
+/// number. The job waits for dispatch again.
+fn queue_next_attempt(inner: &mut JobRecordInner) -> u32 {
+    let next = inner.job.attempt + 1;
+    inner.job.attempt = next;
+    inner.job.attempts.push(JobAttempt {
+        attempt: next,
+        state: JobState::Queued as i32,
+        ..Default::default()
+    });
+    inner.job.state = JobState::Queued as i32;
+    inner.job.finished_at = None;
+    inner.job.deadline = None;
+    inner.job.lease_expires_at = None;
+    inner.dispatched = false;
+    inner.dispatch_tx.send_replace(false);
+    inner.blocked_reason.clear();
+    next
+}
+
+/// Moves the job and its current attempt to `state`.
+fn apply_job_state(job: &mut Job, state: JobState) {
+    job.state = state as i32;
+    if let Some(current) = job.attempts.last_mut() {
+        current.state = state as i32;
+    }
+    match state {
+        JobState::Running => start_job_clock(job),
+        JobState::Success | JobState::Failed | JobState::Cancelled => {
+            let finished = now_ts();
+            job.finished_at = Some(finished);
+            if let Some(current) = job.attempts.last_mut() {
+                current.finished_at = Some(finished);
+            }
+        }
+        _ => {}
+    }
+}
+
+enum Overdue {
+    Deadline { timeout_ms: u64 },
+    LeaseExpired,
+}
+
+fn overdue(job: &Job, now: i64) -> Option<Overdue> {
+    let state = JobState::try_from(job.state).unwrap_or(JobState::Unspecified);
+    if !matches!(state, JobState::Queued | JobState::Running) {
+        return None;
+    }
+    if millis_from_ts(&job.deadline).is_some_and(|deadline| now >= deadline) {
+        return Some(Overdue::Deadline {
+            timeout_ms: job.timeout_ms,
+        });
+    }
+    if millis_from_ts(&job.lease_expires_at).is_some_and(|lease| now >= lease) {
+        return Some(Overdue::LeaseExpired);
+    }
+    None
+}
+
+fn overdue_error(reason: Overdue, correlation_id: &str) -> ErrorDetail {
+    let (message, remedy) = match reason {
+        Overdue::Deadline { timeout_ms } => (
+            format!("Job exceeded its {timeout_ms} ms timeout"),
+            Remediation {
+                title: "Raise the timeout or narrow the job".into(),
+                description: "Re-run with a larger timeout_ms, or split the work so each job finishes within its deadline.".into(),
+                action_id: String::new(),
+                params: vec![],
+            },
+        ),
+        Overdue::LeaseExpired => (
+            "Owning service stopped sending heartbeats; the job was orphaned".to_string(),
+            Remediation {
+                title: "Check the owning service".into(),
+                description: "The service running this job crashed or lost contact with JobService. Check its logs, restart it, and re-run the job.".into(),
+                action_id: String::new(),
+                params: vec![],
+            },
+        ),
+    };
+    ErrorDetail {
+        code: ErrorCode::Timeout as i32,
+        message,
+        technical_details: String::new(),
+        remedies: vec![remedy],
+        correlation_id: correlation_id.to_string(),
+    }
+}
+
+fn clamp_lease_ms(requested: u64) -> u64 {
+    if requested == 0 {
+        DEFAULT_JOB_LEASE_MS
+    } else {
+        requested.clamp(MIN_JOB_LEASE_MS, MAX_JOB_LEASE_MS)
+    }
+}
+
 fn job_sort_key(job: &Job) -> i64 {
     millis_from_ts(&job.finished_at)
         .unwrap_or_else(|| millis_from_ts(&job.created_at).unwrap_or_default())
@@ -583,6 +946,9 @@ fn event_kind(event: &JobEvent) -> JobEventKind {
 }
 
 fn event_matches_filter(event: &JobEvent, filter: &JobHistoryFilter) -> bool {
+    if filter.attempt != 0 && event.attempt != filter.attempt {
+        return false;
+    }
     if !filter.kinds.is_empty() {
         let kind = event_kind(event) as i32;
         if !filter.kinds.contains(&kind) {
@@ -615,16 +981,28 @@ struct RunStreamConfig {
 #[derive(Clone)]
 struct BufferedEvent {
     at_unix_millis: i64,
-    seq: u64,
+    job_id: String,
+    /// Arrival order; only breaks ties between server notices (seq 0).
+    arrival: u64,
     event: JobEvent,
 }
 
+impl BufferedEvent {
+    fn key(&self) -> (i64, &str, u64, u64) {
+        (
+            self.at_unix_millis,
+            self.job_id.as_str(),
+            self.event.seq,
+            self.arrival,
+        )
+    }
+}
+
+/// Events are released by timestamp, then job id and per-job sequence, so a
+/// replayed run stream flushes in the same order as the original.
 impl Ord for BufferedEvent {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.at_unix_millis.cmp(&other.at_unix_millis) {
-            Ordering::Equal => self.seq.cmp(&other.seq),
-            ordering => ordering,
-        }
+        self.key().cmp(&other.key())
     }
 }
 
@@ -636,7 +1014,7 @@ impl PartialOrd for BufferedEvent {
 
 impl PartialEq for BufferedEvent {
     fn eq(&self, other: &Self) -> bool {
-        self.at_unix_millis == other.at_unix_millis && self.seq == other.seq
+        self.key() == other.key()
     }
 }
 
@@ -745,6 +1123,8 @@ fn mk_event(job_id: &str, payload: apkw_proto::apkw::v1::job_event::Payload) ->
         job_id: Some(Id {
             value: job_id.to_string(),
         }),
+        attempt: 0,
+        seq: 0,
         payload: Some(payload),
     }
 }
@@ -753,54 +1133,121 @@ struct JobRecordInner {
     job: Job,
     broadcaster: broadcast::Sender<JobEvent>,
     history: VecDeque<JobEvent>,
+    next_seq: u64,
     cancel_tx: watch::Sender<bool>,
+    /// Set once the scheduler admits a queued job; workers wait on this via
+    /// `AwaitJobDispatch` before they start.
+    dispatched: bool,
+    dispatch_tx: watch::Sender<bool>,
+    blocked_reason: String,
+    /// Set while a `Failed` state is withheld until the worker's `JobFailed`
+    /// shows whether the attempt will be retried.
+    failure_pending_since: Option<i64>,
+    /// Earliest time a re-queued attempt may be dispatched.
+    retry_not_before: Option<i64>,
 }
 
-async fn spawn_job_stream(
+impl JobRecordInner {
+    /// Sequence number of `history[0]`. History is a contiguous suffix of the
+    /// job's events, so it follows from the next sequence to be assigned.
+    fn first_history_seq(&self) -> u64 {
+        self.next_seq
+            .saturating_sub(self.history.len() as u64)
+            .max(1)
+    }
+}
+
+/// Where a job event stream begins.
+#[derive(Clone, Copy)]
+enum StreamStart {
+    /// Only events published after subscribing.
+    Live,
+    /// Retained events with a sequence number above the cursor, then live
+    /// events. `After(0)` replays the whole retained history.
+    After(u64),
+}
+
+fn server_notice(job_id: &str, message: String) -> JobEvent {
+    mk_event(
+        job_id,
+        apkw_proto::apkw::v1::job_event::Payload::Log(JobLogAppended {
+            chunk: Some(LogChunk {
+                stream: "server".into(),
+                data: message.into_bytes(),
+                truncated: false,
+            }),
+            records: Vec::new(),
+        }),
+    )
+}
+
+/// Retained events after `after_seq`, preceded by a notice when the bounded
+/// history no longer reaches back to the cursor.
+fn replay_after(job_id: &str, inner: &JobRecordInner, after_seq: u64) -> Vec<JobEvent> {
+    let mut out = Vec::new();
+    let first_seq = inner.first_history_seq();
+    if after_seq + 1 < first_seq && !inner.history.is_empty() {
+        out.push(server_notice(
+            job_id,
+            format!(
+                "WARNING: events {}..{} are no longer retained\n",
+                after_seq + 1,
+                first_seq - 1
+            ),
+        ));
+    }
+    out.extend(
+        inner
+            .history
+            .iter()
+            .filter(|evt| evt.seq > after_seq)
+            .cloned(),
+    );
+    out
+}
+
+/// Forwards a job's events to `event_tx`, each exactly once and in sequence
+/// order. A subscriber that lags behind the broadcast channel catches up from
+/// the retained history instead of dropping events.
+async fn spawn_job_stream<T: Send + 'static>(
     job_id: String,
     rec: Arc<Mutex<JobRecordInner>>,
-    include_history: bool,
-    event_tx: mpsc::Sender<JobEvent>,
+    start: StreamStart,
+    event_tx: mpsc::Sender<T>,
+    wrap: fn(JobEvent) -> T,
 ) {
-    let (history, mut rx) = {
+    let (replay, mut last_seq, mut rx) = {
         let inner = rec.lock().await;
-        let hist = if include_history {
-            inner.history.iter().cloned().collect::<Vec<_>>()
-        } else {
-            vec![]
+        let last_seq = inner.next_seq.saturating_sub(1);
+        let replay = match start {
+            StreamStart::Live => Vec::new(),
+            StreamStart::After(after_seq) => replay_after(&job_id, &inner, after_seq),
         };
-        (hist, inner.broadcaster.subscribe())
+        (replay, last_seq, inner.broadcaster.subscribe())
     };
 
     tokio::spawn(async move {
-        for evt in history {
-            if event_tx.send(evt).await.is_err() {
+        for evt in replay {
+            if event_tx.send(wrap(evt)).await.is_err() {
                 return;
             }
         }
 
         loop {
-            match rx.recv().await {
-                Ok(evt) => {
-                    if event_tx.send(evt).await.is_err() {
-                        return;
-                    }
-                }
-                Err(broadcast::error::RecvError::Lagged(skipped)) => {
-                    let notice = mk_event(
-                        &job_id,
-                        apkw_proto::apkw::v1::job_event::Payload::Log(JobLogAppended {
-                            chunk: Some(LogChunk {
-                                stream: "server".into(),
-                                data: format!("WARNING: client lagged; skipped {skipped} events\n")
-                                    .into_bytes(),
-                                truncated: false,
-                            }),
-                        }),
-                    );
-                    let _ = event_tx.send(notice).await;
+            let batch = match rx.recv().await {
+                Ok(evt) if evt.seq > last_seq => vec![evt],
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    let inner = rec.lock().await;
+                    replay_after(&job_id, &inner, last_seq)
                 }
                 Err(broadcast::error::RecvError::Closed) => return,
+            };
+            for evt in batch {
+                last_seq = last_seq.max(evt.seq);
+                if event_tx.send(wrap(evt)).await.is_err() {
+                    return;
+                }
             }
         }
     });
@@ -818,6 +1265,7 @@ struct JobStoreInner {
     index_keys: HashMap<String, JobIndexKey>,
     run_index: HashMap<String, HashSet<String>>,
     correlation_index: HashMap<String, HashSet<String>>,
+    log_index: LogIndex,
 }
 
 #[derive(Clone, Default)]
@@ -890,6 +1338,13 @@ impl JobStore {
         let key = JobIndexKey::from_job(&rec.job);
         let mut inner = self.inner.lock().await;
         remove_job_indexes(&mut inner, job_id);
+        inner.log_index.remove_job(job_id);
+        let first_seq = rec.first_history_seq();
+        for (idx, evt) in rec.history.iter().enumerate() {
+            inner
+                .log_index
+                .add_event(job_id, first_seq + idx as u64, evt);
+        }
         inner
             .records
             .insert(job_id.to_string(), Arc::new(Mutex::new(rec)));
@@ -913,6 +1368,43 @@ impl JobStore {
         jobs
     }
 
+    async fn records(&self) -> Vec<(String, Arc<Mutex<JobRecordInner>>)> {
+        let inner = self.inner.lock().await;
+        inner
+            .records
+            .iter()
+            .map(|(job_id, rec)| (job_id.clone(), rec.clone()))
+            .collect()
+    }
+
+    async fn queue_entries(&self) -> Vec<QueueEntry> {
+        let entries = {
+            let inner = self.inner.lock().await;
+            inner.records.values().cloned().collect::<Vec<_>>()
+        };
+        let mut out = Vec::with_capacity(entries.len());
+        for rec in entries {
+            let inner = rec.lock().await;
+            let job = &inner.job;
+            out.push(QueueEntry {
+                job_id: job
+                    .job_id
+                    .as_ref()
+                    .map(|id| id.value.clone())
+                    .unwrap_or_default(),
+                created_at_unix_millis: millis_from_ts(&job.created_at).unwrap_or_default(),
+                state: JobState::try_from(job.state).unwrap_or(JobState::Unspecified),
+                depends_on: job.depends_on.iter().map(|id| id.value.clone()).collect(),
+                concurrency_keys: job.concurrency_keys.clone(),
+                dispatched: inner.dispatched,
+                not_before_unix_millis: inner.retry_not_before,
+                priority: normalize_priority(job.priority),
+                preemptible: job.preemptible,
+            });
+        }
+        out
+    }
+
     async fn snapshot(&self) -> Vec<PersistedJob> {
         let entries = {
             let inner = self.inner.lock().await;
@@ -942,7 +1434,21 @@ impl JobStore {
         inner.records.retain(|job_id, _| keep_ids.contains(job_id));
         for job_id in removed {
             remove_job_indexes(&mut inner, &job_id);
+            inner.log_index.remove_job(&job_id);
+        }
+    }
+
+    async fn index_event(&self, job_id: &str, seq: u64, event: &JobEvent, first_seq: u64) {
+        let mut inner = self.inner.lock().await;
+        if !inner.records.contains_key(job_id) {
+            return;
         }
+        inner.log_index.evict_before(job_id, first_seq);
+        inner.log_index.add_event(job_id, seq, event);
+    }
+
+    async fn search_logs(&self, query: &str) -> HashMap<String, Vec<LogHit>> {
+        self.inner.lock().await.log_index.search(query)
     }
 
     async fn replace_with(&self, other: JobStore) {
@@ -981,7 +1487,8 @@ async fn discover_run_jobs(
     store: &JobStore,
     run_id: &str,
     correlation_id: &str,
-    include_history: bool,
+    start: StreamStart,
+    cursors: &HashMap<String, u64>,
     known_jobs: &mut HashSet<String>,
     event_tx: &mpsc::Sender<JobEvent>,
 ) {
@@ -998,7 +1505,10 @@ async fn discover_run_jobs(
             continue;
         }
         known_jobs.insert(job_id.clone());
-        spawn_job_stream(job_id, rec, include_history, event_tx.clone()).await;
+        let start = cursors
+            .get(&job_id)
+            .map_or(start, |after_seq| StreamStart::After(*after_seq));
+        spawn_job_stream(job_id, rec, start, event_tx.clone(), std::convert::identity).await;
     }
 }
 
@@ -1034,25 +1544,6 @@ async fn flush_run_buffer(
     true
 }
 
-fn load_state_file() -> JobStateFile {
-    let path = state_file_path();
-    match fs::read_to_string(&path) {
-        Ok(data) => match serde_json::from_str::<JobStateFile>(&data) {
-            Ok(file) => file,
-            Err(err) => {
-                warn!("Failed to parse {}: {}", path.display(), err);
-                JobStateFile::default()
-            }
-        },
-        Err(err) => {
-            if err.kind() != io::ErrorKind::NotFound {
-                warn!("Failed to read {}: {}", path.display(), err);
-            }
-            JobStateFile::default()
-        }
-    }
-}
-
 fn apply_retention(jobs: &mut Vec<PersistedJob>, policy: &RetentionPolicy) {
     let now_ms = now_millis();
     let max_age_ms = policy.max_age.map(|age| age.as_millis() as i64);
@@ -1086,13 +1577,14 @@ fn apply_retention(jobs: &mut Vec<PersistedJob>, policy: &RetentionPolicy) {
     *jobs = kept;
 }
 
-async fn load_store(policy: &RetentionPolicy) -> JobStore {
-    let mut state = load_state_file();
-    apply_retention(&mut state.jobs, policy);
-    let count = state.jobs.len();
+async fn load_store(policy: &RetentionPolicy) -> (JobStore, u64) {
+    let recovered = journal::recover(&journal::journal_dir(), &state_file_path());
+    let mut jobs = recovered.jobs;
+    apply_retention(&mut jobs, policy);
+    let count = jobs.len();
     let store = JobStore::default();
 
-    for job in state.jobs {
+    for job in jobs {
         let job_id = job.job_id.clone();
         store.insert(&job_id, job.into_inner()).await;
     }
@@ -1101,46 +1593,33 @@ async fn load_store(policy: &RetentionPolicy) -> JobStore {
         info!(
             "Loaded {} job(s) from {}",
             count,
-            state_file_path().display()
+            journal::journal_dir().display()
         );
     }
 
-    store
+    (store, recovered.segment)
 }
 
-async fn persist_state(store: &JobStore, policy: RetentionPolicy) -> io::Result<()> {
-    let mut jobs = store.snapshot().await;
-    apply_retention(&mut jobs, &policy);
-    let keep_ids: HashSet<String> = jobs.iter().map(|job| job.job_id.clone()).collect();
-    store.prune_to(&keep_ids).await;
-
-    let file = JobStateFile {
-        schema_version: default_schema_version(),
-        jobs,
-    };
-    write_json_atomic(&state_file_path(), &file)
-}
-
-fn spawn_persist_worker(store: JobStore, policy: RetentionPolicy) -> mpsc::Sender<()> {
-    let (tx, mut rx) = mpsc::channel::<()>(32);
+fn spawn_liveness_tick(svc: JobSvc) {
     tokio::spawn(async move {
-        while rx.recv().await.is_some() {
-            tokio::time::sleep(Duration::from_millis(PERSIST_DEBOUNCE_MS)).await;
-            while rx.try_recv().is_ok() {}
-            if let Err(err) = persist_state(&store, policy).await {
-                warn!("Failed to persist job history: {}", err);
-            }
+        let mut ticker = tokio::time::interval(Duration::from_secs(LIVENESS_TICK_SECS));
+        loop {
+            ticker.tick().await;
+            svc.settle_pending_failures().await;
+            svc.expire_overdue_jobs().await;
+            // Also admits retries whose backoff elapsed without a timer, e.g.
+            // after a restart.
+            svc.schedule_queue().await;
         }
     });
-    tx
 }
 
-fn spawn_retention_tick(tx: mpsc::Sender<()>) {
+fn spawn_retention_tick(journal: Journal) {
     tokio::spawn(async move {
         let mut ticker = tokio::time::interval(Duration::from_secs(RETENTION_TICK_SECS));
         loop {
             ticker.tick().await;
-            let _ = tx.try_send(());
+            journal.compact();
         }
     });
 }
@@ -1148,113 +1627,498 @@ fn spawn_retention_tick(tx: mpsc::Sender<()>) {
 #[derive(Clone)]
 struct JobSvc {
     store: JobStore,
-    persist_tx: mpsc::Sender<()>,
-    policy: RetentionPolicy,
+    journal: Journal,
+    job_types: JobTypeRegistry,
+    notifications: NotificationRegistry,
+    secrets: SecretRegistry,
+    scheduler: Arc<Mutex<()>>,
 }
 
 impl JobSvc {
-    fn new(store: JobStore, persist_tx: mpsc::Sender<()>, policy: RetentionPolicy) -> Self {
+    fn new(
+        store: JobStore,
+        journal: Journal,
+        job_types: JobTypeRegistry,
+        notifications: NotificationRegistry,
+        secrets: SecretRegistry,
+    ) -> Self {
         Self {
             store,
-            persist_tx,
-            policy,
+            journal,
+            job_types,
+            notifications,
+            secrets,
+            scheduler: Arc::new(Mutex::new(())),
         }
     }
 
-    fn schedule_persist(&self) {
-        let _ = self.persist_tx.try_send(());
+    async fn publish_server_log(&self, job_id: &str, message: String) {
+        self.publish(
+            job_id,
+            apkw_proto::apkw::v1::job_event::Payload::Log(JobLogAppended {
+                chunk: Some(LogChunk {
+                    stream: "server".into(),
+                    data: message.into_bytes(),
+                    truncated: false,
+                }),
+                records: Vec::new(),
+            }),
+        )
+        .await;
     }
 
-    async fn update_state_only(&self, job_id: &str, state: JobState) {
-        if let Some(rec) = self.store.get(job_id).await {
-            let mut inner = rec.lock().await;
-            inner.job.state = state as i32;
-            match state {
-                JobState::Running => inner.job.started_at = Some(now_ts()),
-                JobState::Success | JobState::Failed | JobState::Cancelled => {
-                    inner.job.finished_at = Some(now_ts())
+    /// Admits queued jobs whose dependencies and concurrency keys allow it and
+    /// cancels jobs whose dependencies can no longer succeed.
+    async fn schedule_queue(&self) {
+        let _guard = self.scheduler.lock().await;
+        loop {
+            let decisions = plan_dispatch(&self.store.queue_entries().await, now_millis());
+            let mut cascaded = false;
+            for decision in decisions {
+                match decision {
+                    QueueDecision::Dispatch { job_id } => self.dispatch_job(&job_id).await,
+                    QueueDecision::Blocked { job_id, reason } => {
+                        self.note_blocked(&job_id, reason).await
+                    }
+                    QueueDecision::DependencyFailed { job_id, reason } => {
+                        self.cancel_for_dependency(&job_id, reason).await;
+                        cascaded = true;
+                    }
+                    QueueDecision::Preempt { job_id, reason } => {
+                        self.preempt_job(&job_id, reason).await;
+                        cascaded = true;
+                    }
                 }
-                _ => {}
             }
-            self.schedule_persist();
+            if !cascaded {
+                break;
+            }
         }
     }
 
-    async fn publish(&self, job_id: &str, payload: apkw_proto::apkw::v1::job_event::Payload) {
-        if let Some(rec) = self.store.get(job_id).await {
-            let evt = mk_event(job_id, payload);
-            {
-                let mut inner = rec.lock().await;
-                // Maintain bounded history.
-                if inner.history.len() >= HISTORY_CAPACITY {
-                    inner.history.pop_front();
-                }
-                inner.history.push_back(evt.clone());
-
-                // Broadcast (ignore send errors if no listeners).
-                let _ = inner.broadcaster.send(evt);
+    async fn dispatch_job(&self, job_id: &str) {
+        let Some(rec) = self.store.get(job_id).await else {
+            return;
+        };
+        let waited_on = {
+            let mut inner = rec.lock().await;
+            if inner.dispatched || inner.job.state != JobState::Queued as i32 {
+                return;
             }
-            self.schedule_persist();
+            inner.dispatched = true;
+            inner.dispatch_tx.send_replace(true);
+            inner.retry_not_before = None;
+            self.journal_job(job_id, &inner);
+            std::mem::take(&mut inner.blocked_reason)
+        };
+        if !waited_on.is_empty() {
+            self.publish_server_log(job_id, "Dispatched after queue wait\n".into())
+                .await;
         }
     }
 
-    async fn set_state(&self, job_id: &str, state: JobState) {
-        if let Some(rec) = self.store.get(job_id).await {
+    async fn note_blocked(&self, job_id: &str, reason: String) {
+        let Some(rec) = self.store.get(job_id).await else {
+            return;
+        };
+        {
             let mut inner = rec.lock().await;
-            inner.job.state = state as i32;
-            match state {
-                JobState::Running => inner.job.started_at = Some(now_ts()),
-                JobState::Success | JobState::Failed | JobState::Cancelled => {
-                    inner.job.finished_at = Some(now_ts())
-                }
-                _ => {}
+            if inner.blocked_reason == reason {
+                return;
+            }
+            inner.blocked_reason = reason.clone();
+        }
+        self.publish_server_log(job_id, format!("Queued: {reason}\n"))
+            .await;
+    }
+
+    async fn cancel_for_dependency(&self, job_id: &str, reason: String) {
+        let Some(rec) = self.store.get(job_id).await else {
+            return;
+        };
+        {
+            let inner = rec.lock().await;
+            if inner.job.state != JobState::Queued as i32 {
+                return;
             }
+            let _ = inner.cancel_tx.send(true);
         }
+        self.publish_server_log(job_id, format!("Cancelled: {reason}\n"))
+            .await;
+        self.set_state(job_id, JobState::Cancelled).await;
+    }
+
+    /// Stops a running preemptible job and queues it again as a new attempt.
+    /// Its worker sees the `Queued` state change through `WatchCancellation`
+    /// and stops; `run_job_attempts` then waits for the next dispatch.
+    async fn preempt_job(&self, job_id: &str, reason: String) {
+        let Some(rec) = self.store.get(job_id).await else {
+            return;
+        };
+        let next_attempt = {
+            let mut inner = rec.lock().await;
+            if inner.job.state != JobState::Running as i32 {
+                return;
+            }
+            let correlation_id = inner.job.correlation_id.clone();
+            if let Some(current) = inner.job.attempts.last_mut() {
+                current.state = JobState::Cancelled as i32;
+                current.finished_at = Some(now_ts());
+                current.preempted = true;
+                current.error = Some(ErrorDetail {
+                    code: ErrorCode::Cancelled as i32,
+                    message: reason.clone(),
+                    technical_details: String::new(),
+                    remedies: vec![],
+                    correlation_id,
+                });
+            }
+            let next = queue_next_attempt(&mut inner);
+            self.journal_job(job_id, &inner);
+            next
+        };
+        self.publish_server_log(
+            job_id,
+            format!(
+                "Attempt {} {reason}; re-queued as attempt {next_attempt}\n",
+                next_attempt - 1
+            ),
+        )
+        .await;
         self.publish(
             job_id,
             apkw_proto::apkw::v1::job_event::Payload::StateChanged(JobStateChanged {
-                new_state: state as i32,
+                new_state: JobState::Queued as i32,
             }),
         )
         .await;
     }
-}
 
-async fn stream_run_events_task(
-    store: JobStore,
-    run_id: String,
-    correlation_id: String,
-    include_history: bool,
-    config: RunStreamConfig,
-    mut out_tx: mpsc::Sender<Result<JobEvent, Status>>,
-) {
-    let (event_tx, mut event_rx) = mpsc::channel::<JobEvent>(config.buffer_max_events * 2);
-    let mut known_jobs = HashSet::new();
-    let mut buffer: BinaryHeap<Reverse<BufferedEvent>> = BinaryHeap::new();
-    let mut seq = 0u64;
+    /// Gives every admitted job a fresh lease after a restart or reload so
+    /// owners that are still alive can resume heartbeating, while jobs whose
+    /// owner died with the previous process expire.
+    async fn grant_restart_leases(&self) {
+        let lease_until = now_millis().saturating_add(STARTUP_LEASE_GRACE_MS as i64);
+        for (_, rec) in self.store.records().await {
+            let mut inner = rec.lock().await;
+            let state = JobState::try_from(inner.job.state).unwrap_or(JobState::Unspecified);
+            let admitted =
+                state == JobState::Running || (state == JobState::Queued && inner.dispatched);
+            if admitted && inner.job.lease_expires_at.is_none() {
+                inner.job.lease_expires_at = Some(Timestamp {
+                    unix_millis: lease_until,
+                });
+            }
+        }
+    }
 
-    discover_run_jobs(
-        &store,
-        &run_id,
-        &correlation_id,
-        include_history,
-        &mut known_jobs,
-        &event_tx,
-    )
-    .await;
+    /// Fails active jobs whose deadline passed or whose owning service stopped
+    /// renewing its heartbeat lease.
+    async fn expire_overdue_jobs(&self) {
+        let now = now_millis();
+        let mut expired = Vec::new();
+        for (job_id, rec) in self.store.records().await {
+            let inner = rec.lock().await;
+            if let Some(reason) = overdue(&inner.job, now) {
+                // Stop an owner that is still alive before failing the job.
+                inner.cancel_tx.send_replace(true);
+                expired.push((job_id, reason, inner.job.correlation_id.clone()));
+            }
+        }
+        if expired.is_empty() {
+            return;
+        }
+        for (job_id, reason, correlation_id) in expired {
+            let error = overdue_error(reason, &correlation_id);
+            self.publish_server_log(&job_id, format!("{}\n", error.message))
+                .await;
+            self.set_state(&job_id, JobState::Failed).await;
+            self.publish(
+                &job_id,
+                apkw_proto::apkw::v1::job_event::Payload::Failed(JobFailed {
+                    error: Some(error),
+                    will_retry: false,
+                }),
+            )
+            .await;
+        }
+        self.schedule_queue().await;
+    }
 
-    let mut discovery_tick = tokio::time::interval(config.discovery_interval);
-    let mut flush_tick = tokio::time::interval(config.flush_interval);
+    /// Fails jobs whose withheld `Failed` state was never followed by a
+    /// `JobFailed` event, e.g. because the worker exited.
+    async fn settle_pending_failures(&self) {
+        let cutoff = now_millis().saturating_sub(FAILURE_SETTLE_MS);
+        let mut settled = Vec::new();
+        for (job_id, rec) in self.store.records().await {
+            let mut inner = rec.lock().await;
+            if inner
+                .failure_pending_since
+                .is_some_and(|since| since <= cutoff)
+            {
+                inner.failure_pending_since = None;
+                settled.push(job_id);
+            }
+        }
+        for job_id in settled {
+            self.set_state(&job_id, JobState::Failed).await;
+        }
+    }
 
-    loop {
-        tokio::select! {
-            _ = discovery_tick.tick() => {
-                discover_run_jobs(
-                    &store,
-                    &run_id,
-                    &correlation_id,
-                    include_history,
-                    &mut known_jobs,
+    /// Withholds a worker's `Failed` state while the job's retry policy could
+    /// still re-queue it. Returns true when the state was withheld.
+    async fn hold_failure_for_retry(&self, job_id: &str) -> bool {
+        let Some(rec) = self.store.get(job_id).await else {
+            return false;
+        };
+        let mut inner = rec.lock().await;
+        let active = matches!(
+            JobState::try_from(inner.job.state).unwrap_or(JobState::Unspecified),
+            JobState::Queued | JobState::Running
+        );
+        let attempts_left = inner
+            .job
+            .retry_policy
+            .as_ref()
+            .is_some_and(|policy| attempts_used(&inner.job.attempts) < policy.max_attempts);
+        if !active || !attempts_left {
+            return false;
+        }
+        inner.failure_pending_since.get_or_insert_with(now_millis);
+        true
+    }
+
+    /// Records a failed attempt and either fails the job or re-queues it
+    /// according to its retry policy.
+    async fn fail_attempt(&self, job_id: &str, mut failed: JobFailed) {
+        let Some(rec) = self.store.get(job_id).await else {
+            return;
+        };
+        let (retry_in, was_pending) = {
+            let mut inner = rec.lock().await;
+            let was_pending = inner.failure_pending_since.take().is_some();
+            let active = matches!(
+                JobState::try_from(inner.job.state).unwrap_or(JobState::Unspecified),
+                JobState::Queued | JobState::Running
+            );
+            let code = failed
+                .error
+                .as_ref()
+                .map(|err| err.code)
+                .unwrap_or_default();
+            let retry_in = inner
+                .job
+                .retry_policy
+                .as_ref()
+                .filter(|policy| active && is_retryable(policy, code))
+                .and_then(|policy| retry_backoff_ms(policy, attempts_used(&inner.job.attempts)));
+            if let Some(current) = inner.job.attempts.last_mut() {
+                current.error = failed.error.clone();
+            }
+            (retry_in, was_pending)
+        };
+        failed.will_retry = retry_in.is_some();
+        match retry_in {
+            Some(delay_ms) => {
+                self.publish(
+                    job_id,
+                    apkw_proto::apkw::v1::job_event::Payload::Failed(failed),
+                )
+                .await;
+                self.requeue_for_retry(job_id, delay_ms).await;
+            }
+            None => {
+                if was_pending {
+                    // The worker's StateChanged(Failed) was withheld; publish it now.
+                    self.set_state(job_id, JobState::Failed).await;
+                } else {
+                    self.update_state_only(job_id, JobState::Failed).await;
+                }
+                self.publish(
+                    job_id,
+                    apkw_proto::apkw::v1::job_event::Payload::Failed(failed),
+                )
+                .await;
+            }
+        }
+        self.schedule_queue().await;
+    }
+
+    async fn requeue_for_retry(&self, job_id: &str, delay_ms: u64) {
+        let Some(rec) = self.store.get(job_id).await else {
+            return;
+        };
+        let (failed_attempt, used, max_attempts) = {
+            let inner = rec.lock().await;
+            let max_attempts = inner
+                .job
+                .retry_policy
+                .as_ref()
+                .map(|policy| policy.max_attempts)
+                .unwrap_or_default();
+            (
+                inner.job.attempt,
+                attempts_used(&inner.job.attempts),
+                max_attempts,
+            )
+        };
+        self.publish_server_log(
+            job_id,
+            format!(
+                "Attempt {failed_attempt} failed; retrying in {delay_ms} ms (attempt {} of {max_attempts})\n",
+                used + 1
+            ),
+        )
+        .await;
+        {
+            let mut inner = rec.lock().await;
+            apply_job_state(&mut inner.job, JobState::Failed);
+            queue_next_attempt(&mut inner);
+            inner.retry_not_before =
+                Some(now_millis().saturating_add(delay_ms.min(i64::MAX as u64) as i64));
+            self.journal_job(job_id, &inner);
+        }
+        self.publish(
+            job_id,
+            apkw_proto::apkw::v1::job_event::Payload::StateChanged(JobStateChanged {
+                new_state: JobState::Queued as i32,
+            }),
+        )
+        .await;
+
+        let svc = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            svc.schedule_queue().await;
+        });
+    }
+
+    fn journal_job(&self, job_id: &str, inner: &JobRecordInner) {
+        self.journal.append(JournalRecord::JobUpdated {
+            job: PersistedJob::meta_from_inner(job_id, inner),
+        });
+    }
+
+    async fn update_state_only(&self, job_id: &str, state: JobState) {
+        if let Some(rec) = self.store.get(job_id).await {
+            let mut inner = rec.lock().await;
+            if state == JobState::Running && !inner.dispatched {
+                // Workers that skip AwaitJobDispatch still hold their keys.
+                inner.dispatched = true;
+                inner.dispatch_tx.send_replace(true);
+            }
+            if state == JobState::Cancelled {
+                // Cancellation published by another service still reaches
+                // WatchCancellation subscribers.
+                inner.cancel_tx.send_replace(true);
+            }
+            apply_job_state(&mut inner.job, state);
+            self.journal_job(job_id, &inner);
+        }
+    }
+
+    async fn publish(&self, job_id: &str, payload: apkw_proto::apkw::v1::job_event::Payload) {
+        if let Some(rec) = self.store.get(job_id).await {
+            let finished = finished_state(&payload).map(|state| (state, payload.clone()));
+            let mut evt = mk_event(job_id, payload);
+            let mut inner = rec.lock().await;
+            // Stamped under the lock so timestamps never run backwards
+            // within a job's sequence.
+            evt.at = Some(now_ts());
+            evt.attempt = inner.job.attempt;
+            let seq = inner.next_seq;
+            inner.next_seq += 1;
+            evt.seq = seq;
+            if let Some(event) = PersistedEvent::from_proto(&evt, seq) {
+                self.journal.append(JournalRecord::Event {
+                    job_id: job_id.to_string(),
+                    event,
+                });
+            }
+
+            // Maintain bounded history.
+            if inner.history.len() >= HISTORY_CAPACITY {
+                inner.history.pop_front();
+            }
+            inner.history.push_back(evt.clone());
+            // Indexed under the record lock so a job's lines stay in order.
+            self.store
+                .index_event(job_id, seq, &evt, inner.first_history_seq())
+                .await;
+
+            // Broadcast (ignore send errors if no listeners).
+            let _ = inner.broadcaster.send(evt);
+
+            if let Some((state, payload)) = finished {
+                let job = inner.job.clone();
+                drop(inner);
+                self.notifications.dispatch(&job, state, &payload).await;
+            }
+        }
+    }
+
+    async fn set_state(&self, job_id: &str, state: JobState) {
+        if let Some(rec) = self.store.get(job_id).await {
+            let mut inner = rec.lock().await;
+            apply_job_state(&mut inner.job, state);
+            self.journal_job(job_id, &inner);
+        }
+        self.publish(
+            job_id,
+            apkw_proto::apkw::v1::job_event::Payload::StateChanged(JobStateChanged {
+                new_state: state as i32,
+            }),
+        )
+        .await;
+    }
+}
+
+async fn stream_run_events_task(
+    store: JobStore,
+    run_id: String,
+    correlation_id: String,
+    include_history: bool,
+    cursors: HashMap<String, u64>,
+    config: RunStreamConfig,
+    mut out_tx: mpsc::Sender<Result<JobEvent, Status>>,
+) {
+    let (event_tx, mut event_rx) = mpsc::channel::<JobEvent>(config.buffer_max_events * 2);
+    let mut known_jobs = HashSet::new();
+    let mut buffer: BinaryHeap<Reverse<BufferedEvent>> = BinaryHeap::new();
+    let mut arrival = 0u64;
+
+    // A resuming client (any cursor given) must also see jobs it has not
+    // heard of yet from their first event. Otherwise jobs, including those
+    // discovered later, start at their current sequence.
+    let start = if include_history || !cursors.is_empty() {
+        StreamStart::After(0)
+    } else {
+        StreamStart::Live
+    };
+    discover_run_jobs(
+        &store,
+        &run_id,
+        &correlation_id,
+        start,
+        &cursors,
+        &mut known_jobs,
+        &event_tx,
+    )
+    .await;
+
+    let mut discovery_tick = tokio::time::interval(config.discovery_interval);
+    let mut flush_tick = tokio::time::interval(config.flush_interval);
+
+    loop {
+        tokio::select! {
+            _ = discovery_tick.tick() => {
+                discover_run_jobs(
+                    &store,
+                    &run_id,
+                    &correlation_id,
+                    start,
+                    &cursors,
+                    &mut known_jobs,
                     &event_tx,
                 )
                 .await;
@@ -1272,12 +2136,18 @@ async fn stream_run_events_task(
                     break;
                 };
                 let at = event_timestamp(&evt);
+                let job_id = evt
+                    .job_id
+                    .as_ref()
+                    .map(|id| id.value.clone())
+                    .unwrap_or_default();
                 buffer.push(Reverse(BufferedEvent {
                     at_unix_millis: at,
-                    seq,
+                    job_id,
+                    arrival,
                     event: evt,
                 }));
-                seq = seq.wrapping_add(1);
+                arrival = arrival.wrapping_add(1);
                 if !flush_run_buffer(&mut buffer, &mut out_tx, &config, false).await {
                     return;
                 }
@@ -1299,15 +2169,31 @@ impl JobService for JobSvc {
         if job_type.is_empty() {
             return Err(Status::invalid_argument("job_type is required"));
         }
-        if !is_known_job_type(job_type) {
-            return Err(Status::invalid_argument(format!(
-                "unknown job_type: {job_type}"
-            )));
+        let display_name = self
+            .job_types
+            .get(job_type)
+            .await
+            .map(|info| info.display_name)
+            .ok_or_else(|| Status::invalid_argument(format!("unknown job_type: {job_type}")))?;
+        let mut depends_on = Vec::with_capacity(req.depends_on.len());
+        for dep in &req.depends_on {
+            let dep_id = dep.value.trim();
+            if dep_id.is_empty() {
+                return Err(Status::invalid_argument("depends_on ids must not be empty"));
+            }
+            if self.store.get(dep_id).await.is_none() {
+                return Err(Status::not_found(format!(
+                    "dependency job not found: {dep_id}"
+                )));
+            }
+            if !depends_on.iter().any(|id: &Id| id.value == dep_id) {
+                depends_on.push(Id {
+                    value: dep_id.to_string(),
+                });
+            }
         }
-        let display_name = match job_type {
-            "workflow.pipeline" => "Workflow Pipeline",
-            _ => job_type,
-        };
+        let concurrency_keys = normalize_concurrency_keys(&req.concurrency_keys);
+        let retry_policy = normalize_retry_policy(req.retry_policy)?;
 
         let job_id = Uuid::new_v4().to_string();
         let (btx, _brx) = broadcast::channel::<JobEvent>(BROADCAST_CAPACITY);
@@ -1344,23 +2230,47 @@ impl JobService for JobSvc {
             created_at: Some(now_ts()),
             started_at: None,
             finished_at: None,
-            display_name: display_name.into(),
+            display_name,
             correlation_id,
             run_id: run_ref,
             project_id: req.project_id,
             target_id: req.target_id,
             toolchain_set_id: req.toolchain_set_id,
+            depends_on,
+            concurrency_keys,
+            timeout_ms: req.timeout_ms,
+            deadline: None,
+            lease_expires_at: None,
+            retry_policy,
+            attempt: 1,
+            attempts: vec![JobAttempt {
+                attempt: 1,
+                state: JobState::Queued as i32,
+                ..Default::default()
+            }],
+            priority: normalize_priority(req.priority) as i32,
+            preemptible: req.preemptible,
         };
 
+        let (dispatch_tx, _dispatch_rx) = watch::channel(false);
         let rec = JobRecordInner {
             job,
             broadcaster: btx.clone(),
             history: VecDeque::with_capacity(HISTORY_CAPACITY),
+            next_seq: 1,
             cancel_tx,
+            dispatched: false,
+            dispatch_tx,
+            blocked_reason: String::new(),
+            failure_pending_since: None,
+            retry_not_before: None,
         };
 
+        self.journal.append(JournalRecord::JobCreated {
+            job: PersistedJob::meta_from_inner(&job_id, &rec),
+        });
         self.store.insert(&job_id, rec).await;
-        self.schedule_persist();
+        self.schedule_queue().await;
 
         Ok(Response::new(StartJobResponse {
             job: Some(JobRef {
@@ -1408,13 +2318,183 @@ impl JobService for JobSvc {
         ) {
             return Ok(Response::new(CancelJobResponse { accepted: false }));
         }
+        let cancellable = self
+            .job_types
+            .get(&inner.job.job_type)
+            .await
+            .is_none_or(|info| info.cancellable);
+        if !cancellable {
+            return Ok(Response::new(CancelJobResponse { accepted: false }));
+        }
         let _ = inner.cancel_tx.send(true);
         drop(inner);
 
         self.set_state(&job_id, JobState::Cancelled).await;
+        self.schedule_queue().await;
         Ok(Response::new(CancelJobResponse { accepted: true }))
     }
 
+    async fn await_job_dispatch(
+        &self,
+        request: Request<AwaitJobDispatchRequest>,
+    ) -> Result<Response<AwaitJobDispatchResponse>, Status> {
+        let req = request.into_inner();
+        let job_id = req.job_id.map(|i| i.value).unwrap_or_default();
+        let rec = self
+            .store
+            .get(&job_id)
+            .await
+            .ok_or_else(|| Status::not_found(format!("Job not found: {job_id}")))?;
+        self.schedule_queue().await;
+
+        let (mut dispatch_rx, mut cancel_rx) = {
+            let inner = rec.lock().await;
+            (inner.dispatch_tx.subscribe(), inner.cancel_tx.subscribe())
+        };
+        loop {
+            {
+                let inner = rec.lock().await;
+                let state = JobState::try_from(inner.job.state).unwrap_or(JobState::Unspecified);
+                let dispatched = match state {
+                    JobState::Success | JobState::Failed | JobState::Cancelled => Some(false),
+                    JobState::Running => Some(true),
+                    _ if inner.dispatched => Some(true),
+                    _ => None,
+                };
+                if let Some(dispatched) = dispatched {
+                    return Ok(Response::new(AwaitJobDispatchResponse {
+                        dispatched,
+                        state: state as i32,
+                    }));
+                }
+            }
+            // Terminal transitions published by other services do not touch
+            // the watch channels, so re-check periodically as well.
+            tokio::select! {
+                changed = dispatch_rx.changed() => {
+                    if changed.is_err() {
+                        return Err(Status::aborted("job record was reloaded"));
+                    }
+                }
+                changed = cancel_rx.changed() => {
+                    if changed.is_err() {
+                        return Err(Status::aborted("job record was reloaded"));
+                    }
+                }
+                _ = tokio::time::sleep(Duration::from_secs(DISPATCH_RECHECK_SECS)) => {}
+            }
+        }
+    }
+
+    async fn renew_job_lease(
+        &self,
+        request: Request<RenewJobLeaseRequest>,
+    ) -> Result<Response<RenewJobLeaseResponse>, Status> {
+        let req = request.into_inner();
+        let job_id = req.job_id.map(|i| i.value).unwrap_or_default();
+        let rec = self
+            .store
+            .get(&job_id)
+            .await
+            .ok_or_else(|| Status::not_found(format!("Job not found: {job_id}")))?;
+
+        let mut inner = rec.lock().await;
+        let state = JobState::try_from(inner.job.state).unwrap_or(JobState::Unspecified);
+        if !matches!(state, JobState::Queued | JobState::Running) {
+            return Ok(Response::new(RenewJobLeaseResponse {
+                accepted: false,
+                lease_expires_at: None,
+            }));
+        }
+        let lease_expires_at = Timestamp {
+            unix_millis: now_millis().saturating_add(clamp_lease_ms(req.lease_ms) as i64),
+        };
+        inner.job.lease_expires_at = Some(lease_expires_at);
+        Ok(Response::new(RenewJobLeaseResponse {
+            accepted: true,
+            lease_expires_at: Some(lease_expires_at),
+        }))
+    }
+
+    type WatchCancellationStream = ReceiverStream<Result<JobCancellation, Status>>;
+
+    async fn watch_cancellation(
+        &self,
+        request: Request<WatchCancellationRequest>,
+    ) -> Result<Response<Self::WatchCancellationStream>, Status> {
+        let req = request.into_inner();
+        let job_id = req.job_id.map(|i| i.value).unwrap_or_default();
+        let rec = self
+            .store
+            .get(&job_id)
+            .await
+            .ok_or_else(|| Status::not_found(format!("Job not found: {job_id}")))?;
+
+        // Subscribe under the record lock so no transition slips between the
+        // state check and the live watch.
+        let (state, mut cancel_rx, mut events) = {
+            let inner = rec.lock().await;
+            (
+                JobState::try_from(inner.job.state).unwrap_or(JobState::Unspecified),
+                inner.cancel_tx.subscribe(),
+                inner.broadcaster.subscribe(),
+            )
+        };
+
+        let (tx, out_rx) = mpsc::channel::<Result<JobCancellation, Status>>(1);
+        tokio::spawn(async move {
+            let cancelled = if state == JobState::Cancelled || *cancel_rx.borrow() {
+                Ok(true)
+            } else if matches!(state, JobState::Success | JobState::Failed) {
+                Ok(false)
+            } else {
+                loop {
+                    tokio::select! {
+                        changed = cancel_rx.changed() => {
+                            if changed.is_err() {
+                                break Err(Status::aborted("job record was reloaded"));
+                            }
+                            if *cancel_rx.borrow() {
+                                break Ok(true);
+                            }
+                        }
+                        evt = events.recv() => match evt {
+                            Ok(evt) => {
+                                if let Some(apkw_proto::apkw::v1::job_event::Payload::StateChanged(
+                                    change,
+                                )) = evt.payload
+                                {
+                                    match JobState::try_from(change.new_state) {
+                                        // A running attempt sent back to the
+                                        // queue (preemption) must stop too.
+                                        Ok(JobState::Cancelled | JobState::Queued) => break Ok(true),
+                                        Ok(JobState::Success | JobState::Failed) => break Ok(false),
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            Err(broadcast::error::RecvError::Lagged(_)) => {}
+                            Err(broadcast::error::RecvError::Closed) => {
+                                break Err(Status::aborted("job record was reloaded"));
+                            }
+                        },
+                    }
+                }
+            };
+            let msg = match cancelled {
+                Ok(true) => Ok(JobCancellation {
+                    job_id: Some(Id { value: job_id }),
+                    at: Some(now_ts()),
+                }),
+                Ok(false) => return,
+                Err(status) => Err(status),
+            };
+            let _ = tx.send(msg).await;
+        });
+
+        Ok(Response::new(ReceiverStream::new(out_rx)))
+    }
+
     type StreamJobEventsStream = ReceiverStream<Result<JobEvent, Status>>;
     type StreamRunEventsStream = ReceiverStream<Result<JobEvent, Status>>;
 
@@ -1436,58 +2516,15 @@ impl JobService for JobSvc {
             Status::not_found(format!("{:?}", err))
         })?;
 
-        // Snapshot history and subscribe to broadcast.
-        let (history, mut rx) = {
-            let inner = rec.lock().await;
-            let hist = if req.include_history {
-                inner.history.iter().cloned().collect::<Vec<_>>()
-            } else {
-                vec![]
-            };
-            (hist, inner.broadcaster.subscribe())
+        let start = if req.after_seq > 0 {
+            StreamStart::After(req.after_seq)
+        } else if req.include_history {
+            StreamStart::After(0)
+        } else {
+            StreamStart::Live
         };
-
         let (tx, out_rx) = mpsc::channel::<Result<JobEvent, Status>>(1024);
-
-        // Producer task: replay history then forward live events.
-        let job_id_clone = job_id.clone();
-        tokio::spawn(async move {
-            for evt in history {
-                if tx.send(Ok(evt)).await.is_err() {
-                    return;
-                }
-            }
-
-            loop {
-                match rx.recv().await {
-                    Ok(evt) => {
-                        if tx.send(Ok(evt)).await.is_err() {
-                            return;
-                        }
-                    }
-                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
-                        // Best-effort notice to the client.
-                        let notice = mk_event(
-                            &job_id_clone,
-                            apkw_proto::apkw::v1::job_event::Payload::Log(JobLogAppended {
-                                chunk: Some(LogChunk {
-                                    stream: "server".into(),
-                                    data: format!(
-                                        "WARNING: client lagged; skipped {skipped} events\n"
-                                    )
-                                    .into_bytes(),
-                                    truncated: false,
-                                }),
-                            }),
-                        );
-                        let _ = tx.send(Ok(notice)).await;
-                    }
-                    Err(broadcast::error::RecvError::Closed) => {
-                        return;
-                    }
-                }
-            }
-        });
+        spawn_job_stream(job_id, rec, start, tx, Ok).await;
 
         Ok(Response::new(ReceiverStream::new(out_rx)))
     }
@@ -1511,14 +2548,35 @@ impl JobService for JobSvc {
             ));
         }
 
+        let mut cursors = HashMap::new();
+        for cursor in &req.cursors {
+            let job_id = cursor
+                .job_id
+                .as_ref()
+                .map(|id| id.value.trim())
+                .unwrap_or_default();
+            if job_id.is_empty() {
+                return Err(Status::invalid_argument("cursor job_id is required"));
+            }
+            cursors.insert(job_id.to_string(), cursor.after_seq);
+        }
+
         let config = run_stream_config(&req);
         let (tx, out_rx) = mpsc::channel::<Result<JobEvent, Status>>(1024);
         let store = self.store.clone();
         let include_history = req.include_history;
 
         tokio::spawn(async move {
-            stream_run_events_task(store, run_id, correlation_id, include_history, config, tx)
-                .await;
+            stream_run_events_task(
+                store,
+                run_id,
+                correlation_id,
+                include_history,
+                cursors,
+                config,
+                tx,
+            )
+            .await;
         });
 
         Ok(Response::new(ReceiverStream::new(out_rx)))
@@ -1544,23 +2602,35 @@ impl JobService for JobSvc {
         let payload = event
             .payload
             .ok_or_else(|| Status::invalid_argument("event.payload is required"))?;
+        let payload = self.secrets.redact(payload).await;
 
-        match &payload {
+        let state_changed = match &payload {
             apkw_proto::apkw::v1::job_event::Payload::StateChanged(state) => {
                 let new_state =
                     JobState::try_from(state.new_state).unwrap_or(JobState::Unspecified);
+                if new_state == JobState::Failed && self.hold_failure_for_retry(&job_id).await {
+                    // The JobFailed event that follows decides whether this
+                    // attempt is retried or the job fails.
+                    return Ok(Response::new(PublishJobEventResponse { accepted: true }));
+                }
                 self.update_state_only(&job_id, new_state).await;
+                true
             }
             apkw_proto::apkw::v1::job_event::Payload::Completed(_) => {
                 self.update_state_only(&job_id, JobState::Success).await;
+                true
             }
-            apkw_proto::apkw::v1::job_event::Payload::Failed(_) => {
-                self.update_state_only(&job_id, JobState::Failed).await;
+            apkw_proto::apkw::v1::job_event::Payload::Failed(failed) => {
+                self.fail_attempt(&job_id, failed.clone()).await;
+                return Ok(Response::new(PublishJobEventResponse { accepted: true }));
             }
-            _ => {}
-        }
+            _ => false,
+        };
 
         self.publish(&job_id, payload).await;
+        if state_changed {
+            self.schedule_queue().await;
+        }
 
         Ok(Response::new(PublishJobEventResponse { accepted: true }))
     }
@@ -1644,16 +2714,213 @@ impl JobService for JobSvc {
         &self,
         _request: Request<ReloadStateRequest>,
     ) -> Result<Response<ReloadStateResponse>, Status> {
-        let store = load_store(&self.policy).await;
-        let count = store.list_jobs().await.len() as u32;
-        self.store.replace_with(store).await;
-        self.schedule_persist();
+        let count = self
+            .journal
+            .reload()
+            .await
+            .ok_or_else(|| Status::unavailable("job journal writer is not running"))?;
+        self.job_types.reload().await;
+        self.notifications.reload().await;
+        self.secrets.reload().await;
+        self.grant_restart_leases().await;
+        self.schedule_queue().await;
         Ok(Response::new(ReloadStateResponse {
             ok: true,
-            item_count: count,
+            item_count: count as u32,
             detail: "job state reloaded".into(),
         }))
     }
+
+    async fn register_job_type(
+        &self,
+        request: Request<RegisterJobTypeRequest>,
+    ) -> Result<Response<RegisterJobTypeResponse>, Status> {
+        let info = request
+            .into_inner()
+            .job_type
+            .ok_or_else(|| Status::invalid_argument("job_type is required"))?;
+        let (info, created) = self.job_types.register(info).await?;
+        info!(
+            "{} job type {} ({})",
+            if created { "registered" } else { "updated" },
+            info.job_type,
+            if info.service_addr.is_empty() {
+                "no service address"
+            } else {
+                info.service_addr.as_str()
+            }
+        );
+        Ok(Response::new(RegisterJobTypeResponse {
+            job_type: Some(info),
+            created,
+        }))
+    }
+
+    async fn list_job_types(
+        &self,
+        _request: Request<ListJobTypesRequest>,
+    ) -> Result<Response<ListJobTypesResponse>, Status> {
+        Ok(Response::new(ListJobTypesResponse {
+            job_types: self.job_types.list().await,
+        }))
+    }
+
+    async fn search_job_logs(
+        &self,
+        request: Request<SearchJobLogsRequest>,
+    ) -> Result<Response<SearchJobLogsResponse>, Status> {
+        let req = request.into_inner();
+        let query = req.query.trim();
+        if query.is_empty() {
+            return Err(Status::invalid_argument("query is required"));
+        }
+        let (start, page_size) = parse_page(req.page)?;
+        let filter = req.filter.unwrap_or_default();
+        let max_matches = match req.max_matches_per_job as usize {
+            0 => DEFAULT_SEARCH_MATCHES_PER_JOB,
+            n => n.min(MAX_SEARCH_MATCHES_PER_JOB),
+        };
+
+        let mut found = Vec::new();
+        for (job_id, hits) in self.store.search_logs(query).await {
+            let Some(rec) = self.store.get(&job_id).await else {
+                continue;
+            };
+            let (job, first_seq) = {
+                let inner = rec.lock().await;
+                (inner.job.clone(), inner.first_history_seq())
+            };
+            if job_matches_filter(&job, &filter) {
+                found.push((job, first_seq, hits));
+            }
+        }
+        found.sort_by_key(|(job, _, _)| Reverse(job_sort_key(job)));
+
+        let total = found.len();
+        if start >= total && total != 0 {
+            return Err(Status::invalid_argument("page_token out of range"));
+        }
+        let end = (start + page_size).min(total);
+        let results = found
+            .drain(start..end)
+            .map(|(job, first_seq, hits)| JobLogSearchResult {
+                job: Some(job),
+                total_matches: hits.len() as u32,
+                matches: hits
+                    .into_iter()
+                    .take(max_matches)
+                    .map(|hit| JobLogMatch {
+                        event_seq: hit.seq,
+                        event_offset: hit.seq.saturating_sub(first_seq),
+                        attempt: hit.attempt.max(1),
+                        at: Some(Timestamp {
+                            unix_millis: hit.at_unix_millis,
+                        }),
+                        stream: hit.stream,
+                        snippet: hit.snippet,
+                    })
+                    .collect(),
+            })
+            .collect();
+        let next_token = if end < total {
+            end.to_string()
+        } else {
+            String::new()
+        };
+
+        Ok(Response::new(SearchJobLogsResponse {
+            results,
+            page_info: Some(PageInfo {
+                next_page_token: next_token,
+            }),
+        }))
+    }
+
+    async fn create_notification_rule(
+        &self,
+        request: Request<CreateNotificationRuleRequest>,
+    ) -> Result<Response<CreateNotificationRuleResponse>, Status> {
+        let rule = request
+            .into_inner()
+            .rule
+            .ok_or_else(|| Status::invalid_argument("rule is required"))?;
+        let rule = self.notifications.create(rule).await?;
+        info!("saved notification rule {} ({})", rule.rule_id, rule.name);
+        Ok(Response::new(CreateNotificationRuleResponse {
+            rule: Some(rule),
+        }))
+    }
+
+    async fn list_notification_rules(
+        &self,
+        _request: Request<ListNotificationRulesRequest>,
+    ) -> Result<Response<ListNotificationRulesResponse>, Status> {
+        Ok(Response::new(ListNotificationRulesResponse {
+            rules: self.notifications.list().await,
+        }))
+    }
+
+    async fn delete_notification_rule(
+        &self,
+        request: Request<DeleteNotificationRuleRequest>,
+    ) -> Result<Response<DeleteNotificationRuleResponse>, Status> {
+        let rule_id = request.into_inner().rule_id;
+        let rule_id = rule_id.trim();
+        if rule_id.is_empty() {
+            return Err(Status::invalid_argument("rule_id is required"));
+        }
+        let deleted = self.notifications.delete(rule_id).await?;
+        Ok(Response::new(DeleteNotificationRuleResponse { deleted }))
+    }
+
+    async fn set_secret(
+        &self,
+        request: Request<SetSecretRequest>,
+    ) -> Result<Response<SetSecretResponse>, Status> {
+        let req = request.into_inner();
+        let name = req.name.trim();
+        if name.is_empty() {
+            return Err(Status::invalid_argument("name is required"));
+        }
+        let (secret, created) = self
+            .secrets
+            .set(name, &req.value, req.description.trim())
+            .await?;
+        info!(
+            "{} secret {}",
+            if created { "stored" } else { "updated" },
+            secret.name
+        );
+        Ok(Response::new(SetSecretResponse {
+            secret: Some(secret),
+            created,
+        }))
+    }
+
+    async fn list_secrets(
+        &self,
+        _request: Request<ListSecretsRequest>,
+    ) -> Result<Response<ListSecretsResponse>, Status> {
+        Ok(Response::new(ListSecretsResponse {
+            secrets: self.secrets.list().await?,
+        }))
+    }
+
+    async fn delete_secret(
+        &self,
+        request: Request<DeleteSecretRequest>,
+    ) -> Result<Response<DeleteSecretResponse>, Status> {
+        let name = request.into_inner().name;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Status::invalid_argument("name is required"));
+        }
+        let deleted = self.secrets.delete(name).await?;
+        if deleted {
+            info!("deleted secret {name}");
+        }
+        Ok(Response::new(DeleteSecretResponse { deleted }))
+    }
 }
 
 #[tokio::main]
@@ -1663,11 +2930,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     init_service_telemetry("apkw-core", env!("CARGO_PKG_VERSION"), "job");
 
     let retention = retention_policy_from_env();
-    let store = load_store(&retention).await;
-    let persist_tx = spawn_persist_worker(store.clone(), retention);
-    spawn_retention_tick(persist_tx.clone());
-    let svc = JobSvc::new(store, persist_tx.clone(), retention);
-    let _ = persist_tx.try_send(());
+    let (store, segment) = load_store(&retention).await;
+    let journal = journal::spawn_writer(store.clone(), retention, segment);
+    spawn_retention_tick(journal.clone());
+    let svc = JobSvc::new(
+        store,
+        journal.clone(),
+        JobTypeRegistry::load(),
+        NotificationRegistry::load(),
+        SecretRegistry::load(),
+    );
+    svc.grant_restart_leases().await;
+    svc.schedule_queue().await;
+    spawn_liveness_tick(svc.clone());
+    // Fold recovered segments (or a legacy jobs.json) into a fresh snapshot.
+    journal.compact();
 
     serve_grpc(
         "apkw-core",
@@ -1677,3 +2954,327 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     )
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_svc(name: &str) -> JobSvc {
+        let dir = std::env::temp_dir().join(format!(
+            "apkw-core-svc-{name}-{}-{}",
+            std::process::id(),
+            now_millis()
+        ));
+        JobSvc::new(
+            JobStore::default(),
+            Journal::detached(),
+            JobTypeRegistry::at(dir.join("job-types.json")),
+            NotificationRegistry::at(dir.join("notification-rules.json")),
+            SecretRegistry::unavailable(),
+        )
+    }
+
+    async fn start(svc: &JobSvc, req: StartJobRequest) -> String {
+        svc.start_job(Request::new(StartJobRequest {
+            job_type: "build.run".into(),
+            ..req
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .job
+        .and_then(|job| job.job_id)
+        .unwrap()
+        .value
+    }
+
+    async fn job(svc: &JobSvc, job_id: &str) -> Job {
+        svc.store
+            .get(job_id)
+            .await
+            .unwrap()
+            .lock()
+            .await
+            .job
+            .clone()
+    }
+
+    async fn publish_state(svc: &JobSvc, job_id: &str, state: JobState) {
+        svc.publish_job_event(Request::new(PublishJobEventRequest {
+            event: Some(mk_event(
+                job_id,
+                apkw_proto::apkw::v1::job_event::Payload::StateChanged(JobStateChanged {
+                    new_state: state as i32,
+                }),
+            )),
+        }))
+        .await
+        .unwrap();
+    }
+
+    /// The last `JobFailed` recorded in the job's history.
+    async fn last_failure(svc: &JobSvc, job_id: &str) -> Option<JobFailed> {
+        let rec = svc.store.get(job_id).await.unwrap();
+        let inner = rec.lock().await;
+        inner
+            .history
+            .iter()
+            .rev()
+            .find_map(|evt| match &evt.payload {
+                Some(apkw_proto::apkw::v1::job_event::Payload::Failed(failed)) => {
+                    Some(failed.clone())
+                }
+                _ => None,
+            })
+    }
+
+    async fn publish_failure(svc: &JobSvc, job_id: &str, code: ErrorCode) {
+        publish_state(svc, job_id, JobState::Failed).await;
+        svc.publish_job_event(Request::new(PublishJobEventRequest {
+            event: Some(mk_event(
+                job_id,
+                apkw_proto::apkw::v1::job_event::Payload::Failed(JobFailed {
+                    error: Some(ErrorDetail {
+                        code: code as i32,
+                        message: "gradle daemon disappeared".into(),
+                        ..Default::default()
+                    }),
+                    will_retry: false,
+                }),
+            )),
+        }))
+        .await
+        .unwrap();
+    }
+
+    async fn publish_log(svc: &JobSvc, job_id: &str, text: &str) {
+        svc.publish_job_event(Request::new(PublishJobEventRequest {
+            event: Some(server_notice(job_id, text.to_string())),
+        }))
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn lease_requests_are_clamped() {
+        assert_eq!(clamp_lease_ms(0), DEFAULT_JOB_LEASE_MS);
+        assert_eq!(clamp_lease_ms(1), MIN_JOB_LEASE_MS);
+        assert_eq!(clamp_lease_ms(45_000), 45_000);
+        assert_eq!(clamp_lease_ms(u64::MAX), MAX_JOB_LEASE_MS);
+    }
+
+    #[tokio::test]
+    async fn overdue_jobs_fail_and_live_ones_are_kept() {
+        let svc = test_svc("overdue");
+        let timed_out = start(
+            &svc,
+            StartJobRequest {
+                timeout_ms: 60_000,
+                ..Default::default()
+            },
+        )
+        .await;
+        let orphaned = start(&svc, StartJobRequest::default()).await;
+        let healthy = start(&svc, StartJobRequest::default()).await;
+        for job_id in [&timed_out, &orphaned, &healthy] {
+            publish_state(&svc, job_id, JobState::Running).await;
+        }
+        assert!(job(&svc, &timed_out).await.deadline.is_some());
+
+        let past = Some(Timestamp {
+            unix_millis: now_millis() - 1,
+        });
+        let future = Some(Timestamp {
+            unix_millis: now_millis() + 60_000,
+        });
+        svc.store
+            .get(&timed_out)
+            .await
+            .unwrap()
+            .lock()
+            .await
+            .job
+            .deadline = past;
+        svc.store
+            .get(&orphaned)
+            .await
+            .unwrap()
+            .lock()
+            .await
+            .job
+            .lease_expires_at = past;
+        svc.store
+            .get(&healthy)
+            .await
+            .unwrap()
+            .lock()
+            .await
+            .job
+            .lease_expires_at = future;
+
+        svc.expire_overdue_jobs().await;
+
+        for job_id in [&timed_out, &orphaned] {
+            let job = job(&svc, job_id).await;
+            assert_eq!(job.state, JobState::Failed as i32);
+            assert_eq!(job.attempts[0].state, JobState::Failed as i32);
+            assert!(job.finished_at.is_some());
+            let failed = last_failure(&svc, job_id).await.unwrap();
+            assert_eq!(failed.error.unwrap().code, ErrorCode::Timeout as i32);
+            assert!(!failed.will_retry);
+            let rec = svc.store.get(job_id).await.unwrap();
+            assert!(*rec.lock().await.cancel_tx.borrow());
+        }
+        assert!(last_failure(&svc, &timed_out)
+            .await
+            .and_then(|failed| failed.error)
+            .is_some_and(|err| err.message.contains("60000 ms timeout")));
+        assert_eq!(job(&svc, &healthy).await.state, JobState::Running as i32);
+    }
+
+    #[tokio::test]
+    async fn failed_attempts_are_requeued_until_the_policy_is_used_up() {
+        let svc = test_svc("retry");
+        let job_id = start(
+            &svc,
+            StartJobRequest {
+                retry_policy: Some(RetryPolicy {
+                    max_attempts: 2,
+                    initial_backoff_ms: 60_000,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+        .await;
+        publish_state(&svc, &job_id, JobState::Running).await;
+        publish_failure(&svc, &job_id, ErrorCode::Internal).await;
+
+        let requeued = job(&svc, &job_id).await;
+        assert_eq!(requeued.state, JobState::Queued as i32);
+        assert_eq!(requeued.attempt, 2);
+        assert_eq!(requeued.attempts.len(), 2);
+        assert_eq!(requeued.attempts[0].state, JobState::Failed as i32);
+        assert_eq!(
+            requeued.attempts[0].error.as_ref().map(|err| err.code),
+            Some(ErrorCode::Internal as i32)
+        );
+        assert_eq!(requeued.attempts[1].state, JobState::Queued as i32);
+        assert!(requeued.finished_at.is_none());
+        assert!(last_failure(&svc, &job_id).await.unwrap().will_retry);
+        {
+            let rec = svc.store.get(&job_id).await.unwrap();
+            let inner = rec.lock().await;
+            // Backoff holds the next attempt, and the withheld Failed state
+            // was never published.
+            assert!(!inner.dispatched);
+            assert!(inner
+                .retry_not_before
+                .is_some_and(|at| at > now_millis() + 30_000));
+            assert!(inner.failure_pending_since.is_none());
+            assert!(!inner.history.iter().any(|evt| matches!(
+                &evt.payload,
+                Some(apkw_proto::apkw::v1::job_event::Payload::StateChanged(change))
+                    if change.new_state == JobState::Failed as i32
+            )));
+        }
+
+        publish_state(&svc, &job_id, JobState::Running).await;
+        publish_failure(&svc, &job_id, ErrorCode::Internal).await;
+        let failed = job(&svc, &job_id).await;
+        assert_eq!(failed.state, JobState::Failed as i32);
+        assert_eq!(failed.attempts.len(), 2);
+        assert_eq!(failed.attempts[1].state, JobState::Failed as i32);
+        assert!(!last_failure(&svc, &job_id).await.unwrap().will_retry);
+    }
+
+    #[tokio::test]
+    async fn jobs_discovered_later_start_live_without_history() {
+        use tokio_stream::StreamExt;
+
+        let svc = test_svc("run-stream");
+        let mut stream = svc
+            .stream_run_events(Request::new(StreamRunEventsRequest {
+                run_id: Some(RunId {
+                    value: "run-1".into(),
+                }),
+                max_delay_ms: 1,
+                discovery_interval_ms: 5,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        // Let the initial discovery pass find nothing.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let job_id = start(
+            &svc,
+            StartJobRequest {
+                run_id: Some(RunId {
+                    value: "run-1".into(),
+                }),
+                ..Default::default()
+            },
+        )
+        .await;
+        publish_log(&svc, &job_id, "before discovery\n").await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        publish_log(&svc, &job_id, "after discovery\n").await;
+
+        let evt = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let Some(apkw_proto::apkw::v1::job_event::Payload::Log(log)) = evt.payload else {
+            panic!("expected a log event");
+        };
+        assert_eq!(log.chunk.unwrap().data, b"after discovery\n");
+    }
+
+    #[tokio::test]
+    async fn cancellation_reaches_watchers_subscribed_before_and_after() {
+        use tokio_stream::StreamExt;
+
+        let svc = test_svc("watch-cancel");
+        let watch = |job_id: &str| {
+            svc.watch_cancellation(Request::new(WatchCancellationRequest {
+                job_id: Some(Id {
+                    value: job_id.to_string(),
+                }),
+            }))
+        };
+        let cancel = |job_id: &str| {
+            svc.cancel_job(Request::new(CancelJobRequest {
+                job_id: Some(Id {
+                    value: job_id.to_string(),
+                }),
+            }))
+        };
+
+        let running = start(&svc, StartJobRequest::default()).await;
+        publish_state(&svc, &running, JobState::Running).await;
+        let mut stream = watch(&running).await.unwrap().into_inner();
+        assert!(cancel(&running).await.unwrap().into_inner().accepted);
+        let message = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("cancellation pushed")
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.job_id.unwrap().value, running);
+
+        let cancelled = start(&svc, StartJobRequest::default()).await;
+        assert!(cancel(&cancelled).await.unwrap().into_inner().accepted);
+        let mut stream = watch(&cancelled).await.unwrap().into_inner();
+        let message = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("cancellation replayed")
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.job_id.unwrap().value, cancelled);
+
+        let missing = watch("no-such-job").await.unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+    }
+}
@@ -0,0 +1,606 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::Duration,
+};
+
+use apkw_proto::apkw::v1::{
+    job_event::Payload, ErrorCode, ErrorDetail, Job, JobState, NotificationRule, RunId, Timestamp,
+};
+use apkw_util::{state_file_path, write_json_atomic};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{io::AsyncWriteExt, process::Command, sync::RwLock};
+use tonic::Status;
+use tracing::warn;
+use uuid::Uuid;
+
+const NOTIFICATION_RULES_FILE_NAME: &str = "notification-rules.json";
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// States a rule fires on when it lists none.
+const DEFAULT_STATES: &[JobState] = &[JobState::Success, JobState::Failed, JobState::Cancelled];
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct NotificationRulesFile {
+    rules: Vec<NotificationRuleRecord>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct NotificationRuleRecord {
+    rule_id: String,
+    name: String,
+    job_types: Vec<String>,
+    states: Vec<i32>,
+    run_id: String,
+    webhook_url: String,
+    command: Vec<String>,
+    disabled: bool,
+}
+
+impl NotificationRuleRecord {
+    fn from_proto(rule: &NotificationRule) -> Self {
+        Self {
+            rule_id: rule.rule_id.clone(),
+            name: rule.name.clone(),
+            job_types: rule.job_types.clone(),
+            states: rule.states.clone(),
+            run_id: rule
+                .run_id
+                .as_ref()
+                .map(|id| id.value.clone())
+                .unwrap_or_default(),
+            webhook_url: rule.webhook_url.clone(),
+            command: rule.command.clone(),
+            disabled: rule.disabled,
+        }
+    }
+
+    fn into_proto(self) -> NotificationRule {
+        NotificationRule {
+            rule_id: self.rule_id,
+            name: self.name,
+            job_types: self.job_types,
+            states: self.states,
+            run_id: (!self.run_id.is_empty()).then_some(RunId { value: self.run_id }),
+            webhook_url: self.webhook_url,
+            command: self.command,
+            disabled: self.disabled,
+        }
+    }
+}
+
+/// Validates a rule and fills defaults.
+#[allow(clippy::result_large_err)]
+fn normalize_rule(mut rule: NotificationRule) -> Result<NotificationRule, Status> {
+    rule.rule_id = rule.rule_id.trim().to_string();
+    if rule.rule_id.is_empty() {
+        rule.rule_id = Uuid::new_v4().to_string();
+    }
+    rule.name = rule.name.trim().to_string();
+    if rule.name.is_empty() {
+        rule.name = rule.rule_id.clone();
+    }
+    rule.job_types = rule
+        .job_types
+        .iter()
+        .map(|job_type| job_type.trim().to_string())
+        .filter(|job_type| !job_type.is_empty())
+        .collect();
+    for state in &rule.states {
+        let state = JobState::try_from(*state).unwrap_or(JobState::Unspecified);
+        if !DEFAULT_STATES.contains(&state) {
+            return Err(Status::invalid_argument(format!(
+                "notification states must be terminal (success, failed, cancelled): {}",
+                state.as_str_name()
+            )));
+        }
+    }
+    rule.states.sort_unstable();
+    rule.states.dedup();
+    if let Some(run_id) = rule.run_id.as_mut() {
+        run_id.value = run_id.value.trim().to_string();
+    }
+    if rule.run_id.as_ref().is_some_and(|id| id.value.is_empty()) {
+        rule.run_id = None;
+    }
+
+    rule.webhook_url = rule.webhook_url.trim().to_string();
+    rule.command.retain(|arg| !arg.is_empty());
+    match (rule.webhook_url.is_empty(), rule.command.is_empty()) {
+        (true, true) => {
+            return Err(Status::invalid_argument(
+                "a webhook_url or command is required",
+            ))
+        }
+        (false, false) => {
+            return Err(Status::invalid_argument(
+                "webhook_url and command are mutually exclusive",
+            ))
+        }
+        _ => {}
+    }
+    let is_http =
+        rule.webhook_url.starts_with("http://") || rule.webhook_url.starts_with("https://");
+    if !rule.webhook_url.is_empty() && !is_http {
+        return Err(Status::invalid_argument(format!(
+            "webhook_url must be an http(s) URL: {}",
+            rule.webhook_url
+        )));
+    }
+    Ok(rule)
+}
+
+fn rule_matches(rule: &NotificationRule, job: &Job, state: JobState) -> bool {
+    if rule.disabled {
+        return false;
+    }
+    let state_matches = if rule.states.is_empty() {
+        DEFAULT_STATES.contains(&state)
+    } else {
+        rule.states.contains(&(state as i32))
+    };
+    let type_matches = rule.job_types.is_empty() || rule.job_types.contains(&job.job_type);
+    let run_matches = match rule.run_id.as_ref() {
+        Some(run_id) => job
+            .run_id
+            .as_ref()
+            .is_some_and(|id| id.value == run_id.value),
+        None => true,
+    };
+    state_matches && type_matches && run_matches
+}
+
+/// The terminal state an event finishes its job in, if any. Failures that
+/// will be retried do not finish the job.
+pub(crate) fn finished_state(payload: &Payload) -> Option<JobState> {
+    match payload {
+        Payload::Completed(_) => Some(JobState::Success),
+        Payload::Failed(failed) if !failed.will_retry => Some(JobState::Failed),
+        Payload::StateChanged(changed) if changed.new_state == JobState::Cancelled as i32 => {
+            Some(JobState::Cancelled)
+        }
+        _ => None,
+    }
+}
+
+fn millis(ts: &Option<Timestamp>) -> Value {
+    ts.as_ref()
+        .map(|ts| json!(ts.unix_millis))
+        .unwrap_or(Value::Null)
+}
+
+fn payload_json(rule: &NotificationRule, job: &Job, state: JobState, payload: &Payload) -> Value {
+    let (summary, outputs, error) = match payload {
+        Payload::Completed(done) => (
+            done.summary.clone(),
+            done.outputs
+                .iter()
+                .map(|kv| (kv.key.clone(), Value::String(kv.value.clone())))
+                .collect::<serde_json::Map<_, _>>(),
+            Value::Null,
+        ),
+        Payload::Failed(failed) => (
+            String::new(),
+            serde_json::Map::new(),
+            failed.error.as_ref().map(error_json).unwrap_or(Value::Null),
+        ),
+        _ => (String::new(), serde_json::Map::new(), Value::Null),
+    };
+    json!({
+        "rule_id": rule.rule_id,
+        "rule_name": rule.name,
+        "job_id": job.job_id.as_ref().map(|id| id.value.as_str()).unwrap_or_default(),
+        "job_type": job.job_type,
+        "display_name": job.display_name,
+        "state": state_label(state),
+        "run_id": job.run_id.as_ref().map(|id| id.value.as_str()).unwrap_or_default(),
+        "correlation_id": job.correlation_id,
+        "attempt": job.attempt,
+        "created_at_unix_millis": millis(&job.created_at),
+        "started_at_unix_millis": millis(&job.started_at),
+        "finished_at_unix_millis": millis(&job.finished_at),
+        "summary": summary,
+        "outputs": outputs,
+        "error": error,
+    })
+}
+
+fn error_json(error: &ErrorDetail) -> Value {
+    json!({
+        "code": ErrorCode::try_from(error.code)
+            .unwrap_or(ErrorCode::Unspecified)
+            .as_str_name(),
+        "message": error.message,
+        "technical_details": error.technical_details,
+    })
+}
+
+fn state_label(state: JobState) -> &'static str {
+    match state {
+        JobState::Success => "success",
+        JobState::Failed => "failed",
+        JobState::Cancelled => "cancelled",
+        _ => "unknown",
+    }
+}
+
+async fn post_webhook(client: &reqwest::Client, url: &str, body: &Value) -> Result<(), String> {
+    let resp = client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    Ok(())
+}
+
+async fn run_command(
+    argv: &[String],
+    job: &Job,
+    state: JobState,
+    body: &Value,
+) -> Result<(), String> {
+    let (program, args) = argv.split_first().ok_or("empty command")?;
+    let mut child = Command::new(program)
+        .args(args)
+        .env(
+            "APKW_JOB_ID",
+            job.job_id
+                .as_ref()
+                .map(|id| id.value.as_str())
+                .unwrap_or_default(),
+        )
+        .env("APKW_JOB_TYPE", &job.job_type)
+        .env("APKW_JOB_STATE", state_label(state))
+        .env(
+            "APKW_RUN_ID",
+            job.run_id
+                .as_ref()
+                .map(|id| id.value.as_str())
+                .unwrap_or_default(),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| format!("failed to start {program}: {err}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that ignores stdin may close it early; that is not an error.
+        let _ = stdin.write_all(body.to_string().as_bytes()).await;
+    }
+    let status = tokio::time::timeout(COMMAND_TIMEOUT, child.wait())
+        .await
+        .map_err(|_| format!("{program} timed out"))?
+        .map_err(|err| err.to_string())?;
+    if !status.success() {
+        return Err(format!("{program} exited with {status}"));
+    }
+    Ok(())
+}
+
+/// Notification rules persisted in `notification-rules.json`, fired when a
+/// job finishes.
+#[derive(Clone)]
+pub(crate) struct NotificationRegistry {
+    rules: Arc<RwLock<BTreeMap<String, NotificationRule>>>,
+    path: PathBuf,
+    http: reqwest::Client,
+}
+
+impl NotificationRegistry {
+    pub(crate) fn load() -> Self {
+        let path = state_file_path(NOTIFICATION_RULES_FILE_NAME);
+        let rules = Self::read(&path);
+        let http = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            rules: Arc::new(RwLock::new(rules)),
+            path,
+            http,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn at(path: PathBuf) -> Self {
+        let rules = Self::read(&path);
+        Self {
+            rules: Arc::new(RwLock::new(rules)),
+            path,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn read(path: &Path) -> BTreeMap<String, NotificationRule> {
+        let file = match fs::read(path) {
+            Ok(data) => match serde_json::from_slice::<NotificationRulesFile>(&data) {
+                Ok(file) => file,
+                Err(err) => {
+                    warn!("ignoring unreadable {}: {err}", path.display());
+                    return BTreeMap::new();
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return BTreeMap::new(),
+            Err(err) => {
+                warn!("failed to read {}: {err}", path.display());
+                return BTreeMap::new();
+            }
+        };
+        let mut rules = BTreeMap::new();
+        for record in file.rules {
+            match normalize_rule(record.into_proto()) {
+                Ok(rule) => {
+                    rules.insert(rule.rule_id.clone(), rule);
+                }
+                Err(err) => warn!("skipping invalid notification rule: {}", err.message()),
+            }
+        }
+        rules
+    }
+
+    /// Re-reads rules from disk (used by `ReloadState`).
+    pub(crate) async fn reload(&self) -> usize {
+        let rules = Self::read(&self.path);
+        let count = rules.len();
+        *self.rules.write().await = rules;
+        count
+    }
+
+    pub(crate) async fn list(&self) -> Vec<NotificationRule> {
+        self.rules.read().await.values().cloned().collect()
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn persist(&self, rules: &BTreeMap<String, NotificationRule>) -> Result<(), Status> {
+        let file = NotificationRulesFile {
+            rules: rules
+                .values()
+                .map(NotificationRuleRecord::from_proto)
+                .collect(),
+        };
+        write_json_atomic(&self.path, &file)
+            .map_err(|err| Status::internal(format!("failed to persist notification rules: {err}")))
+    }
+
+    /// Adds a rule, or replaces the rule with the same id.
+    #[allow(clippy::result_large_err)]
+    pub(crate) async fn create(&self, rule: NotificationRule) -> Result<NotificationRule, Status> {
+        let rule = normalize_rule(rule)?;
+        let mut rules = self.rules.write().await;
+        let mut next = rules.clone();
+        next.insert(rule.rule_id.clone(), rule.clone());
+        self.persist(&next)?;
+        *rules = next;
+        Ok(rule)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) async fn delete(&self, rule_id: &str) -> Result<bool, Status> {
+        let mut rules = self.rules.write().await;
+        if !rules.contains_key(rule_id) {
+            return Ok(false);
+        }
+        let mut next = rules.clone();
+        next.remove(rule_id);
+        self.persist(&next)?;
+        *rules = next;
+        Ok(true)
+    }
+
+    /// Fires every enabled rule matching a job that just finished. Delivery
+    /// runs in the background; failures are logged and not retried.
+    pub(crate) async fn dispatch(&self, job: &Job, state: JobState, payload: &Payload) {
+        let rules: Vec<NotificationRule> = self
+            .rules
+            .read()
+            .await
+            .values()
+            .filter(|rule| rule_matches(rule, job, state))
+            .cloned()
+            .collect();
+        for rule in rules {
+            let job = job.clone();
+            let payload = payload.clone();
+            let http = self.http.clone();
+            tokio::spawn(async move {
+                if let Err(err) = deliver(&http, &rule, &job, state, &payload).await {
+                    warn!(
+                        "notification rule {} ({}) for job {} failed: {err}",
+                        rule.name,
+                        rule.rule_id,
+                        job.job_id
+                            .as_ref()
+                            .map(|id| id.value.as_str())
+                            .unwrap_or_default()
+                    );
+                }
+            });
+        }
+    }
+}
+
+/// Sends one rule's notification: a webhook POST, or the command with the
+/// JSON body on stdin.
+async fn deliver(
+    http: &reqwest::Client,
+    rule: &NotificationRule,
+    job: &Job,
+    state: JobState,
+    payload: &Payload,
+) -> Result<(), String> {
+    let body = payload_json(rule, job, state, payload);
+    if rule.webhook_url.is_empty() {
+        run_command(&rule.command, job, state, &body).await
+    } else {
+        post_webhook(http, &rule.webhook_url, &body)
+            .await
+            .map_err(|err| format!("POST {}: {err}", rule.webhook_url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apkw_proto::apkw::v1::Id;
+
+    #[test]
+    fn rules_are_validated_and_matched() {
+        let rule = normalize_rule(NotificationRule {
+            job_types: vec![" build.run ".into(), "".into()],
+            command: vec!["notify-send".into(), "done".into()],
+            run_id: Some(RunId { value: " ".into() }),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!rule.rule_id.is_empty());
+        assert_eq!(rule.name, rule.rule_id);
+        assert_eq!(rule.job_types, vec!["build.run".to_string()]);
+        assert!(rule.run_id.is_none());
+
+        let job = Job {
+            job_id: Some(Id { value: "j1".into() }),
+            job_type: "build.run".into(),
+            ..Default::default()
+        };
+        assert!(rule_matches(&rule, &job, JobState::Failed));
+        assert!(!rule_matches(
+            &rule,
+            &Job {
+                job_type: "targets.install".into(),
+                ..job.clone()
+            },
+            JobState::Failed
+        ));
+
+        let failures_only = NotificationRule {
+            states: vec![JobState::Failed as i32],
+            ..rule.clone()
+        };
+        assert!(!rule_matches(&failures_only, &job, JobState::Success));
+
+        for bad in [
+            NotificationRule::default(),
+            NotificationRule {
+                webhook_url: "ftp://example.com".into(),
+                ..Default::default()
+            },
+            NotificationRule {
+                webhook_url: "https://example.com/hook".into(),
+                command: vec!["true".into()],
+                ..Default::default()
+            },
+            NotificationRule {
+                command: vec!["true".into()],
+                states: vec![JobState::Running as i32],
+                ..Default::default()
+            },
+        ] {
+            assert!(normalize_rule(bad).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn rules_survive_a_reload() {
+        let path = std::env::temp_dir().join(format!(
+            "apkw-core-notification-rules-{}-{}.json",
+            std::process::id(),
+            apkw_util::now_millis()
+        ));
+        let registry = NotificationRegistry::at(path.clone());
+        let rule = registry
+            .create(NotificationRule {
+                command: vec!["true".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let reloaded = NotificationRegistry::at(path.clone()).list().await;
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded[0], rule);
+
+        assert!(registry.delete(&rule.rule_id).await.unwrap());
+        assert!(!registry.delete(&rule.rule_id).await.unwrap());
+        assert_eq!(registry.reload().await, 0);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn commands_receive_the_job_as_json_on_stdin() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!(
+            "apkw-core-notify-{}-{}",
+            std::process::id(),
+            apkw_util::now_millis()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("notify.sh");
+        fs::write(
+            &script,
+            "#!/bin/sh\necho \"$APKW_JOB_ID $APKW_JOB_STATE\" > \"$1.env\"\ncat > \"$1\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let out = dir.join("body.json");
+
+        let rule = normalize_rule(NotificationRule {
+            name: "local".into(),
+            command: vec![
+                script.to_string_lossy().to_string(),
+                out.to_string_lossy().to_string(),
+            ],
+            ..Default::default()
+        })
+        .unwrap();
+        let job = Job {
+            job_id: Some(Id { value: "j1".into() }),
+            job_type: "build.run".into(),
+            attempt: 2,
+            ..Default::default()
+        };
+        let failed = Payload::Failed(apkw_proto::apkw::v1::JobFailed {
+            error: Some(ErrorDetail {
+                code: ErrorCode::BuildFailed as i32,
+                message: "compileDebugKotlin failed".into(),
+                ..Default::default()
+            }),
+            will_retry: false,
+        });
+        let http = reqwest::Client::new();
+        deliver(&http, &rule, &job, JobState::Failed, &failed)
+            .await
+            .unwrap();
+
+        let body: Value = serde_json::from_slice(&fs::read(&out).unwrap()).unwrap();
+        assert_eq!(body["job_id"], "j1");
+        assert_eq!(body["state"], "failed");
+        assert_eq!(body["attempt"], 2);
+        assert_eq!(body["error"]["code"], "ERROR_CODE_BUILD_FAILED");
+        assert_eq!(body["rule_name"], "local");
+        let env = fs::read_to_string(dir.join("body.json.env")).unwrap();
+        assert_eq!(env.trim(), "j1 failed");
+
+        let failing = NotificationRule {
+            command: vec!["false".into()],
+            ..rule
+        };
+        let err = deliver(&http, &failing, &job, JobState::Failed, &failed)
+            .await
+            .unwrap_err();
+        assert!(err.contains("false exited"), "{err}");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
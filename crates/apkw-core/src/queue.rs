@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+
+use apkw_proto::apkw::v1::{JobPriority, JobState};
+
+/// Scheduling view of one job, captured under its record lock.
+#[derive(Clone, Debug)]
+pub(crate) struct QueueEntry {
+    pub(crate) job_id: String,
+    pub(crate) created_at_unix_millis: i64,
+    pub(crate) state: JobState,
+    pub(crate) depends_on: Vec<String>,
+    pub(crate) concurrency_keys: Vec<String>,
+    pub(crate) dispatched: bool,
+    /// Earliest admission time for a job waiting out retry backoff.
+    pub(crate) not_before_unix_millis: Option<i64>,
+    pub(crate) priority: JobPriority,
+    pub(crate) preemptible: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum QueueDecision {
+    Dispatch {
+        job_id: String,
+    },
+    Blocked {
+        job_id: String,
+        reason: String,
+    },
+    DependencyFailed {
+        job_id: String,
+        reason: String,
+    },
+    /// Stop the running job and queue it again as a new attempt.
+    Preempt {
+        job_id: String,
+        reason: String,
+    },
+}
+
+impl QueueEntry {
+    fn holds_keys(&self) -> bool {
+        match self.state {
+            JobState::Running => true,
+            JobState::Queued => self.dispatched,
+            _ => false,
+        }
+    }
+
+    fn awaiting_dispatch(&self) -> bool {
+        self.state == JobState::Queued && !self.dispatched
+    }
+
+    fn is_active(&self) -> bool {
+        matches!(self.state, JobState::Queued | JobState::Running)
+    }
+
+    fn yields_to_interactive(&self) -> bool {
+        self.preemptible && self.priority != JobPriority::Interactive
+    }
+}
+
+/// Maps a request's priority to the one the scheduler uses.
+pub(crate) fn normalize_priority(priority: i32) -> JobPriority {
+    match JobPriority::try_from(priority).unwrap_or(JobPriority::Unspecified) {
+        JobPriority::Unspecified => JobPriority::Normal,
+        priority => priority,
+    }
+}
+
+/// Oldest active interactive job, plus every job it (transitively) depends
+/// on; those dependencies must not be deferred or the interactive job would
+/// wait forever.
+fn interactive_demand(entries: &[QueueEntry]) -> Option<(&str, HashSet<&str>)> {
+    let by_id: HashMap<&str, &QueueEntry> = entries
+        .iter()
+        .map(|entry| (entry.job_id.as_str(), entry))
+        .collect();
+    let mut interactive = entries
+        .iter()
+        .filter(|entry| entry.is_active() && entry.priority == JobPriority::Interactive)
+        .collect::<Vec<_>>();
+    interactive.sort_by(|a, b| {
+        a.created_at_unix_millis
+            .cmp(&b.created_at_unix_millis)
+            .then_with(|| a.job_id.cmp(&b.job_id))
+    });
+    let oldest = interactive.first()?.job_id.as_str();
+
+    let mut needed = HashSet::new();
+    let mut stack = interactive
+        .iter()
+        .flat_map(|entry| entry.depends_on.iter().map(String::as_str))
+        .collect::<Vec<_>>();
+    while let Some(dep) = stack.pop() {
+        if needed.insert(dep) {
+            if let Some(entry) = by_id.get(dep) {
+                stack.extend(entry.depends_on.iter().map(String::as_str));
+            }
+        }
+    }
+    Some((oldest, needed))
+}
+
+fn state_label(state: JobState) -> &'static str {
+    match state {
+        JobState::Queued => "queued",
+        JobState::Running => "running",
+        JobState::Success => "succeeded",
+        JobState::Failed => "failed",
+        JobState::Cancelled => "cancelled",
+        JobState::Unspecified => "unknown",
+    }
+}
+
+/// Decides which waiting jobs can be admitted. Jobs are considered highest
+/// priority first, then oldest first; a job is admitted only when every
+/// dependency has succeeded and none of its concurrency keys is held by an
+/// admitted or running job. Retries stay blocked until their backoff has
+/// elapsed at `now_unix_millis`. While an interactive job is active,
+/// preemptible lower-priority jobs are deferred and running ones preempted,
+/// unless the interactive job depends on them. Callers should re-plan after
+/// applying `DependencyFailed` or `Preempt` so cancellations cascade and
+/// released keys are reused.
+pub(crate) fn plan_dispatch(entries: &[QueueEntry], now_unix_millis: i64) -> Vec<QueueDecision> {
+    let states: HashMap<&str, JobState> = entries
+        .iter()
+        .map(|entry| (entry.job_id.as_str(), entry.state))
+        .collect();
+    let demand = interactive_demand(entries);
+    let yields = |entry: &QueueEntry| {
+        demand.as_ref().and_then(|(interactive, needed)| {
+            (entry.yields_to_interactive() && !needed.contains(entry.job_id.as_str()))
+                .then_some(*interactive)
+        })
+    };
+
+    let mut decisions = Vec::new();
+    let mut held: HashMap<&str, &str> = HashMap::new();
+    for entry in entries.iter().filter(|entry| entry.holds_keys()) {
+        // Jobs that are dispatched but not yet running are preempted once
+        // they report Running.
+        if entry.state == JobState::Running {
+            if let Some(interactive) = yields(entry) {
+                decisions.push(QueueDecision::Preempt {
+                    job_id: entry.job_id.clone(),
+                    reason: format!("preempted by interactive job {interactive}"),
+                });
+                continue;
+            }
+        }
+        for key in &entry.concurrency_keys {
+            held.insert(key.as_str(), entry.job_id.as_str());
+        }
+    }
+
+    let mut waiting = entries
+        .iter()
+        .filter(|entry| entry.awaiting_dispatch())
+        .collect::<Vec<_>>();
+    waiting.sort_by(|a, b| {
+        (b.priority as i32)
+            .cmp(&(a.priority as i32))
+            .then_with(|| a.created_at_unix_millis.cmp(&b.created_at_unix_millis))
+            .then_with(|| a.job_id.cmp(&b.job_id))
+    });
+
+    for entry in waiting {
+        let mut pending = None;
+        let mut failed = None;
+        for dep in &entry.depends_on {
+            match states.get(dep.as_str()).copied() {
+                Some(JobState::Success) => {}
+                Some(state @ (JobState::Failed | JobState::Cancelled)) => {
+                    failed = Some(format!("dependency {dep} {}", state_label(state)));
+                    break;
+                }
+                Some(_) => {
+                    if pending.is_none() {
+                        pending = Some(dep.as_str());
+                    }
+                }
+                None => {
+                    failed = Some(format!("dependency {dep} no longer exists"));
+                    break;
+                }
+            }
+        }
+        if let Some(reason) = failed {
+            decisions.push(QueueDecision::DependencyFailed {
+                job_id: entry.job_id.clone(),
+                reason,
+            });
+            continue;
+        }
+        if let Some(dep) = pending {
+            decisions.push(QueueDecision::Blocked {
+                job_id: entry.job_id.clone(),
+                reason: format!("waiting for dependency {dep}"),
+            });
+            continue;
+        }
+        if entry
+            .not_before_unix_millis
+            .is_some_and(|not_before| not_before > now_unix_millis)
+        {
+            decisions.push(QueueDecision::Blocked {
+                job_id: entry.job_id.clone(),
+                reason: "waiting for retry backoff".into(),
+            });
+            continue;
+        }
+        if let Some(interactive) = yields(entry) {
+            decisions.push(QueueDecision::Blocked {
+                job_id: entry.job_id.clone(),
+                reason: format!("deferred while interactive job {interactive} is active"),
+            });
+            continue;
+        }
+        let conflict = entry
+            .concurrency_keys
+            .iter()
+            .find_map(|key| held.get(key.as_str()).map(|holder| (key, *holder)));
+        if let Some((key, holder)) = conflict {
+            decisions.push(QueueDecision::Blocked {
+                job_id: entry.job_id.clone(),
+                reason: format!("waiting for concurrency key {key} held by job {holder}"),
+            });
+            continue;
+        }
+        for key in &entry.concurrency_keys {
+            held.insert(key.as_str(), entry.job_id.as_str());
+        }
+        decisions.push(QueueDecision::Dispatch {
+            job_id: entry.job_id.clone(),
+        });
+    }
+    decisions
+}
+
+/// Trims, drops empties and de-duplicates caller supplied concurrency keys.
+pub(crate) fn normalize_concurrency_keys(keys: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    keys.iter()
+        .map(|key| key.trim())
+        .filter(|key| !key.is_empty())
+        .filter(|key| seen.insert(key.to_string()))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(job_id: &str, created: i64, state: JobState) -> QueueEntry {
+        QueueEntry {
+            job_id: job_id.into(),
+            created_at_unix_millis: created,
+            state,
+            depends_on: Vec::new(),
+            concurrency_keys: Vec::new(),
+            dispatched: false,
+            not_before_unix_millis: None,
+            priority: JobPriority::Normal,
+            preemptible: false,
+        }
+    }
+
+    #[test]
+    fn shared_concurrency_key_admits_oldest_job_only() {
+        let mut first = entry("a", 1, JobState::Queued);
+        first.concurrency_keys = vec!["project:demo".into()];
+        let mut second = entry("b", 2, JobState::Queued);
+        second.concurrency_keys = vec!["project:demo".into()];
+        let mut third = entry("c", 3, JobState::Queued);
+        third.not_before_unix_millis = Some(10);
+
+        let decisions = plan_dispatch(&[second.clone(), third.clone(), first.clone()], 5);
+        assert_eq!(
+            decisions[2],
+            QueueDecision::Blocked {
+                job_id: "c".into(),
+                reason: "waiting for retry backoff".into(),
+            }
+        );
+
+        let decisions = plan_dispatch(&[second, third, first], 10);
+        assert_eq!(
+            decisions,
+            vec![
+                QueueDecision::Dispatch { job_id: "a".into() },
+                QueueDecision::Blocked {
+                    job_id: "b".into(),
+                    reason: "waiting for concurrency key project:demo held by job a".into(),
+                },
+                QueueDecision::Dispatch { job_id: "c".into() },
+            ]
+        );
+    }
+
+    #[test]
+    fn dependencies_gate_and_cancel_dependents() {
+        let build = entry("build", 1, JobState::Running);
+        let mut install = entry("install", 2, JobState::Queued);
+        install.depends_on = vec!["build".into()];
+        let failed = entry("old", 0, JobState::Failed);
+        let mut launch = entry("launch", 3, JobState::Queued);
+        launch.depends_on = vec!["old".into()];
+
+        let decisions = plan_dispatch(&[build, install, failed, launch], 0);
+        assert_eq!(
+            decisions,
+            vec![
+                QueueDecision::Blocked {
+                    job_id: "install".into(),
+                    reason: "waiting for dependency build".into(),
+                },
+                QueueDecision::DependencyFailed {
+                    job_id: "launch".into(),
+                    reason: "dependency old failed".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn interactive_jobs_jump_the_queue_and_preempt_background_work() {
+        let mut download = entry("download", 1, JobState::Running);
+        download.priority = JobPriority::Background;
+        download.preemptible = true;
+        download.concurrency_keys = vec!["toolchain-root".into()];
+        let mut cleanup = entry("cleanup", 2, JobState::Queued);
+        cleanup.priority = JobPriority::Background;
+        cleanup.preemptible = true;
+        let mut cache = entry("cache", 3, JobState::Queued);
+        cache.priority = JobPriority::Background;
+        cache.preemptible = true;
+        let mut build = entry("build", 4, JobState::Queued);
+        build.priority = JobPriority::Interactive;
+        build.depends_on = vec!["cache".into()];
+        let lint = entry("lint", 0, JobState::Queued);
+
+        let decisions = plan_dispatch(&[download, cleanup, cache, build, lint], 0);
+        assert_eq!(
+            decisions,
+            vec![
+                QueueDecision::Preempt {
+                    job_id: "download".into(),
+                    reason: "preempted by interactive job build".into(),
+                },
+                QueueDecision::Blocked {
+                    job_id: "build".into(),
+                    reason: "waiting for dependency cache".into(),
+                },
+                QueueDecision::Dispatch {
+                    job_id: "lint".into()
+                },
+                QueueDecision::Blocked {
+                    job_id: "cleanup".into(),
+                    reason: "deferred while interactive job build is active".into(),
+                },
+                QueueDecision::Dispatch {
+                    job_id: "cache".into()
+                },
+            ]
+        );
+        assert_eq!(normalize_priority(0), JobPriority::Normal);
+    }
+}
@@ -0,0 +1,102 @@
+use apkw_proto::apkw::v1::{ErrorCode, JobAttempt, RetryPolicy};
+use tonic::Status;
+
+/// Upper bound on `RetryPolicy.max_attempts` accepted from callers.
+const MAX_RETRY_ATTEMPTS: u32 = 10;
+
+/// Validates a caller supplied policy. Policies that allow a single attempt
+/// are dropped so the rest of the service only sees real retry policies.
+#[allow(clippy::result_large_err)]
+pub(crate) fn normalize_retry_policy(
+    policy: Option<RetryPolicy>,
+) -> Result<Option<RetryPolicy>, Status> {
+    let Some(mut policy) = policy else {
+        return Ok(None);
+    };
+    if policy.max_attempts <= 1 {
+        return Ok(None);
+    }
+    if policy.max_attempts > MAX_RETRY_ATTEMPTS {
+        return Err(Status::invalid_argument(format!(
+            "retry_policy.max_attempts must be at most {MAX_RETRY_ATTEMPTS}"
+        )));
+    }
+    if !policy.backoff_multiplier.is_finite() || policy.backoff_multiplier < 1.0 {
+        policy.backoff_multiplier = 1.0;
+    }
+    policy.retryable_codes.sort_unstable();
+    policy.retryable_codes.dedup();
+    Ok(Some(policy))
+}
+
+pub(crate) fn is_retryable(policy: &RetryPolicy, code: i32) -> bool {
+    if policy.retryable_codes.is_empty() {
+        return code != ErrorCode::Cancelled as i32;
+    }
+    policy.retryable_codes.contains(&code)
+}
+
+/// Attempts that count toward `max_attempts`; preempted attempts are free.
+pub(crate) fn attempts_used(attempts: &[JobAttempt]) -> u32 {
+    attempts.iter().filter(|attempt| !attempt.preempted).count() as u32
+}
+
+/// Backoff before the attempt that follows `failed_attempt` (1-based), or
+/// `None` once the policy's attempts are used up.
+pub(crate) fn retry_backoff_ms(policy: &RetryPolicy, failed_attempt: u32) -> Option<u64> {
+    if failed_attempt >= policy.max_attempts {
+        return None;
+    }
+    let exponent = failed_attempt.saturating_sub(1) as i32;
+    let scaled = policy.initial_backoff_ms as f64 * policy.backoff_multiplier.powi(exponent);
+    let mut backoff = if scaled.is_finite() {
+        scaled.min(u64::MAX as f64) as u64
+    } else {
+        u64::MAX
+    };
+    if policy.max_backoff_ms > 0 {
+        backoff = backoff.min(policy.max_backoff_ms);
+    }
+    Some(backoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_and_stops_after_max_attempts() {
+        let policy = normalize_retry_policy(Some(RetryPolicy {
+            max_attempts: 4,
+            initial_backoff_ms: 1_000,
+            backoff_multiplier: 2.0,
+            max_backoff_ms: 3_000,
+            retryable_codes: vec![],
+        }))
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(retry_backoff_ms(&policy, 1), Some(1_000));
+        assert_eq!(retry_backoff_ms(&policy, 2), Some(2_000));
+        assert_eq!(retry_backoff_ms(&policy, 3), Some(3_000));
+        assert_eq!(retry_backoff_ms(&policy, 4), None);
+    }
+
+    #[test]
+    fn retryable_codes_filter_failures() {
+        let any = RetryPolicy {
+            max_attempts: 2,
+            ..Default::default()
+        };
+        assert!(is_retryable(&any, ErrorCode::InstallFailed as i32));
+        assert!(!is_retryable(&any, ErrorCode::Cancelled as i32));
+
+        let listed = RetryPolicy {
+            max_attempts: 2,
+            retryable_codes: vec![ErrorCode::Unavailable as i32],
+            ..Default::default()
+        };
+        assert!(is_retryable(&listed, ErrorCode::Unavailable as i32));
+        assert!(!is_retryable(&listed, ErrorCode::InstallFailed as i32));
+    }
+}
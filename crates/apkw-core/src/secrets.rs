@@ -0,0 +1,198 @@
+use std::{io, sync::Arc};
+
+use apkw_proto::apkw::v1::{job_event::Payload, SecretInfo};
+use apkw_util::secrets::{Redactor, SecretStore};
+use tokio::sync::RwLock;
+use tonic::Status;
+use tracing::warn;
+
+struct SecretsState {
+    store: SecretStore,
+    redactor: Arc<Redactor>,
+}
+
+/// The secrets store and the redactor built from its values.
+#[derive(Clone)]
+pub(crate) struct SecretRegistry {
+    state: Arc<RwLock<Option<SecretsState>>>,
+}
+
+fn read_state() -> Option<SecretsState> {
+    let store = match SecretStore::load() {
+        Ok(store) => store,
+        Err(err) => {
+            warn!("secrets store unavailable: {err}");
+            return None;
+        }
+    };
+    let redactor = match store.values() {
+        Ok(values) => Redactor::new(values),
+        Err(err) => {
+            warn!("secrets cannot be decrypted, logs are not redacted: {err}");
+            Redactor::default()
+        }
+    };
+    Some(SecretsState {
+        store,
+        redactor: Arc::new(redactor),
+    })
+}
+
+fn store_error(err: io::Error) -> Status {
+    match err.kind() {
+        io::ErrorKind::InvalidInput => Status::invalid_argument(err.to_string()),
+        _ => Status::internal(format!("secrets store: {err}")),
+    }
+}
+
+impl SecretRegistry {
+    pub(crate) fn load() -> Self {
+        Self {
+            state: Arc::new(RwLock::new(read_state())),
+        }
+    }
+
+    /// A registry without a store: nothing is redacted and writes fail.
+    #[cfg(test)]
+    pub(crate) fn unavailable() -> Self {
+        Self {
+            state: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Re-reads the store from disk (used by `ReloadState`).
+    pub(crate) async fn reload(&self) {
+        *self.state.write().await = read_state();
+    }
+
+    pub(crate) async fn list(&self) -> Result<Vec<SecretInfo>, Status> {
+        match self.state.read().await.as_ref() {
+            Some(state) => Ok(state.store.list()),
+            None => Err(Status::unavailable("secrets store is unreadable")),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) async fn set(
+        &self,
+        name: &str,
+        value: &str,
+        description: &str,
+    ) -> Result<(SecretInfo, bool), Status> {
+        let mut guard = self.state.write().await;
+        let state = guard
+            .as_mut()
+            .ok_or_else(|| Status::unavailable("secrets store is unreadable"))?;
+        let (info, created) = state
+            .store
+            .set(name, value, description)
+            .map_err(store_error)?;
+        state.redactor = Arc::new(Redactor::new(state.store.values().map_err(store_error)?));
+        Ok((info, created))
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) async fn delete(&self, name: &str) -> Result<bool, Status> {
+        let mut guard = self.state.write().await;
+        let state = guard
+            .as_mut()
+            .ok_or_else(|| Status::unavailable("secrets store is unreadable"))?;
+        if !state.store.remove(name).map_err(store_error)? {
+            return Ok(false);
+        }
+        state.redactor = Arc::new(Redactor::new(state.store.values().map_err(store_error)?));
+        Ok(true)
+    }
+
+    /// Masks stored secret values in log output and failure details.
+    pub(crate) async fn redact(&self, payload: Payload) -> Payload {
+        let redactor = match self.state.read().await.as_ref() {
+            Some(state) if !state.redactor.is_empty() => state.redactor.clone(),
+            _ => return payload,
+        };
+        redact_payload(&redactor, payload)
+    }
+}
+
+fn redact_payload(redactor: &Redactor, payload: Payload) -> Payload {
+    match payload {
+        Payload::Log(mut log) => {
+            if let Some(chunk) = log.chunk.as_mut() {
+                chunk.data = redactor.redact_bytes(&chunk.data).into_owned();
+            }
+            for record in &mut log.records {
+                record.message = redactor.redact(&record.message).into_owned();
+            }
+            Payload::Log(log)
+        }
+        Payload::Failed(mut failed) => {
+            if let Some(error) = failed.error.as_mut() {
+                error.message = redactor.redact(&error.message).into_owned();
+                error.technical_details = redactor.redact(&error.technical_details).into_owned();
+            }
+            Payload::Failed(failed)
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apkw_proto::apkw::v1::{ErrorDetail, JobFailed, JobLogAppended, LogChunk, LogRecord};
+
+    #[test]
+    fn log_and_failure_payloads_are_redacted() {
+        let redactor = Redactor::new(["p4ssw0rd".to_string()]);
+        let Payload::Log(log) = redact_payload(
+            &redactor,
+            Payload::Log(JobLogAppended {
+                chunk: Some(LogChunk {
+                    stream: "stdout".into(),
+                    data: b"storePassword=p4ssw0rd\n".to_vec(),
+                    truncated: false,
+                }),
+                records: vec![LogRecord {
+                    message: "using p4ssw0rd".into(),
+                    ..Default::default()
+                }],
+            }),
+        ) else {
+            panic!("expected a log payload");
+        };
+        assert_eq!(log.chunk.unwrap().data, b"storePassword=***\n");
+        assert_eq!(log.records[0].message, "using ***");
+
+        let Payload::Failed(failed) = redact_payload(
+            &redactor,
+            Payload::Failed(JobFailed {
+                error: Some(ErrorDetail {
+                    technical_details: "bad p4ssw0rd".into(),
+                    ..Default::default()
+                }),
+                will_retry: false,
+            }),
+        ) else {
+            panic!("expected a failed payload");
+        };
+        assert_eq!(failed.error.unwrap().technical_details, "bad ***");
+    }
+
+    #[tokio::test]
+    async fn an_unreadable_store_rejects_writes_and_redacts_nothing() {
+        let registry = SecretRegistry::unavailable();
+        let err = registry.set("signing", "p4ssw0rd", "").await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unavailable);
+        assert!(registry.list().await.is_err());
+
+        let log = Payload::Log(JobLogAppended {
+            chunk: Some(LogChunk {
+                stream: "stdout".into(),
+                data: b"p4ssw0rd\n".to_vec(),
+                truncated: false,
+            }),
+            records: Vec::new(),
+        });
+        assert_eq!(registry.redact(log.clone()).await, log);
+    }
+}
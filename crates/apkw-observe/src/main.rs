@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::BTreeMap,
     fs, io,
     io::Write,
     path::{Path, PathBuf},
@@ -12,16 +12,17 @@ use apkw_proto::apkw::v1::{
     job_service_client::JobServiceClient,
     observe_service_server::{ObserveService, ObserveServiceServer},
     ErrorCode, ErrorDetail, ExportEvidenceBundleRequest, ExportEvidenceBundleResponse,
-    ExportSupportBundleRequest, ExportSupportBundleResponse, GetJobRequest, Id, JobCompleted,
-    JobEvent, JobFailed, JobLogAppended, JobProgress, JobProgressUpdated, JobState,
-    JobStateChanged, KeyValue, ListRunOutputsRequest, ListRunOutputsResponse, ListRunsRequest,
-    ListRunsResponse, LogChunk, PageInfo, Pagination, PublishJobEventRequest, ReloadStateRequest,
+    ExportSupportBundleRequest, ExportSupportBundleResponse, Id, JobCompleted, JobEvent, JobFailed,
+    JobLogAppended, JobPriority, JobProgress, JobProgressUpdated, JobState, JobStateChanged,
+    KeyValue, ListRunOutputsRequest, ListRunOutputsResponse, ListRunsRequest, ListRunsResponse,
+    LogChunk, PageInfo, Pagination, PublishJobEventRequest, ReloadStateRequest,
     ReloadStateResponse, RunFilter, RunId, RunOutput, RunOutputFilter, RunOutputKind,
-    RunOutputSummary, RunRecord, StartJobRequest, StreamJobEventsRequest, Timestamp,
-    UpsertRunOutputsRequest, UpsertRunOutputsResponse, UpsertRunRequest, UpsertRunResponse,
+    RunOutputSummary, RunRecord, StartJobRequest, Timestamp, UpsertRunOutputsRequest,
+    UpsertRunOutputsResponse, UpsertRunRequest, UpsertRunResponse,
 };
 use apkw_util::{
-    data_dir, job_addr, now_millis, now_ts, serve_grpc_with_telemetry, write_json_atomic,
+    collect_job_history, data_dir, job_addr, now_millis, now_ts, secrets::Redactor,
+    serve_grpc_with_telemetry, spawn_cancel_watcher, wait_for_job_dispatch, write_json_atomic,
 };
 use serde::{Deserialize, Serialize};
 use tokio::sync::{watch, Mutex};
@@ -31,7 +32,6 @@ use uuid::Uuid;
 use zip::{write::FileOptions, CompressionMethod, ZipWriter};
 
 const STATE_FILE_NAME: &str = "observe.json";
-const JOB_STATE_FILE_NAME: &str = "jobs.json";
 const DEFAULT_PAGE_SIZE: usize = 25;
 const MAX_RUNS: usize = 200;
 const DEFAULT_BUNDLE_RETENTION_DAYS: u64 = 30;
@@ -156,43 +156,6 @@ fn run_output_kind_proto(kind: RunOutputKindEntry) -> i32 {
     }
 }
 
-#[derive(Default, Deserialize)]
-#[serde(default)]
-struct JobHistoryFile {
-    jobs: Vec<JobHistoryEntry>,
-}
-
-#[derive(Default, Deserialize)]
-#[serde(default)]
-struct JobHistoryEntry {
-    job_id: String,
-    history: Vec<JobHistoryEvent>,
-}
-
-#[derive(Default, Deserialize)]
-#[serde(default)]
-struct JobHistoryEvent {
-    payload: Option<JobHistoryPayload>,
-}
-
-#[derive(Deserialize)]
-#[serde(tag = "type", content = "data", rename_all = "snake_case")]
-enum JobHistoryPayload {
-    Log {
-        chunk: Option<JobLogChunk>,
-    },
-    #[serde(other)]
-    Other,
-}
-
-#[derive(Default, Deserialize)]
-#[serde(default)]
-struct JobLogChunk {
-    stream: String,
-    data: Vec<u8>,
-    truncated: bool,
-}
-
 #[derive(Clone)]
 struct Svc {
     state: Arc<Mutex<State>>,
@@ -234,10 +197,6 @@ fn state_file_path() -> PathBuf {
     apkw_util::state_file_path(STATE_FILE_NAME)
 }
 
-fn job_state_file_path() -> PathBuf {
-    apkw_util::state_file_path(JOB_STATE_FILE_NAME)
-}
-
 fn bundles_dir() -> PathBuf {
     data_dir().join("bundles")
 }
@@ -651,71 +610,6 @@ async fn connect_job() -> Result<JobServiceClient<Channel>, Status> {
     Ok(JobServiceClient::new(channel))
 }
 
-async fn job_is_cancelled(client: &mut JobServiceClient<Channel>, job_id: &str) -> bool {
-    let resp = client
-        .get_job(GetJobRequest {
-            job_id: Some(Id {
-                value: job_id.to_string(),
-            }),
-        })
-        .await;
-    let job = match resp {
-        Ok(resp) => resp.into_inner().job,
-        Err(_) => return false,
-    };
-    matches!(
-        job.and_then(|job| JobState::try_from(job.state).ok()),
-        Some(JobState::Cancelled)
-    )
-}
-
-async fn spawn_cancel_watcher(job_id: String) -> watch::Receiver<bool> {
-    let (tx, rx) = watch::channel(false);
-    let mut client = match connect_job().await {
-        Ok(client) => client,
-        Err(err) => {
-            warn!("cancel watcher: failed to connect job service: {err}");
-            return rx;
-        }
-    };
-    let mut stream = match client
-        .stream_job_events(StreamJobEventsRequest {
-            job_id: Some(Id {
-                value: job_id.clone(),
-            }),
-            include_history: true,
-        })
-        .await
-    {
-        Ok(resp) => resp.into_inner(),
-        Err(err) => {
-            warn!("cancel watcher: stream failed for {job_id}: {err}");
-            return rx;
-        }
-    };
-
-    tokio::spawn(async move {
-        loop {
-            match stream.message().await {
-                Ok(Some(evt)) => {
-                    if let Some(JobPayload::StateChanged(state)) = evt.payload {
-                        if JobState::try_from(state.new_state).unwrap_or(JobState::Unspecified)
-                            == JobState::Cancelled
-                        {
-                            let _ = tx.send(true);
-                            break;
-                        }
-                    }
-                }
-                Ok(None) => break,
-                Err(_) => break,
-            }
-        }
-    });
-
-    rx
-}
-
 fn cancel_requested(cancel_rx: &watch::Receiver<bool>) -> bool {
     *cancel_rx.borrow()
 }
@@ -740,6 +634,12 @@ async fn start_job(
             toolchain_set_id: toolchain_set_id.map(|value| Id { value }),
             correlation_id: correlation_id.to_string(),
             run_id,
+            depends_on: Vec::new(),
+            concurrency_keys: Vec::new(),
+            timeout_ms: 0,
+            retry_policy: None,
+            priority: JobPriority::Background as i32,
+            preemptible: false,
         })
         .await
         .map_err(|e| Status::unavailable(format!("job start failed: {e}")))?
@@ -769,6 +669,8 @@ async fn publish_job_event(
                 job_id: Some(Id {
                     value: job_id.to_string(),
                 }),
+                attempt: 0,
+                seq: 0,
                 payload: Some(payload),
             }),
         })
@@ -834,6 +736,7 @@ async fn publish_log(
                 data: message.as_bytes().to_vec(),
                 truncated: false,
             }),
+            records: Vec::new(),
         }),
     )
     .await
@@ -866,7 +769,10 @@ async fn publish_failed(
     publish_job_event(
         client,
         job_id,
-        JobPayload::Failed(JobFailed { error: Some(error) }),
+        JobPayload::Failed(JobFailed {
+            error: Some(error),
+            will_retry: false,
+        }),
     )
     .await
 }
@@ -899,8 +805,10 @@ fn ensure_parent_dir(path: &Path) -> io::Result<()> {
     Ok(())
 }
 
+/// Writes the bundle with stored secret values masked in every entry.
 fn write_zip_bundle(plan: BundlePlan) -> io::Result<()> {
     ensure_parent_dir(&plan.output_path)?;
+    let redactor = Redactor::from_store();
     let file = fs::File::create(&plan.output_path)?;
     let mut zip = ZipWriter::new(file);
     let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
@@ -913,11 +821,15 @@ fn write_zip_bundle(plan: BundlePlan) -> io::Result<()> {
                 }
                 zip.start_file(name, options)?;
                 let mut input = fs::File::open(source)?;
-                io::copy(&mut input, &mut zip)?;
+                if redactor.is_empty() {
+                    io::copy(&mut input, &mut zip)?;
+                } else {
+                    redactor.redact_stream(input, &mut zip)?;
+                }
             }
             BundleItem::Generated { name, contents } => {
                 zip.start_file(name, options)?;
-                zip.write_all(&contents)?;
+                zip.write_all(&redactor.redact_bytes(&contents))?;
             }
         }
     }
@@ -959,26 +871,6 @@ fn sanitize_segment(value: &str) -> String {
     }
 }
 
-fn load_job_history() -> Option<JobHistoryFile> {
-    let path = job_state_file_path();
-    let data = match fs::read_to_string(&path) {
-        Ok(data) => data,
-        Err(err) => {
-            if err.kind() != io::ErrorKind::NotFound {
-                warn!("Failed to read {}: {}", path.display(), err);
-            }
-            return None;
-        }
-    };
-    match serde_json::from_str::<JobHistoryFile>(&data) {
-        Ok(file) => Some(file),
-        Err(err) => {
-            warn!("Failed to parse {}: {}", path.display(), err);
-            None
-        }
-    }
-}
-
 fn log_readme(message: String) -> BundleItem {
     BundleItem::Generated {
         name: "logs/README.txt".into(),
@@ -986,7 +878,11 @@ fn log_readme(message: String) -> BundleItem {
     }
 }
 
-fn collect_log_items(run_id: &str, runs_snapshot: &[RunRecordEntry]) -> Vec<BundleItem> {
+async fn collect_log_items(
+    job_client: &mut JobServiceClient<Channel>,
+    run_id: &str,
+    runs_snapshot: &[RunRecordEntry],
+) -> Vec<BundleItem> {
     let Some(run) = runs_snapshot.iter().find(|item| item.run_id == run_id) else {
         return vec![log_readme(format!(
             "No run record found for run_id {run_id}\n"
@@ -998,28 +894,23 @@ fn collect_log_items(run_id: &str, runs_snapshot: &[RunRecordEntry]) -> Vec<Bund
         ))];
     }
 
-    let file = match load_job_history() {
-        Some(file) => file,
-        None => {
-            return vec![log_readme(format!(
-                "Job history file {} not available\n",
-                job_state_file_path().display()
-            ))];
-        }
-    };
-
-    let job_set: HashSet<String> = run.job_ids.iter().cloned().collect();
     let mut logs: BTreeMap<String, BTreeMap<String, Vec<u8>>> = BTreeMap::new();
+    let mut unavailable = Vec::new();
 
-    for job in file.jobs {
-        if !job_set.contains(&job.job_id) {
-            continue;
-        }
-        for event in job.history {
-            let Some(JobHistoryPayload::Log { chunk }) = event.payload else {
+    for job_id in &run.job_ids {
+        let events = match collect_job_history(job_client, job_id).await {
+            Ok(events) => events,
+            Err(err) => {
+                warn!("Failed to load history for job {job_id}: {err}");
+                unavailable.push(job_id.clone());
+                continue;
+            }
+        };
+        for event in events {
+            let Some(JobPayload::Log(log)) = event.payload else {
                 continue;
             };
-            let Some(chunk) = chunk else {
+            let Some(chunk) = log.chunk else {
                 continue;
             };
             let stream = if chunk.stream.trim().is_empty() {
@@ -1027,7 +918,7 @@ fn collect_log_items(run_id: &str, runs_snapshot: &[RunRecordEntry]) -> Vec<Bund
             } else {
                 chunk.stream
             };
-            logs.entry(job.job_id.clone())
+            logs.entry(job_id.clone())
                 .or_default()
                 .entry(stream)
                 .or_default()
@@ -1035,10 +926,16 @@ fn collect_log_items(run_id: &str, runs_snapshot: &[RunRecordEntry]) -> Vec<Bund
         }
     }
 
+    if !unavailable.is_empty() && logs.is_empty() {
+        return vec![log_readme(format!(
+            "Job history unavailable from JobService for job(s): {}\n",
+            unavailable.join(", ")
+        ))];
+    }
+
     if logs.is_empty() {
         return vec![log_readme(format!(
-            "No job logs found in {} for run {run_id}\n",
-            job_state_file_path().display()
+            "No job logs recorded for run {run_id}\n"
         ))];
     }
 
@@ -1091,6 +988,7 @@ fn support_bundle_plan(
     run_id: &str,
     req: &ExportSupportBundleRequest,
     runs_snapshot: Vec<RunRecordEntry>,
+    log_items: Vec<BundleItem>,
 ) -> Result<BundlePlan, Status> {
     let mut items = Vec::new();
     let correlation_id = req.correlation_id.trim().to_string();
@@ -1148,7 +1046,7 @@ fn support_bundle_plan(
     }
 
     if req.include_logs {
-        items.extend(collect_log_items(run_id, &runs_snapshot));
+        items.extend(log_items);
     }
 
     Ok(BundlePlan { output_path, items })
@@ -1247,7 +1145,7 @@ async fn run_support_bundle_job(
     req: ExportSupportBundleRequest,
 ) -> Result<(), Status> {
     let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(
             &mut job_client,
             &job_id,
@@ -1295,25 +1193,32 @@ async fn run_support_bundle_job(
         st.runs.clone()
     };
 
-    let plan = match support_bundle_plan(output_path.clone(), &run_id, &req, runs_snapshot) {
-        Ok(plan) => plan,
-        Err(err) => {
-            let detail = error_detail(
-                ErrorCode::Internal,
-                "bundle planning failed",
-                err.message(),
-                &job_id,
-            );
-            let _ = publish_failed(&mut job_client, &job_id, detail).await;
-            let _ = update_run_state(state, &run_id, |entry| {
-                entry.result = "failed".into();
-                entry.finished_at = Some(now_millis());
-            })
-            .await;
-            return Err(err);
-        }
+    let log_items = if req.include_logs {
+        collect_log_items(&mut job_client, &run_id, &runs_snapshot).await
+    } else {
+        Vec::new()
     };
 
+    let plan =
+        match support_bundle_plan(output_path.clone(), &run_id, &req, runs_snapshot, log_items) {
+            Ok(plan) => plan,
+            Err(err) => {
+                let detail = error_detail(
+                    ErrorCode::Internal,
+                    "bundle planning failed",
+                    err.message(),
+                    &job_id,
+                );
+                let _ = publish_failed(&mut job_client, &job_id, detail).await;
+                let _ = update_run_state(state, &run_id, |entry| {
+                    entry.result = "failed".into();
+                    entry.finished_at = Some(now_millis());
+                })
+                .await;
+                return Err(err);
+            }
+        };
+
     if cancel_requested(&cancel_rx) {
         let _ = publish_log(&mut job_client, &job_id, "Support bundle cancelled\n").await;
         let _ = update_run_state(state, &run_id, |entry| {
@@ -1431,7 +1336,7 @@ async fn run_evidence_bundle_job(
     output_path: PathBuf,
 ) -> Result<(), Status> {
     let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(
             &mut job_client,
             &job_id,
@@ -10,17 +10,18 @@ use apkw_proto::apkw::v1::{
     job_event::Payload as JobPayload,
     job_service_client::JobServiceClient,
     project_service_server::{ProjectService, ProjectServiceServer},
-    CreateProjectRequest, CreateProjectResponse, ErrorCode, ErrorDetail, GetJobRequest,
-    GetProjectRequest, GetProjectResponse, Id, JobCompleted, JobEvent, JobFailed, JobLogAppended,
+    CreateProjectRequest, CreateProjectResponse, ErrorCode, ErrorDetail, GetProjectRequest,
+    GetProjectResponse, Id, JobCompleted, JobEvent, JobFailed, JobLogAppended, JobPriority,
     JobProgress, JobProgressUpdated, JobState, JobStateChanged, KeyValue,
     ListRecentProjectsRequest, ListRecentProjectsResponse, ListTemplatesRequest,
     ListTemplatesResponse, LogChunk, OpenProjectRequest, OpenProjectResponse, PageInfo, Project,
     PublishJobEventRequest, ReloadStateRequest, ReloadStateResponse, RunId,
-    SetProjectConfigRequest, SetProjectConfigResponse, StartJobRequest, StreamJobEventsRequest,
-    Template, Timestamp, ToolchainKind,
+    SetProjectConfigRequest, SetProjectConfigResponse, StartJobRequest, Template, Timestamp,
+    ToolchainKind,
 };
 use apkw_util::{
-    expand_user, job_addr, now_millis, now_ts, serve_grpc_with_telemetry, write_json_atomic,
+    expand_user, job_addr, now_millis, now_ts, serve_grpc_with_telemetry, spawn_cancel_watcher,
+    wait_for_job_dispatch, write_json_atomic,
 };
 use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, watch, Mutex};
@@ -691,71 +692,6 @@ async fn connect_job() -> Result<JobServiceClient<Channel>, Status> {
     Ok(JobServiceClient::new(channel))
 }
 
-async fn job_is_cancelled(client: &mut JobServiceClient<Channel>, job_id: &str) -> bool {
-    let resp = client
-        .get_job(GetJobRequest {
-            job_id: Some(Id {
-                value: job_id.to_string(),
-            }),
-        })
-        .await;
-    let job = match resp {
-        Ok(resp) => resp.into_inner().job,
-        Err(_) => return false,
-    };
-    matches!(
-        job.and_then(|job| JobState::try_from(job.state).ok()),
-        Some(JobState::Cancelled)
-    )
-}
-
-async fn spawn_cancel_watcher(job_id: String) -> watch::Receiver<bool> {
-    let (tx, rx) = watch::channel(false);
-    let mut client = match connect_job().await {
-        Ok(client) => client,
-        Err(err) => {
-            warn!("cancel watcher: failed to connect job service: {err}");
-            return rx;
-        }
-    };
-    let mut stream = match client
-        .stream_job_events(StreamJobEventsRequest {
-            job_id: Some(Id {
-                value: job_id.clone(),
-            }),
-            include_history: true,
-        })
-        .await
-    {
-        Ok(resp) => resp.into_inner(),
-        Err(err) => {
-            warn!("cancel watcher: stream failed for {job_id}: {err}");
-            return rx;
-        }
-    };
-
-    tokio::spawn(async move {
-        loop {
-            match stream.message().await {
-                Ok(Some(evt)) => {
-                    if let Some(JobPayload::StateChanged(state)) = evt.payload {
-                        if JobState::try_from(state.new_state).unwrap_or(JobState::Unspecified)
-                            == JobState::Cancelled
-                        {
-                            let _ = tx.send(true);
-                            break;
-                        }
-                    }
-                }
-                Ok(None) => break,
-                Err(_) => break,
-            }
-        }
-    });
-
-    rx
-}
-
 fn cancel_requested(cancel_rx: &watch::Receiver<bool>) -> bool {
     *cancel_rx.borrow()
 }
@@ -777,6 +713,12 @@ async fn start_job(
             toolchain_set_id: None,
             correlation_id: correlation_id.to_string(),
             run_id,
+            depends_on: Vec::new(),
+            concurrency_keys: Vec::new(),
+            timeout_ms: 0,
+            retry_policy: None,
+            priority: JobPriority::Interactive as i32,
+            preemptible: false,
         })
         .await
         .map_err(|e| Status::unavailable(format!("job start failed: {e}")))?
@@ -806,6 +748,8 @@ async fn publish_job_event(
                 job_id: Some(Id {
                     value: job_id.to_string(),
                 }),
+                attempt: 0,
+                seq: 0,
                 payload: Some(payload),
             }),
         })
@@ -871,6 +815,7 @@ async fn publish_log(
                 data: message.as_bytes().to_vec(),
                 truncated: false,
             }),
+            records: Vec::new(),
         }),
     )
     .await
@@ -904,6 +849,7 @@ async fn publish_failed(
         job_id,
         JobPayload::Failed(JobFailed {
             error: Some(detail),
+            will_retry: false,
         }),
     )
     .await
@@ -1136,7 +1082,7 @@ async fn run_create_job(
     job_id: String,
 ) -> Result<(), Status> {
     let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(
             &mut job_client,
             &job_id,
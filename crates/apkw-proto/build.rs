@@ -1,7 +1,14 @@
-use std::path::PathBuf;
+use std::{fmt::Write as _, path::PathBuf};
+
+use prost::Message;
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, FileDescriptorSet,
+};
 
 fn main() {
     let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
     let workspace_root = manifest_dir
         .parent()
         .and_then(|p| p.parent())
@@ -24,9 +31,99 @@ fn main() {
         println!("cargo:rerun-if-changed={}", p.display());
     }
 
+    let descriptor_path = out_dir.join("apkw_descriptor.bin");
     tonic_build::configure()
         .build_server(true)
         .build_client(true)
+        .file_descriptor_set_path(&descriptor_path)
         .compile_protos(&protos, &[proto_root])
         .expect("failed to compile protos");
+
+    let descriptors = std::fs::read(&descriptor_path).expect("failed to read descriptor set");
+    pbjson_build::Builder::new()
+        .register_descriptors(&descriptors)
+        .expect("failed to register descriptors")
+        .preserve_proto_field_names()
+        .emit_fields()
+        .build(&[".apkw.v1"])
+        .expect("failed to generate JSON mappings");
+
+    let set = FileDescriptorSet::decode(descriptors.as_slice()).expect("invalid descriptor set");
+    std::fs::write(out_dir.join("apkw.v1.rpc.rs"), unary_table(&set))
+        .expect("failed to write the unary rpc table");
+}
+
+/// The message named by a fully qualified `.apkw.v1.Name` type.
+fn message<'a>(set: &'a FileDescriptorSet, type_name: &str) -> &'a DescriptorProto {
+    let name = type_name
+        .strip_prefix(".apkw.v1.")
+        .unwrap_or_else(|| panic!("{type_name} is not an apkw.v1 message"));
+    set.file
+        .iter()
+        .flat_map(|file| &file.message_type)
+        .find(|message| message.name() == name)
+        .unwrap_or_else(|| panic!("{type_name} is not a top-level message"))
+}
+
+/// Messages holding a single string `value` (`Id`, `RunId`), which callers
+/// may give as a plain string.
+fn is_id(message: &DescriptorProto) -> bool {
+    matches!(message.field.as_slice(), [field] if field.name() == "value" && field.r#type() == Type::String)
+}
+
+/// `UNARY_METHODS` and `call_unary_json` for every unary RPC in the set.
+fn unary_table(set: &FileDescriptorSet) -> String {
+    let mut table = String::new();
+    let mut calls = String::new();
+    for file in &set.file {
+        for service in &file.service {
+            for method in &service.method {
+                if method.client_streaming() || method.server_streaming() {
+                    continue;
+                }
+                let input = message(set, method.input_type());
+                let output = message(set, method.output_type());
+                let mut fields = String::new();
+                for field in &input.field {
+                    let kind = match field.r#type() {
+                        Type::Bool => "Bool",
+                        Type::Message if is_id(message(set, field.type_name())) => "Id",
+                        Type::Message => "Message",
+                        _ => "Scalar",
+                    };
+                    let _ = write!(
+                        fields,
+                        "RequestField {{ name: {:?}, kind: FieldKind::{kind}, repeated: {} }}, ",
+                        field.name(),
+                        field.label() == Label::Repeated,
+                    );
+                }
+                let _ = writeln!(
+                    table,
+                    "    UnaryMethod {{ service: {:?}, method: {:?}, request_fields: &[{fields}] }},",
+                    service.name(),
+                    method.name(),
+                );
+                let _ = writeln!(
+                    calls,
+                    "        ({:?}, {:?}) => call::<crate::apkw::v1::{}, crate::apkw::v1::{}>(channel, \"/apkw.v1.{}/{}\", request).await,",
+                    service.name(),
+                    method.name(),
+                    input.name(),
+                    output.name(),
+                    service.name(),
+                    method.name(),
+                );
+            }
+        }
+    }
+    format!(
+        "/// Every unary RPC of the apkw.v1 services.\n\
+         pub const UNARY_METHODS: &[UnaryMethod] = &[\n{table}];\n\n\
+         /// Calls `service`/`method` with a proto3 JSON request and returns the\n\
+         /// JSON response.\n\
+         pub async fn call_unary_json(\n    channel: Channel,\n    service: &str,\n    method: &str,\n    request: serde_json::Value,\n) -> Result<serde_json::Value, Status> {{\n    \
+         match (service, method) {{\n{calls}        \
+         _ => Err(Status::unimplemented(format!(\"no unary rpc {{service}}/{{method}}\"))),\n    }}\n}}\n"
+    )
 }
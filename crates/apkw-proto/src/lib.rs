@@ -1,5 +1,8 @@
 pub mod apkw {
     pub mod v1 {
         tonic::include_proto!("apkw.v1");
+        include!(concat!(env!("OUT_DIR"), "/apkw.v1.serde.rs"));
     }
 }
+
+pub mod rpc;
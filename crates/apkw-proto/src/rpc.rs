@@ -0,0 +1,104 @@
+//! Unary RPCs called by name with proto3 JSON messages, for callers that
+//! learn the method at runtime (workflow steps). Field names are the proto
+//! names (`project_id`, not `projectId`).
+
+use prost::Message;
+use serde::{de::DeserializeOwned, Serialize};
+use tonic::{
+    codec::ProstCodec, codegen::http::uri::PathAndQuery, transport::Channel, Request, Status,
+};
+
+/// How a request field is written in JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    /// A string, number or enum name.
+    Scalar,
+    Bool,
+    /// A message with a single string `value` (`Id`, `RunId`).
+    Id,
+    /// Any other message, as a JSON object.
+    Message,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RequestField {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    pub repeated: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UnaryMethod {
+    /// Service name without the package, e.g. `BuildService`.
+    pub service: &'static str,
+    pub method: &'static str,
+    pub request_fields: &'static [RequestField],
+}
+
+impl UnaryMethod {
+    pub fn field(&self, name: &str) -> Option<&'static RequestField> {
+        self.request_fields.iter().find(|field| field.name == name)
+    }
+}
+
+pub fn unary_method(service: &str, method: &str) -> Option<&'static UnaryMethod> {
+    UNARY_METHODS
+        .iter()
+        .find(|m| m.service == service && m.method == method)
+}
+
+#[allow(clippy::result_large_err)]
+async fn call<Req, Resp>(
+    channel: Channel,
+    path: &'static str,
+    request: serde_json::Value,
+) -> Result<serde_json::Value, Status>
+where
+    Req: Message + DeserializeOwned + Send + Sync + 'static,
+    Resp: Message + Serialize + Default + Send + Sync + 'static,
+{
+    let request: Req = serde_json::from_value(request)
+        .map_err(|err| Status::invalid_argument(format!("{path}: invalid request: {err}")))?;
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready()
+        .await
+        .map_err(|err| Status::unavailable(format!("{path}: service not ready: {err}")))?;
+    let response: Resp = grpc
+        .unary(
+            Request::new(request),
+            PathAndQuery::from_static(path),
+            ProstCodec::default(),
+        )
+        .await?
+        .into_inner();
+    serde_json::to_value(response)
+        .map_err(|err| Status::internal(format!("{path}: response is not JSON: {err}")))
+}
+
+include!(concat!(env!("OUT_DIR"), "/apkw.v1.rpc.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apkw::v1::{BuildRequest, BuildVariant};
+
+    #[test]
+    fn requests_use_proto_field_names() {
+        let method = unary_method("BuildService", "Build").unwrap();
+        assert_eq!(method.field("project_id").unwrap().kind, FieldKind::Id);
+        assert!(method.field("tasks").unwrap().repeated);
+        assert!(unary_method("JobService", "StreamJobEvents").is_none());
+
+        let request: BuildRequest = serde_json::from_value(serde_json::json!({
+            "project_id": { "value": "proj-1" },
+            "variant": "BUILD_VARIANT_RELEASE",
+            "tasks": ["assembleRelease"],
+        }))
+        .unwrap();
+        assert_eq!(request.project_id.as_ref().unwrap().value, "proj-1");
+        assert_eq!(request.variant, BuildVariant::Release as i32);
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["tasks"][0], "assembleRelease");
+        assert_eq!(json["clean_first"], false);
+    }
+}
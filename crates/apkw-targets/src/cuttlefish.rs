@@ -23,8 +23,8 @@ use crate::adb::{
 };
 use crate::ids::{canonicalize_adb_serial, normalize_target_id, normalize_target_id_for_compare};
 use crate::jobs::{
-    cancel_requested, connect_job, job_error_detail, job_is_cancelled, metric, publish_completed,
-    publish_failed, publish_log, publish_progress, publish_state, spawn_cancel_watcher,
+    cancel_requested, connect_job, job_error_detail, metric, publish_completed, publish_failed,
+    publish_log, publish_progress, publish_state, spawn_cancel_watcher, wait_for_job_dispatch,
 };
 use crate::state::data_dir;
 
@@ -2586,7 +2586,7 @@ pub(crate) async fn run_cuttlefish_start_job(job_id: String, show_full_ui: bool)
     };
 
     let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(
             &mut job_client,
             &job_id,
@@ -3055,7 +3055,7 @@ pub(crate) async fn run_cuttlefish_stop_job(job_id: String) {
     };
 
     let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(
             &mut job_client,
             &job_id,
@@ -3184,7 +3184,7 @@ pub(crate) async fn run_cuttlefish_install_job(job_id: String, options: Cuttlefi
     };
 
     let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(
             &mut job_client,
             &job_id,
@@ -1,13 +1,16 @@
 use apkw_proto::apkw::v1::{
     job_event::Payload as JobPayload, job_service_client::JobServiceClient, ErrorCode, ErrorDetail,
-    GetJobRequest, Id, JobCompleted, JobEvent, JobFailed, JobLogAppended, JobProgress,
+    Id, JobCompleted, JobEvent, JobFailed, JobLogAppended, JobPriority, JobProgress,
     JobProgressUpdated, JobState, JobStateChanged, KeyValue, LogChunk, PublishJobEventRequest,
-    RunId, StartJobRequest, StreamJobEventsRequest,
+    RetryPolicy, RunId, StartJobRequest,
 };
 use apkw_util::job_addr;
+pub(crate) use apkw_util::{run_job_attempts, spawn_cancel_watcher, wait_for_job_dispatch};
 use tokio::sync::watch;
 use tonic::{transport::Channel, Status};
 
+use crate::ids::normalize_target_id_for_compare;
+
 pub(crate) async fn connect_job() -> Result<JobServiceClient<Channel>, Status> {
     let addr = job_addr();
     let endpoint = format!("http://{addr}");
@@ -19,69 +22,47 @@ pub(crate) async fn connect_job() -> Result<JobServiceClient<Channel>, Status> {
     Ok(JobServiceClient::new(channel))
 }
 
-pub(crate) async fn job_is_cancelled(client: &mut JobServiceClient<Channel>, job_id: &str) -> bool {
-    let resp = client
-        .get_job(GetJobRequest {
-            job_id: Some(Id {
-                value: job_id.to_string(),
-            }),
-        })
-        .await;
-    let job = match resp {
-        Ok(resp) => resp.into_inner().job,
-        Err(_) => return false,
-    };
-    matches!(
-        job.and_then(|job| JobState::try_from(job.state).ok()),
-        Some(JobState::Cancelled)
-    )
+/// Jobs touching the same device, and all Cuttlefish host operations,
+/// serialize through the job queue.
+fn concurrency_keys_for(job_type: &str, target_id: Option<&Id>) -> Vec<String> {
+    let mut keys = Vec::new();
+    if let Some(id) = target_id.filter(|id| !id.value.trim().is_empty()) {
+        keys.push(format!(
+            "target:{}",
+            normalize_target_id_for_compare(&id.value)
+        ));
+    }
+    if job_type.starts_with("targets.cuttlefish.") {
+        keys.push("cuttlefish".to_string());
+    }
+    keys
 }
 
-pub(crate) async fn spawn_cancel_watcher(job_id: String) -> watch::Receiver<bool> {
-    let (tx, rx) = watch::channel(false);
-    let mut client = match connect_job().await {
-        Ok(client) => client,
-        Err(err) => {
-            tracing::warn!("cancel watcher: failed to connect job service: {err}");
-            return rx;
-        }
-    };
-    let mut stream = match client
-        .stream_job_events(StreamJobEventsRequest {
-            job_id: Some(Id {
-                value: job_id.clone(),
-            }),
-            include_history: true,
-        })
-        .await
-    {
-        Ok(resp) => resp.into_inner(),
-        Err(err) => {
-            tracing::warn!("cancel watcher: stream failed for {job_id}: {err}");
-            return rx;
-        }
-    };
-
-    tokio::spawn(async move {
-        loop {
-            match stream.message().await {
-                Ok(Some(evt)) => {
-                    if let Some(JobPayload::StateChanged(state)) = evt.payload {
-                        if JobState::try_from(state.new_state).unwrap_or(JobState::Unspecified)
-                            == JobState::Cancelled
-                        {
-                            let _ = tx.send(true);
-                            break;
-                        }
-                    }
-                }
-                Ok(None) => break,
-                Err(_) => break,
-            }
-        }
-    });
-
-    rx
+/// Installs and Cuttlefish boots retry while the device is still coming up.
+fn retry_policy_for(job_type: &str) -> Option<RetryPolicy> {
+    match job_type {
+        "targets.install" | "targets.cuttlefish.start" => Some(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 2_000,
+            backoff_multiplier: 2.0,
+            max_backoff_ms: 30_000,
+            retryable_codes: vec![
+                ErrorCode::TargetNotReachable as i32,
+                ErrorCode::Unavailable as i32,
+            ],
+        }),
+        _ => None,
+    }
+}
+
+/// Device operations a developer is waiting on run interactively; the
+/// Cuttlefish download runs in the background and yields to them.
+fn priority_for(job_type: &str) -> (JobPriority, bool) {
+    match job_type {
+        "targets.install" | "targets.launch" | "targets.stop" => (JobPriority::Interactive, false),
+        "targets.cuttlefish.install" => (JobPriority::Background, true),
+        _ => (JobPriority::Normal, false),
+    }
 }
 
 pub(crate) async fn start_job(
@@ -93,6 +74,8 @@ pub(crate) async fn start_job(
     correlation_id: &str,
     run_id: Option<RunId>,
 ) -> Result<String, Status> {
+    let concurrency_keys = concurrency_keys_for(job_type, target_id.as_ref());
+    let (priority, preemptible) = priority_for(job_type);
     let resp = client
         .start_job(StartJobRequest {
             job_type: job_type.into(),
@@ -102,6 +85,12 @@ pub(crate) async fn start_job(
             toolchain_set_id: None,
             correlation_id: correlation_id.to_string(),
             run_id,
+            depends_on: Vec::new(),
+            concurrency_keys,
+            timeout_ms: 0,
+            retry_policy: retry_policy_for(job_type),
+            priority: priority as i32,
+            preemptible,
         })
         .await
         .map_err(|e| Status::unavailable(format!("job start failed: {e}")))?
@@ -131,6 +120,8 @@ pub(crate) async fn publish_job_event(
                 job_id: Some(Id {
                     value: job_id.to_string(),
                 }),
+                attempt: 0,
+                seq: 0,
                 payload: Some(payload),
             }),
         })
@@ -196,6 +187,7 @@ pub(crate) async fn publish_log(
                 data: message.as_bytes().to_vec(),
                 truncated: false,
             }),
+            records: Vec::new(),
         }),
     )
     .await
@@ -228,7 +220,10 @@ pub(crate) async fn publish_failed(
     publish_job_event(
         client,
         job_id,
-        JobPayload::Failed(JobFailed { error: Some(error) }),
+        JobPayload::Failed(JobFailed {
+            error: Some(error),
+            will_retry: false,
+        }),
     )
     .await
 }
@@ -11,7 +11,7 @@ use apkw_proto::apkw::v1::{
     StartCuttlefishRequest, StartCuttlefishResponse, StopAppRequest, StopAppResponse,
     StopCuttlefishRequest, StopCuttlefishResponse, StreamLogcatRequest, Target, Timestamp,
 };
-use apkw_util::{now_millis, now_ts};
+use apkw_util::{is_sha256_hex, now_millis, now_ts, sha256_file};
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
     process::Command,
@@ -33,8 +33,9 @@ use crate::cuttlefish::{
 };
 use crate::ids::{canonicalize_adb_serial, normalize_target_id, normalize_target_id_for_compare};
 use crate::jobs::{
-    cancel_requested, connect_job, job_error_detail, job_is_cancelled, metric, publish_completed,
-    publish_failed, publish_log, publish_progress, publish_state, spawn_cancel_watcher, start_job,
+    cancel_requested, connect_job, job_error_detail, metric, publish_completed, publish_failed,
+    publish_log, publish_progress, publish_state, run_job_attempts, spawn_cancel_watcher,
+    start_job, wait_for_job_dispatch,
 };
 use crate::state::{
     load_state, merge_inventory_targets, save_state, save_state_best_effort,
@@ -354,7 +355,22 @@ async fn ensure_target_ready(
     }
 }
 
-async fn run_install_job(job_id: String, target_id: String, apk_path: String) {
+/// Hashes `apk_path` off the async runtime.
+async fn apk_sha256(apk_path: &str) -> Result<String, String> {
+    let path = Path::new(apk_path).to_path_buf();
+    match tokio::task::spawn_blocking(move || sha256_file(&path)).await {
+        Ok(Ok(digest)) => Ok(digest),
+        Ok(Err(err)) => Err(format!("failed to hash {apk_path}: {err}")),
+        Err(err) => Err(format!("failed to hash {apk_path}: {err}")),
+    }
+}
+
+async fn run_install_job(
+    job_id: String,
+    target_id: String,
+    apk_path: String,
+    expected_sha256: String,
+) {
     let mut job_client = match connect_job().await {
         Ok(client) => client,
         Err(err) => {
@@ -364,7 +380,7 @@ async fn run_install_job(job_id: String, target_id: String, apk_path: String) {
     };
 
     let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(&mut job_client, &job_id, "Install cancelled before start\n").await;
         return;
     }
@@ -388,6 +404,35 @@ async fn run_install_job(job_id: String, target_id: String, apk_path: String) {
     )
     .await;
 
+    // Hash before touching the device so a replaced APK is never installed.
+    let sha256 = match apk_sha256(&apk_path).await {
+        Ok(digest) => digest,
+        Err(err) => {
+            let detail =
+                job_error_detail(ErrorCode::InstallFailed, "cannot read APK", err, &job_id);
+            let _ = publish_failed(&mut job_client, &job_id, detail).await;
+            return;
+        }
+    };
+    if !expected_sha256.is_empty() && sha256 != expected_sha256 {
+        let detail = job_error_detail(
+            ErrorCode::ArtifactMismatch,
+            "APK does not match the expected sha256",
+            format!("{apk_path}: expected sha256 {expected_sha256}, found {sha256}"),
+            &job_id,
+        );
+        let _ = publish_failed(&mut job_client, &job_id, detail).await;
+        return;
+    }
+    if !expected_sha256.is_empty() {
+        let _ = publish_log(
+            &mut job_client,
+            &job_id,
+            &format!("Verified sha256 {sha256}\n"),
+        )
+        .await;
+    }
+
     if cancel_requested(&cancel_rx) {
         let _ = publish_log(&mut job_client, &job_id, "Install cancelled\n").await;
         return;
@@ -434,10 +479,16 @@ async fn run_install_job(job_id: String, target_id: String, apk_path: String) {
                 &mut job_client,
                 &job_id,
                 "APK installed",
-                vec![KeyValue {
-                    key: "apk_path".into(),
-                    value: apk_path,
-                }],
+                vec![
+                    KeyValue {
+                        key: "apk_path".into(),
+                        value: apk_path,
+                    },
+                    KeyValue {
+                        key: "apk_sha256".into(),
+                        value: sha256,
+                    },
+                ],
             )
             .await;
         }
@@ -473,7 +524,7 @@ async fn run_launch_job(
     };
 
     let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(&mut job_client, &job_id, "Launch cancelled before start\n").await;
         return;
     }
@@ -608,7 +659,7 @@ async fn run_stop_job(job_id: String, target_id: String, application_id: String)
     };
 
     let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-    if job_is_cancelled(&mut job_client, &job_id).await {
+    if !wait_for_job_dispatch(&mut job_client, &job_id).await {
         let _ = publish_log(&mut job_client, &job_id, "Stop cancelled before start\n").await;
         return;
     }
@@ -910,6 +961,23 @@ impl TargetService for Svc {
         if !Path::new(&apk_path).exists() {
             return Err(Status::not_found(format!("apk not found: {apk_path}")));
         }
+        let expected_sha256 = req.apk_sha256.trim().to_ascii_lowercase();
+        if !expected_sha256.is_empty() && !is_sha256_hex(&expected_sha256) {
+            return Err(Status::invalid_argument(
+                "apk_sha256 must be a 64-character hex sha256",
+            ));
+        }
+
+        let mut params = vec![KeyValue {
+            key: "apk_path".into(),
+            value: apk_path.clone(),
+        }];
+        if !expected_sha256.is_empty() {
+            params.push(KeyValue {
+                key: "apk_sha256".into(),
+                value: expected_sha256.clone(),
+            });
+        }
 
         let mut job_client = connect_job().await?;
         let job_id = req
@@ -923,10 +991,7 @@ impl TargetService for Svc {
             start_job(
                 &mut job_client,
                 "targets.install",
-                vec![KeyValue {
-                    key: "apk_path".into(),
-                    value: apk_path.clone(),
-                }],
+                params,
                 req.project_id,
                 Some(Id {
                     value: target_id.clone(),
@@ -939,7 +1004,18 @@ impl TargetService for Svc {
             job_id
         };
 
-        tokio::spawn(run_install_job(job_id.clone(), target_id, apk_path));
+        let attempt_job_id = job_id.clone();
+        tokio::spawn(async move {
+            run_job_attempts(&attempt_job_id, || {
+                run_install_job(
+                    attempt_job_id.clone(),
+                    target_id.clone(),
+                    apk_path.clone(),
+                    expected_sha256.clone(),
+                )
+            })
+            .await;
+        });
         Ok(Response::new(InstallApkResponse {
             job_id: Some(Id { value: job_id }),
         }))
@@ -1120,15 +1196,19 @@ impl TargetService for Svc {
             job_id
         };
 
-        tokio::spawn(run_cuttlefish_install_job(
-            job_id.clone(),
-            CuttlefishInstallOptions {
-                force,
-                branch: branch_override,
-                target: target_override,
-                build_id: build_id_override,
-            },
-        ));
+        let attempt_job_id = job_id.clone();
+        let options = CuttlefishInstallOptions {
+            force,
+            branch: branch_override,
+            target: target_override,
+            build_id: build_id_override,
+        };
+        tokio::spawn(async move {
+            run_job_attempts(&attempt_job_id, || {
+                run_cuttlefish_install_job(attempt_job_id.clone(), options.clone())
+            })
+            .await;
+        });
 
         Ok(Response::new(InstallCuttlefishResponse {
             job_id: Some(Id { value: job_id }),
@@ -1230,7 +1310,13 @@ impl TargetService for Svc {
             job_id
         };
 
-        tokio::spawn(run_cuttlefish_start_job(job_id.clone(), show_full_ui));
+        let attempt_job_id = job_id.clone();
+        tokio::spawn(async move {
+            run_job_attempts(&attempt_job_id, || {
+                run_cuttlefish_start_job(attempt_job_id.clone(), show_full_ui)
+            })
+            .await;
+        });
 
         Ok(Response::new(StartCuttlefishResponse {
             job_id: Some(Id { value: job_id }),
@@ -5,6 +5,7 @@ use std::{
 };
 
 use apkw_proto::apkw::v1::{InstalledToolchain, ToolchainArtifact};
+use apkw_util::{hex_encode, sha256_file};
 use futures_util::StreamExt;
 use reqwest::Client;
 use sha2::{Digest, Sha256};
@@ -13,7 +14,7 @@ use tonic::Status;
 use uuid::Uuid;
 
 use crate::cancel::cancel_requested;
-use crate::hashing::{hex_encode, sha256_file, short_hash};
+use crate::hashing::short_hash;
 use crate::provenance::read_provenance;
 use crate::state::data_dir;
 
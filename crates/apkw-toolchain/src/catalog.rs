@@ -6,11 +6,10 @@ use std::{
 use apkw_proto::apkw::v1::{
     AvailableToolchain, Id, ToolchainArtifact, ToolchainKind, ToolchainProvider, ToolchainVersion,
 };
+use apkw_util::sha256_file;
 use serde::Deserialize;
 use tracing::warn;
 
-use crate::hashing::sha256_file;
-
 const FIXTURES_ENV_DIR: &str = "APKW_TOOLCHAIN_FIXTURES_DIR";
 const CATALOG_ENV: &str = "APKW_TOOLCHAIN_CATALOG";
 const HOST_OVERRIDE_ENV: &str = "APKW_TOOLCHAIN_HOST";
@@ -256,6 +255,7 @@ fn fixture_filename(kind: ToolchainKind, version: &str) -> String {
     match kind {
         ToolchainKind::Sdk => format!("sdk-{version}.tar.zst"),
         ToolchainKind::Ndk => format!("ndk-{version}.tar.zst"),
+        ToolchainKind::Jdk => format!("jdk-{version}.tar.zst"),
         _ => format!("toolchain-{version}.tar.zst"),
     }
 }
@@ -308,6 +308,7 @@ fn parse_kind(kind: &str) -> ToolchainKind {
     match kind.to_lowercase().as_str() {
         "sdk" => ToolchainKind::Sdk,
         "ndk" => ToolchainKind::Ndk,
+        "jdk" => ToolchainKind::Jdk,
         _ => ToolchainKind::Unspecified,
     }
 }
@@ -1,22 +1,9 @@
 use std::io::Read;
 use std::{fs, io, path::Path};
 
+use apkw_util::hex_encode;
 use sha2::{Digest, Sha256};
 
-pub(crate) fn sha256_file(path: &Path) -> io::Result<String> {
-    let mut file = fs::File::open(path)?;
-    let mut hasher = Sha256::new();
-    let mut buf = [0u8; 8192];
-    loop {
-        let read = file.read(&mut buf)?;
-        if read == 0 {
-            break;
-        }
-        hasher.update(&buf[..read]);
-    }
-    Ok(hex_encode(&hasher.finalize()))
-}
-
 pub(crate) fn sha256_file_bytes(path: &Path) -> io::Result<[u8; 32]> {
     let mut file = fs::File::open(path)?;
     let mut hasher = Sha256::new();
@@ -34,26 +21,9 @@ pub(crate) fn sha256_file_bytes(path: &Path) -> io::Result<[u8; 32]> {
     Ok(out)
 }
 
-pub(crate) fn hex_encode(bytes: &[u8]) -> String {
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for b in bytes {
-        out.push(nibble_to_hex(b >> 4));
-        out.push(nibble_to_hex(b & 0x0f));
-    }
-    out
-}
-
 pub(crate) fn short_hash(input: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(input.as_bytes());
     let full = hex_encode(&hasher.finalize());
     full.chars().take(12).collect()
 }
-
-fn nibble_to_hex(n: u8) -> char {
-    match n {
-        0..=9 => (b'0' + n) as char,
-        10..=15 => (b'a' + (n - 10)) as char,
-        _ => '0',
-    }
-}
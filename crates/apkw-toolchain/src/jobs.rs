@@ -1,13 +1,15 @@
 use apkw_proto::apkw::v1::{
     job_event::Payload as JobPayload, job_service_client::JobServiceClient, ErrorCode, ErrorDetail,
-    GetJobRequest, Id, JobCompleted, JobEvent, JobFailed, JobLogAppended, JobProgress,
+    Id, JobCompleted, JobEvent, JobFailed, JobLogAppended, JobPriority, JobProgress,
     JobProgressUpdated, JobState, JobStateChanged, KeyValue, LogChunk, PublishJobEventRequest,
-    RunId, StartJobRequest, StreamJobEventsRequest, ToolchainArtifact,
+    RetryPolicy, RunId, StartJobRequest, ToolchainArtifact,
 };
 use apkw_util::job_addr;
-use tokio::sync::watch;
+pub(crate) use apkw_util::{run_job_attempts, spawn_cancel_watcher, wait_for_job_dispatch};
 use tonic::{transport::Channel, Status};
 
+use crate::state::default_install_root;
+
 pub(crate) async fn connect_job() -> Result<JobServiceClient<Channel>, Status> {
     let addr = job_addr();
     let endpoint = format!("http://{addr}");
@@ -19,69 +21,42 @@ pub(crate) async fn connect_job() -> Result<JobServiceClient<Channel>, Status> {
     Ok(JobServiceClient::new(channel))
 }
 
-pub(crate) async fn job_is_cancelled(client: &mut JobServiceClient<Channel>, job_id: &str) -> bool {
-    let resp = client
-        .get_job(GetJobRequest {
-            job_id: Some(Id {
-                value: job_id.to_string(),
-            }),
-        })
-        .await;
-    let job = match resp {
-        Ok(resp) => resp.into_inner().job,
-        Err(_) => return false,
-    };
-    matches!(
-        job.and_then(|job| JobState::try_from(job.state).ok()),
-        Some(JobState::Cancelled)
-    )
+/// Jobs that write under the toolchain install root serialize on it.
+fn concurrency_keys_for(job_type: &str) -> Vec<String> {
+    match job_type {
+        "toolchain.install"
+        | "toolchain.update"
+        | "toolchain.uninstall"
+        | "toolchain.cleanup_cache" => vec![format!(
+            "toolchain-root:{}",
+            default_install_root().display()
+        )],
+        _ => Vec::new(),
+    }
 }
 
-pub(crate) async fn spawn_cancel_watcher(job_id: String) -> watch::Receiver<bool> {
-    let (tx, rx) = watch::channel(false);
-    let mut client = match connect_job().await {
-        Ok(client) => client,
-        Err(err) => {
-            tracing::warn!("cancel watcher: failed to connect job service: {err}");
-            return rx;
-        }
-    };
-    let mut stream = match client
-        .stream_job_events(StreamJobEventsRequest {
-            job_id: Some(Id {
-                value: job_id.clone(),
-            }),
-            include_history: true,
-        })
-        .await
-    {
-        Ok(resp) => resp.into_inner(),
-        Err(err) => {
-            tracing::warn!("cancel watcher: stream failed for {job_id}: {err}");
-            return rx;
-        }
-    };
-
-    tokio::spawn(async move {
-        loop {
-            match stream.message().await {
-                Ok(Some(evt)) => {
-                    if let Some(JobPayload::StateChanged(state)) = evt.payload {
-                        if JobState::try_from(state.new_state).unwrap_or(JobState::Unspecified)
-                            == JobState::Cancelled
-                        {
-                            let _ = tx.send(true);
-                            break;
-                        }
-                    }
-                }
-                Ok(None) => break,
-                Err(_) => break,
-            }
-        }
-    });
+/// Installs retry artifact downloads that fail on the network.
+fn retry_policy_for(job_type: &str) -> Option<RetryPolicy> {
+    match job_type {
+        "toolchain.install" => Some(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 5_000,
+            backoff_multiplier: 2.0,
+            max_backoff_ms: 60_000,
+            retryable_codes: vec![ErrorCode::Unavailable as i32],
+        }),
+        _ => None,
+    }
+}
 
-    rx
+/// Toolchain jobs run in the background; installs can be preempted by
+/// interactive work and resume as a new attempt.
+fn priority_for(job_type: &str) -> (JobPriority, bool) {
+    match job_type {
+        "toolchain.install" => (JobPriority::Background, true),
+        "toolchain.update" | "toolchain.cleanup_cache" => (JobPriority::Background, false),
+        _ => (JobPriority::Normal, false),
+    }
 }
 
 pub(crate) async fn start_job(
@@ -91,6 +66,7 @@ pub(crate) async fn start_job(
     correlation_id: &str,
     run_id: Option<RunId>,
 ) -> Result<String, Status> {
+    let (priority, preemptible) = priority_for(job_type);
     let resp = client
         .start_job(StartJobRequest {
             job_type: job_type.into(),
@@ -100,6 +76,12 @@ pub(crate) async fn start_job(
             toolchain_set_id: None,
             correlation_id: correlation_id.to_string(),
             run_id,
+            depends_on: Vec::new(),
+            concurrency_keys: concurrency_keys_for(job_type),
+            timeout_ms: 0,
+            retry_policy: retry_policy_for(job_type),
+            priority: priority as i32,
+            preemptible,
         })
         .await
         .map_err(|e| Status::unavailable(format!("job start failed: {e}")))?
@@ -129,6 +111,8 @@ pub(crate) async fn publish_job_event(
                 job_id: Some(Id {
                     value: job_id.to_string(),
                 }),
+                attempt: 0,
+                seq: 0,
                 payload: Some(payload),
             }),
         })
@@ -225,6 +209,7 @@ pub(crate) async fn publish_log(
                 data: message.as_bytes().to_vec(),
                 truncated: false,
             }),
+            records: Vec::new(),
         }),
     )
     .await
@@ -257,7 +242,10 @@ pub(crate) async fn publish_failed(
     publish_job_event(
         client,
         job_id,
-        JobPayload::Failed(JobFailed { error: Some(error) }),
+        JobPayload::Failed(JobFailed {
+            error: Some(error),
+            will_retry: false,
+        }),
     )
     .await
 }
@@ -37,9 +37,9 @@ use crate::catalog::{
     load_catalog, provider_from_catalog, Catalog, CatalogArtifact,
 };
 use crate::jobs::{
-    connect_job, job_error_detail, job_is_cancelled, metric, publish_completed, publish_failed,
-    publish_log, publish_progress, publish_state, spawn_cancel_watcher, start_job,
-    toolchain_base_metrics,
+    connect_job, job_error_detail, metric, publish_completed, publish_failed, publish_log,
+    publish_progress, publish_state, run_job_attempts, spawn_cancel_watcher, start_job,
+    toolchain_base_metrics, wait_for_job_dispatch,
 };
 use crate::provenance::{read_provenance, write_provenance, Provenance};
 use crate::state::{
@@ -296,7 +296,7 @@ impl Svc {
         let mut job_client = connect_job().await?;
         let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
 
-        if job_is_cancelled(&mut job_client, &job_id).await {
+        if !wait_for_job_dispatch(&mut job_client, &job_id).await {
             let _ = publish_log(&mut job_client, &job_id, "Install cancelled before start\n").await;
             return Ok(());
         }
@@ -518,8 +518,14 @@ impl Svc {
                     return Ok(());
                 }
                 Err(err) => {
+                    // Network failures are retryable; hash mismatches are not.
+                    let code = if err.code() == tonic::Code::Unavailable {
+                        ErrorCode::Unavailable
+                    } else {
+                        ErrorCode::ToolchainInstallFailed
+                    };
                     let err_detail = job_error_detail(
-                        ErrorCode::ToolchainInstallFailed,
+                        code,
                         "failed to fetch or verify artifact",
                         err.message().to_string(),
                         &job_id,
@@ -821,7 +827,7 @@ impl Svc {
         let mut job_client = connect_job().await?;
         let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
 
-        if job_is_cancelled(&mut job_client, &job_id).await {
+        if !wait_for_job_dispatch(&mut job_client, &job_id).await {
             let _ = publish_log(
                 &mut job_client,
                 &job_id,
@@ -995,7 +1001,7 @@ impl Svc {
         let mut job_client = connect_job().await?;
         let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
 
-        if job_is_cancelled(&mut job_client, &job_id).await {
+        if !wait_for_job_dispatch(&mut job_client, &job_id).await {
             let _ = publish_log(&mut job_client, &job_id, "Update cancelled before start\n").await;
             return Ok(());
         }
@@ -1528,7 +1534,7 @@ impl Svc {
         let mut job_client = connect_job().await?;
         let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
 
-        if job_is_cancelled(&mut job_client, &job_id).await {
+        if !wait_for_job_dispatch(&mut job_client, &job_id).await {
             let _ = publish_log(&mut job_client, &job_id, "Cleanup cancelled before start\n").await;
             return Ok(());
         }
@@ -1882,20 +1888,23 @@ impl ToolchainService for Svc {
         let job_id_for_spawn = job_id.clone();
 
         tokio::spawn(async move {
-            if let Err(err) = svc
-                .run_install_job(
-                    job_id_for_spawn.clone(),
-                    provider_id,
-                    version,
-                    install_root,
-                    verify_hash,
-                    host,
-                    fixtures,
-                )
-                .await
-            {
-                warn!("install job {} failed to run: {}", job_id_for_spawn, err);
-            }
+            run_job_attempts(&job_id_for_spawn, || async {
+                if let Err(err) = svc
+                    .run_install_job(
+                        job_id_for_spawn.clone(),
+                        provider_id.clone(),
+                        version.clone(),
+                        install_root.clone(),
+                        verify_hash,
+                        host.clone(),
+                        fixtures.clone(),
+                    )
+                    .await
+                {
+                    warn!("install job {} failed to run: {}", job_id_for_spawn, err);
+                }
+            })
+            .await;
         });
 
         Ok(Response::new(InstallToolchainResponse {
@@ -1952,7 +1961,7 @@ impl ToolchainService for Svc {
         };
 
         let cancel_rx = spawn_cancel_watcher(job_id.clone()).await;
-        if job_is_cancelled(&mut job_client, &job_id).await {
+        if !wait_for_job_dispatch(&mut job_client, &job_id).await {
             let _ = publish_log(
                 &mut job_client,
                 &job_id,
@@ -2674,9 +2683,10 @@ impl ToolchainService for Svc {
         let req = request.into_inner();
         let sdk_id = normalize_id(req.sdk_toolchain_id);
         let ndk_id = normalize_id(req.ndk_toolchain_id);
-        if sdk_id.is_none() && ndk_id.is_none() {
+        let jdk_id = normalize_id(req.jdk_toolchain_id);
+        if sdk_id.is_none() && ndk_id.is_none() && jdk_id.is_none() {
             return Err(Status::invalid_argument(
-                "sdk_toolchain_id or ndk_toolchain_id is required",
+                "sdk_toolchain_id, ndk_toolchain_id or jdk_toolchain_id is required",
             ));
         }
 
@@ -2695,9 +2705,21 @@ impl ToolchainService for Svc {
                 )));
             }
         }
+        if let Some(ref jdk_id) = jdk_id {
+            if !installed_toolchain_matches(&st.installed, jdk_id, ToolchainKind::Jdk) {
+                return Err(Status::not_found(format!(
+                    "jdk toolchain not installed: {jdk_id}"
+                )));
+            }
+        }
 
         let display_name = if req.display_name.trim().is_empty() {
-            "SDK+NDK".into()
+            match (ndk_id.is_some(), jdk_id.is_some()) {
+                (_, false) => "SDK+NDK",
+                (true, true) => "SDK+NDK+JDK",
+                (false, true) => "SDK+JDK",
+            }
+            .into()
         } else {
             req.display_name.trim().to_string()
         };
@@ -2709,6 +2731,7 @@ impl ToolchainService for Svc {
             sdk_toolchain_id: sdk_id.map(|value| Id { value }),
             ndk_toolchain_id: ndk_id.map(|value| Id { value }),
             display_name,
+            jdk_toolchain_id: jdk_id.map(|value| Id { value }),
         };
         st.toolchain_sets.push(set.clone());
         save_state_best_effort(&st);
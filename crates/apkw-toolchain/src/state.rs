@@ -54,6 +54,7 @@ struct PersistedToolchainSet {
     toolchain_set_id: String,
     sdk_toolchain_id: Option<String>,
     ndk_toolchain_id: Option<String>,
+    jdk_toolchain_id: Option<String>,
     display_name: String,
 }
 
@@ -143,6 +144,11 @@ impl PersistedToolchainSet {
                 .as_ref()
                 .map(|id| id.value.trim().to_string())
                 .filter(|value| !value.is_empty()),
+            jdk_toolchain_id: set
+                .jdk_toolchain_id
+                .as_ref()
+                .map(|id| id.value.trim().to_string())
+                .filter(|value| !value.is_empty()),
             display_name: set.display_name.clone(),
         })
     }
@@ -155,6 +161,7 @@ impl PersistedToolchainSet {
             sdk_toolchain_id: self.sdk_toolchain_id.map(|value| Id { value }),
             ndk_toolchain_id: self.ndk_toolchain_id.map(|value| Id { value }),
             display_name: self.display_name,
+            jdk_toolchain_id: self.jdk_toolchain_id.map(|value| Id { value }),
         }
     }
 }
@@ -298,6 +305,7 @@ pub(crate) fn installed_toolchain_matches(
             (expected, kind),
             (ToolchainKind::Sdk, ToolchainKind::Sdk)
                 | (ToolchainKind::Ndk, ToolchainKind::Ndk)
+                | (ToolchainKind::Jdk, ToolchainKind::Jdk)
                 | (_, ToolchainKind::Unspecified)
         )
     })
@@ -311,6 +319,7 @@ pub(crate) fn toolchain_referenced_by_sets(sets: &[ToolchainSet], toolchain_id:
     sets.iter().any(|set| {
         set.sdk_toolchain_id.as_ref().map(|id| id.value.as_str()) == Some(toolchain_id)
             || set.ndk_toolchain_id.as_ref().map(|id| id.value.as_str()) == Some(toolchain_id)
+            || set.jdk_toolchain_id.as_ref().map(|id| id.value.as_str()) == Some(toolchain_id)
     })
 }
 
@@ -326,10 +335,15 @@ pub(crate) fn scrub_toolchain_from_sets(
         if set.ndk_toolchain_id.as_ref().map(|id| id.value.as_str()) == Some(toolchain_id) {
             set.ndk_toolchain_id = None;
         }
+        if set.jdk_toolchain_id.as_ref().map(|id| id.value.as_str()) == Some(toolchain_id) {
+            set.jdk_toolchain_id = None;
+        }
     }
 
     sets.retain(|set| {
-        let has_any = set.sdk_toolchain_id.is_some() || set.ndk_toolchain_id.is_some();
+        let has_any = set.sdk_toolchain_id.is_some()
+            || set.ndk_toolchain_id.is_some()
+            || set.jdk_toolchain_id.is_some();
         if !has_any {
             if let Some(set_id) = toolchain_set_id(set) {
                 removed_sets.push(set_id.to_string());
@@ -360,6 +374,12 @@ pub(crate) fn replace_toolchain_in_sets(
             });
             updated += 1;
         }
+        if set.jdk_toolchain_id.as_ref().map(|id| id.value.as_str()) == Some(old_id) {
+            set.jdk_toolchain_id = Some(Id {
+                value: new_id.to_string(),
+            });
+            updated += 1;
+        }
     }
     updated
 }
@@ -584,6 +604,11 @@ fn guess_provider_kind(provider_id: &str, provider_name: &str) -> ToolchainKind
     let combined = format!("{} {}", provider_id, provider_name).to_ascii_lowercase();
     if combined.contains("ndk") {
         ToolchainKind::Ndk
+    } else if ["jdk", "temurin"]
+        .iter()
+        .any(|name| combined.contains(name))
+    {
+        ToolchainKind::Jdk
     } else if combined.contains("sdk") {
         ToolchainKind::Sdk
     } else {
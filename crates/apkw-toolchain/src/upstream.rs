@@ -13,6 +13,8 @@ use crate::catalog::{
 const GITHUB_API_BASE: &str = "https://api.github.com/repos";
 const SDK_REPO: &str = "HomuHomu833/android-sdk-custom";
 const NDK_REPO: &str = "HomuHomu833/android-ndk-custom";
+const JDK_REPO: &str = "adoptium/temurin21-binaries";
+const JDK_PROVIDER_ID: &str = "provider-temurin-jdk";
 
 #[derive(Clone)]
 pub(crate) struct DiscoveredRelease {
@@ -65,6 +67,7 @@ pub(crate) fn provider_repo(provider_id: &str) -> Option<&'static str> {
     match provider_id {
         "provider-android-sdk-custom" => Some(SDK_REPO),
         "provider-android-ndk-custom" => Some(NDK_REPO),
+        JDK_PROVIDER_ID => Some(JDK_REPO),
         _ => None,
     }
 }
@@ -121,7 +124,9 @@ pub(crate) fn build_release_check(
     let releases = releases
         .iter()
         .filter(|release| !release.draft)
-        .filter_map(|release| discovered_release(release, &candidates, &catalog_versions))
+        .filter_map(|release| {
+            discovered_release(release, provider_id, &candidates, &catalog_versions)
+        })
         .collect::<Vec<_>>();
     let latest_upstream_version = releases
         .first()
@@ -175,12 +180,13 @@ pub(crate) fn matching_release_artifact_any_host(
 
 fn discovered_release(
     release: &GithubRelease,
+    provider_id: &str,
     host_candidates: &[String],
     catalog_versions: &HashSet<String>,
 ) -> Option<DiscoveredRelease> {
     let version = release_version(release);
     let in_catalog = catalog_versions.contains(&version);
-    let (host, asset, sha256) = select_host_asset(&release.assets, host_candidates)?;
+    let (host, asset, sha256) = select_host_asset(&release.assets, provider_id, host_candidates)?;
     let published_at = release.published_at.trim().to_string();
 
     Some(DiscoveredRelease {
@@ -243,12 +249,13 @@ fn release_notes(published_at: &str, in_catalog: bool) -> String {
 
 fn select_host_asset(
     assets: &[GithubAsset],
+    provider_id: &str,
     host_candidates: &[String],
 ) -> Option<(String, GithubAsset, String)> {
     for candidate in host_candidates {
         if let Some(asset) = assets
             .iter()
-            .find(|asset| asset_matches_host_candidate(&asset.name, candidate))
+            .find(|asset| asset_matches_host_candidate(provider_id, &asset.name, candidate))
         {
             if let Some(sha256) = asset_sha256(asset) {
                 return Some((candidate.clone(), asset.clone(), sha256));
@@ -258,10 +265,29 @@ fn select_host_asset(
     None
 }
 
-fn asset_matches_host_candidate(asset_name: &str, candidate: &str) -> bool {
+fn asset_matches_host_candidate(provider_id: &str, asset_name: &str, candidate: &str) -> bool {
+    if provider_id == JDK_PROVIDER_ID {
+        return temurin_asset_matches(asset_name, candidate);
+    }
     asset_name.contains(&format!("-{candidate}."))
 }
 
+/// Only Temurin archives are recognised; other distributions (Zulu, say)
+/// name theirs differently and are not offered.
+///
+/// Temurin names archives `OpenJDK21U-jdk_<arch>_<os>_hotspot_<version>.tar.gz`
+/// next to JREs, debug images and `.sha256.txt`/`.sig` sidecars; only the
+/// JDK tarball for the host is installable.
+fn temurin_asset_matches(asset_name: &str, candidate: &str) -> bool {
+    let platform = match candidate {
+        "linux-aarch64" => "aarch64_linux",
+        "aarch64-linux-musl" => "aarch64_alpine-linux",
+        "linux-x86_64" | "x86_64-linux-gnu" => "x64_linux",
+        _ => return false,
+    };
+    asset_name.contains(&format!("-jdk_{platform}_hotspot_")) && asset_name.ends_with(".tar.gz")
+}
+
 fn asset_sha256(asset: &GithubAsset) -> Option<String> {
     let digest = asset.digest.as_deref()?.trim();
     let digest = digest.strip_prefix("sha256:").unwrap_or(digest);
@@ -286,3 +312,44 @@ fn release_artifact_from_asset(asset: &GithubAsset) -> Option<CatalogArtifact> {
         transparency_log_public_key: String::new(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temurin_assets_match_only_the_host_jdk_tarball() {
+        let jdk = "OpenJDK21U-jdk_aarch64_linux_hotspot_21.0.5_11.tar.gz";
+        assert!(temurin_asset_matches(jdk, "linux-aarch64"));
+        assert!(!temurin_asset_matches(jdk, "linux-x86_64"));
+        assert!(!temurin_asset_matches(jdk, "darwin-aarch64"));
+        assert!(temurin_asset_matches(
+            "OpenJDK21U-jdk_aarch64_alpine-linux_hotspot_21.0.5_11.tar.gz",
+            "aarch64-linux-musl"
+        ));
+        assert!(temurin_asset_matches(
+            "OpenJDK21U-jdk_x64_linux_hotspot_21.0.5_11.tar.gz",
+            "x86_64-linux-gnu"
+        ));
+        for other in [
+            "OpenJDK21U-jre_aarch64_linux_hotspot_21.0.5_11.tar.gz",
+            "OpenJDK21U-debugimage_aarch64_linux_hotspot_21.0.5_11.tar.gz",
+            "OpenJDK21U-jdk_aarch64_linux_hotspot_21.0.5_11.tar.gz.sha256.txt",
+            "OpenJDK21U-jdk_aarch64_linux_hotspot_21.0.5_11.tar.gz.sig",
+            "OpenJDK21U-jdk_aarch64_linux_hotspot_21.0.5_11.tar.gz.json",
+            "zulu21.38.21-ca-jdk21.0.5-linux_aarch64.tar.gz",
+        ] {
+            assert!(!temurin_asset_matches(other, "linux-aarch64"), "{other}");
+        }
+        assert!(asset_matches_host_candidate(
+            JDK_PROVIDER_ID,
+            jdk,
+            "linux-aarch64"
+        ));
+        assert!(!asset_matches_host_candidate(
+            JDK_PROVIDER_ID,
+            "jdk-linux-aarch64.tar.gz",
+            "linux-aarch64"
+        ));
+    }
+}
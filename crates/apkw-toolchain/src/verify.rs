@@ -233,6 +233,7 @@ pub(crate) fn validate_toolchain_layout(kind: ToolchainKind, root: &Path) -> Res
     match kind {
         ToolchainKind::Sdk => validate_sdk_layout(root),
         ToolchainKind::Ndk => validate_ndk_layout(root),
+        ToolchainKind::Jdk => validate_jdk_layout(root),
         _ => Ok(()),
     }
 }
@@ -496,3 +497,45 @@ fn validate_ndk_layout(root: &Path) -> Result<(), String> {
 
     Ok(())
 }
+
+fn validate_jdk_layout(root: &Path) -> Result<(), String> {
+    for tool in ["java", "javac"] {
+        let bin = root.join("bin").join(tool);
+        let bin_alt = root.join("bin").join(format!("{tool}.exe"));
+        if !bin.exists() && !bin_alt.exists() {
+            return Err(format!("missing bin/{tool}"));
+        }
+    }
+
+    if !root.join("release").exists() {
+        return Err("missing release file".into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jdk_layouts_need_java_javac_and_release() {
+        let root =
+            std::env::temp_dir().join(format!("apkw-toolchain-jdk-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join("bin")).unwrap();
+        assert_eq!(validate_jdk_layout(&root).unwrap_err(), "missing bin/java");
+
+        fs::write(root.join("bin").join("java"), b"").unwrap();
+        assert_eq!(validate_jdk_layout(&root).unwrap_err(), "missing bin/javac");
+
+        fs::write(root.join("bin").join("javac.exe"), b"").unwrap();
+        assert_eq!(
+            validate_jdk_layout(&root).unwrap_err(),
+            "missing release file"
+        );
+
+        fs::write(root.join("release"), b"JAVA_VERSION=\"21.0.5\"\n").unwrap();
+        assert!(validate_jdk_layout(&root).is_ok());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -3,7 +3,7 @@ use apkw_proto::apkw::v1::{
 };
 
 use crate::config::AppConfig;
-use crate::models::{ProjectTemplateOption, TargetOption, ToolchainSetOption};
+use crate::models::{JobTypeOption, ProjectTemplateOption, TargetOption, ToolchainSetOption};
 
 #[derive(Debug)]
 pub(crate) enum UiCommand {
@@ -37,6 +37,15 @@ pub(crate) enum UiCommand {
         page_token: String,
         page: &'static str,
     },
+    JobsSearchLogs {
+        cfg: AppConfig,
+        query: String,
+        job_types: String,
+        states: String,
+        created_after: String,
+        created_before: String,
+        correlation_id: String,
+    },
     JobsHistory {
         cfg: AppConfig,
         job_id: String,
@@ -52,6 +61,9 @@ pub(crate) enum UiCommand {
         output_path: String,
         page: &'static str,
     },
+    JobsListTypes {
+        cfg: AppConfig,
+    },
     ToolchainListProviders {
         cfg: AppConfig,
     },
@@ -279,6 +291,17 @@ pub(crate) enum UiCommand {
         application_id: String,
         activity: String,
         options: Option<WorkflowPipelineOptions>,
+        workflow: String,
+        inputs: Vec<KeyValue>,
+        target_ids: Vec<String>,
+        target_selector: String,
+        stream_history: bool,
+        plan_only: bool,
+    },
+    WorkflowResumePipeline {
+        cfg: AppConfig,
+        run_id: String,
+        inputs: Vec<KeyValue>,
         stream_history: bool,
     },
     BuildRun {
@@ -299,6 +322,10 @@ pub(crate) enum UiCommand {
         variant: BuildVariant,
         filter: ArtifactFilter,
     },
+    BuildReport {
+        cfg: AppConfig,
+        project_ref: String,
+    },
     StateSave {
         cfg: AppConfig,
         output_path: String,
@@ -357,6 +384,14 @@ pub(crate) enum AppEvent {
         provider_id: String,
         versions: Vec<String>,
     },
+    JobTypes {
+        job_types: Vec<JobTypeOption>,
+    },
+    JobFinished {
+        job_id: String,
+        title: String,
+        body: String,
+    },
     ProjectTemplates {
         templates: Vec<ProjectTemplateOption>,
     },
@@ -393,8 +428,10 @@ impl UiCommand {
             UiCommand::HomeWatchJob { .. } => "home.watch_job",
             UiCommand::HomeCancelCurrent { .. } => "home.cancel_current",
             UiCommand::JobsList { .. } => "jobs.list",
+            UiCommand::JobsSearchLogs { .. } => "jobs.search_logs",
             UiCommand::JobsHistory { .. } => "jobs.history",
             UiCommand::JobsExportLogs { .. } => "jobs.export_logs",
+            UiCommand::JobsListTypes { .. } => "jobs.list_types",
             UiCommand::ToolchainListProviders { .. } => "toolchains.list_providers",
             UiCommand::ToolchainListAvailable { .. } => "toolchains.list_available",
             UiCommand::ToolchainCheckUpstream { .. } => "toolchains.check_upstream",
@@ -433,8 +470,10 @@ impl UiCommand {
             UiCommand::ObserveExportEvidence { .. } => "observe.export_evidence",
             UiCommand::StreamRunEvents { .. } => "observe.stream_run_events",
             UiCommand::WorkflowRunPipeline { .. } => "workflow.run_pipeline",
+            UiCommand::WorkflowResumePipeline { .. } => "workflow.resume_pipeline",
             UiCommand::BuildRun { .. } => "build.run",
             UiCommand::BuildListArtifacts { .. } => "build.list_artifacts",
+            UiCommand::BuildReport { .. } => "build.report",
             UiCommand::StateSave { .. } => "state.save",
             UiCommand::StateOpen { .. } => "state.open",
             UiCommand::StateReload { .. } => "state.reload",
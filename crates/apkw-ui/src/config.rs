@@ -34,6 +34,7 @@ pub(crate) struct AppConfig {
     pub(crate) telemetry_usage_enabled: bool,
     pub(crate) telemetry_crash_enabled: bool,
     pub(crate) telemetry_install_id: String,
+    pub(crate) desktop_notifications: bool,
 }
 
 impl Default for AppConfig {
@@ -68,6 +69,7 @@ impl Default for AppConfig {
             telemetry_usage_enabled: false,
             telemetry_crash_enabled: false,
             telemetry_install_id: String::new(),
+            desktop_notifications: true,
         }
     }
 }
@@ -126,6 +128,7 @@ impl AppConfig {
                     cfg.telemetry_usage_enabled = file_cfg.telemetry_usage_enabled;
                     cfg.telemetry_crash_enabled = file_cfg.telemetry_crash_enabled;
                     cfg.telemetry_install_id = file_cfg.telemetry_install_id;
+                    cfg.desktop_notifications = file_cfg.desktop_notifications;
                 }
                 Err(err) => {
                     eprintln!("Failed to parse {}: {err}", path.display());
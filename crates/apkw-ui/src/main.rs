@@ -499,6 +499,7 @@ fn apply_ui_state(
     workflow.open_check.set_active(state.workflow.step_open);
     workflow.verify_check.set_active(state.workflow.step_verify);
     workflow.build_check.set_active(state.workflow.step_build);
+    workflow.test_check.set_active(state.workflow.step_test);
     workflow
         .install_check
         .set_active(state.workflow.step_install);
@@ -774,6 +775,7 @@ fn capture_ui_state(
     state.workflow.step_open = workflow.open_check.is_active();
     state.workflow.step_verify = workflow.verify_check.is_active();
     state.workflow.step_build = workflow.build_check.is_active();
+    state.workflow.step_test = workflow.test_check.is_active();
     state.workflow.step_install = workflow.install_check.is_active();
     state.workflow.step_launch = workflow.launch_check.is_active();
     state.workflow.step_support = workflow.support_check.is_active();
@@ -1226,8 +1228,9 @@ fn build_ui(app: &gtk::Application) {
     {
         let cfg = cfg.lock().unwrap().clone();
         cmd_tx
-            .try_send(UiCommand::ProjectListTemplates { cfg })
+            .try_send(UiCommand::ProjectListTemplates { cfg: cfg.clone() })
             .ok();
+        cmd_tx.try_send(UiCommand::JobsListTypes { cfg }).ok();
     }
 
     let stack_for_new = stack.clone();
@@ -1509,6 +1512,11 @@ fn build_ui(app: &gtk::Application) {
                 cmd_tx_for_stack
                     .try_send(UiCommand::ProjectListTemplates { cfg })
                     .ok();
+            } else if matches!(name.as_str(), "home" | "jobs") {
+                let cfg = cfg_for_stack.lock().unwrap().clone();
+                cmd_tx_for_stack
+                    .try_send(UiCommand::JobsListTypes { cfg })
+                    .ok();
             }
         }
     });
@@ -1613,6 +1621,28 @@ fn build_ui(app: &gtk::Application) {
                         targets_for_events
                             .set_cuttlefish_state(&state, &adb_serial, webrtc_url.as_deref());
                     }
+                    AppEvent::JobTypes { job_types } => {
+                        home_page_for_events.set_job_types(&job_types);
+                        jobs_for_events.set_job_types(&job_types);
+                    }
+                    AppEvent::JobFinished {
+                        job_id,
+                        title,
+                        body,
+                    } => {
+                        let enabled = cfg_for_events.lock().unwrap().desktop_notifications;
+                        // Only notify when the user is looking elsewhere.
+                        if enabled && !window_for_events.is_active() {
+                            if let Some(app) = window_for_events.application() {
+                                let notification = gtk::gio::Notification::new(&title);
+                                notification.set_body(Some(&body));
+                                app.send_notification(
+                                    Some(&format!("job-{job_id}")),
+                                    &notification,
+                                );
+                            }
+                        }
+                    }
                     AppEvent::ToolchainAvailable {
                         provider_id,
                         versions,
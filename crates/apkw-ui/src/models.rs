@@ -4,6 +4,12 @@ pub(crate) struct ProjectTemplateOption {
     pub(crate) name: String,
 }
 
+#[derive(Clone, Debug)]
+pub(crate) struct JobTypeOption {
+    pub(crate) id: String,
+    pub(crate) label: String,
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct ToolchainSetOption {
     pub(crate) id: String,
@@ -16,7 +16,9 @@ use webkit6 as webkit;
 
 use crate::commands::UiCommand;
 use crate::config::AppConfig;
-use crate::models::{ActiveContext, ProjectTemplateOption, TargetOption, ToolchainSetOption};
+use crate::models::{
+    ActiveContext, JobTypeOption, ProjectTemplateOption, TargetOption, ToolchainSetOption,
+};
 use crate::utils::{
     infer_application_id_from_apk_path, infer_application_id_from_project, parse_list_tokens,
 };
@@ -86,6 +88,19 @@ impl HomePage {
         self.page.append(s);
     }
 
+    /// Replaces the job type dropdown with the types JobService accepts,
+    /// keeping whatever is typed in the job type field.
+    pub(crate) fn set_job_types(&self, job_types: &[JobTypeOption]) {
+        let typed = self.job_type_entry.text().to_string();
+        self.job_type_combo.remove_all();
+        for job_type in job_types {
+            self.job_type_combo
+                .append(Some(job_type.id.as_str()), &job_type.label);
+        }
+        self.job_type_combo.set_active_id(Some(typed.as_str()));
+        self.job_type_entry.set_text(&typed);
+    }
+
     pub(crate) fn set_active_context(&self, ctx: &ActiveContext) {
         self.project_id_entry.set_text(ctx.project_id.trim());
         self.target_id_entry.set_text(ctx.target_id.trim());
@@ -124,6 +139,16 @@ impl JobsHistoryPage {
         self.page.append(s);
     }
 
+    pub(crate) fn set_job_types(&self, job_types: &[JobTypeOption]) {
+        self.job_type_combo.remove_all();
+        self.job_type_combo.append(Some("none"), "Add job type…");
+        for job_type in job_types {
+            self.job_type_combo
+                .append(Some(job_type.id.as_str()), &job_type.label);
+        }
+        self.job_type_combo.set_active_id(Some("none"));
+    }
+
     pub(crate) fn clear(&self) {
         self.page.clear();
     }
@@ -242,6 +267,7 @@ pub(crate) struct WorkflowPage {
     pub(crate) open_check: gtk::CheckButton,
     pub(crate) verify_check: gtk::CheckButton,
     pub(crate) build_check: gtk::CheckButton,
+    pub(crate) test_check: gtk::CheckButton,
     pub(crate) install_check: gtk::CheckButton,
     pub(crate) launch_check: gtk::CheckButton,
     pub(crate) support_check: gtk::CheckButton,
@@ -260,6 +286,7 @@ pub(crate) struct SettingsPage {
     workflow_entry: gtk::Entry,
     usage_check: gtk::CheckButton,
     crash_check: gtk::CheckButton,
+    notify_check: gtk::CheckButton,
     install_label: gtk::Label,
     pub(crate) exclude_downloads: gtk::CheckButton,
     pub(crate) exclude_toolchains: gtk::CheckButton,
@@ -273,6 +300,7 @@ pub(crate) struct SettingsPage {
 pub(crate) struct JobsHistoryPage {
     pub(crate) page: Page,
     pub(crate) job_types_entry: gtk::Entry,
+    pub(crate) job_type_combo: gtk::ComboBoxText,
     pub(crate) states_entry: gtk::Entry,
     pub(crate) created_after_entry: gtk::Entry,
     pub(crate) created_before_entry: gtk::Entry,
@@ -606,6 +634,7 @@ impl SettingsPage {
         self.workflow_entry.set_text(cfg.workflow_addr.trim());
         self.usage_check.set_active(cfg.telemetry_usage_enabled);
         self.crash_check.set_active(cfg.telemetry_crash_enabled);
+        self.notify_check.set_active(cfg.desktop_notifications);
         self.install_label
             .set_text(&telemetry_label_text(&cfg.telemetry_install_id));
     }
@@ -1106,8 +1135,12 @@ const KNOWN_JOB_TYPES: &[&str] = &[
     "workflow.pipeline",
     "project.create",
     "build.run",
+    "build.test",
     "toolchain.install",
     "toolchain.verify",
+    "toolchain.update",
+    "toolchain.uninstall",
+    "toolchain.cleanup_cache",
     "targets.install",
     "targets.launch",
     "targets.stop",
@@ -1152,7 +1185,7 @@ pub(crate) fn page_home(
         job_type_combo.append(Some(job_type), job_type);
     }
     set_tooltip(&job_type_entry, "What: Job type string sent to JobService (for example build.run or workflow.pipeline). Why: JobService uses this to route the request to the right service. How: type a known job type or pick one from the dropdown to fill this field.");
-    set_tooltip(&job_type_combo, "What: Known job types this UI is aware of. Why: helps avoid typos and discover common workflows. How: select one to copy it into the Job type field.");
+    set_tooltip(&job_type_combo, "What: Job types registered with JobService. Why: helps avoid typos and discover common workflows. How: select one to copy it into the Job type field.");
 
     let params_label = gtk::Label::builder()
         .label("Params (key=value per line)")
@@ -1278,8 +1311,8 @@ pub(crate) fn page_home(
             job_type_combo.set_active_id(Some(&cfg.last_job_type));
         } else {
             job_type_combo.set_active(Some(0));
-            if let Some(text) = job_type_combo.active_text() {
-                job_type_entry.set_text(&text);
+            if let Some(id) = job_type_combo.active_id() {
+                job_type_entry.set_text(&id);
             }
         }
         if !cfg.last_job_params.is_empty() {
@@ -1304,8 +1337,8 @@ pub(crate) fn page_home(
 
     let job_type_entry_select = job_type_entry.clone();
     job_type_combo.connect_changed(move |combo| {
-        if let Some(text) = combo.active_text() {
-            job_type_entry_select.set_text(&text);
+        if let Some(id) = combo.active_id() {
+            job_type_entry_select.set_text(&id);
         }
     });
 
@@ -1584,6 +1617,7 @@ pub(crate) fn page_workflow(
     let open_check = gtk::CheckButton::with_label("Open project");
     let verify_check = gtk::CheckButton::with_label("Verify toolchain");
     let build_check = gtk::CheckButton::with_label("Build");
+    let test_check = gtk::CheckButton::with_label("Run tests");
     let install_check = gtk::CheckButton::with_label("Install APK");
     let launch_check = gtk::CheckButton::with_label("Launch app");
     let support_check = gtk::CheckButton::with_label("Export support bundle");
@@ -1596,6 +1630,7 @@ pub(crate) fn page_workflow(
         &build_check,
         "What: Run build.run. Why: produce APKs/AABs. How: enable when you have a project id/path.",
     );
+    set_tooltip(&test_check, "What: Run build.test after the build. Why: fail the pipeline on unit test failures. How: enable with a project id/path; results are attached to the run.");
     set_tooltip(&install_check, "What: Run targets.install. Why: install APK on a device. How: enable with target id and apk path.");
     set_tooltip(&launch_check, "What: Run targets.launch. Why: start the app on the target. How: enable with target id and application id.");
     set_tooltip(&support_check, "What: Run observe.support_bundle. Why: export a support bundle after the run. How: enable to capture logs and config.");
@@ -1614,17 +1649,59 @@ pub(crate) fn page_workflow(
     steps_grid.attach(&launch_check, 1, 1, 1, 1);
     steps_grid.attach(&support_check, 2, 1, 1, 1);
     steps_grid.attach(&evidence_check, 3, 1, 1, 1);
+    steps_grid.attach(&test_check, 0, 2, 1, 1);
     steps_box.append(&steps_grid);
+
+    let workflow_entry = gtk::Entry::builder()
+        .placeholder_text("workflow name (optional)")
+        .hexpand(true)
+        .build();
+    let workflow_inputs_entry = gtk::Entry::builder()
+        .placeholder_text("inputs (KEY=VALUE, comma/space separated)")
+        .hexpand(true)
+        .build();
+    set_tooltip(&workflow_entry, "What: Run a workflow from .apkw/workflows in the project. Why: reuse a declarative pipeline. How: enter its file name without extension; the step toggles above are ignored.");
+    set_tooltip(&workflow_inputs_entry, "What: Workflow input values. Why: override the defaults the workflow declares. How: enter KEY=VALUE pairs.");
+    let workflow_grid = gtk::Grid::builder()
+        .row_spacing(ROW_SPACING)
+        .column_spacing(COL_SPACING)
+        .build();
+    workflow_grid.attach(&gtk::Label::new(Some("Workflow")), 0, 0, 1, 1);
+    workflow_grid.attach(&workflow_entry, 1, 0, 1, 1);
+    workflow_grid.attach(&gtk::Label::new(Some("Inputs")), 0, 1, 1, 1);
+    workflow_grid.attach(&workflow_inputs_entry, 1, 1, 1, 1);
+
+    let target_ids_entry = gtk::Entry::builder()
+        .placeholder_text("more target ids (comma/space separated)")
+        .hexpand(true)
+        .build();
+    let target_selector_entry = gtk::Entry::builder()
+        .placeholder_text("target selector (e.g. provider=adb state=device, or *)")
+        .hexpand(true)
+        .build();
+    set_tooltip(&target_ids_entry, "What: Extra targets for install/launch. Why: validate one build on several devices. How: list target ids; steps run on each in parallel with the Target id above.");
+    set_tooltip(&target_selector_entry, "What: Add every online target matching a selector. Why: fan out without typing ids. How: key=value terms (kind, provider, state, api_level) that must all match, or * for all.");
+    workflow_grid.attach(&gtk::Label::new(Some("Targets")), 0, 2, 1, 1);
+    workflow_grid.attach(&target_ids_entry, 1, 2, 1, 1);
+    workflow_grid.attach(&gtk::Label::new(Some("Target selector")), 0, 3, 1, 1);
+    workflow_grid.attach(&target_selector_entry, 1, 3, 1, 1);
+    steps_box.append(&workflow_grid);
     let steps_frame = section_frame("Pipeline steps", &steps_box);
     sections.append(&steps_frame);
 
     let action_row = gtk::Box::new(gtk::Orientation::Horizontal, ROW_SPACING);
     let run_btn = gtk::Button::with_label("Run pipeline");
+    let plan_btn = gtk::Button::with_label("Plan pipeline");
+    let resume_btn = gtk::Button::with_label("Resume run");
     let stream_btn = gtk::Button::with_label("Stream run events");
     style_primary_button(&run_btn);
     set_tooltip(&run_btn, "What: Start the workflow pipeline. Why: orchestrate multi-service steps in order. How: fill inputs and click.");
     set_tooltip(&stream_btn, "What: Stream run-level events. Why: watch pipeline progress across jobs. How: enter run id or correlation id and click.");
+    set_tooltip(&resume_btn, "What: Resume a failed pipeline run. Why: rerun only the failed and remaining steps, reusing earlier outputs such as the built APK. How: enter the run id (and optional input overrides) and click.");
+    set_tooltip(&plan_btn, "What: Show what Run pipeline would do. Why: inferred steps and resolved ids are hard to predict. How: fill inputs and click; the ordered steps, resolved inputs and validation errors are logged without running anything.");
     action_row.append(&run_btn);
+    action_row.append(&plan_btn);
+    action_row.append(&resume_btn);
     action_row.append(&stream_btn);
     let action_frame = section_frame("Actions", &action_row);
     sections.append(&action_frame);
@@ -1719,11 +1796,16 @@ pub(crate) fn page_workflow(
     let open_check_run = open_check.clone();
     let verify_check_run = verify_check.clone();
     let build_check_run = build_check.clone();
+    let test_check_run = test_check.clone();
     let install_check_run = install_check.clone();
     let launch_check_run = launch_check.clone();
     let support_check_run = support_check.clone();
     let evidence_check_run = evidence_check.clone();
-    run_btn.connect_clicked(move |_| {
+    let workflow_entry_run = workflow_entry.clone();
+    let workflow_inputs_entry_run = workflow_inputs_entry.clone();
+    let target_ids_entry_run = target_ids_entry.clone();
+    let target_selector_entry_run = target_selector_entry.clone();
+    let queue_pipeline = Rc::new(move |plan_only: bool| {
         let job_id_raw = job_id_entry_run.text().to_string();
         let correlation_id = correlation_id_entry_run.text().to_string();
         let job_id = if use_job_id_run.is_active() && !job_id_raw.trim().is_empty() {
@@ -1778,12 +1860,25 @@ pub(crate) fn page_workflow(
                 create_project: create_check_run.is_active(),
                 open_project: open_check_run.is_active(),
                 build: build_check_run.is_active(),
+                run_tests: test_check_run.is_active(),
                 install_apk: install_check_run.is_active(),
                 launch_app: launch_check_run.is_active(),
                 export_support_bundle: support_check_run.is_active(),
                 export_evidence_bundle: evidence_check_run.is_active(),
+                support_bundle_on_failure: false,
             })
         };
+        let workflow = workflow_entry_run.text().trim().to_string();
+        let inputs = parse_list_tokens(&workflow_inputs_entry_run.text())
+            .into_iter()
+            .filter_map(|token| {
+                let (key, value) = token.split_once('=')?;
+                Some(KeyValue {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                })
+            })
+            .collect();
 
         cmd_tx_run
             .try_send(UiCommand::WorkflowRunPipeline {
@@ -1806,7 +1901,42 @@ pub(crate) fn page_workflow(
                 application_id,
                 activity,
                 options,
+                workflow,
+                inputs,
+                target_ids: parse_list_tokens(&target_ids_entry_run.text()),
+                target_selector: target_selector_entry_run.text().trim().to_string(),
                 stream_history: include_history_run.is_active(),
+                plan_only,
+            })
+            .ok();
+    });
+    let queue_pipeline_run = queue_pipeline.clone();
+    run_btn.connect_clicked(move |_| queue_pipeline_run(false));
+    plan_btn.connect_clicked(move |_| queue_pipeline(true));
+
+    let cfg_resume = cfg.clone();
+    let cmd_tx_resume = cmd_tx.clone();
+    let run_id_entry_resume = run_id_entry.clone();
+    let workflow_inputs_entry_resume = workflow_inputs_entry.clone();
+    let include_history_resume = include_history_check.clone();
+    resume_btn.connect_clicked(move |_| {
+        let cfg = cfg_resume.lock().unwrap().clone();
+        let inputs = parse_list_tokens(&workflow_inputs_entry_resume.text())
+            .into_iter()
+            .filter_map(|token| {
+                let (key, value) = token.split_once('=')?;
+                Some(KeyValue {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                })
+            })
+            .collect();
+        cmd_tx_resume
+            .try_send(UiCommand::WorkflowResumePipeline {
+                cfg,
+                run_id: run_id_entry_resume.text().to_string(),
+                inputs,
+                stream_history: include_history_resume.is_active(),
             })
             .ok();
     });
@@ -1855,6 +1985,7 @@ pub(crate) fn page_workflow(
         open_check,
         verify_check,
         build_check,
+        test_check,
         install_check,
         launch_check,
         support_check,
@@ -1882,6 +2013,12 @@ pub(crate) fn page_jobs_history(
         .placeholder_text("job types (comma/space)")
         .hexpand(true)
         .build();
+    let job_type_combo = gtk::ComboBoxText::new();
+    job_type_combo.append(Some("none"), "Add job type…");
+    for job_type in KNOWN_JOB_TYPES {
+        job_type_combo.append(Some(job_type), job_type);
+    }
+    job_type_combo.set_active_id(Some("none"));
     let states_entry = gtk::Entry::builder()
         .placeholder_text("states (queued,running,success,failed,cancelled)")
         .hexpand(true)
@@ -1914,6 +2051,7 @@ pub(crate) fn page_jobs_history(
 
     let list_btn = gtk::Button::with_label("List jobs");
     set_tooltip(&job_types_entry, "What: Comma/space list of job types to include. Why: narrows the job list. How: enter values like build.run workflow.pipeline.");
+    set_tooltip(&job_type_combo, "What: Job types registered with JobService. Why: avoids typos in the job type filter. How: select one to add it to the Job types field.");
     set_tooltip(&states_entry, "What: Job state filter. Why: focus on failures or running jobs. How: use queued,running,success,failed,cancelled (case-insensitive).");
     set_tooltip(&created_after_entry, "What: Lower bound for job creation time in unix millis. Why: limit results to recent jobs. How: paste an epoch millis value.");
     set_tooltip(&created_before_entry, "What: Upper bound for job creation time in unix millis. Why: limit results to older jobs. How: paste an epoch millis value.");
@@ -1929,6 +2067,7 @@ pub(crate) fn page_jobs_history(
 
     list_grid.attach(&gtk::Label::new(Some("Job types")), 0, 0, 1, 1);
     list_grid.attach(&job_types_entry, 1, 0, 1, 1);
+    list_grid.attach(&job_type_combo, 2, 0, 1, 1);
     list_grid.attach(&gtk::Label::new(Some("States")), 0, 1, 1, 1);
     list_grid.attach(&states_entry, 1, 1, 1, 1);
     list_grid.attach(&gtk::Label::new(Some("Created after")), 0, 2, 1, 1);
@@ -1948,6 +2087,21 @@ pub(crate) fn page_jobs_history(
     list_grid.attach(&list_btn, 1, 9, 1, 1);
 
     let list_frame = section_frame("List jobs", &list_grid);
+    let search_grid = gtk::Grid::builder()
+        .row_spacing(ROW_SPACING)
+        .column_spacing(COL_SPACING)
+        .build();
+    let search_entry = gtk::Entry::builder()
+        .placeholder_text("text to find, e.g. INSTALL_FAILED_UPDATE_INCOMPATIBLE")
+        .hexpand(true)
+        .build();
+    let search_btn = gtk::Button::with_label("Search logs");
+    set_tooltip(&search_entry, "What: Text to find in job logs, failure messages and summaries. Why: locate which job printed an error. How: type part of a log line (case-insensitive) and press Enter.");
+    set_tooltip(&search_btn, "What: Search job logs. Why: find jobs by their output. How: uses the job types, states, created and correlation filters above; results show the history offset of each match.");
+    search_grid.attach(&gtk::Label::new(Some("Search")), 0, 0, 1, 1);
+    search_grid.attach(&search_entry, 1, 0, 1, 1);
+    search_grid.attach(&search_btn, 2, 0, 1, 1);
+    let search_frame = section_frame("Search logs", &search_grid);
     let history_grid = gtk::Grid::builder()
         .row_spacing(ROW_SPACING)
         .column_spacing(COL_SPACING)
@@ -2010,6 +2164,7 @@ pub(crate) fn page_jobs_history(
     let history_frame = section_frame("Job history", &history_grid);
 
     sections.append(&list_frame);
+    sections.append(&search_frame);
     sections.append(&history_frame);
 
     {
@@ -2025,6 +2180,26 @@ pub(crate) fn page_jobs_history(
         }
     }
 
+    let job_types_entry_add = job_types_entry.clone();
+    job_type_combo.connect_changed(move |combo| {
+        let Some(id) = combo.active_id().filter(|id| id.as_str() != "none") else {
+            return;
+        };
+        let current = job_types_entry_add.text().to_string();
+        let present = current
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .any(|token| token == id.as_str());
+        if !present {
+            let next = if current.trim().is_empty() {
+                id.to_string()
+            } else {
+                format!("{} {id}", current.trim_end())
+            };
+            job_types_entry_add.set_text(&next);
+        }
+        combo.set_active_id(Some("none"));
+    });
+
     let cfg_list = cfg.clone();
     let cmd_tx_list = cmd_tx.clone();
     let job_types_entry_list = job_types_entry.clone();
@@ -2057,6 +2232,32 @@ pub(crate) fn page_jobs_history(
             .ok();
     });
 
+    let cfg_search = cfg.clone();
+    let cmd_tx_search = cmd_tx.clone();
+    let search_entry_search = search_entry.clone();
+    let job_types_entry_search = job_types_entry.clone();
+    let states_entry_search = states_entry.clone();
+    let created_after_entry_search = created_after_entry.clone();
+    let created_before_entry_search = created_before_entry.clone();
+    let correlation_id_entry_search = correlation_id_entry.clone();
+    let run_search = move || {
+        let cfg = cfg_search.lock().unwrap().clone();
+        cmd_tx_search
+            .try_send(UiCommand::JobsSearchLogs {
+                cfg,
+                query: search_entry_search.text().to_string(),
+                job_types: job_types_entry_search.text().to_string(),
+                states: states_entry_search.text().to_string(),
+                created_after: created_after_entry_search.text().to_string(),
+                created_before: created_before_entry_search.text().to_string(),
+                correlation_id: correlation_id_entry_search.text().to_string(),
+            })
+            .ok();
+    };
+    let run_search_btn = run_search.clone();
+    search_btn.connect_clicked(move |_| run_search_btn());
+    search_entry.connect_activate(move |_| run_search());
+
     let cfg_history = cfg.clone();
     let cmd_tx_history = cmd_tx.clone();
     let job_id_entry_history = job_id_entry.clone();
@@ -2119,6 +2320,7 @@ pub(crate) fn page_jobs_history(
     JobsHistoryPage {
         page,
         job_types_entry,
+        job_type_combo,
         states_entry,
         created_after_entry,
         created_before_entry,
@@ -4016,12 +4218,14 @@ pub(crate) fn page_console(
         .build();
 
     let list_artifacts = gtk::Button::with_label("List artifacts");
+    let build_report = gtk::Button::with_label("Build report");
     set_tooltip(&artifact_modules_entry, "What: Module filter list. Why: narrow artifact listing to specific modules. How: enter comma/space separated module names.");
     set_tooltip(&artifact_variant_entry, "What: Variant filter override. Why: list artifacts for a specific variant; overrides the dropdown if set. How: enter a variant name or leave blank.");
     set_tooltip(&artifact_types_entry, "What: Artifact types filter. Why: limit results to apk/aab/aar/mapping/test. How: enter a comma/space list of types.");
     set_tooltip(&artifact_name_entry, "What: Substring to match artifact name. Why: filter results by name. How: enter a partial name.");
     set_tooltip(&artifact_path_entry, "What: Substring to match artifact path. Why: filter results by path. How: enter a partial path.");
     set_tooltip(&list_artifacts, "What: List artifacts for the project. Why: find build outputs to install or share. How: set filters and click.");
+    set_tooltip(&build_report, "What: Show the latest build's task report. Why: see the slowest tasks and how many were UP-TO-DATE or FROM-CACHE. How: set the project and click after a build.");
 
     let label_artifact_modules = gtk::Label::builder().label("Modules").xalign(0.0).build();
     let label_artifact_variant = gtk::Label::builder().label("Variant").xalign(0.0).build();
@@ -4048,6 +4252,7 @@ pub(crate) fn page_console(
 
     let list_row = gtk::Box::new(gtk::Orientation::Horizontal, ROW_SPACING);
     list_row.append(&list_artifacts);
+    list_row.append(&build_report);
     artifact_form.attach(&list_row, 1, 5, 1, 1);
     let artifacts_box = gtk::Box::new(gtk::Orientation::Vertical, ROW_SPACING);
     artifacts_box.append(&artifacts_label);
@@ -4187,6 +4392,17 @@ pub(crate) fn page_console(
             .ok();
     });
 
+    let cfg_report = cfg.clone();
+    let cmd_tx_report = cmd_tx.clone();
+    let project_entry_report = project_entry.clone();
+    build_report.connect_clicked(move |_| {
+        let cfg = cfg_report.lock().unwrap().clone();
+        let project_ref = project_entry_report.text().to_string();
+        cmd_tx_report
+            .try_send(UiCommand::BuildReport { cfg, project_ref })
+            .ok();
+    });
+
     BuildPage {
         page,
         project_entry,
@@ -4954,6 +5170,25 @@ pub(crate) fn page_settings(
     let state_frame = section_frame("State archives", &state_box);
     sections.append(&state_frame);
 
+    let notify_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    let notify_check = gtk::CheckButton::with_label("Notify when a watched job finishes");
+    set_tooltip(
+        &notify_check,
+        "What: Show a desktop notification when a job started from the UI finishes. Why: long builds and installs can run in the background. How: only shown while the window is not focused.",
+    );
+    notify_check.set_active(cfg.lock().unwrap().desktop_notifications);
+    let cfg_notify = cfg.clone();
+    notify_check.connect_toggled(move |check| {
+        let mut cfg = cfg_notify.lock().unwrap();
+        cfg.desktop_notifications = check.is_active();
+        if let Err(err) = cfg.save() {
+            eprintln!("Failed to persist UI config: {err}");
+        }
+    });
+    notify_box.append(&notify_check);
+    let notify_frame = section_frame("Notifications", &notify_box);
+    sections.append(&notify_frame);
+
     let telemetry_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
     let usage_check = gtk::CheckButton::with_label("Write usage analytics");
     let crash_check = gtk::CheckButton::with_label("Write crash reports");
@@ -5078,6 +5313,7 @@ pub(crate) fn page_settings(
         workflow_entry,
         usage_check,
         crash_check,
+        notify_check,
         install_label,
         exclude_downloads,
         exclude_toolchains,
@@ -62,6 +62,7 @@ pub(crate) struct WorkflowState {
     pub(crate) step_open: bool,
     pub(crate) step_verify: bool,
     pub(crate) step_build: bool,
+    pub(crate) step_test: bool,
     pub(crate) step_install: bool,
     pub(crate) step_launch: bool,
     pub(crate) step_support: bool,
@@ -240,6 +241,7 @@ impl Default for WorkflowState {
             step_open: false,
             step_verify: false,
             step_build: false,
+            step_test: false,
             step_install: false,
             step_launch: false,
             step_support: false,
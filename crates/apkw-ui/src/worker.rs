@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     fs, io,
     path::{Path, PathBuf},
     time::{Duration, Instant},
@@ -13,17 +13,19 @@ use apkw_proto::apkw::v1::{
     workflow_service_client::WorkflowServiceClient, Artifact, ArtifactType, BuildRequest,
     CancelJobRequest, CheckUpstreamReleasesRequest, CleanupToolchainCacheRequest,
     CreateProjectRequest, CreateToolchainSetRequest, ExportEvidenceBundleRequest,
-    ExportSupportBundleRequest, GetActiveToolchainSetRequest, GetCuttlefishStatusRequest,
-    GetDefaultTargetRequest, GetJobRequest, Id, InstallApkRequest, InstallCuttlefishRequest,
-    InstallToolchainRequest, InstalledToolchain, Job, JobEvent, JobEventKind, JobFilter,
-    JobHistoryFilter, JobState, KeyValue, LaunchRequest, ListArtifactsRequest,
-    ListAvailableRequest, ListInstalledRequest, ListJobHistoryRequest, ListJobsRequest,
-    ListProvidersRequest, ListRecentProjectsRequest, ListRunOutputsRequest, ListRunsRequest,
-    ListTargetsRequest, ListTemplatesRequest, ListToolchainSetsRequest, OpenProjectRequest,
-    Pagination, ReloadStateRequest, ResolveCuttlefishBuildRequest, RunFilter, RunId,
-    RunOutputFilter, RunOutputKind, SetActiveToolchainSetRequest, SetDefaultTargetRequest,
-    SetProjectConfigRequest, StartCuttlefishRequest, StartJobRequest, StopCuttlefishRequest,
-    StreamJobEventsRequest, StreamLogcatRequest, StreamRunEventsRequest, Timestamp, ToolchainKind,
+    ExportSupportBundleRequest, GetActiveToolchainSetRequest, GetBuildReportRequest,
+    GetCuttlefishStatusRequest, GetDefaultTargetRequest, GetJobRequest, Id, InstallApkRequest,
+    InstallCuttlefishRequest, InstallToolchainRequest, InstalledToolchain, Job, JobEvent,
+    JobEventCursor, JobEventKind, JobFilter, JobHistoryFilter, JobPriority, JobState, KeyValue,
+    LaunchRequest, ListArtifactsRequest, ListAvailableRequest, ListInstalledRequest,
+    ListJobHistoryRequest, ListJobTypesRequest, ListJobsRequest, ListProvidersRequest,
+    ListRecentProjectsRequest, ListRunOutputsRequest, ListRunsRequest, ListTargetsRequest,
+    ListTemplatesRequest, ListToolchainSetsRequest, OpenProjectRequest, Pagination,
+    PlanPipelineResponse, ReloadStateRequest, ResolveCuttlefishBuildRequest, ResumePipelineRequest,
+    RunFilter, RunId, RunOutputFilter, RunOutputKind, SearchJobLogsRequest,
+    SetActiveToolchainSetRequest, SetDefaultTargetRequest, SetProjectConfigRequest,
+    StartCuttlefishRequest, StartJobRequest, StopCuttlefishRequest, StreamJobEventsRequest,
+    StreamLogcatRequest, StreamRunEventsRequest, TaskOutcome, Timestamp, ToolchainKind,
     UninstallToolchainRequest, UpdateToolchainRequest, VerifyToolchainRequest,
     WorkflowPipelineRequest,
 };
@@ -37,7 +39,7 @@ use tonic::transport::Channel;
 
 use crate::commands::{AppEvent, UiCommand};
 use crate::config::{write_json_atomic, AppConfig};
-use crate::models::{ProjectTemplateOption, TargetOption, ToolchainSetOption};
+use crate::models::{JobTypeOption, ProjectTemplateOption, TargetOption, ToolchainSetOption};
 use crate::pages::{PROVIDER_NDK_ID, PROVIDER_SDK_ID};
 use crate::ui_events::UiEventSender;
 use crate::utils::{infer_application_id_from_apk_path, parse_list_tokens};
@@ -134,6 +136,70 @@ fn kv_pairs(items: &[KeyValue]) -> String {
         .join(", ")
 }
 
+fn format_pipeline_plan(plan: &PlanPipelineResponse) -> String {
+    let mut out = format!("Plan for workflow {}:\n", plan.workflow);
+    for value in &plan.resolved {
+        out.push_str(&format!(
+            "  {}={} ({})\n",
+            value.key, value.value, value.source
+        ));
+    }
+    if !plan.targets.is_empty() {
+        let targets: Vec<&str> = plan.targets.iter().map(|id| id.value.as_str()).collect();
+        out.push_str(&format!("  targets={}\n", targets.join(",")));
+    }
+    for step in &plan.steps {
+        let target = step
+            .target_id
+            .as_ref()
+            .map(|id| format!(" @{}", id.value))
+            .unwrap_or_default();
+        let status = if step.skipped {
+            " [skipped]"
+        } else if !step.missing_inputs.is_empty() {
+            " [missing inputs]"
+        } else {
+            ""
+        };
+        let on_failure = if step.on_failure { " (on_failure)" } else { "" };
+        out.push_str(&format!(
+            "  wave {} step {}{target}: {}{on_failure}{status}\n",
+            step.wave, step.id, step.action
+        ));
+        if !step.condition.is_empty() {
+            out.push_str(&format!("    if: {}\n", step.condition));
+        }
+        if !step.skipped_reason.is_empty() {
+            out.push_str(&format!("    reason: {}\n", step.skipped_reason));
+        }
+        let inputs: Vec<KeyValue> = step
+            .inputs
+            .iter()
+            .filter(|kv| !kv.value.is_empty())
+            .cloned()
+            .collect();
+        if !inputs.is_empty() {
+            out.push_str(&format!("    {}\n", kv_pairs(&inputs)));
+        }
+        if !step.missing_inputs.is_empty() {
+            out.push_str(&format!(
+                "    missing: {}\n",
+                step.missing_inputs.join(", ")
+            ));
+        }
+    }
+    for warning in &plan.warnings {
+        out.push_str(&format!("  warning: {warning}\n"));
+    }
+    for error in &plan.errors {
+        out.push_str(&format!("  error: {error}\n"));
+    }
+    if plan.errors.is_empty() {
+        out.push_str("  plan is valid\n");
+    }
+    out
+}
+
 fn parse_job_states(raw: &str) -> (Vec<i32>, Vec<String>) {
     let mut states = Vec::new();
     let mut unknown = Vec::new();
@@ -330,6 +396,11 @@ impl LogExportEvent {
                     .as_ref()
                     .map(|err| format!("{} ({})", err.message, err.code))
                     .unwrap_or_else(|| "failed".into());
+                let summary = if failed.will_retry {
+                    format!("attempt {} failed, retrying: {summary}", event.attempt)
+                } else {
+                    summary
+                };
                 Self {
                     at_unix_millis,
                     kind: "failed".into(),
@@ -762,6 +833,12 @@ pub(crate) async fn handle_command(
                     toolchain_set_id: to_optional_id(&toolchain_set_id),
                     correlation_id: correlation_id.trim().to_string(),
                     run_id: run_id_from_optional(&correlation_id),
+                    depends_on: Vec::new(),
+                    concurrency_keys: Vec::new(),
+                    timeout_ms: 0,
+                    retry_policy: None,
+                    priority: JobPriority::Interactive as i32,
+                    preemptible: false,
                 })
                 .await?
                 .into_inner();
@@ -856,6 +933,27 @@ pub(crate) async fn handle_command(
             }
         }
 
+        UiCommand::JobsListTypes { cfg } => {
+            let mut client = JobServiceClient::new(connect(&cfg.job_addr).await?);
+            let resp = client
+                .list_job_types(ListJobTypesRequest {})
+                .await?
+                .into_inner();
+            let job_types = resp
+                .job_types
+                .into_iter()
+                .map(|info| JobTypeOption {
+                    label: if info.display_name.is_empty() || info.display_name == info.job_type {
+                        info.job_type.clone()
+                    } else {
+                        format!("{} ({})", info.display_name, info.job_type)
+                    },
+                    id: info.job_type,
+                })
+                .collect();
+            ui.send(AppEvent::JobTypes { job_types }).ok();
+        }
+
         UiCommand::JobsList {
             cfg,
             job_types,
@@ -1020,6 +1118,125 @@ pub(crate) async fn handle_command(
             }
         }
 
+        UiCommand::JobsSearchLogs {
+            cfg,
+            query,
+            job_types,
+            states,
+            created_after,
+            created_before,
+            correlation_id,
+        } => {
+            let page = "jobs";
+            if query.trim().is_empty() {
+                ui.send(AppEvent::Log {
+                    page,
+                    line: "Enter text to search for.\n".into(),
+                })
+                .ok();
+                return Ok(());
+            }
+            let (state_filters, unknown_states) = parse_job_states(&states);
+            if !unknown_states.is_empty() {
+                ui.send(AppEvent::Log {
+                    page,
+                    line: format!("Unknown states: {}\n", unknown_states.join(", ")),
+                })
+                .ok();
+            }
+            let created_after = match parse_optional_millis(&created_after) {
+                Ok(value) => value,
+                Err(err) => {
+                    ui.send(AppEvent::Log {
+                        page,
+                        line: format!("{err}\n"),
+                    })
+                    .ok();
+                    return Ok(());
+                }
+            };
+            let created_before = match parse_optional_millis(&created_before) {
+                Ok(value) => value,
+                Err(err) => {
+                    ui.send(AppEvent::Log {
+                        page,
+                        line: format!("{err}\n"),
+                    })
+                    .ok();
+                    return Ok(());
+                }
+            };
+            let filter = JobFilter {
+                job_types: parse_list_tokens(&job_types),
+                states: state_filters,
+                created_after: created_after.map(|ms| Timestamp { unix_millis: ms }),
+                created_before: created_before.map(|ms| Timestamp { unix_millis: ms }),
+                finished_after: None,
+                finished_before: None,
+                correlation_id: correlation_id.trim().to_string(),
+                run_id: None,
+            };
+
+            let mut client = JobServiceClient::new(connect(&cfg.job_addr).await?);
+            let resp = client
+                .search_job_logs(SearchJobLogsRequest {
+                    query: query.clone(),
+                    filter: Some(filter),
+                    page: Some(Pagination {
+                        page_size: 20,
+                        page_token: String::new(),
+                    }),
+                    max_matches_per_job: 5,
+                })
+                .await?
+                .into_inner();
+
+            if resp.results.is_empty() {
+                ui.send(AppEvent::Log {
+                    page,
+                    line: format!("No log lines match \"{}\".\n", query.trim()),
+                })
+                .ok();
+                return Ok(());
+            }
+            ui.send(AppEvent::Log {
+                page,
+                line: format!(
+                    "Jobs matching \"{}\" ({})\n",
+                    query.trim(),
+                    resp.results.len()
+                ),
+            })
+            .ok();
+            for result in &resp.results {
+                if let Some(job) = result.job.as_ref() {
+                    ui.send(AppEvent::Log {
+                        page,
+                        line: format_job_row(job),
+                    })
+                    .ok();
+                }
+                for m in &result.matches {
+                    ui.send(AppEvent::Log {
+                        page,
+                        line: format!(
+                            "    offset={} attempt={} [{}] {}\n",
+                            m.event_offset, m.attempt, m.stream, m.snippet
+                        ),
+                    })
+                    .ok();
+                }
+                let hidden = (result.total_matches as usize).saturating_sub(result.matches.len());
+                if hidden > 0 {
+                    ui.send(AppEvent::Log {
+                        page,
+                        line: format!("    … {hidden} more match(es)\n"),
+                    })
+                    .ok();
+                }
+            }
+        }
+
         UiCommand::JobsHistory {
             cfg,
             job_id,
@@ -1078,6 +1295,7 @@ pub(crate) async fn handle_command(
                 kinds: kind_filters,
                 after: after.map(|ms| Timestamp { unix_millis: ms }),
                 before: before.map(|ms| Timestamp { unix_millis: ms }),
+                attempt: 0,
             };
 
             let channel = match connect(&cfg.job_addr).await {
@@ -1491,6 +1709,7 @@ pub(crate) async fn handle_command(
                         }),
                     correlation_id: correlation_id.trim().to_string(),
                     run_id: run_id_from_optional(&correlation_id),
+                    secrets: Vec::new(),
                 })
                 .await
             {
@@ -1808,11 +2027,16 @@ pub(crate) async fn handle_command(
                         .as_ref()
                         .map(|id| id.value.as_str())
                         .unwrap_or("");
+                    let jdk = set
+                        .jdk_toolchain_id
+                        .as_ref()
+                        .map(|id| id.value.as_str())
+                        .unwrap_or("");
                     ui.send(AppEvent::Log {
                         page: "toolchains",
                         line: format!(
-                            "- {} ({})\n  sdk={}\n  ndk={}\n",
-                            set.display_name, set_id, sdk, ndk
+                            "- {} ({})\n  sdk={}\n  ndk={}\n  jdk={}\n",
+                            set.display_name, set_id, sdk, ndk, jdk
                         ),
                     })
                     .ok();
@@ -1995,6 +2219,7 @@ pub(crate) async fn handle_command(
                     sdk_toolchain_id: sdk_id.map(|value| Id { value }),
                     ndk_toolchain_id: ndk_id.map(|value| Id { value }),
                     display_name: display_name.trim().to_string(),
+                    jdk_toolchain_id: None,
                 })
                 .await?;
 
@@ -2118,6 +2343,11 @@ pub(crate) async fn handle_command(
                     return Ok(());
                 }
             };
+            // A JDK is optional; without one builds use the host `java`.
+            let jdk_id = latest_installed_by_kind(&items, ToolchainKind::Jdk)
+                .and_then(|jdk| jdk.toolchain_id.as_ref())
+                .map(|id| id.value.clone())
+                .filter(|value| !value.trim().is_empty());
             let sdk_version = sdk
                 .version
                 .as_ref()
@@ -2145,6 +2375,7 @@ pub(crate) async fn handle_command(
                         value: ndk_id.clone(),
                     }),
                     display_name: display_name.clone(),
+                    jdk_toolchain_id: jdk_id.clone().map(|value| Id { value }),
                 })
                 .await
             {
@@ -2186,8 +2417,11 @@ pub(crate) async fn handle_command(
             ui.send(AppEvent::Log {
                 page: "toolchains",
                 line: format!(
-                    "Created set {set_id}\n  display_name={}\n  sdk={}\n  ndk={}\n",
-                    set.display_name, sdk_id, ndk_id
+                    "Created set {set_id}\n  display_name={}\n  sdk={}\n  ndk={}\n  jdk={}\n",
+                    set.display_name,
+                    sdk_id,
+                    ndk_id,
+                    jdk_id.as_deref().unwrap_or("")
                 ),
             })
             .ok();
@@ -3452,6 +3686,7 @@ pub(crate) async fn handle_command(
                         }),
                     correlation_id: correlation_id.trim().to_string(),
                     run_id: run_id_from_optional(&correlation_id),
+                    apk_sha256: String::new(),
                 })
                 .await
             {
@@ -4103,7 +4338,12 @@ pub(crate) async fn handle_command(
             application_id,
             activity,
             options,
+            workflow,
+            inputs,
+            target_ids,
+            target_selector,
             stream_history,
+            plan_only,
         } => {
             ui.send(AppEvent::Log {
                 page: "workflow",
@@ -4123,34 +4363,62 @@ pub(crate) async fn handle_command(
             };
             let mut client = WorkflowServiceClient::new(channel);
 
-            let resp = match client
-                .run_pipeline(WorkflowPipelineRequest {
-                    run_id: run_id_from_optional(&run_id),
-                    correlation_id: correlation_id.trim().to_string(),
-                    job_id: job_id
-                        .as_ref()
-                        .filter(|value| !value.trim().is_empty())
-                        .map(|value| Id {
-                            value: value.clone(),
-                        }),
-                    project_id: to_optional_id(&project_id),
-                    project_path: project_path.trim().to_string(),
-                    project_name: project_name.trim().to_string(),
-                    template_id: to_optional_id(&template_id),
-                    toolchain_id: to_optional_id(&toolchain_id),
-                    toolchain_set_id: to_optional_id(&toolchain_set_id),
-                    target_id: to_optional_id(&target_id),
-                    build_variant: build_variant as i32,
-                    module,
-                    variant_name,
-                    tasks,
-                    apk_path,
-                    application_id,
-                    activity,
-                    options,
-                })
-                .await
-            {
+            let request = WorkflowPipelineRequest {
+                run_id: run_id_from_optional(&run_id),
+                correlation_id: correlation_id.trim().to_string(),
+                job_id: job_id
+                    .as_ref()
+                    .filter(|value| !value.trim().is_empty())
+                    .map(|value| Id {
+                        value: value.clone(),
+                    }),
+                project_id: to_optional_id(&project_id),
+                project_path: project_path.trim().to_string(),
+                project_name: project_name.trim().to_string(),
+                template_id: to_optional_id(&template_id),
+                toolchain_id: to_optional_id(&toolchain_id),
+                toolchain_set_id: to_optional_id(&toolchain_set_id),
+                target_id: to_optional_id(&target_id),
+                build_variant: build_variant as i32,
+                module,
+                variant_name,
+                tasks,
+                apk_path,
+                application_id,
+                activity,
+                options,
+                workflow,
+                inputs,
+                target_ids: target_ids
+                    .iter()
+                    .map(String::as_str)
+                    .filter_map(to_optional_id)
+                    .collect(),
+                target_selector,
+                secrets: Vec::new(),
+                apk_sha256: String::new(),
+                artifact_run_id: None,
+            };
+            if plan_only {
+                match client.plan_pipeline(request).await {
+                    Ok(resp) => {
+                        ui.send(AppEvent::Log {
+                            page: "workflow",
+                            line: format_pipeline_plan(&resp.into_inner()),
+                        })
+                        .ok();
+                    }
+                    Err(err) => {
+                        ui.send(AppEvent::Log {
+                            page: "workflow",
+                            line: format!("PlanPipeline failed: {err}\n"),
+                        })
+                        .ok();
+                    }
+                }
+                return Ok(());
+            }
+            let resp = match client.run_pipeline(request).await {
                 Ok(resp) => resp.into_inner(),
                 Err(err) => {
                     ui.send(AppEvent::Log {
@@ -4275,6 +4543,89 @@ pub(crate) async fn handle_command(
             });
         }
 
+        UiCommand::WorkflowResumePipeline {
+            cfg,
+            run_id,
+            inputs,
+            stream_history,
+        } => {
+            let run_id = run_id.trim().to_string();
+            if run_id.is_empty() {
+                ui.send(AppEvent::Log {
+                    page: "workflow",
+                    line: "Run id is required to resume a run.\n".into(),
+                })
+                .ok();
+                return Ok(());
+            }
+            let channel = match connect(&cfg.workflow_addr).await {
+                Ok(channel) => channel,
+                Err(err) => {
+                    ui.send(AppEvent::Log {
+                        page: "workflow",
+                        line: format!("WorkflowService connection failed: {err}\n"),
+                    })
+                    .ok();
+                    return Ok(());
+                }
+            };
+            let mut client = WorkflowServiceClient::new(channel);
+            let resp = match client
+                .resume_pipeline(ResumePipelineRequest {
+                    run_id: Some(RunId {
+                        value: run_id.clone(),
+                    }),
+                    job_id: None,
+                    inputs,
+                })
+                .await
+            {
+                Ok(resp) => resp.into_inner(),
+                Err(err) => {
+                    ui.send(AppEvent::Log {
+                        page: "workflow",
+                        line: format!("ResumePipeline failed: {err}\n"),
+                    })
+                    .ok();
+                    return Ok(());
+                }
+            };
+            let job_id_value = resp
+                .job_id
+                .as_ref()
+                .map(|id| id.value.clone())
+                .unwrap_or_default();
+            ui.send(AppEvent::Log {
+                page: "workflow",
+                line: format!(
+                    "Pipeline resumed: run_id={run_id} job_id={job_id_value} {}\n",
+                    kv_pairs(&resp.outputs)
+                ),
+            })
+            .ok();
+
+            let job_addr = cfg.job_addr.clone();
+            let ui_stream = ui.clone();
+            let ui_err = ui.clone();
+            stream_tasks.spawn(async move {
+                if let Err(err) = stream_run_events(
+                    job_addr,
+                    run_id,
+                    String::new(),
+                    stream_history,
+                    "workflow",
+                    ui_stream,
+                )
+                .await
+                {
+                    let _ = ui_err.send(AppEvent::Log {
+                        page: "workflow",
+                        line: format!("run stream error: {err}\n"),
+                    });
+                }
+            });
+        }
+
         UiCommand::BuildRun {
             cfg,
             project_ref,
@@ -4377,6 +4728,73 @@ pub(crate) async fn handle_command(
             });
         }
 
+        UiCommand::BuildReport { cfg, project_ref } => {
+            if project_ref.trim().is_empty() {
+                ui.send(AppEvent::Log {
+                    page: "console",
+                    line: "Project path or id is required.\n".into(),
+                })
+                .ok();
+                return Ok(());
+            }
+
+            let mut client = BuildServiceClient::new(connect(&cfg.build_addr).await?);
+            let report = match client
+                .get_build_report(GetBuildReportRequest {
+                    job_id: None,
+                    project_id: Some(Id {
+                        value: project_ref.trim().to_string(),
+                    }),
+                    slowest_limit: 10,
+                })
+                .await
+            {
+                Ok(resp) => resp.into_inner().report.unwrap_or_default(),
+                Err(err) => {
+                    ui.send(AppEvent::Log {
+                        page: "console",
+                        line: format!("Build report failed: {err}\n"),
+                    })
+                    .ok();
+                    return Ok(());
+                }
+            };
+
+            let mut text = format!(
+                "Build report {} ({} {}{})\n  {} tasks: {} executed, {} up-to-date, {} from cache, {} skipped, {} failed\n  cache hit rate {:.0}%, task time {} ms\n",
+                report.job_id.map(|id| id.value).unwrap_or_default(),
+                report.module,
+                report.variant_name,
+                if report.failed { ", failed" } else { "" },
+                report.tasks.len(),
+                report.tasks_executed,
+                report.tasks_up_to_date,
+                report.tasks_from_cache,
+                report.tasks_skipped,
+                report.tasks_failed,
+                report.cache_hit_rate * 100.0,
+                report.task_duration_ms
+            );
+            if !report.slowest_tasks.is_empty() {
+                text.push_str("  Slowest tasks:\n");
+            }
+            for task in &report.slowest_tasks {
+                let outcome = TaskOutcome::try_from(task.outcome)
+                    .unwrap_or(TaskOutcome::Unspecified)
+                    .as_str_name()
+                    .trim_start_matches("TASK_OUTCOME_");
+                text.push_str(&format!(
+                    "    {:>8} ms  {:<10}  {}\n",
+                    task.duration_ms, outcome, task.path
+                ));
+            }
+            ui.send(AppEvent::Log {
+                page: "console",
+                line: text,
+            })
+            .ok();
+        }
+
         UiCommand::BuildListArtifacts {
             cfg,
             project_ref,
@@ -4824,22 +5242,16 @@ async fn stream_job_events_home(
     job_id: &str,
     ui: UiEventSender,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = JobServiceClient::new(connect(addr).await?);
-    let mut stream = client
-        .stream_job_events(StreamJobEventsRequest {
-            job_id: Some(Id {
-                value: job_id.to_string(),
-            }),
-            include_history: true,
-        })
-        .await?
-        .into_inner();
+    let mut stream = open_job_stream(addr, job_id, 0).await?;
+    let mut last_seq = 0u64;
+    let mut resumes = 0u32;
     let mut last_progress: Option<(u32, Instant)> = None;
     let progress_throttle = Duration::from_millis(250);
 
     while let Some(item) = stream.next().await {
         match item {
             Ok(evt) => {
+                last_seq = last_seq.max(evt.seq);
                 if let Some(payload) = evt.payload.as_ref() {
                     match payload {
                         JobPayload::StateChanged(state) => {
@@ -4894,8 +5306,13 @@ async fn stream_job_events_home(
                                 .as_ref()
                                 .map(|err| err.message.clone())
                                 .unwrap_or_else(|| "failed".into());
+                            let state = if failed.will_retry {
+                                "Retrying"
+                            } else {
+                                "Failed"
+                            };
                             ui.send(AppEvent::HomeState {
-                                state: "Failed".into(),
+                                state: state.into(),
                             })
                             .ok();
                             ui.send(AppEvent::HomeResult { result: message }).ok();
@@ -4909,8 +5326,10 @@ async fn stream_job_events_home(
                 if let Some(JobPayload::Completed(_)) = evt.payload.as_ref() {
                     break;
                 }
-                if let Some(JobPayload::Failed(_)) = evt.payload.as_ref() {
-                    break;
+                if let Some(JobPayload::Failed(failed)) = evt.payload.as_ref() {
+                    if !failed.will_retry {
+                        break;
+                    }
                 }
                 if let Some(JobPayload::StateChanged(state)) = evt.payload.as_ref() {
                     let state =
@@ -4926,7 +5345,16 @@ async fn stream_job_events_home(
                     line: format!("job stream error: {err}\n"),
                 })
                 .ok();
-                break;
+                let Some(resumed) = reopen_job_stream(addr, job_id, last_seq, &mut resumes).await
+                else {
+                    break;
+                };
+                ui.send(AppEvent::Log {
+                    page: "home",
+                    line: format!("job stream resumed after event {last_seq}\n"),
+                })
+                .ok();
+                stream = resumed;
             }
         }
     }
@@ -4934,6 +5362,45 @@ async fn stream_job_events_home(
     Ok(())
 }
 
+const STREAM_MAX_RESUMES: u32 = 5;
+const STREAM_RESUME_DELAY: Duration = Duration::from_secs(1);
+
+async fn open_job_stream(
+    addr: &str,
+    job_id: &str,
+    after_seq: u64,
+) -> Result<tonic::Streaming<JobEvent>, Box<dyn std::error::Error>> {
+    let mut client = JobServiceClient::new(connect(addr).await?);
+    Ok(client
+        .stream_job_events(StreamJobEventsRequest {
+            job_id: Some(Id {
+                value: job_id.to_string(),
+            }),
+            include_history: true,
+            after_seq,
+        })
+        .await?
+        .into_inner())
+}
+
+/// Reconnects a dropped job stream so only the events after `after_seq` are
+/// delivered again. Returns `None` once `STREAM_MAX_RESUMES` is used up.
+async fn reopen_job_stream(
+    addr: &str,
+    job_id: &str,
+    after_seq: u64,
+    resumes: &mut u32,
+) -> Option<tonic::Streaming<JobEvent>> {
+    while *resumes < STREAM_MAX_RESUMES {
+        *resumes += 1;
+        tokio::time::sleep(STREAM_RESUME_DELAY).await;
+        if let Ok(stream) = open_job_stream(addr, job_id, after_seq).await {
+            return Some(stream);
+        }
+    }
+    None
+}
+
 async fn stream_job_events(
     addr: String,
     job_id: String,
@@ -4945,7 +5412,8 @@ async fn stream_job_events(
 
 fn is_terminal_job_event(evt: &JobEvent) -> bool {
     match evt.payload.as_ref() {
-        Some(JobPayload::Completed(_)) | Some(JobPayload::Failed(_)) => true,
+        Some(JobPayload::Completed(_)) => true,
+        Some(JobPayload::Failed(failed)) => !failed.will_retry,
         Some(JobPayload::StateChanged(state)) => {
             let state = JobState::try_from(state.new_state).unwrap_or(JobState::Unspecified);
             matches!(
@@ -4957,6 +5425,35 @@ fn is_terminal_job_event(evt: &JobEvent) -> bool {
     }
 }
 
+/// Desktop notification for the event that finishes a watched job.
+fn job_finished_event(job_id: &str, evt: &JobEvent) -> Option<AppEvent> {
+    let (title, body) = match evt.payload.as_ref()? {
+        JobPayload::Completed(completed) => ("Job succeeded", completed.summary.clone()),
+        JobPayload::Failed(failed) if !failed.will_retry => (
+            "Job failed",
+            failed
+                .error
+                .as_ref()
+                .map(|err| err.message.clone())
+                .unwrap_or_default(),
+        ),
+        JobPayload::StateChanged(state) if state.new_state == JobState::Cancelled as i32 => {
+            ("Job cancelled", String::new())
+        }
+        _ => return None,
+    };
+    let body = if body.trim().is_empty() {
+        format!("Job {job_id}")
+    } else {
+        body
+    };
+    Some(AppEvent::JobFinished {
+        job_id: job_id.to_string(),
+        title: title.to_string(),
+        body,
+    })
+}
+
 async fn fetch_and_emit_cuttlefish_status(
     targets_addr: &str,
     page: &'static str,
@@ -5008,21 +5505,15 @@ async fn stream_job_events_with_targets_status(
     page: &'static str,
     ui: UiEventSender,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = JobServiceClient::new(connect(&addr).await?);
-    let mut stream = client
-        .stream_job_events(StreamJobEventsRequest {
-            job_id: Some(Id {
-                value: job_id.clone(),
-            }),
-            include_history: true,
-        })
-        .await?
-        .into_inner();
+    let mut stream = open_job_stream(&addr, &job_id, 0).await?;
+    let mut last_seq = 0u64;
+    let mut resumes = 0u32;
     let mut pending_cuttlefish_refresh = targets_addr;
 
     while let Some(item) = stream.next().await {
         match item {
             Ok(evt) => {
+                last_seq = last_seq.max(evt.seq);
                 if let Some(JobPayload::Completed(completed)) = evt.payload.as_ref() {
                     if page == "console" {
                         if let Some(apk) = completed.outputs.iter().find(|kv| kv.key == "apk_path")
@@ -5052,6 +5543,9 @@ async fn stream_job_events_with_targets_status(
                 for line in stream_job_event_lines(&job_id, &evt) {
                     ui.send(AppEvent::Log { page, line }).ok();
                 }
+                if let Some(finished) = job_finished_event(&job_id, &evt) {
+                    ui.send(finished).ok();
+                }
                 if is_terminal_job_event(&evt) {
                     if let Some(targets_addr) = pending_cuttlefish_refresh.take() {
                         if let Err(err) = fetch_and_emit_cuttlefish_status(
@@ -5080,7 +5574,16 @@ async fn stream_job_events_with_targets_status(
                     line: format!("job {job_id} stream error: {err}\n"),
                 })
                 .ok();
-                break;
+                let Some(resumed) = reopen_job_stream(&addr, &job_id, last_seq, &mut resumes).await
+                else {
+                    break;
+                };
+                ui.send(AppEvent::Log {
+                    page,
+                    line: format!("job {job_id} stream resumed after event {last_seq}\n"),
+                })
+                .ok();
+                stream = resumed;
             }
         }
     }
@@ -5096,22 +5599,45 @@ async fn stream_run_events(
     page: &'static str,
     ui: UiEventSender,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = JobServiceClient::new(connect(&addr).await?);
-    let mut stream = client
-        .stream_run_events(StreamRunEventsRequest {
+    let open = |cursors: &HashMap<String, u64>| {
+        let addr = addr.clone();
+        let request = StreamRunEventsRequest {
             run_id: run_id_from_optional(&run_id),
-            correlation_id,
+            correlation_id: correlation_id.clone(),
             include_history,
             buffer_max_events: 0,
             max_delay_ms: 0,
             discovery_interval_ms: 0,
-        })
-        .await?
-        .into_inner();
+            cursors: cursors
+                .iter()
+                .map(|(job_id, after_seq)| JobEventCursor {
+                    job_id: Some(Id {
+                        value: job_id.clone(),
+                    }),
+                    after_seq: *after_seq,
+                })
+                .collect(),
+        };
+        async move {
+            let mut client = JobServiceClient::new(connect(&addr).await?);
+            let stream = client.stream_run_events(request).await?.into_inner();
+            Ok::<_, Box<dyn std::error::Error>>(stream)
+        }
+    };
+    let mut cursors: HashMap<String, u64> = HashMap::new();
+    let mut resumes = 0u32;
+    let mut stream = open(&cursors).await?;
 
-    while let Some(item) = stream.next().await {
+    loop {
+        let Some(item) = stream.next().await else {
+            break;
+        };
         match item {
             Ok(evt) => {
+                if let Some(job_id) = evt.job_id.as_ref().filter(|_| evt.seq > 0) {
+                    let last = cursors.entry(job_id.value.clone()).or_default();
+                    *last = (*last).max(evt.seq);
+                }
                 for line in run_stream_lines(&evt) {
                     ui.send(AppEvent::Log { page, line }).ok();
                 }
@@ -5122,7 +5648,21 @@ async fn stream_run_events(
                     line: format!("run stream error: {err}\n"),
                 })
                 .ok();
-                break;
+                let mut resumed = None;
+                while resumed.is_none() && resumes < STREAM_MAX_RESUMES {
+                    resumes += 1;
+                    tokio::time::sleep(STREAM_RESUME_DELAY).await;
+                    resumed = open(&cursors).await.ok();
+                }
+                let Some(resumed) = resumed else {
+                    break;
+                };
+                ui.send(AppEvent::Log {
+                    page,
+                    line: "run stream resumed\n".to_string(),
+                })
+                .ok();
+                stream = resumed;
             }
         }
     }
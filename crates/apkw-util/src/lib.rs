@@ -1,5 +1,8 @@
+pub mod secrets;
+
 use std::{
     fs,
+    future::Future,
     io::{self, Write},
     net::SocketAddr,
     path::{Path, PathBuf},
@@ -8,14 +11,16 @@ use std::{
 };
 
 use apkw_proto::apkw::v1::{
-    job_service_client::JobServiceClient, Id, JobEvent, ListJobHistoryRequest, Pagination,
-    Timestamp,
+    job_service_client::JobServiceClient, AwaitJobDispatchRequest, GetJobRequest, Id, Job,
+    JobEvent, JobState, ListJobHistoryRequest, Pagination, RenewJobLeaseRequest, Timestamp,
+    WatchCancellationRequest,
 };
 use apkw_telemetry as telemetry;
 use fs2::FileExt;
 use serde::Serialize;
+use tokio::sync::watch;
 use tonic::transport::{server::Router, Channel, Server};
-use tracing::info;
+use tracing::{info, warn};
 use walkdir::WalkDir;
 use zip::write::FileOptions;
 
@@ -30,6 +35,11 @@ pub const DEFAULT_TARGETS_ADDR: &str = "127.0.0.1:50055";
 pub const DEFAULT_OBSERVE_ADDR: &str = "127.0.0.1:50056";
 pub const DEFAULT_WORKFLOW_ADDR: &str = "127.0.0.1:50057";
 
+const JOB_LEASE_MS: u64 = 30_000;
+const JOB_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+const CANCEL_WATCH_MIN_BACKOFF: Duration = Duration::from_millis(500);
+const CANCEL_WATCH_MAX_BACKOFF: Duration = Duration::from_secs(10);
+
 static TMP_FILE_SEQ: AtomicU64 = AtomicU64::new(0);
 
 pub fn env_var(key: &str) -> Option<String> {
@@ -128,6 +138,31 @@ pub fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> io::Result<()>
     Ok(())
 }
 
+/// Hex sha256 of a file's contents, as recorded on build artifacts.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut buf = [0u8; 8192];
+    loop {
+        let read = io::Read::read(&mut file, &mut buf)?;
+        if read == 0 {
+            break;
+        }
+        context.update(&buf[..read]);
+    }
+    Ok(hex_encode(context.finish().as_ref()))
+}
+
+/// Lowercase hex encoding of a digest.
+pub fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// True for a 64-character hex sha256 digest.
+pub fn is_sha256_hex(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
 pub fn now_millis() -> i64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -179,6 +214,175 @@ pub async fn collect_job_history(
     Ok(events)
 }
 
+/// Blocks until JobService admits `job_id` (dependencies succeeded and its
+/// concurrency keys are free). Returns `false` when the job was cancelled or
+/// finished while queued; if JobService cannot answer the job proceeds.
+pub async fn wait_for_job_dispatch(client: &mut JobServiceClient<Channel>, job_id: &str) -> bool {
+    match client
+        .await_job_dispatch(AwaitJobDispatchRequest {
+            job_id: Some(Id {
+                value: job_id.to_string(),
+            }),
+        })
+        .await
+    {
+        Ok(resp) => resp.into_inner().dispatched,
+        Err(err) => {
+            warn!("await dispatch for {job_id} failed: {err}");
+            true
+        }
+    }
+}
+
+/// Renews the heartbeat lease on `job_id` until JobService reports the job
+/// finished. If this process dies the lease lapses and JobService fails the
+/// job as orphaned. Transient RPC errors are retried on the next beat. Abort
+/// the returned task to stop beating for an attempt that ended.
+pub fn spawn_job_heartbeat(
+    mut client: JobServiceClient<Channel>,
+    job_id: String,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(JOB_HEARTBEAT_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let resp = client
+                .renew_job_lease(RenewJobLeaseRequest {
+                    job_id: Some(Id {
+                        value: job_id.clone(),
+                    }),
+                    lease_ms: JOB_LEASE_MS,
+                })
+                .await;
+            match resp {
+                Ok(resp) if !resp.get_ref().accepted => break,
+                Ok(_) => {}
+                Err(err) if err.code() == tonic::Code::NotFound => break,
+                Err(err) => warn!("heartbeat for {job_id} failed: {err}"),
+            }
+        }
+    })
+}
+
+pub async fn connect_job_client(
+) -> Result<JobServiceClient<Channel>, Box<dyn std::error::Error + Send + Sync>> {
+    let channel = Channel::from_shared(format!("http://{}", job_addr()))?
+        .connect()
+        .await?;
+    Ok(JobServiceClient::new(channel))
+}
+
+async fn fetch_job(client: &mut JobServiceClient<Channel>, job_id: &str) -> Option<Job> {
+    client
+        .get_job(GetJobRequest {
+            job_id: Some(Id {
+                value: job_id.to_string(),
+            }),
+        })
+        .await
+        .ok()
+        .and_then(|resp| resp.into_inner().job)
+}
+
+/// Runs `run_attempt` once per attempt of `job_id`. When an attempt ends with
+/// JobService re-queuing the job under its retry policy, the next attempt is
+/// started; attempts wait for dispatch themselves, which covers the backoff.
+pub async fn run_job_attempts<F, Fut>(job_id: &str, mut run_attempt: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut client = match connect_job_client().await {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("job attempts: failed to connect job service: {err}");
+            run_attempt().await;
+            return;
+        }
+    };
+    let mut attempt = fetch_job(&mut client, job_id)
+        .await
+        .map(|job| job.attempt)
+        .unwrap_or_default();
+    loop {
+        run_attempt().await;
+        match fetch_job(&mut client, job_id).await {
+            Some(job) if job.state == JobState::Queued as i32 && job.attempt > attempt => {
+                info!("job {job_id} re-queued for attempt {}", job.attempt);
+                attempt = job.attempt;
+            }
+            _ => break,
+        }
+    }
+}
+
+/// Returns a receiver that flips to `true` as soon as JobService reports
+/// `job_id` cancelled. Cancellation is pushed over `WatchCancellation`; when
+/// the stream breaks (JobService restarted, say) the watch reconnects with
+/// backoff, and if JobService cannot be reached at all the receiver simply
+/// stays `false`. The job's heartbeat lease is renewed until the attempt is
+/// cancelled, re-queued or finished, so a retried job's earlier attempts
+/// leave no heartbeat behind.
+pub async fn spawn_cancel_watcher(job_id: String) -> watch::Receiver<bool> {
+    let (tx, rx) = watch::channel(false);
+    let mut client = match connect_job_client().await {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("cancel watcher: failed to connect job service: {err}");
+            return rx;
+        }
+    };
+    let heartbeat = spawn_job_heartbeat(client.clone(), job_id.clone());
+
+    tokio::spawn(async move {
+        let mut backoff = CANCEL_WATCH_MIN_BACKOFF;
+        loop {
+            let watched = client
+                .watch_cancellation(WatchCancellationRequest {
+                    job_id: Some(Id {
+                        value: job_id.clone(),
+                    }),
+                })
+                .await;
+            let result = match watched {
+                Ok(resp) => {
+                    backoff = CANCEL_WATCH_MIN_BACKOFF;
+                    resp.into_inner().message().await
+                }
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(Some(_)) => {
+                    let _ = tx.send(true);
+                    break;
+                }
+                Ok(None) => break,
+                Err(err) if err.code() == tonic::Code::NotFound => {
+                    warn!("cancel watcher: job {job_id} is gone: {err}");
+                    break;
+                }
+                // JobService went away; keep beating so a restarted one
+                // sees the job is still owned, and watch it again.
+                Err(err) => warn!(
+                    "cancel watcher: watch of {job_id} failed, retrying in {backoff:?}: {err}"
+                ),
+            }
+            if tx.is_closed() {
+                break;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(CANCEL_WATCH_MAX_BACKOFF);
+            match connect_job_client().await {
+                Ok(reconnected) => client = reconnected,
+                Err(err) => warn!("cancel watcher: failed to reconnect job service: {err}"),
+            }
+        }
+        heartbeat.abort();
+    });
+
+    rx
+}
+
 pub fn init_tracing() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -683,7 +887,7 @@ fn should_exclude_rel(rel: &Path, opts: &StateArchiveOptions) -> bool {
         return true;
     };
     let name = first.to_string_lossy();
-    if name == STATE_EXPORTS_DIR || name == STATE_OPS_DIR {
+    if name == STATE_EXPORTS_DIR || name == STATE_OPS_DIR || name == secrets::KEYS_DIR {
         return true;
     }
     if opts.exclude_downloads && name == LARGE_DIR_DOWNLOADS {
@@ -702,7 +906,11 @@ fn should_exclude_rel(rel: &Path, opts: &StateArchiveOptions) -> bool {
 }
 
 fn preserve_dirs(opts: &StateArchiveOptions) -> Vec<String> {
-    let mut dirs = vec![STATE_EXPORTS_DIR.to_string(), STATE_OPS_DIR.to_string()];
+    let mut dirs = vec![
+        STATE_EXPORTS_DIR.to_string(),
+        STATE_OPS_DIR.to_string(),
+        secrets::KEYS_DIR.to_string(),
+    ];
     if opts.exclude_downloads {
         dirs.push(LARGE_DIR_DOWNLOADS.to_string());
     }
@@ -0,0 +1,640 @@
+//! File-backed secrets store shared by the services.
+//!
+//! Values are sealed with ChaCha20-Poly1305 under a local key kept in
+//! `keys/secrets.key`, which state archives never include, and stored in
+//! `state/secrets.json`. Requests reference secrets by name through
+//! `SecretRef`; the job that needs them resolves the values into
+//! environment variables, and `Redactor` masks them in anything recorded.
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use apkw_proto::apkw::v1::{SecretInfo, SecretRef, Timestamp};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{data_dir, hex_encode, now_millis, state_file_path, write_json_atomic};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+const SECRETS_FILE_NAME: &str = "secrets.json";
+pub(crate) const KEYS_DIR: &str = "keys";
+const KEY_FILE_NAME: &str = "secrets.key";
+const KEY_LEN: usize = 32;
+
+/// Shorter values would mask unrelated log text, so they are not accepted.
+pub const MIN_SECRET_LEN: usize = 4;
+pub const REDACTED: &str = "***";
+/// Prefix Gradle maps to project properties (`-P`) from the environment.
+const GRADLE_PROPERTY_ENV_PREFIX: &str = "ORG_GRADLE_PROJECT_";
+
+pub fn secrets_file_path() -> PathBuf {
+    state_file_path(SECRETS_FILE_NAME)
+}
+
+/// Directory for key material, which state archives never include.
+pub fn keys_dir() -> PathBuf {
+    data_dir().join(KEYS_DIR)
+}
+
+pub fn secrets_key_path() -> PathBuf {
+    keys_dir().join(KEY_FILE_NAME)
+}
+
+pub fn is_secret_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.'))
+}
+
+fn is_env_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+fn is_gradle_property(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '.'))
+}
+
+/// Parses `NAME`, `ENV_VAR=NAME` or `-Pproperty=NAME`.
+pub fn parse_secret_ref(spec: &str) -> Result<SecretRef, String> {
+    let spec = spec.trim();
+    let (target, name) = match spec.split_once('=') {
+        Some((target, name)) => (target.trim(), name.trim()),
+        None => ("", spec),
+    };
+    let mut secret = SecretRef {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    match target.strip_prefix("-P") {
+        Some(property) => secret.gradle_property = property.to_string(),
+        None => secret.env = target.to_string(),
+    }
+    validate_secret_ref(&secret)?;
+    Ok(secret)
+}
+
+/// The inverse of `parse_secret_ref`.
+pub fn format_secret_ref(secret: &SecretRef) -> String {
+    if !secret.gradle_property.is_empty() {
+        format!("-P{}={}", secret.gradle_property, secret.name)
+    } else if !secret.env.is_empty() {
+        format!("{}={}", secret.env, secret.name)
+    } else {
+        secret.name.clone()
+    }
+}
+
+pub fn validate_secret_ref(secret: &SecretRef) -> Result<(), String> {
+    if !is_secret_name(&secret.name) {
+        return Err(format!(
+            "invalid secret name {:?} (letters, digits, '_', '-' or '.')",
+            secret.name
+        ));
+    }
+    if !secret.env.is_empty() && !secret.gradle_property.is_empty() {
+        return Err(format!(
+            "secret {}: env and gradle_property are mutually exclusive",
+            secret.name
+        ));
+    }
+    if !secret.env.is_empty() && !is_env_name(&secret.env) {
+        return Err(format!(
+            "secret {}: invalid environment variable {:?}",
+            secret.name, secret.env
+        ));
+    }
+    if !secret.gradle_property.is_empty() && !is_gradle_property(&secret.gradle_property) {
+        return Err(format!(
+            "secret {}: invalid Gradle property {:?}",
+            secret.name, secret.gradle_property
+        ));
+    }
+    Ok(())
+}
+
+/// The environment variable a reference sets: `env`, the Gradle property
+/// variable, or the secret name upper-cased with other characters as `_`.
+pub fn secret_env_name(secret: &SecretRef) -> String {
+    if !secret.gradle_property.is_empty() {
+        return format!("{GRADLE_PROPERTY_ENV_PREFIX}{}", secret.gradle_property);
+    }
+    if !secret.env.is_empty() {
+        return secret.env.clone();
+    }
+    let name: String = secret
+        .name
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() {
+                ch.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.starts_with(|ch: char| ch.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SecretsFile {
+    secrets: Vec<SecretRecord>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SecretRecord {
+    name: String,
+    description: String,
+    nonce: String,
+    ciphertext: String,
+    created_at: i64,
+    updated_at: i64,
+}
+
+impl SecretRecord {
+    fn info(&self) -> SecretInfo {
+        SecretInfo {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            created_at: Some(Timestamp {
+                unix_millis: self.created_at,
+            }),
+            updated_at: Some(Timestamp {
+                unix_millis: self.updated_at,
+            }),
+        }
+    }
+}
+
+pub struct SecretStore {
+    path: PathBuf,
+    key_path: PathBuf,
+    secrets: BTreeMap<String, SecretRecord>,
+}
+
+impl SecretStore {
+    /// The store at the default state location.
+    pub fn load() -> io::Result<Self> {
+        Self::open(&secrets_file_path(), &secrets_key_path())
+    }
+
+    pub fn open(path: &Path, key_path: &Path) -> io::Result<Self> {
+        let secrets = match fs::read(path) {
+            Ok(data) => serde_json::from_slice::<SecretsFile>(&data)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+                .secrets
+                .into_iter()
+                .map(|record| (record.name.clone(), record))
+                .collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            key_path: key_path.to_path_buf(),
+            secrets,
+        })
+    }
+
+    pub fn list(&self) -> Vec<SecretInfo> {
+        self.secrets.values().map(SecretRecord::info).collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.secrets.contains_key(name)
+    }
+
+    /// Stores `value` under `name`; returns the entry and whether it is new.
+    pub fn set(
+        &mut self,
+        name: &str,
+        value: &str,
+        description: &str,
+    ) -> io::Result<(SecretInfo, bool)> {
+        if !is_secret_name(name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid secret name {name:?} (letters, digits, '_', '-' or '.')"),
+            ));
+        }
+        if value.chars().count() < MIN_SECRET_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("secret values must be at least {MIN_SECRET_LEN} characters"),
+            ));
+        }
+        let key = self.key(true)?;
+        let (nonce, ciphertext) = seal(&key, name, value)?;
+        let now = now_millis();
+        let record = SecretRecord {
+            name: name.to_string(),
+            description: description.to_string(),
+            nonce: hex_encode(&nonce),
+            ciphertext: hex_encode(&ciphertext),
+            created_at: self
+                .secrets
+                .get(name)
+                .map_or(now, |existing| existing.created_at),
+            updated_at: now,
+        };
+        let info = record.info();
+        // The entry only stays when it reached the disk.
+        let previous = self.secrets.insert(name.to_string(), record);
+        if let Err(err) = self.save() {
+            match previous {
+                Some(previous) => self.secrets.insert(name.to_string(), previous),
+                None => self.secrets.remove(name),
+            };
+            return Err(err);
+        }
+        Ok((info, previous.is_none()))
+    }
+
+    pub fn remove(&mut self, name: &str) -> io::Result<bool> {
+        let Some(previous) = self.secrets.remove(name) else {
+            return Ok(false);
+        };
+        if let Err(err) = self.save() {
+            self.secrets.insert(name.to_string(), previous);
+            return Err(err);
+        }
+        Ok(true)
+    }
+
+    /// The decrypted value of `name`, if stored.
+    pub fn get(&self, name: &str) -> io::Result<Option<String>> {
+        let Some(record) = self.secrets.get(name) else {
+            return Ok(None);
+        };
+        let key = self.key(false)?;
+        open(&key, record).map(Some)
+    }
+
+    /// Every decrypted value, for redaction.
+    pub fn values(&self) -> io::Result<Vec<String>> {
+        if self.secrets.is_empty() {
+            return Ok(Vec::new());
+        }
+        let key = self.key(false)?;
+        self.secrets
+            .values()
+            .map(|record| open(&key, record))
+            .collect()
+    }
+
+    /// Resolves references into the environment variables a job sets.
+    pub fn resolve_env(&self, refs: &[SecretRef]) -> Result<Vec<(String, String)>, String> {
+        let mut env = Vec::with_capacity(refs.len());
+        for secret in refs {
+            validate_secret_ref(secret)?;
+            let value = self
+                .get(&secret.name)
+                .map_err(|err| format!("secret {}: {err}", secret.name))?
+                .ok_or_else(|| format!("secret {} is not stored", secret.name))?;
+            env.push((secret_env_name(secret), value));
+        }
+        Ok(env)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        write_json_atomic(
+            &self.path,
+            &SecretsFile {
+                secrets: self.secrets.values().cloned().collect(),
+            },
+        )
+    }
+
+    fn key(&self, create: bool) -> io::Result<LessSafeKey> {
+        let bytes = match fs::read(&self.key_path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && create => {
+                match create_key_file(&self.key_path) {
+                    Ok(key) => key,
+                    // Another process created the key first; use theirs.
+                    Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                        fs::read(&self.key_path)?
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("secrets key {} is missing", self.key_path.display()),
+                ))
+            }
+            Err(err) => return Err(err),
+        };
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &bytes).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("secrets key {} is malformed", self.key_path.display()),
+            )
+        })?;
+        Ok(LessSafeKey::new(key))
+    }
+}
+
+/// Writes a new random key to `path`, failing with `AlreadyExists` when
+/// the key is already there. The key is written to a temporary file and
+/// linked into place, so readers never see a partly written key.
+fn create_key_file(path: &Path) -> io::Result<Vec<u8>> {
+    let rng = SystemRandom::new();
+    let mut key = vec![0u8; KEY_LEN];
+    let mut suffix = [0u8; 8];
+    rng.fill(&mut key)
+        .and_then(|_| rng.fill(&mut suffix))
+        .map_err(|_| io::Error::other("system random source failed"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp = path.with_file_name(format!(
+        ".{file_name}.{:016x}.tmp",
+        u64::from_le_bytes(suffix)
+    ));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let written = options.open(&tmp).and_then(|mut file| {
+        file.write_all(&key)?;
+        file.sync_all()
+    });
+    let linked = written.and_then(|_| fs::hard_link(&tmp, path));
+    let _ = fs::remove_file(&tmp);
+    linked?;
+    Ok(key)
+}
+
+/// Seals `value`, binding it to `name` so records cannot be swapped.
+fn seal(key: &LessSafeKey, name: &str, value: &str) -> io::Result<([u8; NONCE_LEN], Vec<u8>)> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| io::Error::other("system random source failed"))?;
+    let mut data = value.as_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(name.as_bytes()),
+        &mut data,
+    )
+    .map_err(|_| io::Error::other("secret encryption failed"))?;
+    Ok((nonce, data))
+}
+
+fn open(key: &LessSafeKey, record: &SecretRecord) -> io::Result<String> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "secret {} cannot be decrypted with the local key",
+                record.name
+            ),
+        )
+    };
+    let nonce: [u8; NONCE_LEN] = hex_decode(&record.nonce)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(invalid)?;
+    let mut data = hex_decode(&record.ciphertext).ok_or_else(invalid)?;
+    let plain = key
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(record.name.as_bytes()),
+            &mut data,
+        )
+        .map_err(|_| invalid())?;
+    String::from_utf8(plain.to_vec()).map_err(|_| invalid())
+}
+
+fn hex_decode(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Masks secret values in text and log bytes.
+#[derive(Clone, Debug, Default)]
+pub struct Redactor {
+    values: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new(values: impl IntoIterator<Item = String>) -> Self {
+        let mut values: Vec<String> = values
+            .into_iter()
+            .filter(|value| value.chars().count() >= MIN_SECRET_LEN)
+            .collect();
+        // Longest first, so a secret containing another is masked whole.
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+        values.dedup();
+        Self { values }
+    }
+
+    /// Every value in the default store; empty when it cannot be read.
+    pub fn from_store() -> Self {
+        SecretStore::load()
+            .and_then(|store| store.values())
+            .map(Self::new)
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut out = Cow::Borrowed(text);
+        for value in &self.values {
+            if out.contains(value.as_str()) {
+                out = Cow::Owned(out.replace(value.as_str(), REDACTED));
+            }
+        }
+        out
+    }
+
+    /// Copies `reader` to `writer` a line at a time with secret values
+    /// masked, so large files are never read whole. Values spanning a line
+    /// break are not masked.
+    pub fn redact_stream(&self, reader: impl Read, mut writer: impl Write) -> io::Result<()> {
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            writer.write_all(&self.redact_bytes(&line))?;
+        }
+    }
+
+    pub fn redact_bytes<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+        let mut out = Cow::Borrowed(data);
+        for value in &self.values {
+            if let Some(replaced) = replace_bytes(&out, value.as_bytes(), REDACTED.as_bytes()) {
+                out = Cow::Owned(replaced);
+            }
+        }
+        out
+    }
+}
+
+fn replace_bytes(data: &[u8], needle: &[u8], with: &[u8]) -> Option<Vec<u8>> {
+    let first = data
+        .windows(needle.len())
+        .position(|window| window == needle)?;
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..first]);
+    out.extend_from_slice(with);
+    let mut rest = &data[first + needle.len()..];
+    while let Some(pos) = rest
+        .windows(needle.len())
+        .position(|window| window == needle)
+    {
+        out.extend_from_slice(&rest[..pos]);
+        out.extend_from_slice(with);
+        rest = &rest[pos + needle.len()..];
+    }
+    out.extend_from_slice(rest);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_root(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "apkw-secrets-{name}-{}-{}",
+            std::process::id(),
+            now_millis()
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn secrets_are_encrypted_at_rest_and_resolved_by_name() {
+        let root = test_root("store");
+        let path = root.join("state").join("secrets.json");
+        let key_path = root.join("keys").join("secrets.key");
+
+        let mut store = SecretStore::open(&path, &key_path).unwrap();
+        let (info, created) = store
+            .set("release-store", "hunter2-store", "upload keystore")
+            .unwrap();
+        assert!(created);
+        assert_eq!(info.name, "release-store");
+        assert!(store.set("short", "abc", "").is_err());
+        assert!(store.set("bad name", "long-enough", "").is_err());
+
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("hunter2-store"));
+
+        let store = SecretStore::open(&path, &key_path).unwrap();
+        assert_eq!(
+            store.get("release-store").unwrap().as_deref(),
+            Some("hunter2-store")
+        );
+        let refs = [
+            parse_secret_ref("release-store").unwrap(),
+            parse_secret_ref("STORE_PASS=release-store").unwrap(),
+            parse_secret_ref("-PsigningPassword=release-store").unwrap(),
+        ];
+        let env = store.resolve_env(&refs).unwrap();
+        let names: Vec<&str> = env.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "RELEASE_STORE",
+                "STORE_PASS",
+                "ORG_GRADLE_PROJECT_signingPassword"
+            ]
+        );
+        assert!(env.iter().all(|(_, value)| value == "hunter2-store"));
+        assert!(store
+            .resolve_env(&[parse_secret_ref("missing").unwrap()])
+            .is_err());
+        assert_eq!(
+            format_secret_ref(&refs[2]),
+            "-PsigningPassword=release-store"
+        );
+        assert!(parse_secret_ref("1BAD=release-store").is_err());
+
+        let raced = root.join("keys").join("raced.key");
+        let first = create_key_file(&raced).unwrap();
+        assert_eq!(
+            create_key_file(&raced).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(fs::read(&raced).unwrap(), first);
+        assert_eq!(fs::read_dir(root.join("keys")).unwrap().count(), 2);
+
+        // A store that cannot be written keeps nothing it failed to save.
+        let blocker = root.join("blocker");
+        let mut unwritable = SecretStore::open(&blocker.join("secrets.json"), &key_path).unwrap();
+        fs::write(&blocker, b"").unwrap();
+        assert!(unwritable.set("release-key", "hunter2-key", "").is_err());
+        assert!(!unwritable.contains("release-key"));
+        assert!(unwritable.list().is_empty());
+
+        fs::write(&key_path, [7u8; KEY_LEN]).unwrap();
+        let store = SecretStore::open(&path, &key_path).unwrap();
+        assert!(store.get("release-store").is_err());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn redactor_masks_values_in_text_and_bytes() {
+        let redactor = Redactor::new(["s3cret".to_string(), "s3cret-long".to_string()]);
+        assert_eq!(
+            redactor.redact("pass=s3cret-long and s3cret"),
+            "pass=*** and ***"
+        );
+        assert!(matches!(redactor.redact("nothing here"), Cow::Borrowed(_)));
+        assert_eq!(
+            redactor.redact_bytes(b"x s3cret y s3cret").as_ref(),
+            b"x *** y ***"
+        );
+        let mut out = Vec::new();
+        redactor
+            .redact_stream(&b"one s3cret\ntwo s3cret-long\nlast"[..], &mut out)
+            .unwrap();
+        assert_eq!(out, b"one ***\ntwo ***\nlast");
+        assert!(Redactor::new(["abc".to_string()]).is_empty());
+    }
+}
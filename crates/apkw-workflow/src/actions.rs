@@ -0,0 +1,943 @@
+use std::collections::BTreeMap;
+
+use apkw_proto::apkw::v1::{
+    ArtifactFilter, BuildRequest, BuildVariant, CreateProjectRequest, ExportEvidenceBundleRequest,
+    ExportSupportBundleRequest, Id, InstallApkRequest, JobPriority, JobState, KeyValue,
+    LaunchRequest, ListArtifactsRequest, OpenProjectRequest, RunId, RunTestsRequest, SecretRef,
+    StartJobRequest, StopAppRequest, TestKind, VerifyToolchainRequest,
+};
+use apkw_proto::rpc::{call_unary_json, unary_method, FieldKind, RequestField, UnaryMethod};
+use apkw_util::secrets::parse_secret_ref;
+#[cfg(test)]
+use futures_util::future::BoxFuture;
+use serde_json::{json, Map, Value};
+use tonic::transport::Channel;
+
+use crate::{
+    artifacts::StepArtifact, connect_build, connect_job, connect_observe, connect_project,
+    connect_targets, connect_toolchain, select_artifact, upsert_run_outputs_best_effort,
+    wait_for_job, WorkflowConfig,
+};
+
+/// A workflow step action: the service call it makes and the inputs it takes.
+pub(crate) struct ActionSpec {
+    pub(crate) name: &'static str,
+    /// Inputs that must resolve to a non-empty value for the step to run.
+    pub(crate) required: &'static [&'static str],
+    pub(crate) optional: &'static [&'static str],
+}
+
+impl ActionSpec {
+    pub(crate) fn accepts(&self, input: &str) -> bool {
+        self.required.contains(&input) || self.optional.contains(&input)
+    }
+}
+
+pub(crate) const ACTIONS: &[ActionSpec] = &[
+    ActionSpec {
+        name: "project.create",
+        required: &["template_id", "project_path"],
+        optional: &["name", "toolchain_set_id"],
+    },
+    ActionSpec {
+        name: "project.open",
+        required: &["project_path"],
+        optional: &[],
+    },
+    ActionSpec {
+        name: "toolchain.verify",
+        required: &["toolchain_id"],
+        optional: &[],
+    },
+    ActionSpec {
+        name: "build.run",
+        required: &["project"],
+        optional: &[
+            "variant",
+            "module",
+            "variant_name",
+            "tasks",
+            "gradle_args",
+            "clean_first",
+            "secrets",
+        ],
+    },
+    ActionSpec {
+        name: "build.test",
+        required: &["project"],
+        optional: &[
+            "kind",
+            "variant",
+            "module",
+            "variant_name",
+            "target_id",
+            "filters",
+            "gradle_args",
+            "secrets",
+        ],
+    },
+    ActionSpec {
+        name: "targets.install",
+        required: &["target_id", "apk_path"],
+        optional: &["project_id", "apk_sha256"],
+    },
+    ActionSpec {
+        name: "targets.launch",
+        required: &["target_id", "application_id"],
+        optional: &["activity"],
+    },
+    ActionSpec {
+        name: "targets.stop",
+        required: &["target_id", "application_id"],
+        optional: &[],
+    },
+    ActionSpec {
+        name: "observe.support_bundle",
+        required: &[],
+        optional: &[
+            "project_id",
+            "target_id",
+            "toolchain_set_id",
+            "include_recent_runs",
+        ],
+    },
+    ActionSpec {
+        name: "observe.evidence_bundle",
+        required: &[],
+        optional: &[],
+    },
+    ActionSpec {
+        name: "job.start",
+        required: &["job_type"],
+        optional: &["params", "project_id", "target_id", "toolchain_set_id"],
+    },
+];
+
+pub(crate) fn action_spec(name: &str) -> Option<&'static ActionSpec> {
+    ACTIONS.iter().find(|spec| spec.name == name)
+}
+
+/// Action prefixes of the services `<service>.<Rpc>` steps can call.
+const RPC_SERVICES: &[(&str, &str)] = &[
+    ("job", "JobService"),
+    ("toolchain", "ToolchainService"),
+    ("project", "ProjectService"),
+    ("build", "BuildService"),
+    ("targets", "TargetService"),
+    ("observe", "ObserveService"),
+    ("workflow", "WorkflowService"),
+];
+
+/// The unary RPC a `<service>.<Rpc>` action (e.g. `targets.ListTargets`)
+/// calls. Its inputs are the request's fields.
+pub(crate) fn rpc_action(name: &str) -> Option<&'static UnaryMethod> {
+    let (prefix, method) = name.split_once('.')?;
+    let (_, service) = RPC_SERVICES.iter().find(|(p, _)| *p == prefix)?;
+    unary_method(service, method)
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum InputValue {
+    One(String),
+    List(Vec<String>),
+}
+
+/// Step inputs after template rendering.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StepInputs {
+    pub(crate) values: BTreeMap<String, InputValue>,
+}
+
+impl StepInputs {
+    pub(crate) fn get(&self, key: &str) -> &str {
+        match self.values.get(key) {
+            Some(InputValue::One(value)) => value,
+            _ => "",
+        }
+    }
+
+    pub(crate) fn list(&self, key: &str) -> Vec<String> {
+        match self.values.get(key) {
+            Some(InputValue::List(values)) => values.clone(),
+            Some(InputValue::One(value)) if !value.is_empty() => vec![value.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        matches!(
+            self.get(key).to_ascii_lowercase().as_str(),
+            "true" | "1" | "yes"
+        )
+    }
+
+    fn id(&self, key: &str) -> Option<Id> {
+        let value = self.get(key);
+        (!value.is_empty()).then(|| Id {
+            value: value.to_string(),
+        })
+    }
+
+    fn key_values(&self, key: &str) -> Vec<KeyValue> {
+        self.list(key)
+            .into_iter()
+            .map(|item| match item.split_once('=') {
+                Some((key, value)) => KeyValue {
+                    key: key.trim().to_string(),
+                    value: value.to_string(),
+                },
+                None => KeyValue {
+                    key: item.trim().to_string(),
+                    value: String::new(),
+                },
+            })
+            .collect()
+    }
+
+    /// `secrets` entries (`NAME`, `ENV_VAR=NAME` or `-Pproperty=NAME`),
+    /// separated by whitespace or commas.
+    fn secret_refs(&self, action: &str) -> Result<Vec<SecretRef>, StepFailure> {
+        self.list("secrets")
+            .iter()
+            .flat_map(|item| item.split(|ch: char| ch == ',' || ch.is_whitespace()))
+            .filter(|spec| !spec.is_empty())
+            .map(|spec| {
+                parse_secret_ref(spec)
+                    .map_err(|err| StepFailure::new(format!("{action} failed"), err))
+            })
+            .collect()
+    }
+
+    /// Required inputs of `spec` that resolved to nothing.
+    pub(crate) fn missing(&self, spec: &ActionSpec) -> Vec<&'static str> {
+        spec.required
+            .iter()
+            .copied()
+            .filter(|key| self.list(key).iter().all(|value| value.trim().is_empty()))
+            .collect()
+    }
+}
+
+/// Run-wide values every step call carries.
+pub(crate) struct StepEnv<'a> {
+    pub(crate) config: &'a WorkflowConfig,
+    pub(crate) run_id: &'a str,
+    pub(crate) correlation_id: &'a str,
+}
+
+impl StepEnv<'_> {
+    fn run_id(&self) -> Option<RunId> {
+        Some(RunId {
+            value: self.run_id.to_string(),
+        })
+    }
+}
+
+pub(crate) struct StepFailure {
+    pub(crate) message: String,
+    pub(crate) detail: String,
+}
+
+impl StepFailure {
+    pub(crate) fn new(message: impl Into<String>, detail: impl ToString) -> Self {
+        Self {
+            message: message.into(),
+            detail: detail.to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct StepOutcome {
+    pub(crate) job_ids: Vec<String>,
+    pub(crate) outputs: BTreeMap<String, String>,
+    pub(crate) failure: Option<StepFailure>,
+}
+
+impl StepOutcome {
+    fn output(&mut self, key: &str, value: impl ToString) {
+        self.outputs.insert(key.to_string(), value.to_string());
+    }
+
+    /// Waits for the job a service call started and merges the outputs it
+    /// completed with. Services that run synchronously may return no job.
+    async fn await_job(
+        &mut self,
+        env: &StepEnv<'_>,
+        action: &str,
+        job_id: Option<&Id>,
+        job_required: bool,
+    ) -> Result<(), StepFailure> {
+        let job_id = job_id
+            .map(|id| id.value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        let Some(job_id) = job_id else {
+            if job_required {
+                return Err(StepFailure::new(format!("{action} failed"), "empty job_id"));
+            }
+            return Ok(());
+        };
+        self.job_ids.push(job_id.clone());
+        self.output("job_id", &job_id);
+        let mut job_client = connect_job(&env.config.job_addr)
+            .await
+            .map_err(|err| StepFailure::new("job service unavailable", err))?;
+        let finished = wait_for_job(&mut job_client, &job_id)
+            .await
+            .map_err(|err| StepFailure::new(format!("{action} job failed"), err))?;
+        if finished.state != JobState::Success {
+            return Err(match finished.error {
+                Some(error) if !error.message.is_empty() => StepFailure::new(
+                    format!("{action} failed: {}", error.message),
+                    if error.technical_details.is_empty() {
+                        format!("job {job_id} finished as {:?}", finished.state)
+                    } else {
+                        error.technical_details
+                    },
+                ),
+                _ => StepFailure::new(
+                    format!("{action} failed"),
+                    format!("job {job_id} finished as {:?}", finished.state),
+                ),
+            });
+        }
+        for kv in finished.outputs {
+            self.outputs.entry(kv.key).or_insert(kv.value);
+        }
+        Ok(())
+    }
+}
+
+/// Stands in for the services in tests: takes a step's action and inputs.
+#[cfg(test)]
+pub(crate) type StubAction =
+    Box<dyn Fn(&str, &StepInputs) -> BoxFuture<'static, StepOutcome> + Send + Sync>;
+
+/// What makes the calls of workflow steps: the services, or a stub in
+/// tests.
+pub(crate) enum StepRunner {
+    Services,
+    #[cfg(test)]
+    Stub(StubAction),
+}
+
+impl StepRunner {
+    pub(crate) async fn run(
+        &self,
+        env: &StepEnv<'_>,
+        action: &str,
+        inputs: &StepInputs,
+    ) -> StepOutcome {
+        match self {
+            StepRunner::Services => run_action(env, action, inputs).await,
+            #[cfg(test)]
+            StepRunner::Stub(stub) => stub(action, inputs).await,
+        }
+    }
+}
+
+/// Runs one step. The outcome carries the jobs the step started even when it
+/// fails, so the run record lists them.
+async fn run_action(env: &StepEnv<'_>, action: &str, inputs: &StepInputs) -> StepOutcome {
+    let mut outcome = StepOutcome::default();
+    let result = match action {
+        "project.create" => create_project(env, inputs, &mut outcome).await,
+        "project.open" => open_project(env, inputs, &mut outcome).await,
+        "toolchain.verify" => verify_toolchain(env, inputs, &mut outcome).await,
+        "build.run" => build(env, inputs, &mut outcome).await,
+        "build.test" => run_tests(env, inputs, &mut outcome).await,
+        "targets.install" => install_apk(env, inputs, &mut outcome).await,
+        "targets.launch" => launch(env, inputs, &mut outcome).await,
+        "targets.stop" => stop_app(env, inputs, &mut outcome).await,
+        "observe.support_bundle" => support_bundle(env, inputs, &mut outcome).await,
+        "observe.evidence_bundle" => evidence_bundle(env, &mut outcome).await,
+        "job.start" => start_job(env, inputs, &mut outcome).await,
+        other => match rpc_action(other) {
+            Some(method) => call_rpc(env, other, method, inputs, &mut outcome).await,
+            None => Err(StepFailure::new(
+                "pipeline failed",
+                format!("unknown action {other}"),
+            )),
+        },
+    };
+    if let Err(failure) = result {
+        outcome.failure = Some(failure);
+    }
+    outcome
+}
+
+async fn create_project(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let mut client = connect_project(&env.config.project_addr)
+        .await
+        .map_err(|err| StepFailure::new("project service unavailable", err))?;
+    let resp = client
+        .create_project(CreateProjectRequest {
+            name: inputs.get("name").to_string(),
+            path: inputs.get("project_path").to_string(),
+            template_id: inputs.id("template_id"),
+            params: vec![],
+            toolchain_set_id: inputs.id("toolchain_set_id"),
+            job_id: None,
+            correlation_id: env.correlation_id.to_string(),
+            run_id: env.run_id(),
+        })
+        .await
+        .map_err(|err| StepFailure::new("project.create failed", err))?
+        .into_inner();
+    if let Some(project_id) = resp.project_id.as_ref().filter(|id| !id.value.is_empty()) {
+        outcome.output("project_id", &project_id.value);
+    }
+    outcome.output("project_path", inputs.get("project_path"));
+    outcome
+        .await_job(env, "project.create", resp.job_id.as_ref(), false)
+        .await
+}
+
+async fn open_project(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let mut client = connect_project(&env.config.project_addr)
+        .await
+        .map_err(|err| StepFailure::new("project service unavailable", err))?;
+    let resp = client
+        .open_project(OpenProjectRequest {
+            path: inputs.get("project_path").to_string(),
+        })
+        .await
+        .map_err(|err| StepFailure::new("project.open failed", err))?
+        .into_inner();
+    if let Some(project) = resp.project {
+        if let Some(project_id) = project.project_id.filter(|id| !id.value.is_empty()) {
+            outcome.output("project_id", project_id.value);
+        }
+        outcome.output("project_path", project.path);
+        if let Some(set_id) = project.toolchain_set_id.filter(|id| !id.value.is_empty()) {
+            outcome.output("toolchain_set_id", set_id.value);
+        }
+        if let Some(target_id) = project.default_target_id.filter(|id| !id.value.is_empty()) {
+            outcome.output("default_target_id", target_id.value);
+        }
+    }
+    Ok(())
+}
+
+async fn verify_toolchain(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let mut client = connect_toolchain(&env.config.toolchain_addr)
+        .await
+        .map_err(|err| StepFailure::new("toolchain service unavailable", err))?;
+    let resp = client
+        .verify_toolchain(VerifyToolchainRequest {
+            toolchain_id: inputs.id("toolchain_id"),
+            job_id: None,
+            correlation_id: env.correlation_id.to_string(),
+            run_id: env.run_id(),
+        })
+        .await
+        .map_err(|err| StepFailure::new("toolchain.verify failed", err))?
+        .into_inner();
+    outcome
+        .await_job(env, "toolchain.verify", resp.job_id.as_ref(), false)
+        .await?;
+    outcome.output("verified", resp.verified);
+    if !resp.verified {
+        return Err(StepFailure::new(
+            "toolchain.verify failed",
+            "verification failed",
+        ));
+    }
+    Ok(())
+}
+
+fn parse_variant(action: &str, value: &str) -> Result<BuildVariant, StepFailure> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "" | "debug" => Ok(BuildVariant::Debug),
+        "release" => Ok(BuildVariant::Release),
+        other => Err(StepFailure::new(
+            format!("{action} failed"),
+            format!("unknown variant {other} (expected debug or release)"),
+        )),
+    }
+}
+
+async fn build(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let project_ref = inputs.get("project").to_string();
+    let variant = parse_variant("build.run", inputs.get("variant"))?;
+    let module = inputs.get("module").to_string();
+    let variant_name = inputs.get("variant_name").to_string();
+    let secrets = inputs.secret_refs("build.run")?;
+    let mut client = connect_build(&env.config.build_addr)
+        .await
+        .map_err(|err| StepFailure::new("build service unavailable", err))?;
+    let resp = client
+        .build(BuildRequest {
+            project_id: Some(Id {
+                value: project_ref.clone(),
+            }),
+            variant: variant as i32,
+            clean_first: inputs.flag("clean_first"),
+            gradle_args: inputs.key_values("gradle_args"),
+            job_id: None,
+            module: module.clone(),
+            variant_name: variant_name.clone(),
+            tasks: inputs.list("tasks"),
+            correlation_id: env.correlation_id.to_string(),
+            run_id: env.run_id(),
+            secrets,
+        })
+        .await
+        .map_err(|err| StepFailure::new("build.run failed", err))?
+        .into_inner();
+    outcome
+        .await_job(env, "build.run", resp.job_id.as_ref(), true)
+        .await?;
+    let build_job_id = outcome.outputs.get("job_id").cloned().unwrap_or_default();
+
+    let artifacts = client
+        .list_artifacts(ListArtifactsRequest {
+            project_id: Some(Id { value: project_ref }),
+            variant: variant as i32,
+            filter: Some(ArtifactFilter {
+                modules: if module.is_empty() {
+                    vec![]
+                } else {
+                    vec![module]
+                },
+                variant: variant_name,
+                types: vec![],
+                name_contains: "".into(),
+                path_contains: "".into(),
+            }),
+        })
+        .await
+        .map(|resp| resp.into_inner().artifacts)
+        .unwrap_or_default();
+    upsert_run_outputs_best_effort(
+        &env.config.observe_addr,
+        env.run_id,
+        &build_job_id,
+        &artifacts,
+    )
+    .await;
+    if let Some(artifact) = select_artifact(&artifacts) {
+        for (key, value) in StepArtifact::from_build(artifact).outputs() {
+            outcome.output(key, value);
+        }
+    }
+    Ok(())
+}
+
+/// Runs unit tests, or instrumented tests on `target_id` (every connected
+/// device when unset). The job fails when a test fails; its counts become
+/// step outputs when all pass.
+async fn run_tests(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let kind = match inputs.get("kind").trim().to_ascii_lowercase().as_str() {
+        "" | "unit" => TestKind::Unit,
+        "instrumented" | "connected" => TestKind::Instrumented,
+        other => {
+            return Err(StepFailure::new(
+                "build.test failed",
+                format!("unknown test kind {other} (expected unit or instrumented)"),
+            ))
+        }
+    };
+    let variant = parse_variant("build.test", inputs.get("variant"))?;
+    let secrets = inputs.secret_refs("build.test")?;
+    let mut client = connect_build(&env.config.build_addr)
+        .await
+        .map_err(|err| StepFailure::new("build service unavailable", err))?;
+    let resp = client
+        .run_tests(RunTestsRequest {
+            project_id: inputs.id("project"),
+            variant: variant as i32,
+            variant_name: inputs.get("variant_name").to_string(),
+            module: inputs.get("module").to_string(),
+            kind: kind as i32,
+            device_serial: inputs.get("target_id").to_string(),
+            filters: inputs.list("filters"),
+            gradle_args: inputs.key_values("gradle_args"),
+            job_id: None,
+            correlation_id: env.correlation_id.to_string(),
+            run_id: env.run_id(),
+            secrets,
+        })
+        .await
+        .map_err(|err| StepFailure::new("build.test failed", err))?
+        .into_inner();
+    outcome
+        .await_job(env, "build.test", resp.job_id.as_ref(), true)
+        .await
+}
+
+async fn install_apk(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let mut client = connect_targets(&env.config.targets_addr)
+        .await
+        .map_err(|err| StepFailure::new("targets service unavailable", err))?;
+    let resp = client
+        .install_apk(InstallApkRequest {
+            target_id: inputs.id("target_id"),
+            project_id: inputs.id("project_id"),
+            apk_path: inputs.get("apk_path").to_string(),
+            job_id: None,
+            correlation_id: env.correlation_id.to_string(),
+            run_id: env.run_id(),
+            apk_sha256: inputs.get("apk_sha256").to_string(),
+        })
+        .await
+        .map_err(|err| StepFailure::new("targets.install failed", err))?
+        .into_inner();
+    outcome
+        .await_job(env, "targets.install", resp.job_id.as_ref(), true)
+        .await
+}
+
+async fn launch(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let mut client = connect_targets(&env.config.targets_addr)
+        .await
+        .map_err(|err| StepFailure::new("targets service unavailable", err))?;
+    let resp = client
+        .launch(LaunchRequest {
+            target_id: inputs.id("target_id"),
+            application_id: inputs.get("application_id").to_string(),
+            activity: inputs.get("activity").to_string(),
+            job_id: None,
+            correlation_id: env.correlation_id.to_string(),
+            run_id: env.run_id(),
+        })
+        .await
+        .map_err(|err| StepFailure::new("targets.launch failed", err))?
+        .into_inner();
+    outcome
+        .await_job(env, "targets.launch", resp.job_id.as_ref(), true)
+        .await
+}
+
+async fn stop_app(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let mut client = connect_targets(&env.config.targets_addr)
+        .await
+        .map_err(|err| StepFailure::new("targets service unavailable", err))?;
+    let resp = client
+        .stop_app(StopAppRequest {
+            target_id: inputs.id("target_id"),
+            application_id: inputs.get("application_id").to_string(),
+            job_id: None,
+            correlation_id: env.correlation_id.to_string(),
+            run_id: env.run_id(),
+        })
+        .await
+        .map_err(|err| StepFailure::new("targets.stop failed", err))?
+        .into_inner();
+    outcome
+        .await_job(env, "targets.stop", resp.job_id.as_ref(), true)
+        .await
+}
+
+async fn support_bundle(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let mut client = connect_observe(&env.config.observe_addr)
+        .await
+        .map_err(|err| StepFailure::new("observe service unavailable", err))?;
+    let resp = client
+        .export_support_bundle(ExportSupportBundleRequest {
+            include_logs: true,
+            include_config: true,
+            include_toolchain_provenance: true,
+            include_recent_runs: inputs.flag("include_recent_runs"),
+            recent_runs_limit: 10,
+            job_id: None,
+            project_id: inputs.id("project_id"),
+            target_id: inputs.id("target_id"),
+            toolchain_set_id: inputs.id("toolchain_set_id"),
+            correlation_id: env.correlation_id.to_string(),
+            run_id: env.run_id(),
+        })
+        .await
+        .map_err(|err| StepFailure::new("observe.support_bundle failed", err))?
+        .into_inner();
+    if !resp.output_path.is_empty() {
+        outcome.output("output_path", &resp.output_path);
+    }
+    outcome
+        .await_job(env, "observe.support_bundle", resp.job_id.as_ref(), false)
+        .await
+}
+
+async fn evidence_bundle(env: &StepEnv<'_>, outcome: &mut StepOutcome) -> Result<(), StepFailure> {
+    let mut client = connect_observe(&env.config.observe_addr)
+        .await
+        .map_err(|err| StepFailure::new("observe service unavailable", err))?;
+    let resp = client
+        .export_evidence_bundle(ExportEvidenceBundleRequest {
+            run_id: env.run_id(),
+            job_id: None,
+            correlation_id: env.correlation_id.to_string(),
+        })
+        .await
+        .map_err(|err| StepFailure::new("observe.evidence_bundle failed", err))?
+        .into_inner();
+    if !resp.output_path.is_empty() {
+        outcome.output("output_path", &resp.output_path);
+    }
+    outcome
+        .await_job(env, "observe.evidence_bundle", resp.job_id.as_ref(), false)
+        .await
+}
+
+/// Starts a job of any registered type (e.g. a plugin job) and waits for it.
+async fn start_job(
+    env: &StepEnv<'_>,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let mut client = connect_job(&env.config.job_addr)
+        .await
+        .map_err(|err| StepFailure::new("job service unavailable", err))?;
+    let resp = client
+        .start_job(StartJobRequest {
+            job_type: inputs.get("job_type").to_string(),
+            params: inputs.key_values("params"),
+            project_id: inputs.id("project_id"),
+            target_id: inputs.id("target_id"),
+            toolchain_set_id: inputs.id("toolchain_set_id"),
+            correlation_id: env.correlation_id.to_string(),
+            run_id: env.run_id(),
+            depends_on: Vec::new(),
+            concurrency_keys: Vec::new(),
+            timeout_ms: 0,
+            retry_policy: None,
+            priority: JobPriority::Normal as i32,
+            preemptible: false,
+        })
+        .await
+        .map_err(|err| StepFailure::new("job.start failed", err))?
+        .into_inner();
+    let job_id = resp.job.and_then(|job| job.job_id);
+    outcome
+        .await_job(env, "job.start", job_id.as_ref(), true)
+        .await
+}
+
+/// A step input as the JSON value of request field `field`. Ids are plain
+/// strings, messages are JSON objects and repeated fields take lists.
+fn rpc_value(field: &RequestField, input: &InputValue) -> Result<Option<Value>, String> {
+    let one = |value: &str| -> Result<Value, String> {
+        match field.kind {
+            FieldKind::Scalar => Ok(Value::String(value.to_string())),
+            FieldKind::Bool => match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" => Ok(Value::Bool(true)),
+                "false" | "0" | "no" => Ok(Value::Bool(false)),
+                other => Err(format!(
+                    "{}: expected true or false, got {other}",
+                    field.name
+                )),
+            },
+            FieldKind::Id => Ok(json!({ "value": value })),
+            FieldKind::Message => serde_json::from_str(value)
+                .map_err(|err| format!("{}: expected a JSON object: {err}", field.name)),
+        }
+    };
+    match input {
+        InputValue::One(value) if value.trim().is_empty() => Ok(None),
+        InputValue::One(value) if field.repeated => Ok(Some(Value::Array(vec![one(value)?]))),
+        InputValue::One(value) => one(value).map(Some),
+        InputValue::List(values) if field.repeated => values
+            .iter()
+            .map(|value| one(value))
+            .collect::<Result<_, _>>()
+            .map(|values| Some(Value::Array(values))),
+        InputValue::List(_) => Err(format!("{} takes a single value", field.name)),
+    }
+}
+
+/// Adds `value` to `outputs` under `key`, nested fields as `key.field` and
+/// list items as `key.N`. Ids collapse to their value.
+fn flatten_output(key: &str, value: &Value, outputs: &mut BTreeMap<String, String>) {
+    let nested = |child: &str| {
+        if key.is_empty() {
+            child.to_string()
+        } else {
+            format!("{key}.{child}")
+        }
+    };
+    match value {
+        Value::Null => {}
+        Value::String(text) => {
+            outputs.insert(key.to_string(), text.clone());
+        }
+        Value::Bool(_) | Value::Number(_) => {
+            outputs.insert(key.to_string(), value.to_string());
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                flatten_output(&nested(&index.to_string()), item, outputs);
+            }
+        }
+        Value::Object(fields) => match fields.get("value") {
+            Some(id) if fields.len() == 1 && !key.is_empty() => flatten_output(key, id, outputs),
+            _ => {
+                for (name, field) in fields {
+                    flatten_output(&nested(name), field, outputs);
+                }
+            }
+        },
+    }
+}
+
+fn rpc_addr<'a>(config: &'a WorkflowConfig, service: &str) -> &'a str {
+    match service {
+        "JobService" => &config.job_addr,
+        "ToolchainService" => &config.toolchain_addr,
+        "ProjectService" => &config.project_addr,
+        "BuildService" => &config.build_addr,
+        "TargetService" => &config.targets_addr,
+        "ObserveService" => &config.observe_addr,
+        _ => &config.workflow_addr,
+    }
+}
+
+/// Calls any unary service RPC with the step inputs as request fields;
+/// `correlation_id` and `run_id` default to the run's. Response fields
+/// become outputs (see `flatten_output`) and a `job_id` in the response is
+/// waited for.
+async fn call_rpc(
+    env: &StepEnv<'_>,
+    action: &str,
+    method: &UnaryMethod,
+    inputs: &StepInputs,
+    outcome: &mut StepOutcome,
+) -> Result<(), StepFailure> {
+    let failed = format!("{action} failed");
+    let mut request = Map::new();
+    for (key, input) in &inputs.values {
+        let Some(field) = method.field(key) else {
+            return Err(StepFailure::new(
+                failed,
+                format!("{action} has no field {key}"),
+            ));
+        };
+        if let Some(value) =
+            rpc_value(field, input).map_err(|err| StepFailure::new(failed.clone(), err))?
+        {
+            request.insert(key.clone(), value);
+        }
+    }
+    if method.field("correlation_id").is_some() {
+        request
+            .entry("correlation_id")
+            .or_insert_with(|| json!(env.correlation_id));
+    }
+    if method.field("run_id").is_some() {
+        request
+            .entry("run_id")
+            .or_insert_with(|| json!({ "value": env.run_id }));
+    }
+
+    let addr = rpc_addr(env.config, method.service);
+    let channel = Channel::from_shared(format!("http://{addr}"))
+        .map_err(|err| StepFailure::new(failed.clone(), err))?
+        .connect()
+        .await
+        .map_err(|err| StepFailure::new(format!("{} unavailable", method.service), err))?;
+    let response = call_unary_json(
+        channel,
+        method.service,
+        method.method,
+        Value::Object(request),
+    )
+    .await
+    .map_err(|err| StepFailure::new(failed, err))?;
+    flatten_output("", &response, &mut outcome.outputs);
+    let job_id = outcome.outputs.get("job_id").map(|value| Id {
+        value: value.clone(),
+    });
+    outcome.await_job(env, action, job_id.as_ref(), false).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rpc_actions_map_inputs_and_outputs() {
+        let method = rpc_action("build.Build").expect("build.Build is a unary rpc");
+        assert_eq!(method.service, "BuildService");
+        assert!(rpc_action("targets.StreamLogcat").is_none());
+        assert!(rpc_action("build.Nope").is_none());
+        assert!(rpc_action("nope.Build").is_none());
+
+        let field = |name: &str| method.field(name).unwrap();
+        let one = |value: &str| InputValue::One(value.to_string());
+        assert_eq!(
+            rpc_value(field("project_id"), &one("proj-1")).unwrap(),
+            Some(json!({ "value": "proj-1" }))
+        );
+        assert_eq!(
+            rpc_value(field("clean_first"), &one("yes")).unwrap(),
+            Some(json!(true))
+        );
+        assert!(rpc_value(field("clean_first"), &one("maybe")).is_err());
+        assert_eq!(
+            rpc_value(field("tasks"), &one("assembleDebug")).unwrap(),
+            Some(json!(["assembleDebug"]))
+        );
+        assert_eq!(
+            rpc_value(field("gradle_args"), &one(r#"{"key": "a", "value": "b"}"#)).unwrap(),
+            Some(json!([{ "key": "a", "value": "b" }]))
+        );
+        assert!(rpc_value(field("module"), &InputValue::List(vec!["a".into()])).is_err());
+        assert_eq!(rpc_value(field("module"), &one("")).unwrap(), None);
+
+        let mut outputs = BTreeMap::new();
+        flatten_output(
+            "",
+            &json!({
+                "job_id": { "value": "job-1" },
+                "project": { "path": "/p", "verified": false },
+                "artifacts": [{ "name": "app" }],
+                "missing": null,
+            }),
+            &mut outputs,
+        );
+        assert_eq!(
+            outputs,
+            BTreeMap::from([
+                ("job_id".to_string(), "job-1".to_string()),
+                ("project.path".to_string(), "/p".to_string()),
+                ("project.verified".to_string(), "false".to_string()),
+                ("artifacts.0.name".to_string(), "app".to_string()),
+            ])
+        );
+    }
+}
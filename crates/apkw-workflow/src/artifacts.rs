@@ -0,0 +1,229 @@
+use std::{collections::BTreeMap, path::Path};
+
+use apkw_proto::apkw::v1::{
+    Artifact, ArtifactType, KeyValue, ListRunOutputsRequest, Pagination, RunId, RunOutput,
+    RunOutputFilter, RunOutputKind,
+};
+use apkw_util::sha256_file;
+use tonic::Status;
+
+use crate::{artifact_type_label, connect_observe, WorkflowConfig};
+
+/// Step outputs an artifact is flattened into, so templates can reference
+/// `${steps.<id>.outputs.artifact_sha256}` and friends.
+const OUTPUT_KEYS: &[&str] = &[
+    "artifact_path",
+    "artifact_sha256",
+    "artifact_type",
+    "artifact_module",
+    "artifact_variant",
+    "artifact_abi",
+];
+
+/// A build artifact handed from one step (or an earlier run) to the next.
+/// `sha256` is what consumers verify the file against before using it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct StepArtifact {
+    pub(crate) path: String,
+    pub(crate) sha256: String,
+    /// `apk`, `aab`, `aar`, `mapping` or `test_result`.
+    pub(crate) artifact_type: String,
+    pub(crate) module: String,
+    pub(crate) variant: String,
+    pub(crate) abi: String,
+}
+
+fn metadata_value(metadata: &[KeyValue], key: &str) -> String {
+    metadata
+        .iter()
+        .find(|kv| kv.key == key)
+        .map(|kv| kv.value.clone())
+        .unwrap_or_default()
+}
+
+impl StepArtifact {
+    pub(crate) fn from_build(artifact: &Artifact) -> Self {
+        let artifact_type =
+            ArtifactType::try_from(artifact.r#type).unwrap_or(ArtifactType::Unspecified);
+        Self {
+            path: artifact.path.clone(),
+            sha256: artifact.sha256.clone(),
+            artifact_type: artifact_type_label(artifact_type).to_string(),
+            module: metadata_value(&artifact.metadata, "module"),
+            variant: metadata_value(&artifact.metadata, "variant"),
+            abi: metadata_value(&artifact.metadata, "abi"),
+        }
+    }
+
+    /// An artifact recorded as a run output; its digest is the `sha256`
+    /// metadata written when the output was recorded.
+    pub(crate) fn from_run_output(output: &RunOutput) -> Self {
+        Self {
+            path: output.path.clone(),
+            sha256: metadata_value(&output.metadata, "sha256"),
+            artifact_type: output.output_type.clone(),
+            module: metadata_value(&output.metadata, "module"),
+            variant: metadata_value(&output.metadata, "variant"),
+            abi: metadata_value(&output.metadata, "abi"),
+        }
+    }
+
+    /// Reads back an artifact a step recorded with `outputs`.
+    pub(crate) fn from_outputs(outputs: &BTreeMap<String, String>) -> Option<Self> {
+        let value = |key: &str| outputs.get(key).cloned().unwrap_or_default();
+        let path = value("artifact_path");
+        if path.is_empty() {
+            return None;
+        }
+        Some(Self {
+            path,
+            sha256: value("artifact_sha256"),
+            artifact_type: value("artifact_type"),
+            module: value("artifact_module"),
+            variant: value("artifact_variant"),
+            abi: value("artifact_abi"),
+        })
+    }
+
+    /// The non-empty fields as `artifact_*` step outputs.
+    pub(crate) fn outputs(&self) -> Vec<(&'static str, String)> {
+        let values = [
+            &self.path,
+            &self.sha256,
+            &self.artifact_type,
+            &self.module,
+            &self.variant,
+            &self.abi,
+        ];
+        OUTPUT_KEYS
+            .iter()
+            .zip(values)
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| (*key, value.clone()))
+            .collect()
+    }
+
+    /// Checks the file still exists and, when a digest was recorded, still
+    /// hashes to it. Hashing runs off the async runtime.
+    pub(crate) async fn verify(&self) -> Result<(), String> {
+        let artifact = self.clone();
+        tokio::task::spawn_blocking(move || artifact.verify_blocking())
+            .await
+            .unwrap_or_else(|err| Err(format!("failed to hash {}: {err}", self.path)))
+    }
+
+    fn verify_blocking(&self) -> Result<(), String> {
+        let path = Path::new(&self.path);
+        if !path.is_file() {
+            return Err(format!("artifact {} no longer exists", self.path));
+        }
+        if self.sha256.is_empty() {
+            return Ok(());
+        }
+        let actual =
+            sha256_file(path).map_err(|err| format!("failed to hash {}: {err}", self.path))?;
+        if !actual.eq_ignore_ascii_case(&self.sha256) {
+            return Err(format!(
+                "artifact {} changed: expected sha256 {}, found {actual}",
+                self.path, self.sha256
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The newest APK recorded as an artifact output of `run_id`, checked
+/// against the sha256 ObserveService recorded for it.
+#[allow(clippy::result_large_err)]
+pub(crate) async fn run_artifact(
+    config: &WorkflowConfig,
+    run_id: &str,
+) -> Result<StepArtifact, Status> {
+    let mut client = connect_observe(&config.observe_addr).await?;
+    let outputs = client
+        .list_run_outputs(ListRunOutputsRequest {
+            run_id: Some(RunId {
+                value: run_id.to_string(),
+            }),
+            page: Some(Pagination {
+                page_size: 500,
+                page_token: String::new(),
+            }),
+            filter: Some(RunOutputFilter {
+                kind: RunOutputKind::Artifact as i32,
+                output_type: artifact_type_label(ArtifactType::Apk).into(),
+                path_contains: String::new(),
+                label_contains: String::new(),
+            }),
+        })
+        .await?
+        .into_inner()
+        .outputs;
+    let output = outputs
+        .iter()
+        .max_by_key(|output| output.created_at.as_ref().map(|ts| ts.unix_millis))
+        .ok_or_else(|| Status::not_found(format!("run {run_id} recorded no APK artifact")))?;
+    let artifact = StepArtifact::from_run_output(output);
+    if artifact.sha256.is_empty() {
+        return Err(Status::failed_precondition(format!(
+            "artifact {} of run {run_id} was recorded without a sha256",
+            artifact.path
+        )));
+    }
+    artifact
+        .verify()
+        .await
+        .map_err(Status::failed_precondition)?;
+    Ok(artifact)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn outputs_round_trip_and_verify_the_digest() {
+        let dir =
+            std::env::temp_dir().join(format!("apkw-workflow-artifact-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app-debug.apk");
+        fs::write(&path, b"abc").unwrap();
+
+        let artifact = StepArtifact::from_build(&Artifact {
+            path: path.display().to_string(),
+            sha256: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into(),
+            r#type: ArtifactType::Apk as i32,
+            metadata: vec![
+                KeyValue {
+                    key: "module".into(),
+                    value: "app".into(),
+                },
+                KeyValue {
+                    key: "abi".into(),
+                    value: "arm64-v8a".into(),
+                },
+            ],
+            ..Default::default()
+        });
+        let outputs: BTreeMap<String, String> = artifact
+            .outputs()
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        assert_eq!(outputs["artifact_type"], "apk");
+        assert!(!outputs.contains_key("artifact_variant"));
+        assert_eq!(StepArtifact::from_outputs(&outputs), Some(artifact.clone()));
+        assert!(artifact.verify().await.is_ok());
+
+        fs::write(&path, b"rebuilt").unwrap();
+        assert!(artifact.verify().await.unwrap_err().contains("changed"));
+        fs::remove_file(&path).unwrap();
+        assert!(artifact
+            .verify()
+            .await
+            .unwrap_err()
+            .contains("no longer exists"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
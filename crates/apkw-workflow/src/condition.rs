@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+
+use crate::template::{parse_ref, Ref, Scope};
+
+/// What a condition can look at besides template references.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CondRef {
+    /// Any template reference (`inputs.*`, `steps.ID.outputs.*`, `run.*`,
+    /// `target.id`).
+    Value(Ref),
+    /// `steps.ID.status`: `succeeded`, `failed` or `skipped` (on the
+    /// instance's target for `for_each: targets` steps).
+    Status(String),
+    /// `target.kind`: `device`, `emulatorlike`, `remote` or `unspecified`.
+    TargetKind,
+}
+
+#[derive(Clone, Debug)]
+enum Operand {
+    Literal(String),
+    Ref(CondRef),
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Truthy(Operand),
+    Eq(Operand, Operand),
+    Ne(Operand, Operand),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+/// A step's `if:` expression, e.g.
+/// `steps.install.succeeded && target.kind == emulatorlike`.
+///
+/// Operands are references, `true`/`false`, quoted strings or bare words;
+/// they combine with `==`, `!=`, `!`, `&&`, `||` and parentheses. A lone
+/// operand is true unless it is empty, `false` or `0`. `steps.ID.succeeded`,
+/// `.failed` and `.skipped` are shorthands for comparing `steps.ID.status`.
+#[derive(Clone, Debug)]
+pub(crate) struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+/// Step statuses and target kinds conditions read alongside the template
+/// scope. Per-target statuses of `for_each: targets` steps are keyed
+/// `STEP@TARGET`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RunState {
+    pub(crate) statuses: BTreeMap<String, String>,
+    pub(crate) target_kinds: BTreeMap<String, String>,
+}
+
+impl RunState {
+    pub(crate) fn status_key(step: &str, target: &str) -> String {
+        format!("{step}@{target}")
+    }
+
+    /// A step's status on `target`, falling back to its overall status;
+    /// empty for steps that have not run.
+    pub(crate) fn status(&self, step: &str, target: Option<&str>) -> &str {
+        target
+            .and_then(|target| self.statuses.get(&Self::status_key(step, target)))
+            .or_else(|| self.statuses.get(step))
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Eq,
+    Ne,
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+fn tokenize(raw: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = raw.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if ch == '(' { Token::Open } else { Token::Close });
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(c) if c == ch => break,
+                        Some(c) => text.push(c),
+                        None => return Err(format!("unterminated string in `{raw}`")),
+                    }
+                }
+                tokens.push(Token::Quoted(text));
+            }
+            '=' | '!' | '&' | '|' => {
+                chars.next();
+                let next = chars.peek().copied();
+                let token = match (ch, next) {
+                    ('=', Some('=')) => Token::Eq,
+                    ('!', Some('=')) => Token::Ne,
+                    ('&', Some('&')) => Token::And,
+                    ('|', Some('|')) => Token::Or,
+                    ('!', _) => {
+                        tokens.push(Token::Not);
+                        continue;
+                    }
+                    _ => return Err(format!("unexpected `{ch}` in `{raw}`")),
+                };
+                chars.next();
+                tokens.push(token);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()'\"=!&|".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_operand_word(word: &str) -> Result<Operand, String> {
+    if word == "target.kind" {
+        return Ok(Operand::Ref(CondRef::TargetKind));
+    }
+    if let Some(rest) = word.strip_prefix("steps.") {
+        if let Some((step, field)) = rest.split_once('.') {
+            match field {
+                "status" => return Ok(Operand::Ref(CondRef::Status(step.to_string()))),
+                "succeeded" | "failed" | "skipped" => {
+                    // Rewritten to `steps.ID.status == FIELD` by the caller.
+                    return Ok(Operand::Ref(CondRef::Status(format!("{step}.{field}"))));
+                }
+                _ => {}
+            }
+        }
+    }
+    // Any other dotted word must be a reference, so a typo like
+    // `input.variant` fails validation instead of comparing as text.
+    if word.contains('.') {
+        return parse_ref(word).map(|r| Operand::Ref(CondRef::Value(r)));
+    }
+    Ok(Operand::Literal(word.to_string()))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::Open) => {
+                self.pos += 1;
+                let expr = self.or()?;
+                if self.next() != Some(Token::Close) {
+                    return Err("missing `)`".into());
+                }
+                Ok(expr)
+            }
+            _ => self.compare(),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Word(word)) => parse_operand_word(&word),
+            Some(Token::Quoted(text)) => Ok(Operand::Literal(text)),
+            Some(other) => Err(format!("expected a value, found {other:?}")),
+            None => Err("expected a value, found the end".into()),
+        }
+    }
+
+    fn compare(&mut self) -> Result<Expr, String> {
+        let left = self.operand()?;
+        // `steps.ID.succeeded` and friends compare the step's status.
+        if let Operand::Ref(CondRef::Status(name)) = &left {
+            if let Some((step, status)) = name.split_once('.') {
+                return Ok(Expr::Eq(
+                    Operand::Ref(CondRef::Status(step.to_string())),
+                    Operand::Literal(status.to_string()),
+                ));
+            }
+        }
+        match self.peek() {
+            Some(Token::Eq) => {
+                self.pos += 1;
+                Ok(Expr::Eq(left, self.operand()?))
+            }
+            Some(Token::Ne) => {
+                self.pos += 1;
+                Ok(Expr::Ne(left, self.operand()?))
+            }
+            _ => Ok(Expr::Truthy(left)),
+        }
+    }
+}
+
+fn truthy(value: &str) -> bool {
+    !matches!(value.trim(), "" | "false" | "0")
+}
+
+impl Expr {
+    fn refs<'a>(&'a self, out: &mut Vec<&'a CondRef>) {
+        let mut operand = |operand: &'a Operand| {
+            if let Operand::Ref(r) = operand {
+                out.push(r);
+            }
+        };
+        match self {
+            Expr::Truthy(a) => operand(a),
+            Expr::Eq(a, b) | Expr::Ne(a, b) => {
+                operand(a);
+                operand(b);
+            }
+            Expr::Not(inner) => inner.refs(out),
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                a.refs(out);
+                b.refs(out);
+            }
+        }
+    }
+
+    fn eval(&self, scope: &Scope, state: &RunState) -> bool {
+        let value = |operand: &Operand| match operand {
+            Operand::Literal(text) => text.clone(),
+            Operand::Ref(r) => lookup(r, scope, state),
+        };
+        match self {
+            Expr::Truthy(a) => truthy(&value(a)),
+            Expr::Eq(a, b) => value(a).eq_ignore_ascii_case(&value(b)),
+            Expr::Ne(a, b) => !value(a).eq_ignore_ascii_case(&value(b)),
+            Expr::Not(inner) => !inner.eval(scope, state),
+            Expr::And(a, b) => a.eval(scope, state) && b.eval(scope, state),
+            Expr::Or(a, b) => a.eval(scope, state) || b.eval(scope, state),
+        }
+    }
+}
+
+fn lookup(r: &CondRef, scope: &Scope, state: &RunState) -> String {
+    match r {
+        CondRef::Value(r) => scope.lookup(r).cloned().unwrap_or_default(),
+        CondRef::Status(step) => state.status(step, scope.target.as_deref()).to_string(),
+        CondRef::TargetKind => scope
+            .target
+            .as_ref()
+            .and_then(|target| state.target_kinds.get(target))
+            .cloned()
+            .unwrap_or_default(),
+    }
+}
+
+impl Condition {
+    pub(crate) fn parse(raw: &str) -> Result<Self, String> {
+        let tokens = tokenize(raw)?;
+        if tokens.is_empty() {
+            return Err("empty condition".into());
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {token:?} in `{raw}`"));
+        }
+        Ok(Self {
+            source: raw.trim().to_string(),
+            expr,
+        })
+    }
+
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub(crate) fn refs(&self) -> Vec<&CondRef> {
+        let mut out = Vec::new();
+        self.expr.refs(&mut out);
+        out
+    }
+
+    pub(crate) fn eval(&self, scope: &Scope, state: &RunState) -> bool {
+        self.expr.eval(scope, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions_read_statuses_targets_and_inputs() {
+        let launch =
+            Condition::parse("steps.install.succeeded && target.kind == emulatorlike").unwrap();
+        let mut scope = Scope {
+            target: Some("emulator-5554".into()),
+            ..Default::default()
+        };
+        let mut state = RunState::default();
+        state
+            .target_kinds
+            .insert("emulator-5554".into(), "emulatorlike".into());
+        state.statuses.insert("install".into(), "failed".into());
+        state.statuses.insert(
+            RunState::status_key("install", "emulator-5554"),
+            "succeeded".into(),
+        );
+        assert!(launch.eval(&scope, &state));
+        scope.target = Some("R58M".into());
+        assert!(!launch.eval(&scope, &state));
+
+        scope.inputs.insert("variant".into(), "release".into());
+        let release =
+            Condition::parse("inputs.variant == 'release' || !(steps.build.status)").unwrap();
+        assert!(release.eval(&scope, &state));
+        scope.inputs.insert("variant".into(), "debug".into());
+        assert!(release.eval(&scope, &state));
+        state.statuses.insert("build".into(), "succeeded".into());
+        assert!(!release.eval(&scope, &state));
+        assert_eq!(release.refs().len(), 2);
+
+        assert!(Condition::parse("").is_err());
+        assert!(Condition::parse("steps.build.succeeded &&").is_err());
+        assert!(Condition::parse("input.variant == release").is_err());
+        assert!(Condition::parse("(inputs.variant").is_err());
+        assert!(Condition::parse("inputs.variant = release").is_err());
+    }
+}
@@ -0,0 +1,1212 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use apkw_proto::apkw::v1::{WorkflowInfo, WorkflowPipelineRequest, WorkflowStepInfo};
+use apkw_util::secrets::format_secret_ref;
+use serde::{Deserialize, Serialize};
+use tonic::Status;
+
+use crate::{
+    actions::{action_spec, rpc_action, InputValue, StepInputs},
+    condition::{CondRef, Condition, RunState},
+    resolve_project_name, resolve_variant,
+    template::{Ref, Scope, Template, FAILURE_RUN_KEYS},
+};
+
+/// Workflows live in `<project>/.apkw/workflows/<name>.{yaml,yml,json}`.
+const WORKFLOWS_DIR: &[&str] = &[".apkw", "workflows"];
+const WORKFLOW_EXTENSIONS: &[&str] = &["yaml", "yml", "json"];
+
+/// Request fields every workflow can reference as `${inputs.NAME}`.
+pub(crate) const REQUEST_INPUTS: &[&str] = &[
+    "project_id",
+    "project_path",
+    "project_name",
+    "template_id",
+    "toolchain_id",
+    "toolchain_set_id",
+    "target_id",
+    "apk_path",
+    "apk_sha256",
+    "application_id",
+    "activity",
+    "module",
+    "variant_name",
+    "variant",
+    "secrets",
+];
+
+/// A scalar as written in YAML or JSON; steps receive it as a string.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Scalar {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scalar::Bool(value) => write!(f, "{value}"),
+            Scalar::Int(value) => write!(f, "{value}"),
+            Scalar::Float(value) => write!(f, "{value}"),
+            Scalar::Text(value) => f.write_str(value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawInput {
+    List(Vec<Scalar>),
+    One(Scalar),
+}
+
+/// On-disk workflow file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct WorkflowDef {
+    name: String,
+    description: String,
+    /// Declared inputs with their default values.
+    inputs: BTreeMap<String, Scalar>,
+    steps: Vec<StepDef>,
+    /// Steps that run only when the run fails, before it is marked failed.
+    on_failure: Vec<StepDef>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct StepDef {
+    id: String,
+    action: String,
+    needs: Vec<String>,
+    with: BTreeMap<String, RawInput>,
+    /// Skip the step with a warning (instead of failing the run) when a
+    /// required input resolves to nothing.
+    optional: bool,
+    /// `targets` runs one instance of the step per request target, in
+    /// parallel, with `${target.id}` set to the instance's target.
+    for_each: String,
+    /// Run the step (or target instance) only when this expression holds.
+    /// Either way the step is skipped when a step it needs failed or was
+    /// skipped, unless the expression checks that step's status.
+    #[serde(rename = "if")]
+    condition: String,
+    /// A failure of this step is recorded but does not fail the run.
+    continue_on_failure: bool,
+}
+
+#[derive(Clone, Debug)]
+enum StepInput {
+    One(Template),
+    List(Vec<Template>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Step {
+    pub(crate) id: String,
+    pub(crate) action: String,
+    pub(crate) needs: Vec<String>,
+    pub(crate) optional: bool,
+    pub(crate) for_each_target: bool,
+    pub(crate) condition: Option<Condition>,
+    pub(crate) continue_on_failure: bool,
+    with: BTreeMap<String, StepInput>,
+}
+
+impl Step {
+    fn templates(&self) -> impl Iterator<Item = &Template> {
+        self.with.values().flat_map(|input| match input {
+            StepInput::One(template) => std::slice::from_ref(template),
+            StepInput::List(templates) => templates.as_slice(),
+        })
+    }
+
+    /// Every reference the step's inputs and condition make.
+    pub(crate) fn refs(&self) -> impl Iterator<Item = &Ref> {
+        let condition_refs =
+            self.condition
+                .iter()
+                .flat_map(Condition::refs)
+                .filter_map(|r| match r {
+                    CondRef::Value(r) => Some(r),
+                    _ => None,
+                });
+        self.templates()
+            .flat_map(Template::refs)
+            .chain(condition_refs)
+    }
+
+    /// Why the instance for `scope.target` does not run: a step it needs
+    /// did not succeed there, or its condition is false.
+    pub(crate) fn skip_reason(&self, scope: &Scope, state: &RunState) -> Option<String> {
+        if let Some(reason) = self.unmet_need(scope.target.as_deref(), state) {
+            return Some(reason);
+        }
+        let condition = self.condition.as_ref()?;
+        (!condition.eval(scope, state))
+            .then(|| format!("condition `{}` is false", condition.source()))
+    }
+
+    /// A step this one needs that failed or was skipped on `target`, so its
+    /// outputs are missing. Needs whose status the condition checks are left
+    /// to the condition.
+    pub(crate) fn unmet_need(&self, target: Option<&str>, state: &RunState) -> Option<String> {
+        let checked: Vec<&str> = self
+            .condition
+            .iter()
+            .flat_map(Condition::refs)
+            .filter_map(|r| match r {
+                CondRef::Status(step) => Some(step.as_str()),
+                _ => None,
+            })
+            .collect();
+        self.needs
+            .iter()
+            .filter(|need| !checked.contains(&need.as_str()))
+            .find_map(|need| {
+                let status = state.status(need, target);
+                matches!(status, "failed" | "skipped").then(|| format!("{need} {status}"))
+            })
+    }
+
+    pub(crate) fn resolve(&self, scope: &Scope) -> StepInputs {
+        let values = self
+            .with
+            .iter()
+            .map(|(key, input)| {
+                let value = match input {
+                    StepInput::One(template) => InputValue::One(template.render(scope)),
+                    StepInput::List(templates) => InputValue::List(
+                        templates
+                            .iter()
+                            .map(|template| template.render(scope))
+                            .filter(|value| !value.is_empty())
+                            .collect(),
+                    ),
+                };
+                (key.clone(), value)
+            })
+            .collect();
+        StepInputs { values }
+    }
+}
+
+/// A validated workflow: step ids are unique, actions and inputs are known,
+/// `needs` form no cycle, and every `${steps.ID...}` reference names a step
+/// the referencing step (transitively) needs. `on_failure` steps may only
+/// need each other but can read any step.
+#[derive(Clone, Debug)]
+pub(crate) struct Workflow {
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) defaults: BTreeMap<String, String>,
+    pub(crate) steps: Vec<Step>,
+    pub(crate) on_failure: Vec<Step>,
+    /// The definition this was compiled from, persisted with each run so
+    /// `ResumePipeline` runs the same steps.
+    pub(crate) source: WorkflowDef,
+}
+
+fn is_step_id(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-'))
+}
+
+fn parse_input(step_id: &str, key: &str, raw: &RawInput, errors: &mut Vec<String>) -> StepInput {
+    let mut parse = |value: String| match Template::parse(&value) {
+        Ok(template) => template,
+        Err(err) => {
+            errors.push(format!("step {step_id}: input {key}: {err}"));
+            Template::literal(&value)
+        }
+    };
+    match raw {
+        RawInput::One(value) => StepInput::One(parse(value.to_string())),
+        RawInput::List(values) => StepInput::List(
+            values
+                .iter()
+                .map(|value| parse(value.to_string()))
+                .collect(),
+        ),
+    }
+}
+
+fn compile_step(
+    def: &StepDef,
+    ids: &HashSet<&str>,
+    all_ids: &HashSet<&str>,
+    handler: bool,
+    errors: &mut Vec<String>,
+) -> Step {
+    let id = def.id.as_str();
+    match action_spec(&def.action) {
+        Some(spec) => {
+            for key in def.with.keys() {
+                if !spec.accepts(key) {
+                    errors.push(format!(
+                        "step {id}: {} does not take input {key}",
+                        def.action
+                    ));
+                }
+            }
+            for key in spec.required {
+                if !def.with.contains_key(*key) {
+                    errors.push(format!("step {id}: {} requires {key}", def.action));
+                }
+            }
+        }
+        None => match rpc_action(&def.action) {
+            Some(method) => {
+                for key in def.with.keys() {
+                    if method.field(key).is_none() {
+                        errors.push(format!(
+                            "step {id}: {}.{} has no field {key}",
+                            method.service, method.method
+                        ));
+                    }
+                }
+            }
+            None => errors.push(format!("step {id}: unknown action {:?}", def.action)),
+        },
+    }
+    let mut needs = Vec::new();
+    for need in &def.needs {
+        if need == id {
+            errors.push(format!("step {id} needs itself"));
+        } else if handler && !ids.contains(need.as_str()) && all_ids.contains(need.as_str()) {
+            errors.push(format!(
+                "on_failure step {id} needs {need}, which is not an on_failure step"
+            ));
+        } else if !ids.contains(need.as_str()) {
+            errors.push(format!("step {id} needs unknown step {need}"));
+        } else if !needs.contains(need) {
+            needs.push(need.clone());
+        }
+    }
+    let for_each_target = match def.for_each.as_str() {
+        "" => false,
+        "targets" => true,
+        other => {
+            errors.push(format!(
+                "step {id}: unknown for_each {other:?} (expected targets)"
+            ));
+            false
+        }
+    };
+    let condition = match def.condition.trim() {
+        "" => None,
+        raw => match Condition::parse(raw) {
+            Ok(condition) => Some(condition),
+            Err(err) => {
+                errors.push(format!("step {id}: if: {err}"));
+                None
+            }
+        },
+    };
+    let with = def
+        .with
+        .iter()
+        .map(|(key, raw)| (key.clone(), parse_input(id, key, raw, errors)))
+        .collect();
+    Step {
+        id: def.id.clone(),
+        action: def.action.clone(),
+        needs,
+        optional: def.optional,
+        for_each_target,
+        condition,
+        continue_on_failure: def.continue_on_failure,
+        with,
+    }
+}
+
+/// Checks what a step's inputs and condition read. `visible` holds the
+/// steps whose outputs and status it may use (`None` when a cycle makes
+/// that unknown).
+fn check_refs(
+    step: &Step,
+    declared: &BTreeMap<String, Scalar>,
+    visible: Option<&HashSet<String>>,
+    handler: bool,
+    errors: &mut Vec<String>,
+) {
+    let hidden = |source: &String| visible.is_some_and(|visible| !visible.contains(source));
+    for r in step.refs() {
+        match r {
+            Ref::Input(name)
+                if !REQUEST_INPUTS.contains(&name.as_str()) && !declared.contains_key(name) =>
+            {
+                errors.push(format!("step {}: input {name} is not declared", step.id));
+            }
+            Ref::StepOutput { step: source, .. } if hidden(source) => {
+                errors.push(format!(
+                    "step {} uses outputs of {source} but does not need it",
+                    step.id
+                ));
+            }
+            Ref::Run(key) if !handler && FAILURE_RUN_KEYS.contains(&key.as_str()) => {
+                errors.push(format!(
+                    "step {} uses run.{key}, which only on_failure steps have",
+                    step.id
+                ));
+            }
+            Ref::Target if !step.for_each_target => {
+                errors.push(format!(
+                    "step {} uses target.id without for_each: targets",
+                    step.id
+                ));
+            }
+            _ => {}
+        }
+    }
+    for r in step.condition.iter().flat_map(Condition::refs) {
+        match r {
+            CondRef::Status(source) if hidden(source) => {
+                errors.push(format!(
+                    "step {} uses the status of {source} but does not need it",
+                    step.id
+                ));
+            }
+            CondRef::TargetKind if !step.for_each_target => {
+                errors.push(format!(
+                    "step {} uses target.kind without for_each: targets",
+                    step.id
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl WorkflowDef {
+    /// Validates the definition, collecting every problem found.
+    pub(crate) fn compile(self) -> Result<Workflow, Vec<String>> {
+        let source = self.clone();
+        let mut errors = Vec::new();
+        if self.steps.is_empty() {
+            errors.push("workflow has no steps".to_string());
+        }
+        for name in self.inputs.keys() {
+            if REQUEST_INPUTS.contains(&name.as_str()) {
+                errors.push(format!("input {name} shadows a request field"));
+            }
+        }
+
+        // Ids are unique across steps and on_failure steps, so templates and
+        // run records never confuse the two.
+        let mut all_ids = HashSet::new();
+        for step in self.steps.iter().chain(&self.on_failure) {
+            if !is_step_id(&step.id) {
+                errors.push(format!(
+                    "step id {:?} must be lowercase letters, digits, '_' or '-'",
+                    step.id
+                ));
+            } else if !all_ids.insert(step.id.as_str()) {
+                errors.push(format!("duplicate step id {}", step.id));
+            }
+        }
+        let ids: HashSet<&str> = self.steps.iter().map(|step| step.id.as_str()).collect();
+        let handler_ids: HashSet<&str> = self
+            .on_failure
+            .iter()
+            .map(|step| step.id.as_str())
+            .collect();
+
+        let steps: Vec<Step> = self
+            .steps
+            .iter()
+            .map(|def| compile_step(def, &ids, &all_ids, false, &mut errors))
+            .collect();
+        let on_failure: Vec<Step> = self
+            .on_failure
+            .iter()
+            .map(|def| compile_step(def, &handler_ids, &all_ids, true, &mut errors))
+            .collect();
+
+        let mut ancestors_of = |steps: &[Step]| match ancestors(steps) {
+            Ok(ancestors) => Some(ancestors),
+            Err(cycle) => {
+                errors.push(format!("steps form a cycle: {}", cycle.join(", ")));
+                None
+            }
+        };
+        let step_ancestors = ancestors_of(&steps);
+        let handler_ancestors = ancestors_of(&on_failure);
+        let empty = HashSet::new();
+        for step in &steps {
+            let visible = step_ancestors
+                .as_ref()
+                .map(|ancestors| ancestors.get(&step.id).unwrap_or(&empty));
+            check_refs(step, &self.inputs, visible, false, &mut errors);
+        }
+        for step in &on_failure {
+            let visible = handler_ancestors.as_ref().map(|ancestors| {
+                let mut visible = ancestors.get(&step.id).cloned().unwrap_or_default();
+                visible.extend(ids.iter().map(|id| id.to_string()));
+                visible
+            });
+            check_refs(step, &self.inputs, visible.as_ref(), true, &mut errors);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(Workflow {
+            name: self.name,
+            description: self.description,
+            defaults: self
+                .inputs
+                .into_iter()
+                .map(|(key, value)| (key, value.to_string()))
+                .collect(),
+            steps,
+            on_failure,
+            source,
+        })
+    }
+}
+
+/// Every step each step transitively needs, or the steps left in a cycle.
+fn ancestors(steps: &[Step]) -> Result<HashMap<String, HashSet<String>>, Vec<String>> {
+    let mut done: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut pending: Vec<&Step> = steps.iter().collect();
+    while !pending.is_empty() {
+        let before = pending.len();
+        pending.retain(|step| {
+            if !step.needs.iter().all(|need| done.contains_key(need)) {
+                return true;
+            }
+            let mut set = HashSet::new();
+            for need in &step.needs {
+                set.insert(need.clone());
+                set.extend(done[need].iter().cloned());
+            }
+            done.insert(step.id.clone(), set);
+            false
+        });
+        if pending.len() == before {
+            return Err(pending.iter().map(|step| step.id.clone()).collect());
+        }
+    }
+    Ok(done)
+}
+
+/// Steps grouped the way a run executes them: each wave holds the steps
+/// whose needs all ran in earlier waves.
+fn waves(steps: &[Step]) -> Vec<Vec<&Step>> {
+    let mut finished: HashSet<&str> = HashSet::new();
+    let mut waves = Vec::new();
+    while finished.len() < steps.len() {
+        let wave: Vec<&Step> = steps
+            .iter()
+            .filter(|step| {
+                !finished.contains(step.id.as_str())
+                    && step
+                        .needs
+                        .iter()
+                        .all(|need| finished.contains(need.as_str()))
+            })
+            .collect();
+        if wave.is_empty() {
+            break;
+        }
+        finished.extend(wave.iter().map(|step| step.id.as_str()));
+        waves.push(wave);
+    }
+    waves
+}
+
+impl Workflow {
+    pub(crate) fn waves(&self) -> Vec<Vec<&Step>> {
+        waves(&self.steps)
+    }
+
+    pub(crate) fn on_failure_waves(&self) -> Vec<Vec<&Step>> {
+        waves(&self.on_failure)
+    }
+
+    /// Whether a condition reads `target.kind`, which needs a target lookup.
+    pub(crate) fn uses_target_kind(&self) -> bool {
+        self.steps
+            .iter()
+            .chain(&self.on_failure)
+            .flat_map(|step| step.condition.iter().flat_map(Condition::refs))
+            .any(|r| *r == CondRef::TargetKind)
+    }
+
+    pub(crate) fn info(&self, path: &Path) -> WorkflowInfo {
+        let step_info = |step: &Step, on_failure: bool| WorkflowStepInfo {
+            id: step.id.clone(),
+            action: step.action.clone(),
+            needs: step.needs.clone(),
+            condition: step
+                .condition
+                .as_ref()
+                .map(|condition| condition.source().to_string())
+                .unwrap_or_default(),
+            on_failure,
+            continue_on_failure: step.continue_on_failure,
+        };
+        WorkflowInfo {
+            name: self.name.clone(),
+            path: path.display().to_string(),
+            description: self.description.clone(),
+            steps: self
+                .steps
+                .iter()
+                .map(|step| step_info(step, false))
+                .chain(self.on_failure.iter().map(|step| step_info(step, true)))
+                .collect(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+fn workflows_dir(project_root: &Path) -> PathBuf {
+    WORKFLOWS_DIR
+        .iter()
+        .fold(project_root.to_path_buf(), |dir, part| dir.join(part))
+}
+
+/// Reads a workflow file; `.json` files are JSON, anything else YAML.
+fn parse_workflow_file(path: &Path) -> Result<WorkflowDef, String> {
+    let data = fs::read_to_string(path).map_err(|err| format!("read failed: {err}"))?;
+    let mut def: WorkflowDef = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&data).map_err(|err| err.to_string())?
+    } else {
+        serde_yaml::from_str(&data).map_err(|err| err.to_string())?
+    };
+    if def.name.trim().is_empty() {
+        def.name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+    }
+    Ok(def)
+}
+
+/// Loads and validates the named workflow of a project.
+pub(crate) fn load_workflow(project_root: &Path, name: &str) -> Result<Workflow, Vec<String>> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(vec![format!("invalid workflow name {name:?}")]);
+    }
+    let dir = workflows_dir(project_root);
+    let path = WORKFLOW_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{name}.{ext}")))
+        .find(|path| path.is_file())
+        .ok_or_else(|| vec![format!("workflow {name} not found in {}", dir.display())])?;
+    parse_workflow_file(&path)
+        .map_err(|err| vec![format!("{}: {err}", path.display())])?
+        .compile()
+}
+
+/// Describes every workflow file of a project, including invalid ones.
+pub(crate) fn list_workflows(project_root: &Path) -> Vec<WorkflowInfo> {
+    let dir = workflows_dir(project_root);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| WORKFLOW_EXTENSIONS.iter().any(|known| ext == *known))
+        })
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            match parse_workflow_file(&path).map_err(|err| vec![err]) {
+                Ok(def) => match def.compile() {
+                    Ok(workflow) => workflow.info(&path),
+                    Err(errors) => WorkflowInfo {
+                        name: stem,
+                        path: path.display().to_string(),
+                        errors,
+                        ..Default::default()
+                    },
+                },
+                Err(errors) => WorkflowInfo {
+                    name: stem,
+                    path: path.display().to_string(),
+                    errors,
+                    ..Default::default()
+                },
+            }
+        })
+        .collect()
+}
+
+fn trimmed_id(id: &Option<apkw_proto::apkw::v1::Id>) -> String {
+    id.as_ref()
+        .map(|id| id.value.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Values of the request fields listed in `REQUEST_INPUTS`.
+pub(crate) fn request_inputs(req: &WorkflowPipelineRequest) -> BTreeMap<String, String> {
+    let project_path = req.project_path.trim().to_string();
+    let project_name = if !req.project_name.trim().is_empty() {
+        req.project_name.trim().to_string()
+    } else if !project_path.is_empty() {
+        resolve_project_name(&project_path)
+    } else {
+        "apkw-project".to_string()
+    };
+    let variant = match resolve_variant(req.build_variant) {
+        apkw_proto::apkw::v1::BuildVariant::Release => "release",
+        _ => "debug",
+    };
+    BTreeMap::from([
+        ("project_id".into(), trimmed_id(&req.project_id)),
+        ("project_path".into(), project_path),
+        ("project_name".into(), project_name),
+        ("template_id".into(), trimmed_id(&req.template_id)),
+        ("toolchain_id".into(), trimmed_id(&req.toolchain_id)),
+        ("toolchain_set_id".into(), trimmed_id(&req.toolchain_set_id)),
+        ("target_id".into(), trimmed_id(&req.target_id)),
+        ("apk_path".into(), req.apk_path.trim().to_string()),
+        (
+            "apk_sha256".into(),
+            req.apk_sha256.trim().to_ascii_lowercase(),
+        ),
+        (
+            "application_id".into(),
+            req.application_id.trim().to_string(),
+        ),
+        ("activity".into(), req.activity.trim().to_string()),
+        ("module".into(), req.module.trim().to_string()),
+        ("variant_name".into(), req.variant_name.trim().to_string()),
+        ("variant".into(), variant.to_string()),
+        (
+            "secrets".into(),
+            req.secrets
+                .iter()
+                .map(format_secret_ref)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+    ])
+}
+
+fn one(value: &str) -> RawInput {
+    RawInput::One(Scalar::Text(value.to_string()))
+}
+
+/// Builds the workflow for a request without `workflow`: the steps selected
+/// by `options`, or inferred from the supplied fields when options are unset.
+/// Inferred steps are optional, so one that lacks inputs is skipped. Install
+/// and launch run once per request target.
+#[allow(clippy::result_large_err)]
+pub(crate) fn pipeline_workflow(req: &WorkflowPipelineRequest) -> Result<Workflow, Status> {
+    let inputs = request_inputs(req);
+    let has = |key: &str| inputs.get(key).is_some_and(|value| !value.is_empty());
+    // `artifact_run_id` supplies apk_path and apk_sha256 from an earlier
+    // run, so nothing needs building for the install.
+    let reuse_artifact = req
+        .artifact_run_id
+        .as_ref()
+        .is_some_and(|id| !id.value.trim().is_empty());
+    let has_apk = has("apk_path") || reuse_artifact;
+    let inferred = req.options.is_none();
+    let options = req.options.unwrap_or_default();
+    let wants = |explicit: bool, inferred_when: bool| {
+        if inferred {
+            inferred_when
+        } else {
+            explicit
+        }
+    };
+
+    let mut steps: Vec<StepDef> = Vec::new();
+    let mut push = |id: &str, action: &str, fan_out: bool, with: Vec<(&str, RawInput)>| {
+        let needs = steps.last().map(|prev| vec![prev.id.clone()]);
+        steps.push(StepDef {
+            id: id.to_string(),
+            action: action.to_string(),
+            needs: needs.unwrap_or_default(),
+            with: with
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+            optional: inferred,
+            for_each: if fan_out {
+                "targets".into()
+            } else {
+                String::new()
+            },
+            ..Default::default()
+        });
+    };
+
+    let wants_create = wants(options.create_project, has("template_id"));
+    let wants_open = wants(
+        options.open_project,
+        !has("project_id") && has("project_path"),
+    );
+    // Later steps use the project id produced by create/open when present.
+    let mut project_id_sources = Vec::new();
+    if wants_open {
+        project_id_sources.push("steps.open_project.outputs.project_id");
+    }
+    if wants_create {
+        project_id_sources.push("steps.create_project.outputs.project_id");
+    }
+    project_id_sources.push("inputs.project_id");
+    let project_id = format!("${{{}}}", project_id_sources.join(" || "));
+
+    if wants_create {
+        push(
+            "create_project",
+            "project.create",
+            false,
+            vec![
+                ("template_id", one("${inputs.template_id}")),
+                ("project_path", one("${inputs.project_path}")),
+                ("name", one("${inputs.project_name}")),
+                ("toolchain_set_id", one("${inputs.toolchain_set_id}")),
+            ],
+        );
+    }
+    if wants_open {
+        push(
+            "open_project",
+            "project.open",
+            false,
+            vec![("project_path", one("${inputs.project_path}"))],
+        );
+    }
+    if wants(options.verify_toolchain, has("toolchain_id")) {
+        push(
+            "verify_toolchain",
+            "toolchain.verify",
+            false,
+            vec![("toolchain_id", one("${inputs.toolchain_id}"))],
+        );
+    }
+    let project = format!(
+        "${{{} || inputs.project_path}}",
+        project_id_sources.join(" || ")
+    );
+    let wants_build = wants(
+        options.build,
+        !reuse_artifact && (has("project_id") || has("project_path")),
+    );
+    if wants_build {
+        push(
+            "build",
+            "build.run",
+            false,
+            vec![
+                ("project", one(&project)),
+                ("variant", one("${inputs.variant}")),
+                ("module", one("${inputs.module}")),
+                ("variant_name", one("${inputs.variant_name}")),
+                ("secrets", one("${inputs.secrets}")),
+                (
+                    "tasks",
+                    RawInput::List(
+                        req.tasks
+                            .iter()
+                            .map(|task| Scalar::Text(task.clone()))
+                            .collect(),
+                    ),
+                ),
+            ],
+        );
+    }
+    if wants(options.run_tests, false) {
+        push(
+            "test",
+            "build.test",
+            false,
+            vec![
+                ("project", one(&project)),
+                ("variant", one("${inputs.variant}")),
+                ("module", one("${inputs.module}")),
+                ("variant_name", one("${inputs.variant_name}")),
+                ("secrets", one("${inputs.secrets}")),
+            ],
+        );
+    }
+    if wants(options.install_apk, has_apk) {
+        let (apk_path, apk_sha256) = if wants_build && !has_apk {
+            (
+                "${inputs.apk_path || steps.build.outputs.artifact_path}",
+                "${steps.build.outputs.artifact_sha256}",
+            )
+        } else {
+            ("${inputs.apk_path}", "${inputs.apk_sha256}")
+        };
+        push(
+            "install",
+            "targets.install",
+            true,
+            vec![
+                ("target_id", one("${target.id}")),
+                ("apk_path", one(apk_path)),
+                ("apk_sha256", one(apk_sha256)),
+                ("project_id", one(&project_id)),
+            ],
+        );
+    }
+    if wants(options.launch_app, has("application_id")) {
+        push(
+            "launch",
+            "targets.launch",
+            true,
+            vec![
+                ("target_id", one("${target.id}")),
+                ("application_id", one("${inputs.application_id}")),
+                ("activity", one("${inputs.activity}")),
+            ],
+        );
+    }
+    let bundle_inputs = || {
+        vec![
+            ("project_id", one(&project_id)),
+            ("target_id", one("${inputs.target_id}")),
+            ("toolchain_set_id", one("${inputs.toolchain_set_id}")),
+        ]
+    };
+    if wants(options.export_support_bundle, false) {
+        push(
+            "support_bundle",
+            "observe.support_bundle",
+            false,
+            bundle_inputs(),
+        );
+    }
+    if wants(options.export_evidence_bundle, false) {
+        push("evidence_bundle", "observe.evidence_bundle", false, vec![]);
+    }
+    let mut on_failure = Vec::new();
+    if wants(options.support_bundle_on_failure, false) {
+        on_failure.push(StepDef {
+            id: "failure_bundle".into(),
+            action: "observe.support_bundle".into(),
+            with: bundle_inputs()
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+            ..Default::default()
+        });
+    }
+
+    let def = WorkflowDef {
+        name: "pipeline".into(),
+        description: String::new(),
+        inputs: BTreeMap::new(),
+        steps,
+        on_failure,
+    };
+    // An empty step list is a valid (no-op) pipeline request.
+    if def.steps.is_empty() {
+        return Ok(Workflow {
+            name: def.name.clone(),
+            description: String::new(),
+            defaults: BTreeMap::new(),
+            steps: Vec::new(),
+            on_failure: Vec::new(),
+            source: def,
+        });
+    }
+    def.compile().map_err(|errors| {
+        Status::internal(format!(
+            "generated pipeline workflow is invalid: {}",
+            errors.join("; ")
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apkw_proto::apkw::v1::{Id, RunId, WorkflowPipelineOptions};
+
+    fn parse(yaml: &str) -> Result<Workflow, Vec<String>> {
+        serde_yaml::from_str::<WorkflowDef>(yaml)
+            .expect("valid yaml")
+            .compile()
+    }
+
+    #[test]
+    fn workflows_are_validated_up_front() {
+        let workflow = parse(
+            r#"
+name: build-and-install
+inputs:
+  device: emulator-5554
+steps:
+  - id: build
+    action: build.run
+    with:
+      project: ${inputs.project_path}
+      tasks: [":app:assembleDebug"]
+      clean_first: true
+  - id: install
+    action: targets.install
+    needs: [build]
+    with:
+      target_id: ${inputs.device}
+      apk_path: ${steps.build.outputs.artifact_path}
+  - id: launch
+    action: targets.Launch
+    needs: [install]
+    with:
+      target_id: ${inputs.device}
+      application_id: com.example.app
+"#,
+        )
+        .unwrap();
+        assert_eq!(workflow.steps.len(), 3);
+        assert_eq!(workflow.defaults["device"], "emulator-5554");
+
+        let mut scope = Scope::default();
+        scope
+            .inputs
+            .insert("project_path".into(), "/work/app".into());
+        let inputs = workflow.steps[0].resolve(&scope);
+        assert_eq!(inputs.get("project"), "/work/app");
+        assert_eq!(inputs.list("tasks"), vec![":app:assembleDebug"]);
+        assert_eq!(inputs.get("clean_first"), "true");
+
+        let errors = parse(
+            r#"
+steps:
+  - id: a
+    action: build.run
+    needs: [b]
+    with:
+      project: ${steps.c.outputs.project_id}
+      colour: blue
+  - id: b
+    action: targets.fly
+    needs: [a]
+  - id: c
+    action: project.open
+    with:
+      project_path: ${inputs.missing}/${target.id}
+  - id: d
+    action: targets.InstallApk
+    with:
+      apk_pth: /tmp/app.apk
+"#,
+        )
+        .unwrap_err();
+        let has = |needle: &str| errors.iter().any(|err| err.contains(needle));
+        assert!(has("does not take input colour"), "{errors:?}");
+        assert!(has("unknown action \"targets.fly\""), "{errors:?}");
+        assert!(
+            has("TargetService.InstallApk has no field apk_pth"),
+            "{errors:?}"
+        );
+        assert!(has("cycle"), "{errors:?}");
+        assert!(has("input missing is not declared"), "{errors:?}");
+        assert!(has("uses target.id without for_each"), "{errors:?}");
+    }
+
+    #[test]
+    fn conditions_and_on_failure_steps_are_validated() {
+        let workflow = parse(
+            r#"
+steps:
+  - id: install
+    action: targets.install
+    for_each: targets
+    continue_on_failure: true
+    with:
+      target_id: ${target.id}
+      apk_path: ${inputs.apk_path}
+  - id: launch
+    action: targets.launch
+    needs: [install]
+    for_each: targets
+    if: steps.install.succeeded && target.kind == emulatorlike
+    with:
+      target_id: ${target.id}
+      application_id: ${inputs.application_id}
+on_failure:
+  - id: bundle
+    action: observe.support_bundle
+    if: inputs.variant == release || steps.launch.failed
+    with:
+      project_id: ${steps.install.outputs.project_id || inputs.project_id}
+"#,
+        )
+        .unwrap();
+        assert!(workflow.steps[0].continue_on_failure);
+        assert!(workflow.uses_target_kind());
+        assert_eq!(workflow.on_failure_waves().len(), 1);
+
+        let mut scope = Scope {
+            target: Some("emulator-5554".into()),
+            ..Default::default()
+        };
+        let mut state = RunState::default();
+        state
+            .target_kinds
+            .insert("emulator-5554".into(), "emulatorlike".into());
+        state.statuses.insert(
+            RunState::status_key("install", "emulator-5554"),
+            "succeeded".into(),
+        );
+        let launch = &workflow.steps[1];
+        assert_eq!(launch.skip_reason(&scope, &state), None);
+        scope.target = Some("R58M".into());
+        assert!(launch
+            .skip_reason(&scope, &state)
+            .unwrap()
+            .contains("is false"));
+
+        let errors = parse(
+            r#"
+steps:
+  - id: build
+    action: build.run
+    if: steps.install.succeeded
+    with:
+      project: ${run.error}
+  - id: install
+    action: targets.install
+    if: target.kind == device
+    with:
+      target_id: device
+      apk_path: ${inputs.apk_path}
+  - id: launch
+    action: targets.launch
+    if: steps.install.succeeded &&
+    with:
+      target_id: device
+      application_id: app
+on_failure:
+  - id: build
+    action: observe.evidence_bundle
+  - id: notify
+    action: observe.evidence_bundle
+    needs: [install]
+"#,
+        )
+        .unwrap_err();
+        let has = |needle: &str| errors.iter().any(|err| err.contains(needle));
+        assert!(has("build uses the status of install"), "{errors:?}");
+        assert!(has("uses run.error"), "{errors:?}");
+        assert!(has("uses target.kind without for_each"), "{errors:?}");
+        assert!(has("step launch: if:"), "{errors:?}");
+        assert!(has("duplicate step id build"), "{errors:?}");
+        assert!(
+            has("notify needs install, which is not an on_failure step"),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn pipeline_options_become_a_linear_workflow() {
+        let req = WorkflowPipelineRequest {
+            project_path: "/work/app".into(),
+            target_id: Some(Id {
+                value: "emulator-5554".into(),
+            }),
+            options: Some(WorkflowPipelineOptions {
+                open_project: true,
+                build: true,
+                install_apk: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let workflow = pipeline_workflow(&req).unwrap();
+        let ids: Vec<_> = workflow.steps.iter().map(|step| step.id.as_str()).collect();
+        assert_eq!(ids, ["open_project", "build", "install"]);
+        assert_eq!(workflow.steps[2].needs, ["build"]);
+        assert!(workflow.steps.iter().all(|step| !step.optional));
+
+        let mut scope = Scope {
+            inputs: request_inputs(&req),
+            ..Default::default()
+        };
+        assert_eq!(
+            workflow.steps[1].resolve(&scope).get("project"),
+            "/work/app"
+        );
+        scope.steps.insert(
+            "open_project".into(),
+            BTreeMap::from([("project_id".into(), "proj-1".into())]),
+        );
+        scope.steps.insert(
+            "build".into(),
+            BTreeMap::from([
+                ("artifact_path".into(), "/out/app.apk".into()),
+                ("artifact_sha256".into(), "ab12".into()),
+            ]),
+        );
+        assert_eq!(workflow.steps[1].resolve(&scope).get("project"), "proj-1");
+        assert!(workflow.steps[2].for_each_target);
+        scope.target = Some("emulator-5554".into());
+        let install = workflow.steps[2].resolve(&scope);
+        assert_eq!(install.get("target_id"), "emulator-5554");
+        assert_eq!(install.get("apk_path"), "/out/app.apk");
+        assert_eq!(install.get("apk_sha256"), "ab12");
+        assert_eq!(install.get("project_id"), "proj-1");
+
+        let req = WorkflowPipelineRequest {
+            options: Some(WorkflowPipelineOptions {
+                build: true,
+                support_bundle_on_failure: true,
+                ..Default::default()
+            }),
+            ..req
+        };
+        let workflow = pipeline_workflow(&req).unwrap();
+        assert_eq!(workflow.steps.len(), 1);
+        assert_eq!(workflow.on_failure[0].action, "observe.support_bundle");
+    }
+
+    #[test]
+    fn artifact_run_replaces_the_inferred_build() {
+        let req = WorkflowPipelineRequest {
+            project_path: "/work/app".into(),
+            target_id: Some(Id {
+                value: "emulator-5554".into(),
+            }),
+            artifact_run_id: Some(RunId {
+                value: "run-1".into(),
+            }),
+            ..Default::default()
+        };
+        let workflow = pipeline_workflow(&req).unwrap();
+        let ids: Vec<_> = workflow.steps.iter().map(|step| step.id.as_str()).collect();
+        assert_eq!(ids, ["open_project", "install"]);
+
+        let mut scope = Scope {
+            inputs: request_inputs(&req),
+            target: Some("emulator-5554".into()),
+            ..Default::default()
+        };
+        scope
+            .inputs
+            .insert("apk_path".into(), "/runs/app-release.apk".into());
+        scope.inputs.insert("apk_sha256".into(), "cd34".into());
+        let install = workflow.steps[1].resolve(&scope);
+        assert_eq!(install.get("apk_path"), "/runs/app-release.apk");
+        assert_eq!(install.get("apk_sha256"), "cd34");
+    }
+}
@@ -0,0 +1,996 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use apkw_proto::apkw::v1::{job_service_client::JobServiceClient, JobState, KeyValue};
+use apkw_util::now_millis;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use tonic::transport::Channel;
+use tracing::warn;
+
+use crate::{
+    actions::{action_spec, StepEnv, StepInputs, StepRunner},
+    condition::RunState,
+    connect_job,
+    definition::{Step, Workflow},
+    fail_pipeline, metric, publish_completed, publish_log, publish_progress, publish_state,
+    runs::{RunRecord, RunStore, StepRecord, StepStatus},
+    targets::target_kinds,
+    template::Scope,
+    upsert_run_best_effort, WorkflowConfig,
+};
+
+/// Identity of one pipeline run.
+pub(crate) struct RunIds {
+    pub(crate) pipeline_job_id: String,
+    pub(crate) run_id: String,
+    pub(crate) correlation_id: String,
+}
+
+/// Bookkeeping for the run record while a workflow runs.
+struct Run {
+    config: WorkflowConfig,
+    job_client: JobServiceClient<Channel>,
+    ids: RunIds,
+    project_id: String,
+    target_id: String,
+    toolchain_set_id: String,
+    started_at: i64,
+    store: RunStore,
+    /// Persisted step state; per-target results of fan-out steps are
+    /// `success` or `failed at <step>`.
+    state: RunRecord,
+    targets: Vec<String>,
+    scope: Scope,
+    /// Step statuses and target kinds for step conditions.
+    conditions: RunState,
+    project_from_steps: bool,
+}
+
+impl Run {
+    async fn log(&mut self, line: &str) {
+        let _ = publish_log(&mut self.job_client, &self.ids.pipeline_job_id, line).await;
+    }
+
+    fn target_summary(&self) -> Vec<KeyValue> {
+        self.state
+            .target_results
+            .iter()
+            .map(|(target, result)| metric(&format!("target.{target}"), result))
+            .collect()
+    }
+
+    async fn record(
+        &mut self,
+        result: &str,
+        mut summary: Vec<KeyValue>,
+        finished_at: Option<i64>,
+    ) -> Result<(), tonic::Status> {
+        if !self.state.trigger.is_empty() {
+            summary.push(metric("trigger", &self.state.trigger));
+        }
+        if !self.state.trigger_detail.is_empty() {
+            summary.push(metric("trigger_detail", &self.state.trigger_detail));
+        }
+        upsert_run_best_effort(
+            &self.config.observe_addr,
+            &self.ids.run_id,
+            &self.ids.correlation_id,
+            &self.project_id,
+            &self.target_id,
+            &self.toolchain_set_id,
+            &self.state.job_ids,
+            result,
+            summary,
+            Some(self.started_at),
+            finished_at,
+        )
+        .await
+    }
+
+    /// Where records of main steps or of `on_failure` steps are kept.
+    fn records(&mut self, handlers: bool) -> &mut BTreeMap<String, StepRecord> {
+        if handlers {
+            &mut self.state.handlers
+        } else {
+            &mut self.state.steps
+        }
+    }
+
+    fn take_project_id(&mut self, outputs: &BTreeMap<String, String>) {
+        if let Some(pid) = outputs.get("project_id").filter(|pid| !pid.is_empty()) {
+            self.project_id = pid.clone();
+            self.project_from_steps = true;
+        }
+    }
+
+    async fn finish(&mut self, result: &str) {
+        self.state.result = result.to_string();
+        self.store.save(&self.state).await;
+        self.store.end(&self.ids.run_id).await;
+    }
+
+    async fn fail(&mut self, message: &str, technical: &str) {
+        fail_pipeline(
+            &mut self.job_client,
+            &self.ids.pipeline_job_id,
+            &self.ids.correlation_id,
+            message,
+            technical,
+        )
+        .await;
+        let mut summary = vec![metric("error", message), metric("detail", technical)];
+        summary.extend(self.target_summary());
+        let _ = self.record("failed", summary, Some(now_millis())).await;
+        self.finish("failed").await;
+        let _ = publish_state(
+            &mut self.job_client,
+            &self.ids.pipeline_job_id,
+            JobState::Failed,
+        )
+        .await;
+    }
+}
+
+/// A started step: what its finished instances (the step itself, or one
+/// per target of a `for_each: targets` step) produced so far.
+#[derive(Default)]
+struct Started {
+    /// Instances still running.
+    pending: usize,
+    /// Instances started.
+    runs: usize,
+    /// Outputs, including those a fan-out step carried over from a previous
+    /// attempt.
+    outputs: BTreeMap<String, String>,
+    done_targets: Vec<String>,
+    /// `(target, message, detail)` of each failed instance.
+    failures: Vec<(Option<String>, String, String)>,
+}
+
+/// The step that stopped a run and why.
+struct Failure {
+    step: String,
+    message: String,
+    detail: String,
+}
+
+/// Runs a workflow under its `workflow.pipeline` job. Each step starts as
+/// soon as the steps it needs finished, and the per-target instances of
+/// `for_each: targets` steps run in parallel. Once a step fails the run,
+/// no further steps start; steps already running are awaited and recorded,
+/// then its `on_failure` steps run. The `reused` steps of `state` (a
+/// resumed run, see `RunRecord::reusable_steps`) are not run again; their
+/// recorded outputs are used instead.
+pub(crate) async fn run_workflow(
+    config: WorkflowConfig,
+    store: RunStore,
+    ids: RunIds,
+    workflow: Workflow,
+    state: RunRecord,
+    reused: Vec<String>,
+) {
+    let job_client = match connect_job(&config.job_addr).await {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("pipeline job client error: {err}");
+            store.end(&ids.run_id).await;
+            return;
+        }
+    };
+    drive(
+        config,
+        job_client,
+        store,
+        ids,
+        workflow,
+        state,
+        reused,
+        &StepRunner::Services,
+    )
+    .await;
+}
+
+/// `run_workflow` with `runner` making the step calls.
+#[allow(clippy::too_many_arguments)]
+async fn drive(
+    config: WorkflowConfig,
+    job_client: JobServiceClient<Channel>,
+    store: RunStore,
+    ids: RunIds,
+    workflow: Workflow,
+    state: RunRecord,
+    reused: Vec<String>,
+    runner: &StepRunner,
+) {
+    let inputs = state.inputs.clone();
+    let targets = state.targets.clone();
+    let input = |key: &str| inputs.get(key).cloned().unwrap_or_default();
+    let run_id = ids.run_id.clone();
+    let correlation_id = ids.correlation_id.clone();
+    let mut run = Run {
+        project_id: input("project_id"),
+        target_id: input("target_id"),
+        toolchain_set_id: input("toolchain_set_id"),
+        config: config.clone(),
+        job_client,
+        ids,
+        started_at: now_millis(),
+        store,
+        state,
+        targets: targets.clone(),
+        scope: Scope {
+            inputs: inputs.clone(),
+            steps: BTreeMap::new(),
+            run: BTreeMap::from([
+                ("run_id".to_string(), run_id.clone()),
+                ("correlation_id".to_string(), correlation_id.clone()),
+            ]),
+            target: None,
+        },
+        conditions: RunState::default(),
+        project_from_steps: false,
+    };
+    let env = StepEnv {
+        config: &config,
+        run_id: &run_id,
+        correlation_id: &correlation_id,
+    };
+
+    run.log(&format!(
+        "pipeline run_id={run_id} correlation_id={correlation_id} workflow={}\n",
+        workflow.name
+    ))
+    .await;
+    if targets.len() > 1 {
+        run.log(&format!("pipeline targets={}\n", targets.join(",")))
+            .await;
+    }
+    if workflow.uses_target_kind() && !targets.is_empty() {
+        match target_kinds(&config, &targets).await {
+            Ok(kinds) => run.conditions.target_kinds = kinds,
+            Err(err) => {
+                run.log(&format!(
+                    "WARN: target kind lookup failed, target.kind is empty: {}\n",
+                    err.message()
+                ))
+                .await
+            }
+        }
+    }
+
+    let mut finished: HashSet<&str> = HashSet::new();
+    for step in &workflow.steps {
+        let Some(record) = run.state.steps.get(&step.id).cloned() else {
+            continue;
+        };
+        if !reused.contains(&step.id) {
+            if record.status == StepStatus::Succeeded {
+                run.log(&format!(
+                    "step {} output artifact is missing or changed, running it again\n",
+                    step.id
+                ))
+                .await;
+            }
+            continue;
+        }
+        run.take_project_id(&record.outputs);
+        run.conditions
+            .statuses
+            .insert(step.id.clone(), record.status.as_str().to_string());
+        for target in &record.done_targets {
+            run.conditions.statuses.insert(
+                RunState::status_key(&step.id, target),
+                StepStatus::Succeeded.as_str().to_string(),
+            );
+        }
+        run.scope.steps.insert(step.id.clone(), record.outputs);
+        finished.insert(step.id.as_str());
+    }
+    if !finished.is_empty() {
+        let mut reused: Vec<&str> = finished.iter().copied().collect();
+        reused.sort_unstable();
+        run.log(&format!(
+            "resuming run {run_id}: reusing steps {}\n",
+            reused.join(", ")
+        ))
+        .await;
+    }
+
+    let _ = publish_state(
+        &mut run.job_client,
+        &run.ids.pipeline_job_id,
+        JobState::Running,
+    )
+    .await;
+    run.state.result = "running".into();
+    run.store.save(&run.state).await;
+    if let Err(err) = run.record("running", vec![], None).await {
+        run.log(&format!("WARN: failed to upsert run start: {err}\n"))
+            .await;
+    }
+
+    if let Err(failure) = run_steps(&mut run, &env, runner, &workflow.steps, finished, false).await
+    {
+        run_on_failure(&mut run, &env, runner, &workflow, &failure).await;
+        run.fail(&failure.message, &failure.detail).await;
+        return;
+    }
+
+    let mut outputs = vec![
+        metric("run_id", &run_id),
+        metric("correlation_id", &correlation_id),
+    ];
+    if run.project_from_steps {
+        outputs.push(metric("project_id", &run.project_id));
+    }
+    if let Some(path) = run
+        .scope
+        .steps
+        .values()
+        .find_map(|step_outputs| step_outputs.get("artifact_path"))
+    {
+        outputs.push(metric("artifact_path", path));
+    }
+    for (target, result) in &run.state.target_results {
+        outputs.push(metric(&format!("target.{target}.result"), result));
+    }
+    for step in &workflow.steps {
+        for (key, value) in run.scope.steps.get(&step.id).into_iter().flatten() {
+            outputs.push(KeyValue {
+                key: format!("{}.{key}", step.id),
+                value: value.clone(),
+            });
+        }
+    }
+
+    let mut summary = vec![
+        metric("pipeline", "complete"),
+        metric("workflow", &workflow.name),
+    ];
+    summary.extend(run.target_summary());
+    if let Err(err) = run.record("success", summary, Some(now_millis())).await {
+        run.log(&format!("WARN: failed to upsert run completion: {err}\n"))
+            .await;
+    }
+    run.finish("success").await;
+
+    let _ = publish_completed(
+        &mut run.job_client,
+        &run.ids.pipeline_job_id,
+        "Workflow pipeline completed",
+        outputs,
+    )
+    .await;
+}
+
+/// Runs the workflow's `on_failure` steps with `run.failed_step` and
+/// `run.error` set. Their own failures are only logged: the run already
+/// failed.
+async fn run_on_failure(
+    run: &mut Run,
+    env: &StepEnv<'_>,
+    runner: &StepRunner,
+    workflow: &Workflow,
+    failure: &Failure,
+) {
+    if workflow.on_failure.is_empty() {
+        return;
+    }
+    run.log(&format!(
+        "step {} failed, running on_failure steps\n",
+        failure.step
+    ))
+    .await;
+    run.scope
+        .run
+        .insert("failed_step".into(), failure.step.clone());
+    run.scope
+        .run
+        .insert("error".into(), failure.message.clone());
+    run.state.handlers.clear();
+    if let Err(handler) =
+        run_steps(run, env, runner, &workflow.on_failure, HashSet::new(), true).await
+    {
+        run.log(&format!(
+            "WARN: on_failure step {} failed: {}\n",
+            handler.step, handler.message
+        ))
+        .await;
+    }
+}
+
+/// Runs `steps`, skipping `finished` ones. Each step starts once every step
+/// it needs finished; after a step fails the run, running steps are awaited
+/// but none start. A step or target instance is skipped when a step it needs
+/// failed or was skipped there (unless its condition checks that step) or
+/// when its condition is false; failures of `continue_on_failure` steps are
+/// recorded without stopping the run.
+/// `handlers` selects the `on_failure` bookkeeping: separate records, no
+/// progress or target results.
+async fn run_steps<'w>(
+    run: &mut Run,
+    env: &StepEnv<'_>,
+    runner: &StepRunner,
+    steps: &'w [Step],
+    mut finished: HashSet<&'w str>,
+    handlers: bool,
+) -> Result<(), Failure> {
+    let total_steps = steps.len().max(1);
+    let mut step_index = finished.len();
+    let mut started: HashMap<&'w str, Started> = HashMap::new();
+    let mut running = FuturesUnordered::new();
+    let mut fatal: Option<Failure> = None;
+    loop {
+        while fatal.is_none() {
+            let Some(step) = steps.iter().find(|step| {
+                !finished.contains(step.id.as_str())
+                    && !started.contains_key(step.id.as_str())
+                    && step
+                        .needs
+                        .iter()
+                        .all(|need| finished.contains(need.as_str()))
+            }) else {
+                break;
+            };
+            step_index += 1;
+            let (state, instances) =
+                match start_step(run, env, step, handlers, step_index, total_steps).await {
+                    Ok(started) => started,
+                    Err(failure) => {
+                        fatal = Some(failure);
+                        break;
+                    }
+                };
+            let state = started.entry(step.id.as_str()).or_insert(state);
+            if state.pending == 0 {
+                let state = std::mem::take(state);
+                fatal = finish_step(run, step, state, handlers, &mut finished).await;
+                continue;
+            }
+            for (target, inputs) in instances {
+                running.push(async move {
+                    let outcome = runner.run(env, &step.action, &inputs).await;
+                    (step, target, outcome)
+                });
+            }
+        }
+
+        let Some((step, target, outcome)) = running.next().await else {
+            break;
+        };
+        let state = started
+            .get_mut(step.id.as_str())
+            .expect("running steps are started");
+        state.pending -= 1;
+        run.state.job_ids.extend(outcome.job_ids);
+        if let Some(target) = target.as_ref().filter(|_| !handlers) {
+            // A target keeps the first failure it hit.
+            let failed_before = run
+                .state
+                .target_results
+                .get(target)
+                .is_some_and(|result| result != "success");
+            if !failed_before || outcome.failure.is_none() {
+                let result = match outcome.failure {
+                    Some(_) => format!("failed at {}", step.id),
+                    None => "success".to_string(),
+                };
+                run.state.target_results.insert(target.clone(), result);
+            }
+        }
+        match outcome.failure {
+            Some(failure) => state
+                .failures
+                .push((target, failure.message, failure.detail)),
+            None => {
+                run.take_project_id(&outcome.outputs);
+                match target {
+                    Some(target) => {
+                        for (key, value) in outcome.outputs {
+                            state.outputs.insert(format!("{target}.{key}"), value);
+                        }
+                        state.done_targets.push(target);
+                    }
+                    None => state.outputs.extend(outcome.outputs),
+                }
+            }
+        }
+        if state.pending == 0 {
+            let state = std::mem::take(state);
+            let failure = finish_step(run, step, state, handlers, &mut finished).await;
+            if fatal.is_none() {
+                fatal = failure;
+            }
+        }
+    }
+    match fatal {
+        Some(failure) => Err(failure),
+        None => Ok(()),
+    }
+}
+
+/// Reports a step's progress and resolves the inputs of each instance to
+/// run. Targets that succeeded in a previous attempt are carried over
+/// instead; a missing required input of a non-optional step fails the run.
+async fn start_step(
+    run: &mut Run,
+    env: &StepEnv<'_>,
+    step: &Step,
+    handlers: bool,
+    step_index: usize,
+    total_steps: usize,
+) -> Result<(Started, Vec<(Option<String>, StepInputs)>), Failure> {
+    let targets = run.targets.clone();
+    let step_targets: Vec<Option<String>> = if step.for_each_target && !targets.is_empty() {
+        targets.iter().cloned().map(Some).collect()
+    } else {
+        vec![None]
+    };
+    if !handlers {
+        let mut metrics = vec![
+            metric("pipeline_step", &step.action),
+            metric("step_id", &step.id),
+            metric("step_index", step_index),
+            metric("total_steps", total_steps),
+            metric("run_id", env.run_id),
+            metric("correlation_id", env.correlation_id),
+        ];
+        if step.for_each_target && !targets.is_empty() {
+            metrics.push(metric("targets", targets.len()));
+        }
+        let _ = publish_progress(
+            &mut run.job_client,
+            &run.ids.pipeline_job_id,
+            (step_index * 100 / total_steps) as u32,
+            &step.action,
+            metrics,
+        )
+        .await;
+    }
+
+    let previous = run
+        .records(handlers)
+        .get(&step.id)
+        .cloned()
+        .unwrap_or_default();
+    let mut state = Started::default();
+    let mut instances = Vec::new();
+    for target in step_targets {
+        if let Some(target) = target
+            .as_ref()
+            .filter(|t| previous.done_targets.contains(t))
+        {
+            let prefix = format!("{target}.");
+            state.outputs.extend(
+                previous
+                    .outputs
+                    .iter()
+                    .filter(|(key, _)| key.starts_with(&prefix))
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            );
+            state.done_targets.push(target.clone());
+            continue;
+        }
+        run.scope.target = target.clone();
+        if let Some(reason) = step.skip_reason(&run.scope, &run.conditions) {
+            let on = target
+                .as_ref()
+                .map(|target| format!(" on {target}"))
+                .unwrap_or_default();
+            run.log(&format!("step {}{on} skipped: {reason}\n", step.id))
+                .await;
+            continue;
+        }
+        let step_inputs = step.resolve(&run.scope);
+        let missing = action_spec(&step.action)
+            .map(|spec| step_inputs.missing(spec))
+            .unwrap_or_default();
+        if missing.is_empty() {
+            instances.push((target, step_inputs));
+            continue;
+        }
+        let msg = format!("{} requires {}", step.action, missing.join(" and "));
+        if !step.optional {
+            run.records(handlers).insert(
+                step.id.clone(),
+                StepRecord {
+                    status: StepStatus::Failed,
+                    ..previous
+                },
+            );
+            run.conditions
+                .statuses
+                .insert(step.id.clone(), StepStatus::Failed.as_str().to_string());
+            run.scope.target = None;
+            run.store.save(&run.state).await;
+            run.log(&format!("step {} failed: {msg}\n", step.id)).await;
+            return Err(Failure {
+                step: step.id.clone(),
+                message: "pipeline failed".into(),
+                detail: msg,
+            });
+        }
+        run.log(&format!("WARN: {msg}, skipping\n")).await;
+    }
+    run.scope.target = None;
+    state.pending = instances.len();
+    state.runs = instances.len();
+    Ok((state, instances))
+}
+
+/// Records a step whose instances all finished and makes its outputs
+/// visible to the steps that need it. Returns the failure when the step
+/// fails the run.
+async fn finish_step<'w>(
+    run: &mut Run,
+    step: &'w Step,
+    state: Started,
+    handlers: bool,
+    finished: &mut HashSet<&'w str>,
+) -> Option<Failure> {
+    let targets = run.targets.clone();
+    let Started {
+        runs,
+        mut outputs,
+        done_targets: done,
+        failures,
+        ..
+    } = state;
+    let failed_on = |target: &str| failures.iter().any(|(t, ..)| t.as_deref() == Some(target));
+    let status = if !failures.is_empty() {
+        StepStatus::Failed
+    } else if runs == 0 && done.is_empty() {
+        StepStatus::Skipped
+    } else {
+        StepStatus::Succeeded
+    };
+    if step.for_each_target {
+        for target in &targets {
+            let target_status = if failed_on(target) {
+                StepStatus::Failed
+            } else if done.contains(target) {
+                StepStatus::Succeeded
+            } else {
+                StepStatus::Skipped
+            };
+            run.conditions.statuses.insert(
+                RunState::status_key(&step.id, target),
+                target_status.as_str().to_string(),
+            );
+        }
+    }
+    run.conditions
+        .statuses
+        .insert(step.id.clone(), status.as_str().to_string());
+    if status != StepStatus::Failed && step.for_each_target && !targets.is_empty() {
+        outputs.insert("targets".into(), targets.join(","));
+    }
+    run.records(handlers).insert(
+        step.id.clone(),
+        StepRecord {
+            status,
+            outputs: outputs.clone(),
+            done_targets: done,
+        },
+    );
+    if status != StepStatus::Failed || step.continue_on_failure {
+        run.scope.steps.insert(step.id.clone(), outputs);
+        finished.insert(step.id.as_str());
+    }
+    run.store.save(&run.state).await;
+
+    let (_, message, detail) = failures.first()?;
+    if step.continue_on_failure {
+        for (target, message, _) in &failures {
+            let on = target
+                .as_ref()
+                .map(|target| format!(" on {target}"))
+                .unwrap_or_default();
+            run.log(&format!(
+                "step {}{on} failed, continuing: {message}\n",
+                step.id
+            ))
+            .await;
+        }
+        return None;
+    }
+    let (message, detail) = if step.for_each_target && !targets.is_empty() {
+        let detail = failures
+            .iter()
+            .map(|(target, _, detail)| {
+                format!("{}: {detail}", target.as_deref().unwrap_or_default())
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        (
+            format!("{message} on {} of {runs} targets", failures.len()),
+            detail,
+        )
+    } else {
+        (message.clone(), detail.clone())
+    };
+    run.log(&format!("step {} failed: {message}\n", step.id))
+        .await;
+    Some(Failure {
+        step: step.id.clone(),
+        message,
+        detail,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        actions::{StepFailure, StepOutcome},
+        definition::WorkflowDef,
+    };
+
+    /// What the stub did, in order: `start NAME` and `end NAME`.
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    /// Runs `job.start` steps without services, named by their `job_type`:
+    /// `slow-*` steps take 100ms and `fail-*` steps fail. Others output
+    /// their `params` as `value`.
+    fn stub(calls: Calls) -> StepRunner {
+        StepRunner::Stub(Box::new(move |_action, inputs| {
+            let name = inputs.get("job_type").to_string();
+            let params = inputs.list("params").join(",");
+            let calls = calls.clone();
+            Box::pin(async move {
+                calls.lock().unwrap().push(format!("start {name}"));
+                if name.starts_with("slow-") {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                calls.lock().unwrap().push(format!("end {name}"));
+                let mut outcome = StepOutcome::default();
+                if name.starts_with("fail-") {
+                    outcome.failure = Some(StepFailure::new(format!("{name} failed"), "stub"));
+                } else {
+                    outcome.outputs.insert("value".into(), params);
+                }
+                outcome
+            })
+        }))
+    }
+
+    /// Runs `yaml` from `state` against the stub, with every service
+    /// unreachable, and returns the stored run and the stub's calls.
+    async fn run(yaml: &str, state: RunRecord) -> (RunRecord, Vec<String>) {
+        let workflow = serde_yaml::from_str::<WorkflowDef>(yaml)
+            .expect("valid yaml")
+            .compile()
+            .expect("valid workflow");
+        let path = std::env::temp_dir().join(format!("apkw-workflow-runs-{}.json", Uuid::new_v4()));
+        let store = RunStore::at(path.clone());
+        let unreachable = "127.0.0.1:1".to_string();
+        let config = WorkflowConfig {
+            job_addr: unreachable.clone(),
+            toolchain_addr: unreachable.clone(),
+            project_addr: unreachable.clone(),
+            build_addr: unreachable.clone(),
+            targets_addr: unreachable.clone(),
+            observe_addr: unreachable.clone(),
+            workflow_addr: unreachable,
+        };
+        let job_client =
+            JobServiceClient::new(Channel::from_static("http://127.0.0.1:1").connect_lazy());
+        let ids = RunIds {
+            pipeline_job_id: "job-pipeline".into(),
+            run_id: "run-1".into(),
+            correlation_id: "run-1".into(),
+        };
+        let state = RunRecord {
+            run_id: "run-1".into(),
+            ..state
+        };
+        let reused = state.reusable_steps().await;
+        let calls = Calls::default();
+        drive(
+            config,
+            job_client,
+            store.clone(),
+            ids,
+            workflow,
+            state,
+            reused,
+            &stub(calls.clone()),
+        )
+        .await;
+        let _ = std::fs::remove_file(&path);
+        let record = store.get("run-1").await.expect("run is stored");
+        let calls = calls.lock().unwrap().clone();
+        (record, calls)
+    }
+
+    fn position(calls: &[String], call: &str) -> usize {
+        calls
+            .iter()
+            .position(|c| c == call)
+            .unwrap_or_else(|| panic!("{call} missing from {calls:?}"))
+    }
+
+    fn status(record: &RunRecord, step: &str) -> StepStatus {
+        record.steps[step].status
+    }
+
+    #[tokio::test]
+    async fn steps_start_as_soon_as_their_needs_finish() {
+        let (record, calls) = run(
+            r#"
+steps:
+  - id: slow
+    action: job.start
+    with: { job_type: slow-build, params: [apk] }
+  - id: fast
+    action: job.start
+    with: { job_type: fast, params: [one] }
+  - id: next
+    action: job.start
+    needs: [fast]
+    with: { job_type: next, params: ["${steps.fast.outputs.value}"] }
+  - id: last
+    action: job.start
+    needs: [slow, next]
+    with:
+      job_type: last
+      params: ["${steps.next.outputs.value}", "${steps.slow.outputs.value}"]
+"#,
+            RunRecord::default(),
+        )
+        .await;
+        assert_eq!(record.result, "success");
+        // `next` does not wait for `slow`, which is not among its needs.
+        assert!(position(&calls, "start next") < position(&calls, "end slow-build"));
+        assert!(position(&calls, "start last") > position(&calls, "end slow-build"));
+        assert!(position(&calls, "start last") > position(&calls, "end next"));
+        assert_eq!(record.steps["last"].outputs["value"], "one,apk");
+        for step in ["slow", "fast", "next", "last"] {
+            assert_eq!(status(&record, step), StepStatus::Succeeded);
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_needs_skip_dependents_unless_the_run_continues() {
+        let (record, calls) = run(
+            r#"
+steps:
+  - id: flaky
+    action: job.start
+    continue_on_failure: true
+    with: { job_type: fail-flaky }
+  - id: after
+    action: job.start
+    needs: [flaky]
+    with: { job_type: after }
+  - id: cleanup
+    action: job.start
+    needs: [flaky]
+    if: steps.flaky.failed
+    with: { job_type: cleanup }
+"#,
+            RunRecord::default(),
+        )
+        .await;
+        assert_eq!(record.result, "success");
+        assert_eq!(status(&record, "flaky"), StepStatus::Failed);
+        assert_eq!(status(&record, "after"), StepStatus::Skipped);
+        assert_eq!(status(&record, "cleanup"), StepStatus::Succeeded);
+        assert!(!calls.contains(&"start after".to_string()), "{calls:?}");
+    }
+
+    #[tokio::test]
+    async fn skipped_needs_skip_dependents_unless_their_condition_checks_them() {
+        let (record, calls) = run(
+            r#"
+steps:
+  - id: release
+    action: job.start
+    if: inputs.variant == release
+    with: { job_type: release }
+  - id: publish
+    action: job.start
+    needs: [release]
+    with: { job_type: publish, params: ["${steps.release.outputs.value}"] }
+  - id: notify
+    action: job.start
+    needs: [publish]
+    if: inputs.variant != release
+    with: { job_type: notify }
+  - id: fallback
+    action: job.start
+    needs: [release]
+    if: steps.release.skipped
+    with: { job_type: fallback }
+"#,
+            RunRecord::default(),
+        )
+        .await;
+        assert_eq!(record.result, "success");
+        for step in ["release", "publish", "notify"] {
+            assert_eq!(status(&record, step), StepStatus::Skipped);
+        }
+        assert_eq!(status(&record, "fallback"), StepStatus::Succeeded);
+        assert_eq!(calls, ["start fallback", "end fallback"]);
+    }
+
+    #[tokio::test]
+    async fn fatal_failures_await_running_steps_then_run_on_failure() {
+        let (record, calls) = run(
+            r#"
+steps:
+  - id: build
+    action: job.start
+    with: { job_type: fail-build }
+  - id: side
+    action: job.start
+    with: { job_type: slow-side }
+  - id: install
+    action: job.start
+    needs: [build]
+    with: { job_type: install }
+on_failure:
+  - id: report
+    action: job.start
+    with:
+      job_type: report
+      params: ["${run.failed_step}", "${run.error}"]
+"#,
+            RunRecord::default(),
+        )
+        .await;
+        assert_eq!(record.result, "failed");
+        assert_eq!(status(&record, "build"), StepStatus::Failed);
+        assert_eq!(status(&record, "side"), StepStatus::Succeeded);
+        assert!(!record.steps.contains_key("install"));
+        assert_eq!(
+            record.handlers["report"].outputs["value"],
+            "build,fail-build failed"
+        );
+        assert!(position(&calls, "start report") > position(&calls, "end slow-side"));
+        assert!(!calls.contains(&"start install".to_string()), "{calls:?}");
+    }
+
+    #[tokio::test]
+    async fn resumed_runs_reuse_succeeded_steps() {
+        let mut state = RunRecord::default();
+        state.steps.insert(
+            "build".into(),
+            StepRecord {
+                status: StepStatus::Succeeded,
+                outputs: BTreeMap::from([("value".into(), "cached".into())]),
+                ..Default::default()
+            },
+        );
+        state.steps.insert(
+            "install".into(),
+            StepRecord {
+                status: StepStatus::Failed,
+                ..Default::default()
+            },
+        );
+        let (record, calls) = run(
+            r#"
+steps:
+  - id: build
+    action: job.start
+    with: { job_type: build }
+  - id: install
+    action: job.start
+    needs: [build]
+    with: { job_type: install, params: ["${steps.build.outputs.value}"] }
+"#,
+            state,
+        )
+        .await;
+        assert_eq!(record.result, "success");
+        assert_eq!(calls, vec!["start install", "end install"]);
+        assert_eq!(record.steps["install"].outputs["value"], "cached");
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path, path::PathBuf, time::Duration};
 
 use apkw_proto::apkw::v1::{
     build_service_client::BuildServiceClient,
@@ -9,23 +9,36 @@ use apkw_proto::apkw::v1::{
     target_service_client::TargetServiceClient,
     toolchain_service_client::ToolchainServiceClient,
     workflow_service_server::{WorkflowService, WorkflowServiceServer},
-    Artifact, ArtifactFilter, ArtifactType, BuildRequest, BuildVariant, CreateProjectRequest,
-    ErrorCode, ErrorDetail, ExportEvidenceBundleRequest, ExportSupportBundleRequest, GetJobRequest,
-    Id, InstallApkRequest, JobCompleted, JobEvent, JobFailed, JobLogAppended, JobProgress,
-    JobProgressUpdated, JobState, JobStateChanged, KeyValue, LaunchRequest, ListArtifactsRequest,
-    OpenProjectRequest, PublishJobEventRequest, ReloadStateRequest, ReloadStateResponse, RunId,
+    Artifact, ArtifactType, BuildVariant, CreateTriggerRequest, CreateTriggerResponse,
+    DeleteTriggerRequest, DeleteTriggerResponse, ErrorCode, ErrorDetail, GetJobRequest,
+    GetProjectRequest, Id, JobCompleted, JobEvent, JobFailed, JobLogAppended, JobPriority,
+    JobProgress, JobProgressUpdated, JobState, JobStateChanged, KeyValue, ListTriggersRequest,
+    ListTriggersResponse, ListWorkflowsRequest, ListWorkflowsResponse, PlanPipelineResponse,
+    PublishJobEventRequest, ReloadStateRequest, ReloadStateResponse, ResumePipelineRequest, RunId,
     RunOutput, RunOutputKind, StartJobRequest, StreamJobEventsRequest, Timestamp,
     UpsertRunOutputsRequest, UpsertRunRequest, WorkflowPipelineRequest, WorkflowPipelineResponse,
 };
 use apkw_util::{
-    build_addr, job_addr, now_millis, observe_addr, project_addr, serve_grpc_with_telemetry,
-    targets_addr, toolchain_addr,
+    build_addr, is_sha256_hex, job_addr, now_millis, observe_addr, project_addr,
+    secrets::validate_secret_ref, serve_grpc_with_telemetry, spawn_job_heartbeat, targets_addr,
+    toolchain_addr, workflow_addr,
 };
 use futures_util::StreamExt;
 use tonic::{transport::Channel, Request, Response, Status};
-use tracing::warn;
+use tracing::{info, warn};
 use uuid::Uuid;
 
+mod actions;
+mod artifacts;
+mod condition;
+mod definition;
+mod engine;
+mod plan;
+mod runs;
+mod targets;
+mod template;
+mod triggers;
+
 #[derive(Clone)]
 struct WorkflowConfig {
     job_addr: String,
@@ -34,11 +47,14 @@ struct WorkflowConfig {
     build_addr: String,
     targets_addr: String,
     observe_addr: String,
+    workflow_addr: String,
 }
 
 #[derive(Clone)]
 struct Svc {
     config: WorkflowConfig,
+    runs: runs::RunStore,
+    triggers: triggers::TriggerRegistry,
 }
 
 fn metric(key: &str, value: impl ToString) -> KeyValue {
@@ -120,6 +136,8 @@ async fn publish_job_event(
                 job_id: Some(Id {
                     value: job_id.to_string(),
                 }),
+                attempt: 0,
+                seq: 0,
                 payload: Some(payload),
             }),
         })
@@ -157,6 +175,7 @@ async fn publish_log(
                 data: line.as_bytes().to_vec(),
                 truncated: false,
             }),
+            records: Vec::new(),
         }),
     )
     .await
@@ -210,6 +229,7 @@ async fn publish_failed(
         job_id,
         JobPayload::Failed(JobFailed {
             error: Some(detail),
+            will_retry: false,
         }),
     )
     .await
@@ -241,39 +261,6 @@ async fn fail_pipeline(
     let _ = publish_failed(client, job_id, detail).await;
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn fail_pipeline_and_record(
-    config: &WorkflowConfig,
-    client: &mut JobServiceClient<Channel>,
-    job_id: &str,
-    run_id: &str,
-    correlation_id: &str,
-    project_id: &str,
-    target_id: &str,
-    toolchain_set_id: &str,
-    job_ids: &[String],
-    started_at: i64,
-    message: &str,
-    technical: &str,
-) {
-    fail_pipeline(client, job_id, correlation_id, message, technical).await;
-    let summary = vec![metric("error", message), metric("detail", technical)];
-    let _ = upsert_run_best_effort(
-        &config.observe_addr,
-        run_id,
-        correlation_id,
-        project_id,
-        target_id,
-        toolchain_set_id,
-        job_ids,
-        "failed",
-        summary,
-        Some(started_at),
-        Some(now_millis()),
-    )
-    .await;
-}
-
 fn resolve_project_name(path: &str) -> String {
     Path::new(path)
         .file_name()
@@ -289,20 +276,15 @@ fn resolve_variant(variant: i32) -> BuildVariant {
     }
 }
 
-fn select_artifact_path(artifacts: &[Artifact]) -> Option<String> {
-    let mut apk = None;
-    let mut fallback = None;
-    for artifact in artifacts {
-        if fallback.is_none() {
-            fallback = Some(artifact.path.clone());
-        }
-        let kind = ArtifactType::try_from(artifact.r#type).unwrap_or(ArtifactType::Unspecified);
-        if matches!(kind, ArtifactType::Apk) {
-            apk = Some(artifact.path.clone());
-            break;
-        }
-    }
-    apk.or(fallback)
+/// The first APK among `artifacts`, else the first artifact.
+fn select_artifact(artifacts: &[Artifact]) -> Option<&Artifact> {
+    artifacts
+        .iter()
+        .find(|artifact| {
+            ArtifactType::try_from(artifact.r#type).unwrap_or(ArtifactType::Unspecified)
+                == ArtifactType::Apk
+        })
+        .or(artifacts.first())
 }
 
 fn artifact_type_label(artifact_type: ArtifactType) -> &'static str {
@@ -316,37 +298,106 @@ fn artifact_type_label(artifact_type: ArtifactType) -> &'static str {
     }
 }
 
+const JOB_STREAM_MAX_RESUMES: u32 = 5;
+const JOB_STREAM_RESUME_DELAY: Duration = Duration::from_millis(500);
+/// How long to wait after a success or failure state change for the event
+/// that carries the job's outputs or error.
+const JOB_OUTPUTS_GRACE: Duration = Duration::from_secs(2);
+
+/// How a job finished, with the outputs it completed with or the error it
+/// failed with (if any).
+struct JobOutcome {
+    state: JobState,
+    outputs: Vec<KeyValue>,
+    error: Option<ErrorDetail>,
+}
+
+/// Waits for a job to finish. A dropped event stream is reopened from the last
+/// sequence number seen, so no state change is missed across reconnects.
 async fn wait_for_job(
     client: &mut JobServiceClient<Channel>,
     job_id: &str,
-) -> Result<JobState, Status> {
-    let mut stream = client
-        .stream_job_events(StreamJobEventsRequest {
-            job_id: Some(Id {
-                value: job_id.to_string(),
-            }),
-            include_history: true,
-        })
-        .await?
-        .into_inner();
+) -> Result<JobOutcome, Status> {
+    let mut after_seq = 0u64;
+    let mut resumes = 0u32;
+    'stream: loop {
+        let opened = client
+            .stream_job_events(StreamJobEventsRequest {
+                job_id: Some(Id {
+                    value: job_id.to_string(),
+                }),
+                include_history: true,
+                after_seq,
+            })
+            .await;
+        let mut stream = match opened {
+            Ok(resp) => resp.into_inner(),
+            Err(err) if resumes > 0 && resumes < JOB_STREAM_MAX_RESUMES => {
+                warn!("reopening event stream for job {job_id} failed: {err}");
+                resumes += 1;
+                tokio::time::sleep(JOB_STREAM_RESUME_DELAY).await;
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
 
-    while let Some(evt) = stream.next().await {
-        let evt = evt?;
-        match evt.payload {
-            Some(JobPayload::Completed(_)) => return Ok(JobState::Success),
-            Some(JobPayload::Failed(_)) => return Ok(JobState::Failed),
-            Some(JobPayload::StateChanged(state)) => {
-                let new_state =
-                    JobState::try_from(state.new_state).unwrap_or(JobState::Unspecified);
-                if matches!(
-                    new_state,
-                    JobState::Success | JobState::Failed | JobState::Cancelled
-                ) {
-                    return Ok(new_state);
+        while let Some(evt) = stream.next().await {
+            let evt = match evt {
+                Ok(evt) => evt,
+                Err(err) if resumes < JOB_STREAM_MAX_RESUMES => {
+                    warn!("event stream for job {job_id} dropped after seq {after_seq}: {err}");
+                    resumes += 1;
+                    tokio::time::sleep(JOB_STREAM_RESUME_DELAY).await;
+                    continue 'stream;
+                }
+                Err(err) => return Err(err),
+            };
+            after_seq = after_seq.max(evt.seq);
+            match &evt.payload {
+                Some(JobPayload::Completed(done)) => {
+                    return Ok(JobOutcome {
+                        state: JobState::Success,
+                        outputs: done.outputs.clone(),
+                        error: None,
+                    });
+                }
+                Some(JobPayload::Failed(failed)) if !failed.will_retry => {
+                    return Ok(JobOutcome {
+                        state: JobState::Failed,
+                        outputs: Vec::new(),
+                        error: failed.error.clone(),
+                    });
                 }
+                _ => {}
+            }
+            match terminal_state(evt.payload) {
+                Some(JobState::Success) => {
+                    let outputs = completed_outputs(&mut stream).await;
+                    return Ok(JobOutcome {
+                        state: JobState::Success,
+                        outputs,
+                        error: None,
+                    });
+                }
+                Some(JobState::Failed) => {
+                    let error = failed_error(&mut stream).await;
+                    return Ok(JobOutcome {
+                        state: JobState::Failed,
+                        outputs: Vec::new(),
+                        error,
+                    });
+                }
+                Some(state) => {
+                    return Ok(JobOutcome {
+                        state,
+                        outputs: Vec::new(),
+                        error: None,
+                    })
+                }
+                None => {}
             }
-            _ => {}
         }
+        break;
     }
 
     let resp = client
@@ -361,7 +412,49 @@ async fn wait_for_job(
         .job
         .map(|job| JobState::try_from(job.state).unwrap_or(JobState::Unspecified))
         .unwrap_or(JobState::Unspecified);
-    Ok(state)
+    Ok(JobOutcome {
+        state,
+        outputs: Vec::new(),
+        error: None,
+    })
+}
+
+/// Outputs of the completion event that follows a success state change.
+async fn completed_outputs(stream: &mut tonic::Streaming<JobEvent>) -> Vec<KeyValue> {
+    let deadline = tokio::time::Instant::now() + JOB_OUTPUTS_GRACE;
+    while let Ok(Some(Ok(evt))) = tokio::time::timeout_at(deadline, stream.next()).await {
+        if let Some(JobPayload::Completed(done)) = evt.payload {
+            return done.outputs;
+        }
+    }
+    Vec::new()
+}
+
+/// Error of the failure event that follows a failed state change.
+async fn failed_error(stream: &mut tonic::Streaming<JobEvent>) -> Option<ErrorDetail> {
+    let deadline = tokio::time::Instant::now() + JOB_OUTPUTS_GRACE;
+    while let Ok(Some(Ok(evt))) = tokio::time::timeout_at(deadline, stream.next()).await {
+        if let Some(JobPayload::Failed(failed)) = evt.payload {
+            return failed.error;
+        }
+    }
+    None
+}
+
+fn terminal_state(payload: Option<JobPayload>) -> Option<JobState> {
+    match payload {
+        Some(JobPayload::Completed(_)) => Some(JobState::Success),
+        Some(JobPayload::Failed(failed)) if !failed.will_retry => Some(JobState::Failed),
+        Some(JobPayload::StateChanged(state)) => {
+            let new_state = JobState::try_from(state.new_state).unwrap_or(JobState::Unspecified);
+            matches!(
+                new_state,
+                JobState::Success | JobState::Failed | JobState::Cancelled
+            )
+            .then_some(new_state)
+        }
+        _ => None,
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -459,6 +552,12 @@ async fn upsert_run_outputs_best_effort(
                 value: artifact.name.clone(),
             });
         }
+        if !artifact.sha256.is_empty() {
+            metadata.push(KeyValue {
+                key: "sha256".into(),
+                value: artifact.sha256.clone(),
+            });
+        }
         outputs.push(RunOutput {
             output_id: format!("artifact:{job_id}:{path}"),
             run_id: Some(RunId {
@@ -495,1497 +594,266 @@ async fn upsert_run_outputs_best_effort(
     }
 }
 
-async fn run_pipeline_task(
-    config: WorkflowConfig,
-    pipeline_job_id: String,
-    run_id: String,
-    correlation_id: String,
-    req: WorkflowPipelineRequest,
-) {
-    let mut job_client = match connect_job(&config.job_addr).await {
-        Ok(client) => client,
-        Err(err) => {
-            warn!("pipeline job client error: {err}");
-            return;
-        }
-    };
-
-    let started_at = now_millis();
-    let options = req.options;
-    let inferred = options.is_none();
-    let options = options.unwrap_or_default();
-
-    let mut project_id = req
-        .project_id
-        .as_ref()
-        .map(|id| id.value.trim().to_string())
-        .filter(|value| !value.is_empty())
-        .unwrap_or_default();
-    let project_path = req.project_path.trim().to_string();
-    let project_name = if !req.project_name.trim().is_empty() {
-        req.project_name.trim().to_string()
-    } else if !project_path.is_empty() {
-        resolve_project_name(&project_path)
-    } else {
-        "apkw-project".to_string()
-    };
-    let template_id = req
-        .template_id
-        .as_ref()
-        .map(|id| id.value.trim().to_string())
-        .filter(|value| !value.is_empty())
-        .unwrap_or_default();
-    let toolchain_id = req
-        .toolchain_id
-        .as_ref()
-        .map(|id| id.value.trim().to_string())
-        .filter(|value| !value.is_empty())
-        .unwrap_or_default();
-    let toolchain_set_id = req
-        .toolchain_set_id
-        .as_ref()
-        .map(|id| id.value.trim().to_string())
-        .filter(|value| !value.is_empty())
-        .unwrap_or_default();
-    let target_id = req
-        .target_id
-        .as_ref()
-        .map(|id| id.value.trim().to_string())
-        .filter(|value| !value.is_empty())
-        .unwrap_or_default();
-    let application_id = req.application_id.trim().to_string();
-    let activity = req.activity.trim().to_string();
-    let mut apk_path = req.apk_path.trim().to_string();
-
-    let wants_create = if inferred {
-        !template_id.is_empty()
-    } else {
-        options.create_project
-    };
-    let wants_open = if inferred {
-        project_id.is_empty() && !project_path.is_empty()
-    } else {
-        options.open_project
-    };
-    let wants_verify = if inferred {
-        !toolchain_id.is_empty()
-    } else {
-        options.verify_toolchain
-    };
-    let wants_build = if inferred {
-        !project_id.is_empty() || !project_path.is_empty()
-    } else {
-        options.build
-    };
-    let wants_install = if inferred {
-        !apk_path.is_empty()
-    } else {
-        options.install_apk
-    };
-    let wants_launch = if inferred {
-        !application_id.is_empty()
-    } else {
-        options.launch_app
-    };
-    let wants_support = if inferred {
-        false
-    } else {
-        options.export_support_bundle
-    };
-    let wants_evidence = if inferred {
-        false
-    } else {
-        options.export_evidence_bundle
+/// The directory a request's workflows are loaded from: `project_path`, or
+/// the path of the project `project_id` names.
+#[allow(clippy::result_large_err)]
+async fn project_root(
+    config: &WorkflowConfig,
+    project_path: &str,
+    project_id: Option<&Id>,
+) -> Result<PathBuf, Status> {
+    if !project_path.trim().is_empty() {
+        return Ok(PathBuf::from(project_path.trim()));
+    }
+    let Some(project_id) = project_id.filter(|id| !id.value.trim().is_empty()) else {
+        return Err(Status::invalid_argument(
+            "project_path or project_id is required to load workflows",
+        ));
     };
+    let mut client = connect_project(&config.project_addr).await?;
+    let project = client
+        .get_project(GetProjectRequest {
+            project_id: Some(project_id.clone()),
+        })
+        .await?
+        .into_inner()
+        .project
+        .ok_or_else(|| Status::not_found(format!("project {} not found", project_id.value)))?;
+    Ok(PathBuf::from(project.path))
+}
 
-    let mut total_steps = 0u32;
-    for step in [
-        wants_create,
-        wants_open,
-        wants_verify,
-        wants_build,
-        wants_install,
-        wants_launch,
-        wants_support,
-        wants_evidence,
-    ] {
-        if step {
-            total_steps += 1;
-        }
+/// Picks the workflow a pipeline request runs and the inputs it runs with.
+/// Without `workflow` the request's options are turned into a workflow.
+#[allow(clippy::result_large_err)]
+async fn resolve_workflow(
+    config: &WorkflowConfig,
+    req: &WorkflowPipelineRequest,
+) -> Result<(definition::Workflow, BTreeMap<String, String>), Status> {
+    for secret in &req.secrets {
+        validate_secret_ref(secret).map_err(Status::invalid_argument)?;
     }
-    if total_steps == 0 {
-        total_steps = 1;
+    let apk_sha256 = req.apk_sha256.trim();
+    if !apk_sha256.is_empty() && !is_sha256_hex(apk_sha256) {
+        return Err(Status::invalid_argument(
+            "apk_sha256 must be a 64-character hex sha256",
+        ));
     }
-
-    let mut step_index = 0u32;
-    let mut job_ids = Vec::new();
-    let mut outputs = vec![
-        metric("run_id", &run_id),
-        metric("correlation_id", &correlation_id),
-    ];
-
-    let mut summary = Vec::new();
-    let _ = publish_log(
-        &mut job_client,
-        &pipeline_job_id,
-        &format!("pipeline run_id={run_id} correlation_id={correlation_id}\n"),
-    )
-    .await;
-    let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Running).await;
-
-    if let Err(err) = upsert_run_best_effort(
-        &config.observe_addr,
-        &run_id,
-        &correlation_id,
-        &project_id,
-        &target_id,
-        &toolchain_set_id,
-        &job_ids,
-        "running",
-        vec![],
-        Some(started_at),
-        None,
-    )
-    .await
+    let mut inputs = definition::request_inputs(req);
+    if let Some(run_id) = req
+        .artifact_run_id
+        .as_ref()
+        .map(|id| id.value.trim())
+        .filter(|value| !value.is_empty())
     {
-        let _ = publish_log(
-            &mut job_client,
-            &pipeline_job_id,
-            &format!("WARN: failed to upsert run start: {err}\n"),
-        )
-        .await;
-    }
-
-    if wants_create {
-        step_index += 1;
-        let percent = step_index * 100 / total_steps;
-        let mut metrics = vec![
-            metric("pipeline_step", "project.create"),
-            metric("step_index", step_index),
-            metric("total_steps", total_steps),
-            metric("run_id", &run_id),
-            metric("correlation_id", &correlation_id),
-        ];
-        if !template_id.is_empty() {
-            metrics.push(metric("template_id", &template_id));
-        }
-        if !project_path.is_empty() {
-            metrics.push(metric("project_path", &project_path));
-        }
-        let _ = publish_progress(
-            &mut job_client,
-            &pipeline_job_id,
-            percent,
-            "project.create",
-            metrics,
-        )
-        .await;
-
-        if template_id.is_empty() || project_path.is_empty() {
-            let msg = "project.create requires template_id and project_path";
-            if !inferred && options.create_project {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "pipeline failed",
-                    msg,
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-            let _ = publish_log(
-                &mut job_client,
-                &pipeline_job_id,
-                &format!("WARN: {msg}, skipping\n"),
-            )
-            .await;
-        } else {
-            let mut project_client = match connect_project(&config.project_addr).await {
-                Ok(client) => client,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "project service unavailable",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            let resp = project_client
-                .create_project(CreateProjectRequest {
-                    name: project_name.clone(),
-                    path: project_path.clone(),
-                    template_id: Some(Id {
-                        value: template_id.clone(),
-                    }),
-                    params: vec![],
-                    toolchain_set_id: if toolchain_set_id.is_empty() {
-                        None
-                    } else {
-                        Some(Id {
-                            value: toolchain_set_id.clone(),
-                        })
-                    },
-                    job_id: None,
-                    correlation_id: correlation_id.clone(),
-                    run_id: Some(RunId {
-                        value: run_id.clone(),
-                    }),
-                })
-                .await;
-            let resp = match resp {
-                Ok(resp) => resp.into_inner(),
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "project.create failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            if let Some(job_id) = resp
-                .job_id
-                .as_ref()
-                .map(|id| id.value.clone())
-                .filter(|value| !value.is_empty())
-            {
-                job_ids.push(job_id.clone());
-                if let Err(err) = wait_for_job(&mut job_client, &job_id).await {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "project.create job failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            }
-            if let Some(pid) = resp
-                .project_id
-                .as_ref()
-                .map(|id| id.value.clone())
-                .filter(|value| !value.is_empty())
-            {
-                project_id = pid;
-                outputs.push(metric("project_id", &project_id));
-            }
+        if !req.apk_path.trim().is_empty() {
+            return Err(Status::invalid_argument(
+                "apk_path and artifact_run_id cannot both be set",
+            ));
         }
+        let artifact = artifacts::run_artifact(config, run_id).await?;
+        inputs.insert("apk_path".into(), artifact.path);
+        inputs.insert("apk_sha256".into(), artifact.sha256.to_ascii_lowercase());
     }
-
-    if wants_open {
-        step_index += 1;
-        let percent = step_index * 100 / total_steps;
-        let metrics = vec![
-            metric("pipeline_step", "project.open"),
-            metric("step_index", step_index),
-            metric("total_steps", total_steps),
-            metric("run_id", &run_id),
-            metric("correlation_id", &correlation_id),
-            metric("project_path", &project_path),
-        ];
-        let _ = publish_progress(
-            &mut job_client,
-            &pipeline_job_id,
-            percent,
-            "project.open",
-            metrics,
-        )
-        .await;
-
-        if project_path.is_empty() {
-            let msg = "project.open requires project_path";
-            if !inferred && options.open_project {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "pipeline failed",
-                    msg,
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-            let _ = publish_log(
-                &mut job_client,
-                &pipeline_job_id,
-                &format!("WARN: {msg}, skipping\n"),
-            )
-            .await;
-        } else {
-            let mut project_client = match connect_project(&config.project_addr).await {
-                Ok(client) => client,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "project service unavailable",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            match project_client
-                .open_project(OpenProjectRequest {
-                    path: project_path.clone(),
-                })
-                .await
-            {
-                Ok(resp) => {
-                    if let Some(project) = resp.into_inner().project {
-                        if let Some(pid) = project
-                            .project_id
-                            .as_ref()
-                            .map(|id| id.value.clone())
-                            .filter(|value| !value.is_empty())
-                        {
-                            project_id = pid;
-                            outputs.push(metric("project_id", &project_id));
-                        }
-                    }
-                }
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "project.open failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            }
+    let name = req.workflow.trim();
+    if name.is_empty() {
+        if !req.inputs.is_empty() {
+            return Err(Status::invalid_argument("inputs require a workflow"));
         }
+        return Ok((definition::pipeline_workflow(req)?, inputs));
     }
 
-    if wants_verify {
-        step_index += 1;
-        let percent = step_index * 100 / total_steps;
-        let metrics = vec![
-            metric("pipeline_step", "toolchain.verify"),
-            metric("step_index", step_index),
-            metric("total_steps", total_steps),
-            metric("run_id", &run_id),
-            metric("correlation_id", &correlation_id),
-            metric("toolchain_id", &toolchain_id),
-        ];
-        let _ = publish_progress(
-            &mut job_client,
-            &pipeline_job_id,
-            percent,
-            "toolchain.verify",
-            metrics,
-        )
-        .await;
-
-        if toolchain_id.is_empty() {
-            let msg = "toolchain.verify requires toolchain_id";
-            if !inferred && options.verify_toolchain {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "pipeline failed",
-                    msg,
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-            let _ = publish_log(
-                &mut job_client,
-                &pipeline_job_id,
-                &format!("WARN: {msg}, skipping\n"),
-            )
-            .await;
-        } else {
-            let mut toolchain_client = match connect_toolchain(&config.toolchain_addr).await {
-                Ok(client) => client,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "toolchain service unavailable",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            let resp = toolchain_client
-                .verify_toolchain(apkw_proto::apkw::v1::VerifyToolchainRequest {
-                    toolchain_id: Some(Id {
-                        value: toolchain_id.clone(),
-                    }),
-                    job_id: None,
-                    correlation_id: correlation_id.clone(),
-                    run_id: Some(RunId {
-                        value: run_id.clone(),
-                    }),
-                })
-                .await;
-            let resp = match resp {
-                Ok(resp) => resp.into_inner(),
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "toolchain.verify failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            if let Some(job_id) = resp
-                .job_id
-                .as_ref()
-                .map(|id| id.value.clone())
-                .filter(|value| !value.is_empty())
-            {
-                job_ids.push(job_id.clone());
-                let state = match wait_for_job(&mut job_client, &job_id).await {
-                    Ok(state) => state,
-                    Err(err) => {
-                        fail_pipeline_and_record(
-                            &config,
-                            &mut job_client,
-                            &pipeline_job_id,
-                            &run_id,
-                            &correlation_id,
-                            &project_id,
-                            &target_id,
-                            &toolchain_set_id,
-                            &job_ids,
-                            started_at,
-                            "toolchain.verify job failed",
-                            &err.to_string(),
-                        )
-                        .await;
-                        let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed)
-                            .await;
-                        return;
-                    }
-                };
-                if !matches!(state, JobState::Success) || !resp.verified {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "toolchain.verify failed",
-                        "verification failed",
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            }
+    let root = project_root(config, &req.project_path, req.project_id.as_ref()).await?;
+    let workflow = definition::load_workflow(&root, name).map_err(|errors| {
+        Status::invalid_argument(format!("workflow {name}: {}", errors.join("; ")))
+    })?;
+    inputs.extend(workflow.defaults.clone());
+    for kv in &req.inputs {
+        let key = kv.key.trim();
+        if !workflow.defaults.contains_key(key) && !definition::REQUEST_INPUTS.contains(&key) {
+            return Err(Status::invalid_argument(format!(
+                "workflow {name} has no input {key}"
+            )));
         }
+        inputs.insert(key.to_string(), kv.value.trim().to_string());
     }
+    Ok((workflow, inputs))
+}
 
-    if wants_build {
-        step_index += 1;
-        let percent = step_index * 100 / total_steps;
-        let project_ref = if !project_id.is_empty() {
-            project_id.clone()
-        } else {
-            project_path.clone()
-        };
-        let metrics = vec![
-            metric("pipeline_step", "build.run"),
-            metric("step_index", step_index),
-            metric("total_steps", total_steps),
-            metric("run_id", &run_id),
-            metric("correlation_id", &correlation_id),
-            metric("project_ref", &project_ref),
-        ];
-        let _ = publish_progress(
-            &mut job_client,
-            &pipeline_job_id,
-            percent,
-            "build.run",
-            metrics,
-        )
-        .await;
-
-        if project_ref.trim().is_empty() {
-            let msg = "build.run requires project_id or project_path";
-            if !inferred && options.build {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "pipeline failed",
-                    msg,
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-            let _ = publish_log(
-                &mut job_client,
-                &pipeline_job_id,
-                &format!("WARN: {msg}, skipping\n"),
-            )
-            .await;
-        } else {
-            let mut build_client = match connect_build(&config.build_addr).await {
-                Ok(client) => client,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "build service unavailable",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            let variant = resolve_variant(req.build_variant);
-            let resp = build_client
-                .build(BuildRequest {
-                    project_id: Some(Id {
-                        value: project_ref.clone(),
-                    }),
-                    variant: variant as i32,
-                    clean_first: false,
-                    gradle_args: vec![],
-                    job_id: None,
-                    module: req.module.clone(),
-                    variant_name: req.variant_name.clone(),
-                    tasks: req.tasks.clone(),
-                    correlation_id: correlation_id.clone(),
-                    run_id: Some(RunId {
-                        value: run_id.clone(),
-                    }),
-                })
-                .await;
-            let resp = match resp {
-                Ok(resp) => resp.into_inner(),
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "build.run failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            let build_job_id = resp
-                .job_id
-                .as_ref()
-                .map(|id| id.value.clone())
-                .filter(|value| !value.is_empty())
-                .unwrap_or_default();
-            if build_job_id.is_empty() {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "build.run failed",
-                    "empty job_id",
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-            job_ids.push(build_job_id.clone());
-            let state = match wait_for_job(&mut job_client, &build_job_id).await {
-                Ok(state) => state,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "build.run job failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            if !matches!(state, JobState::Success) {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "build.run failed",
-                    "build job failed",
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-
-            let artifacts = build_client
-                .list_artifacts(ListArtifactsRequest {
-                    project_id: Some(Id {
-                        value: project_ref.clone(),
-                    }),
-                    variant: variant as i32,
-                    filter: Some(ArtifactFilter {
-                        modules: if req.module.trim().is_empty() {
-                            vec![]
-                        } else {
-                            vec![req.module.trim().to_string()]
-                        },
-                        variant: req.variant_name.trim().to_string(),
-                        types: vec![],
-                        name_contains: "".into(),
-                        path_contains: "".into(),
-                    }),
-                })
-                .await
-                .map(|resp| resp.into_inner().artifacts)
-                .unwrap_or_default();
-            upsert_run_outputs_best_effort(
-                &config.observe_addr,
-                &run_id,
-                &build_job_id,
-                &artifacts,
-            )
-            .await;
-            if let Some(path) = select_artifact_path(&artifacts) {
-                if apk_path.is_empty() {
-                    apk_path = path.clone();
-                }
-                outputs.push(metric("artifact_path", path));
-            }
-        }
-    }
-
-    if wants_install {
-        step_index += 1;
-        let percent = step_index * 100 / total_steps;
-        let metrics = vec![
-            metric("pipeline_step", "targets.install"),
-            metric("step_index", step_index),
-            metric("total_steps", total_steps),
-            metric("run_id", &run_id),
-            metric("correlation_id", &correlation_id),
-            metric("target_id", &target_id),
-            metric("apk_path", &apk_path),
-        ];
-        let _ = publish_progress(
-            &mut job_client,
-            &pipeline_job_id,
-            percent,
-            "targets.install",
-            metrics,
-        )
-        .await;
-
-        if target_id.is_empty() || apk_path.is_empty() {
-            let msg = "targets.install requires target_id and apk_path";
-            if !inferred && options.install_apk {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "pipeline failed",
-                    msg,
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-            let _ = publish_log(
-                &mut job_client,
-                &pipeline_job_id,
-                &format!("WARN: {msg}, skipping\n"),
-            )
-            .await;
-        } else {
-            let mut targets_client = match connect_targets(&config.targets_addr).await {
-                Ok(client) => client,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "targets service unavailable",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            let resp = targets_client
-                .install_apk(InstallApkRequest {
-                    target_id: Some(Id {
-                        value: target_id.clone(),
-                    }),
-                    project_id: if project_id.is_empty() {
-                        None
-                    } else {
-                        Some(Id {
-                            value: project_id.clone(),
-                        })
-                    },
-                    apk_path: apk_path.clone(),
-                    job_id: None,
-                    correlation_id: correlation_id.clone(),
-                    run_id: Some(RunId {
-                        value: run_id.clone(),
-                    }),
-                })
-                .await;
-            let resp = match resp {
-                Ok(resp) => resp.into_inner(),
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "targets.install failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            let install_job_id = resp
-                .job_id
-                .as_ref()
-                .map(|id| id.value.clone())
-                .filter(|value| !value.is_empty())
-                .unwrap_or_default();
-            if install_job_id.is_empty() {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "targets.install failed",
-                    "empty job_id",
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-            job_ids.push(install_job_id.clone());
-            let state = match wait_for_job(&mut job_client, &install_job_id).await {
-                Ok(state) => state,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "targets.install job failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
+/// Resolves a pipeline request's workflow, inputs and targets. The first
+/// target stands in for `target_id` when the request has none.
+#[allow(clippy::result_large_err)]
+async fn resolve_request(
+    config: &WorkflowConfig,
+    req: &WorkflowPipelineRequest,
+) -> Result<(definition::Workflow, BTreeMap<String, String>, Vec<String>), Status> {
+    let (workflow, mut inputs) = resolve_workflow(config, req).await?;
+    let targets = targets::resolve_targets(config, req).await?;
+    if let Some(first) = targets.first() {
+        inputs
+            .entry("target_id".into())
+            .and_modify(|value| {
+                if value.is_empty() {
+                    value.clone_from(first);
                 }
-            };
-            if !matches!(state, JobState::Success) {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "targets.install failed",
-                    "install job failed",
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-        }
+            })
+            .or_insert_with(|| first.clone());
     }
+    Ok((workflow, inputs, targets))
+}
 
-    if wants_launch {
-        step_index += 1;
-        let percent = step_index * 100 / total_steps;
-        let metrics = vec![
-            metric("pipeline_step", "targets.launch"),
-            metric("step_index", step_index),
-            metric("total_steps", total_steps),
-            metric("run_id", &run_id),
-            metric("correlation_id", &correlation_id),
-            metric("target_id", &target_id),
-            metric("application_id", &application_id),
-        ];
-        let _ = publish_progress(
-            &mut job_client,
-            &pipeline_job_id,
-            percent,
-            "targets.launch",
-            metrics,
-        )
-        .await;
-
-        if target_id.is_empty() || application_id.is_empty() {
-            let msg = "targets.launch requires target_id and application_id";
-            if !inferred && options.launch_app {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "pipeline failed",
-                    msg,
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-            let _ = publish_log(
-                &mut job_client,
-                &pipeline_job_id,
-                &format!("WARN: {msg}, skipping\n"),
-            )
-            .await;
-        } else {
-            let mut targets_client = match connect_targets(&config.targets_addr).await {
-                Ok(client) => client,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "targets service unavailable",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            let resp = targets_client
-                .launch(LaunchRequest {
-                    target_id: Some(Id {
-                        value: target_id.clone(),
-                    }),
-                    application_id: application_id.clone(),
-                    activity: activity.clone(),
-                    job_id: None,
-                    correlation_id: correlation_id.clone(),
-                    run_id: Some(RunId {
-                        value: run_id.clone(),
-                    }),
-                })
-                .await;
-            let resp = match resp {
-                Ok(resp) => resp.into_inner(),
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "targets.launch failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            let launch_job_id = resp
-                .job_id
-                .as_ref()
-                .map(|id| id.value.clone())
-                .filter(|value| !value.is_empty())
-                .unwrap_or_default();
-            if launch_job_id.is_empty() {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "targets.launch failed",
-                    "empty job_id",
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-            job_ids.push(launch_job_id.clone());
-            let state = match wait_for_job(&mut job_client, &launch_job_id).await {
-                Ok(state) => state,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "targets.launch job failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            if !matches!(state, JobState::Success) {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "targets.launch failed",
-                    "launch job failed",
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-        }
+/// The `workflow.pipeline` job a run reports on: `existing` when the caller
+/// passed one, otherwise a newly started job.
+#[allow(clippy::result_large_err)]
+async fn pipeline_job(
+    config: &WorkflowConfig,
+    existing: Option<&Id>,
+    run_id: &str,
+    correlation_id: &str,
+    inputs: &BTreeMap<String, String>,
+    params: Vec<KeyValue>,
+) -> Result<(JobServiceClient<Channel>, String), Status> {
+    let mut job_client = connect_job(&config.job_addr).await?;
+    if let Some(id) = existing.filter(|id| !id.value.trim().is_empty()) {
+        return Ok((job_client, id.value.trim().to_string()));
     }
-
-    if wants_support {
-        step_index += 1;
-        let percent = step_index * 100 / total_steps;
-        let metrics = vec![
-            metric("pipeline_step", "observe.support_bundle"),
-            metric("step_index", step_index),
-            metric("total_steps", total_steps),
-            metric("run_id", &run_id),
-            metric("correlation_id", &correlation_id),
-        ];
-        let _ = publish_progress(
-            &mut job_client,
-            &pipeline_job_id,
-            percent,
-            "observe.support_bundle",
-            metrics,
-        )
-        .await;
-
-        let mut observe_client = match connect_observe(&config.observe_addr).await {
-            Ok(client) => client,
-            Err(err) => {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "observe service unavailable",
-                    &err.to_string(),
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-        };
-        let resp = observe_client
-            .export_support_bundle(ExportSupportBundleRequest {
-                include_logs: true,
-                include_config: true,
-                include_toolchain_provenance: true,
-                include_recent_runs: false,
-                recent_runs_limit: 10,
-                job_id: None,
-                project_id: if project_id.is_empty() {
-                    None
-                } else {
-                    Some(Id {
-                        value: project_id.clone(),
-                    })
-                },
-                target_id: if target_id.is_empty() {
-                    None
-                } else {
-                    Some(Id {
-                        value: target_id.clone(),
-                    })
-                },
-                toolchain_set_id: if toolchain_set_id.is_empty() {
-                    None
-                } else {
-                    Some(Id {
-                        value: toolchain_set_id.clone(),
-                    })
-                },
-                correlation_id: correlation_id.clone(),
-                run_id: Some(RunId {
-                    value: run_id.clone(),
-                }),
-            })
-            .await;
-        let resp = match resp {
-            Ok(resp) => resp.into_inner(),
-            Err(err) => {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "observe.support_bundle failed",
-                    &err.to_string(),
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-        };
-        if let Some(job_id) = resp
-            .job_id
-            .as_ref()
-            .map(|id| id.value.clone())
+    let input_id = |key: &str| {
+        inputs
+            .get(key)
             .filter(|value| !value.is_empty())
-        {
-            job_ids.push(job_id.clone());
-            let state = match wait_for_job(&mut job_client, &job_id).await {
-                Ok(state) => state,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "observe.support_bundle job failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            if !matches!(state, JobState::Success) {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "observe.support_bundle failed",
-                    "support bundle failed",
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-        }
+            .map(|value| Id {
+                value: value.clone(),
+            })
+    };
+    let resp = job_client
+        .start_job(StartJobRequest {
+            job_type: "workflow.pipeline".into(),
+            params,
+            project_id: input_id("project_id"),
+            target_id: input_id("target_id"),
+            toolchain_set_id: input_id("toolchain_set_id"),
+            correlation_id: correlation_id.to_string(),
+            run_id: Some(RunId {
+                value: run_id.to_string(),
+            }),
+            depends_on: Vec::new(),
+            concurrency_keys: Vec::new(),
+            timeout_ms: 0,
+            retry_policy: None,
+            priority: JobPriority::Normal as i32,
+            preemptible: false,
+        })
+        .await?
+        .into_inner();
+    let job_id = resp
+        .job
+        .and_then(|job| job.job_id)
+        .map(|id| id.value)
+        .unwrap_or_default();
+    if job_id.is_empty() {
+        return Err(Status::internal("pipeline job_id is empty"));
     }
+    Ok((job_client, job_id))
+}
 
-    if wants_evidence {
-        step_index += 1;
-        let percent = step_index * 100 / total_steps;
-        let metrics = vec![
-            metric("pipeline_step", "observe.evidence_bundle"),
-            metric("step_index", step_index),
-            metric("total_steps", total_steps),
-            metric("run_id", &run_id),
-            metric("correlation_id", &correlation_id),
-        ];
-        let _ = publish_progress(
-            &mut job_client,
-            &pipeline_job_id,
-            percent,
-            "observe.evidence_bundle",
-            metrics,
-        )
-        .await;
+/// Starts a pipeline run. `trigger` records what started it (`manual`,
+/// `cron:<trigger_id>` or `watch:<trigger_id>`) and `trigger_detail` why.
+#[allow(clippy::result_large_err)]
+async fn start_pipeline(
+    config: &WorkflowConfig,
+    runs: &runs::RunStore,
+    req: WorkflowPipelineRequest,
+    trigger: &str,
+    trigger_detail: &str,
+) -> Result<WorkflowPipelineResponse, Status> {
+    let run_id = req
+        .run_id
+        .as_ref()
+        .map(|id| id.value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| format!("run-{}", Uuid::new_v4()));
+    let correlation_id = if req.correlation_id.trim().is_empty() {
+        run_id.clone()
+    } else {
+        req.correlation_id.trim().to_string()
+    };
+    let (workflow, inputs, targets) = resolve_request(config, &req).await?;
 
-        let mut observe_client = match connect_observe(&config.observe_addr).await {
-            Ok(client) => client,
-            Err(err) => {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "observe service unavailable",
-                    &err.to_string(),
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-        };
-        let resp = observe_client
-            .export_evidence_bundle(ExportEvidenceBundleRequest {
-                run_id: Some(RunId {
-                    value: run_id.clone(),
-                }),
-                job_id: None,
-                correlation_id: correlation_id.clone(),
-            })
-            .await;
-        let resp = match resp {
-            Ok(resp) => resp.into_inner(),
-            Err(err) => {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "observe.evidence_bundle failed",
-                    &err.to_string(),
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
-        };
-        if let Some(job_id) = resp
-            .job_id
-            .as_ref()
-            .map(|id| id.value.clone())
-            .filter(|value| !value.is_empty())
-        {
-            job_ids.push(job_id.clone());
-            let state = match wait_for_job(&mut job_client, &job_id).await {
-                Ok(state) => state,
-                Err(err) => {
-                    fail_pipeline_and_record(
-                        &config,
-                        &mut job_client,
-                        &pipeline_job_id,
-                        &run_id,
-                        &correlation_id,
-                        &project_id,
-                        &target_id,
-                        &toolchain_set_id,
-                        &job_ids,
-                        started_at,
-                        "observe.evidence_bundle job failed",
-                        &err.to_string(),
-                    )
-                    .await;
-                    let _ =
-                        publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                    return;
-                }
-            };
-            if !matches!(state, JobState::Success) {
-                fail_pipeline_and_record(
-                    &config,
-                    &mut job_client,
-                    &pipeline_job_id,
-                    &run_id,
-                    &correlation_id,
-                    &project_id,
-                    &target_id,
-                    &toolchain_set_id,
-                    &job_ids,
-                    started_at,
-                    "observe.evidence_bundle failed",
-                    "evidence bundle failed",
-                )
-                .await;
-                let _ = publish_state(&mut job_client, &pipeline_job_id, JobState::Failed).await;
-                return;
-            }
+    let mut params = vec![
+        metric("run_id", &run_id),
+        metric("correlation_id", &correlation_id),
+    ];
+    if !req.workflow.trim().is_empty() {
+        params.push(metric("workflow", &workflow.name));
+    }
+    for key in ["project_id", "target_id", "toolchain_set_id"] {
+        if let Some(value) = inputs.get(key).filter(|value| !value.is_empty()) {
+            params.push(metric(key, value));
         }
     }
+    if targets.len() > 1 {
+        params.push(metric("targets", targets.join(",")));
+    }
+    params.push(metric("trigger", trigger));
 
-    summary.push(metric("pipeline", "complete"));
-    if let Err(err) = upsert_run_best_effort(
-        &config.observe_addr,
+    if !runs.begin(&run_id).await {
+        return Err(Status::failed_precondition(format!(
+            "run {run_id} is already running"
+        )));
+    }
+    let (job_client, job_id) = match pipeline_job(
+        config,
+        req.job_id.as_ref(),
         &run_id,
         &correlation_id,
-        &project_id,
-        &target_id,
-        &toolchain_set_id,
-        &job_ids,
-        "success",
-        summary,
-        Some(started_at),
-        Some(now_millis()),
+        &inputs,
+        params,
     )
     .await
     {
-        let _ = publish_log(
-            &mut job_client,
-            &pipeline_job_id,
-            &format!("WARN: failed to upsert run completion: {err}\n"),
-        )
-        .await;
-    }
+        Ok(job) => job,
+        Err(err) => {
+            runs.end(&run_id).await;
+            return Err(err);
+        }
+    };
 
-    let _ = publish_completed(
-        &mut job_client,
-        &pipeline_job_id,
-        "Workflow pipeline completed",
-        outputs,
-    )
-    .await;
+    let record = runs::RunRecord {
+        run_id: run_id.clone(),
+        correlation_id: correlation_id.clone(),
+        workflow: workflow.source.clone(),
+        inputs,
+        targets: targets.clone(),
+        trigger: trigger.to_string(),
+        trigger_detail: trigger_detail.to_string(),
+        ..Default::default()
+    };
+    let ids = engine::RunIds {
+        pipeline_job_id: job_id.clone(),
+        run_id: run_id.clone(),
+        correlation_id: correlation_id.clone(),
+    };
+    spawn_job_heartbeat(job_client, job_id.clone());
+    tokio::spawn(engine::run_workflow(
+        config.clone(),
+        runs.clone(),
+        ids,
+        workflow,
+        record,
+        Vec::new(),
+    ));
+
+    Ok(WorkflowPipelineResponse {
+        run_id: Some(RunId { value: run_id }),
+        job_id: Some(Id { value: job_id }),
+        project_id: req.project_id,
+        outputs: {
+            let mut outputs = vec![metric("correlation_id", correlation_id)];
+            if !targets.is_empty() {
+                outputs.push(metric("targets", targets.join(",")));
+            }
+            outputs
+        },
+    })
 }
 
 #[tonic::async_trait]
@@ -1993,6 +861,15 @@ impl WorkflowService for Svc {
     async fn run_pipeline(
         &self,
         request: Request<WorkflowPipelineRequest>,
+    ) -> Result<Response<WorkflowPipelineResponse>, Status> {
+        let req = request.into_inner();
+        let resp = start_pipeline(&self.config, &self.runs, req, "manual", "").await?;
+        Ok(Response::new(resp))
+    }
+
+    async fn resume_pipeline(
+        &self,
+        request: Request<ResumePipelineRequest>,
     ) -> Result<Response<WorkflowPipelineResponse>, Status> {
         let req = request.into_inner();
         let run_id = req
@@ -2000,93 +877,173 @@ impl WorkflowService for Svc {
             .as_ref()
             .map(|id| id.value.trim().to_string())
             .filter(|value| !value.is_empty())
-            .unwrap_or_else(|| format!("run-{}", Uuid::new_v4()));
-        let correlation_id = if req.correlation_id.trim().is_empty() {
-            run_id.clone()
-        } else {
-            req.correlation_id.trim().to_string()
-        };
-
-        let mut job_client = connect_job(&self.config.job_addr).await?;
-        let job_id = req
-            .job_id
-            .as_ref()
-            .map(|id| id.value.trim().to_string())
-            .filter(|value| !value.is_empty())
+            .ok_or_else(|| Status::invalid_argument("run_id is required"))?;
+        let mut record = self
+            .runs
+            .get(&run_id)
+            .await
+            .ok_or_else(|| Status::not_found(format!("run {run_id} not found")))?;
+        if record.result == "success" {
+            return Err(Status::failed_precondition(format!(
+                "run {run_id} already succeeded"
+            )));
+        }
+        let workflow = record.workflow.clone().compile().map_err(|errors| {
+            Status::failed_precondition(format!(
+                "run {run_id}: stored workflow is invalid: {}",
+                errors.join("; ")
+            ))
+        })?;
+        for kv in &req.inputs {
+            let key = kv.key.trim();
+            let Some(value) = record.inputs.get_mut(key) else {
+                return Err(Status::invalid_argument(format!(
+                    "run {run_id} has no input {key}"
+                )));
+            };
+            *value = kv.value.trim().to_string();
+        }
+        let reused = record.reusable_steps().await;
+        let resume_from = workflow
+            .steps
+            .iter()
+            .find(|step| !reused.contains(&step.id))
+            .map(|step| step.id.clone())
             .unwrap_or_default();
-        let job_id = if job_id.is_empty() {
-            let mut params = vec![
-                metric("run_id", &run_id),
-                metric("correlation_id", &correlation_id),
-            ];
-            if let Some(project_id) = req.project_id.as_ref().map(|id| id.value.clone()) {
-                params.push(metric("project_id", project_id));
-            }
-            if let Some(target_id) = req.target_id.as_ref().map(|id| id.value.clone()) {
-                params.push(metric("target_id", target_id));
-            }
-            if let Some(toolchain_set_id) = req.toolchain_set_id.as_ref().map(|id| id.value.clone())
-            {
-                params.push(metric("toolchain_set_id", toolchain_set_id));
-            }
-            let resp = job_client
-                .start_job(StartJobRequest {
-                    job_type: "workflow.pipeline".into(),
-                    params,
-                    project_id: req.project_id.clone(),
-                    target_id: req.target_id.clone(),
-                    toolchain_set_id: req.toolchain_set_id.clone(),
-                    correlation_id: correlation_id.clone(),
-                    run_id: Some(RunId {
-                        value: run_id.clone(),
-                    }),
-                })
-                .await?
-                .into_inner();
-            resp.job
-                .and_then(|job| job.job_id)
-                .map(|id| id.value)
-                .unwrap_or_default()
-        } else {
-            job_id
-        };
 
-        if job_id.is_empty() {
-            return Err(Status::internal("pipeline job_id is empty"));
-        }
+        let correlation_id = record.correlation_id.clone();
+        let params = vec![
+            metric("run_id", &run_id),
+            metric("correlation_id", &correlation_id),
+            metric("workflow", &workflow.name),
+            metric("resumed", true),
+            metric("resume_from", &resume_from),
+        ];
 
-        let config = self.config.clone();
-        let job_id_for_task = job_id.clone();
-        let run_id_for_task = run_id.clone();
-        let correlation_id_for_task = correlation_id.clone();
-        let project_id_for_response = req.project_id.clone();
-        tokio::spawn(async move {
-            run_pipeline_task(
-                config,
-                job_id_for_task,
-                run_id_for_task,
-                correlation_id_for_task,
-                req,
-            )
-            .await;
-        });
+        if !self.runs.begin(&run_id).await {
+            return Err(Status::failed_precondition(format!(
+                "run {run_id} is still running"
+            )));
+        }
+        let (job_client, job_id) = match pipeline_job(
+            &self.config,
+            req.job_id.as_ref(),
+            &run_id,
+            &correlation_id,
+            &record.inputs,
+            params,
+        )
+        .await
+        {
+            Ok(job) => job,
+            Err(err) => {
+                self.runs.end(&run_id).await;
+                return Err(err);
+            }
+        };
+        let project_id = record
+            .inputs
+            .get("project_id")
+            .filter(|value| !value.is_empty())
+            .map(|value| Id {
+                value: value.clone(),
+            });
+        let ids = engine::RunIds {
+            pipeline_job_id: job_id.clone(),
+            run_id: run_id.clone(),
+            correlation_id: correlation_id.clone(),
+        };
+        record.result = "running".into();
+        spawn_job_heartbeat(job_client, job_id.clone());
+        tokio::spawn(engine::run_workflow(
+            self.config.clone(),
+            self.runs.clone(),
+            ids,
+            workflow,
+            record,
+            reused.clone(),
+        ));
 
         Ok(Response::new(WorkflowPipelineResponse {
             run_id: Some(RunId { value: run_id }),
             job_id: Some(Id { value: job_id }),
-            project_id: project_id_for_response,
-            outputs: vec![metric("correlation_id", correlation_id)],
+            project_id,
+            outputs: vec![
+                metric("correlation_id", correlation_id),
+                metric("resume_from", resume_from),
+                metric("reused_steps", reused.join(",")),
+            ],
+        }))
+    }
+
+    async fn plan_pipeline(
+        &self,
+        request: Request<WorkflowPipelineRequest>,
+    ) -> Result<Response<PlanPipelineResponse>, Status> {
+        let req = request.into_inner();
+        Ok(Response::new(plan::plan_pipeline(&self.config, &req).await))
+    }
+
+    async fn list_workflows(
+        &self,
+        request: Request<ListWorkflowsRequest>,
+    ) -> Result<Response<ListWorkflowsResponse>, Status> {
+        let req = request.into_inner();
+        let root = project_root(&self.config, &req.project_path, req.project_id.as_ref()).await?;
+        Ok(Response::new(ListWorkflowsResponse {
+            workflows: definition::list_workflows(&root),
         }))
     }
 
+    async fn create_trigger(
+        &self,
+        request: Request<CreateTriggerRequest>,
+    ) -> Result<Response<CreateTriggerResponse>, Status> {
+        let trigger = request
+            .into_inner()
+            .trigger
+            .ok_or_else(|| Status::invalid_argument("trigger is required"))?;
+        let trigger = self.triggers.create(trigger).await?;
+        info!(
+            "saved workflow trigger {} ({})",
+            trigger.trigger_id, trigger.name
+        );
+        Ok(Response::new(CreateTriggerResponse {
+            trigger: Some(trigger),
+        }))
+    }
+
+    async fn list_triggers(
+        &self,
+        _request: Request<ListTriggersRequest>,
+    ) -> Result<Response<ListTriggersResponse>, Status> {
+        Ok(Response::new(ListTriggersResponse {
+            triggers: self.triggers.list().await,
+        }))
+    }
+
+    async fn delete_trigger(
+        &self,
+        request: Request<DeleteTriggerRequest>,
+    ) -> Result<Response<DeleteTriggerResponse>, Status> {
+        let trigger_id = request.into_inner().trigger_id;
+        let trigger_id = trigger_id.trim();
+        if trigger_id.is_empty() {
+            return Err(Status::invalid_argument("trigger_id is required"));
+        }
+        let deleted = self.triggers.delete(trigger_id).await?;
+        Ok(Response::new(DeleteTriggerResponse { deleted }))
+    }
+
     async fn reload_state(
         &self,
         _request: Request<ReloadStateRequest>,
     ) -> Result<Response<ReloadStateResponse>, Status> {
+        let count = self.runs.reload().await + self.triggers.reload().await;
         Ok(Response::new(ReloadStateResponse {
             ok: true,
-            item_count: 0,
-            detail: "workflow has no persistent state".into(),
+            item_count: count as u32,
+            detail: "workflow runs and triggers reloaded".into(),
         }))
     }
 }
@@ -2102,8 +1059,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             build_addr: build_addr(),
             targets_addr: targets_addr(),
             observe_addr: observe_addr(),
+            workflow_addr: workflow_addr(),
         },
+        runs: runs::RunStore::load(),
+        triggers: triggers::TriggerRegistry::load(),
     };
+    triggers::spawn_scheduler(svc.config.clone(), svc.runs.clone(), svc.triggers.clone());
 
     serve_grpc_with_telemetry(
         "apkw-workflow",
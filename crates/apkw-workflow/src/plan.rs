@@ -0,0 +1,608 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use apkw_proto::apkw::v1::{
+    ArtifactFilter, GetActiveToolchainSetRequest, GetProjectRequest, Id, KeyValue,
+    ListArtifactsRequest, ListRecentProjectsRequest, PlanPipelineResponse, PlannedStep, Project,
+    ResolvedValue, WorkflowPipelineRequest,
+};
+use apkw_util::secrets::{parse_secret_ref, SecretStore};
+use tonic::Code;
+
+use crate::{
+    actions::{action_spec, InputValue},
+    condition::{CondRef, Condition, RunState},
+    connect_build, connect_project, connect_toolchain,
+    definition::{request_inputs, Workflow},
+    resolve_request, resolve_variant, select_artifact,
+    targets::target_kinds,
+    template::{Ref, Scope},
+    WorkflowConfig,
+};
+
+/// What a plan learns about the run before any step executes. Lookups are
+/// read-only; a service that cannot be reached only costs a warning.
+#[derive(Default)]
+struct Context {
+    resolved: Vec<ResolvedValue>,
+    warnings: Vec<String>,
+    errors: Vec<String>,
+    project: Option<Project>,
+}
+
+impl Context {
+    fn resolve(&mut self, key: &str, value: &str, source: &str) {
+        if !value.is_empty() {
+            self.resolved.push(ResolvedValue {
+                key: key.to_string(),
+                value: value.to_string(),
+                source: source.to_string(),
+            });
+        }
+    }
+}
+
+fn same_path(a: &str, b: &str) -> bool {
+    a.trim().trim_end_matches('/') == b.trim().trim_end_matches('/')
+}
+
+/// Describes what RunPipeline would do with `req` without starting jobs or
+/// touching project, build or target state.
+pub(crate) async fn plan_pipeline(
+    config: &WorkflowConfig,
+    req: &WorkflowPipelineRequest,
+) -> PlanPipelineResponse {
+    let (workflow, inputs, targets) = match resolve_request(config, req).await {
+        Ok(resolved) => resolved,
+        Err(status) => {
+            return PlanPipelineResponse {
+                workflow: req.workflow.trim().to_string(),
+                errors: vec![status.message().to_string()],
+                ..Default::default()
+            }
+        }
+    };
+    let requested = request_inputs(req);
+    let input = |key: &str| inputs.get(key).cloned().unwrap_or_default();
+    let source = |key: &str| {
+        if requested.get(key) == inputs.get(key) {
+            "request"
+        } else {
+            "workflow input"
+        }
+    };
+
+    let mut ctx = Context::default();
+    ctx.project = lookup_project(
+        config,
+        &input("project_id"),
+        &input("project_path"),
+        &mut ctx,
+    )
+    .await;
+    let project = ctx.project.clone().unwrap_or_default();
+    let project_id = project
+        .project_id
+        .as_ref()
+        .map(|id| id.value.clone())
+        .unwrap_or_default();
+    if !input("project_id").is_empty() {
+        ctx.resolve("project_id", &input("project_id"), source("project_id"));
+    } else if !project_id.is_empty() {
+        ctx.resolve("project_id", &project_id, "recent projects");
+    }
+    if !input("project_path").is_empty() {
+        ctx.resolve(
+            "project_path",
+            &input("project_path"),
+            source("project_path"),
+        );
+    } else {
+        ctx.resolve("project_path", &project.path, "project");
+    }
+
+    let set_id = |id: &Option<Id>| id.as_ref().map(|id| id.value.clone()).unwrap_or_default();
+    if !input("toolchain_set_id").is_empty() {
+        ctx.resolve(
+            "toolchain_set_id",
+            &input("toolchain_set_id"),
+            source("toolchain_set_id"),
+        );
+    } else if !set_id(&project.toolchain_set_id).is_empty() {
+        ctx.resolve(
+            "toolchain_set_id",
+            &set_id(&project.toolchain_set_id),
+            "project",
+        );
+    } else {
+        match connect_toolchain(&config.toolchain_addr).await {
+            Ok(mut client) => match client
+                .get_active_toolchain_set(GetActiveToolchainSetRequest {})
+                .await
+            {
+                Ok(resp) => {
+                    let active = resp
+                        .into_inner()
+                        .set
+                        .map(|set| set_id(&set.toolchain_set_id))
+                        .unwrap_or_default();
+                    ctx.resolve("toolchain_set_id", &active, "active toolchain set");
+                }
+                Err(err) => ctx.warnings.push(format!(
+                    "active toolchain set lookup failed: {}",
+                    err.message()
+                )),
+            },
+            Err(err) => ctx
+                .warnings
+                .push(format!("toolchain service unavailable: {}", err.message())),
+        }
+    }
+
+    if targets.is_empty() {
+        ctx.resolve(
+            "default_target_id",
+            &set_id(&project.default_target_id),
+            "project",
+        );
+    }
+    for key in ["variant", "variant_name", "module"] {
+        ctx.resolve(key, &input(key), source(key));
+    }
+
+    let builds = workflow.steps.iter().any(|step| step.action == "build.run");
+    let apk_path = input("apk_path");
+    if !apk_path.is_empty() {
+        let apk_source = match req.artifact_run_id.as_ref() {
+            Some(id) if !id.value.trim().is_empty() => format!("run {}", id.value.trim()),
+            _ => source("apk_path").to_string(),
+        };
+        ctx.resolve("apk_path", &apk_path, &apk_source);
+        ctx.resolve("apk_sha256", &input("apk_sha256"), &apk_source);
+        if !Path::new(&apk_path).exists() {
+            ctx.warnings
+                .push(format!("apk_path {apk_path} does not exist yet"));
+        }
+    } else if builds {
+        let project_ref = if project_id.is_empty() {
+            input("project_id")
+        } else {
+            project_id.clone()
+        };
+        if !project_ref.is_empty() {
+            last_artifact(config, &project_ref, req, &inputs, &mut ctx).await;
+        }
+    }
+
+    let mut run = BTreeMap::new();
+    for (key, value) in [
+        (
+            "run_id",
+            req.run_id.as_ref().map(|id| id.value.trim().to_string()),
+        ),
+        (
+            "correlation_id",
+            Some(req.correlation_id.trim().to_string()),
+        ),
+    ] {
+        let value = value
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| format!("<run.{key}>"));
+        run.insert(key.to_string(), value);
+    }
+    let mut kinds = BTreeMap::new();
+    if workflow.uses_target_kind() && !targets.is_empty() {
+        match target_kinds(config, &targets).await {
+            Ok(found) => kinds = found,
+            Err(err) => ctx.warnings.push(format!(
+                "target kind lookup failed, target.kind is empty: {}",
+                err.message()
+            )),
+        }
+    }
+    let steps = plan_steps(&workflow, &inputs, &targets, kinds, run, &mut ctx);
+    check_secrets(&steps, &mut ctx);
+
+    PlanPipelineResponse {
+        workflow: workflow.name.clone(),
+        steps,
+        resolved: ctx.resolved,
+        inputs: inputs
+            .iter()
+            .map(|(key, value)| KeyValue {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect(),
+        targets: targets.into_iter().map(|value| Id { value }).collect(),
+        errors: ctx.errors,
+        warnings: ctx.warnings,
+    }
+}
+
+/// The project a request refers to: `project_id` looked up directly, or a
+/// recent project at `project_path`.
+async fn lookup_project(
+    config: &WorkflowConfig,
+    project_id: &str,
+    project_path: &str,
+    ctx: &mut Context,
+) -> Option<Project> {
+    if project_id.is_empty() && project_path.is_empty() {
+        return None;
+    }
+    let mut client = match connect_project(&config.project_addr).await {
+        Ok(client) => client,
+        Err(err) => {
+            ctx.warnings
+                .push(format!("project service unavailable: {}", err.message()));
+            return None;
+        }
+    };
+    if !project_id.is_empty() {
+        return match client
+            .get_project(GetProjectRequest {
+                project_id: Some(Id {
+                    value: project_id.to_string(),
+                }),
+            })
+            .await
+        {
+            Ok(resp) => resp.into_inner().project,
+            Err(err) if err.code() == Code::NotFound => {
+                ctx.errors.push(format!("project {project_id} not found"));
+                None
+            }
+            Err(err) => {
+                ctx.warnings
+                    .push(format!("project lookup failed: {}", err.message()));
+                None
+            }
+        };
+    }
+    match client
+        .list_recent_projects(ListRecentProjectsRequest { page: None })
+        .await
+    {
+        Ok(resp) => resp
+            .into_inner()
+            .projects
+            .into_iter()
+            .find(|project| same_path(&project.path, project_path)),
+        Err(err) => {
+            ctx.warnings
+                .push(format!("recent projects lookup failed: {}", err.message()));
+            None
+        }
+    }
+}
+
+/// Reports the artifact the project's last build left behind; the planned
+/// build step replaces it.
+async fn last_artifact(
+    config: &WorkflowConfig,
+    project_ref: &str,
+    req: &WorkflowPipelineRequest,
+    inputs: &BTreeMap<String, String>,
+    ctx: &mut Context,
+) {
+    let module = inputs.get("module").cloned().unwrap_or_default();
+    let mut client = match connect_build(&config.build_addr).await {
+        Ok(client) => client,
+        Err(err) => {
+            ctx.warnings
+                .push(format!("build service unavailable: {}", err.message()));
+            return;
+        }
+    };
+    let artifacts = client
+        .list_artifacts(ListArtifactsRequest {
+            project_id: Some(Id {
+                value: project_ref.to_string(),
+            }),
+            variant: resolve_variant(req.build_variant) as i32,
+            filter: Some(ArtifactFilter {
+                modules: if module.is_empty() {
+                    vec![]
+                } else {
+                    vec![module]
+                },
+                variant: inputs.get("variant_name").cloned().unwrap_or_default(),
+                types: vec![],
+                name_contains: "".into(),
+                path_contains: "".into(),
+            }),
+        })
+        .await
+        .map(|resp| resp.into_inner().artifacts)
+        .unwrap_or_default();
+    if let Some(artifact) = select_artifact(&artifacts) {
+        ctx.resolve("artifact_path", &artifact.path, "last build");
+    }
+}
+
+/// Secrets the planned steps reference must parse and be in the store. Only
+/// names are read; values stay encrypted.
+fn check_secrets(steps: &[PlannedStep], ctx: &mut Context) {
+    let mut names = BTreeSet::new();
+    for step in steps.iter().filter(|step| !step.skipped) {
+        let specs = step
+            .inputs
+            .iter()
+            .filter(|kv| kv.key == "secrets")
+            .flat_map(|kv| kv.value.split(|ch: char| ch == ',' || ch.is_whitespace()))
+            .filter(|spec| !spec.is_empty() && !spec.starts_with('<'));
+        for spec in specs {
+            match parse_secret_ref(spec) {
+                Ok(secret) => {
+                    names.insert(secret.name);
+                }
+                Err(err) => ctx.errors.push(format!("step {}: {err}", step.id)),
+            }
+        }
+    }
+    if names.is_empty() {
+        return;
+    }
+    match SecretStore::load() {
+        Ok(store) => {
+            for name in names.into_iter().filter(|name| !store.contains(name)) {
+                ctx.errors.push(format!("secret {name} is not stored"));
+            }
+        }
+        Err(err) => ctx
+            .warnings
+            .push(format!("secrets store unreadable: {err}")),
+    }
+}
+
+/// Renders every step the way the run would, wave by wave, then the
+/// `on_failure` steps. Outputs of earlier steps are placeholders, except
+/// those a plan can know up front (opening a project that is already
+/// known). Conditions are decided assuming every earlier step succeeds,
+/// unless they read step outputs or belong to `on_failure` steps.
+fn plan_steps(
+    workflow: &Workflow,
+    inputs: &BTreeMap<String, String>,
+    targets: &[String],
+    target_kinds: BTreeMap<String, String>,
+    run: BTreeMap<String, String>,
+    ctx: &mut Context,
+) -> Vec<PlannedStep> {
+    let project = ctx.project.clone();
+    let project = project.as_ref();
+    let mut referenced: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for step in workflow.steps.iter().chain(&workflow.on_failure) {
+        for r in step.refs() {
+            if let Ref::StepOutput { step, key } = r {
+                referenced
+                    .entry(step.as_str())
+                    .or_default()
+                    .insert(key.as_str());
+            }
+        }
+    }
+    let mut scope = Scope {
+        inputs: inputs.clone(),
+        steps: BTreeMap::new(),
+        run,
+        target: None,
+    };
+    let mut conditions = RunState {
+        target_kinds,
+        ..Default::default()
+    };
+
+    let mut planned = Vec::new();
+    let main_waves = workflow.waves();
+    let first_handler_wave = main_waves.len();
+    let waves = main_waves.into_iter().map(|wave| (wave, false)).chain(
+        workflow
+            .on_failure_waves()
+            .into_iter()
+            .map(|wave| (wave, true)),
+    );
+    for (wave_index, (wave, on_failure)) in waves.enumerate() {
+        if on_failure && wave_index == first_handler_wave {
+            scope
+                .run
+                .insert("failed_step".into(), "<run.failed_step>".into());
+            scope.run.insert("error".into(), "<run.error>".into());
+        }
+        let mut wave_outputs = Vec::new();
+        for step in wave {
+            let step_targets: Vec<Option<String>> = if step.for_each_target && !targets.is_empty() {
+                targets.iter().cloned().map(Some).collect()
+            } else {
+                vec![None]
+            };
+            let condition = step.condition.as_ref().map(Condition::source);
+            let undecided = on_failure
+                || step
+                    .condition
+                    .iter()
+                    .flat_map(Condition::refs)
+                    .any(|r| matches!(r, CondRef::Value(Ref::StepOutput { .. })));
+            let mut ran = false;
+            for target in step_targets {
+                scope.target = target.clone();
+                let unmet = step.unmet_need(target.as_deref(), &conditions);
+                let (skipped_by_condition, mut skipped_reason) = match condition {
+                    Some(condition) if undecided && unmet.is_none() => (
+                        false,
+                        format!("condition `{condition}` is decided at run time"),
+                    ),
+                    _ => match step.skip_reason(&scope, &conditions) {
+                        Some(reason) => (true, reason),
+                        None => (false, String::new()),
+                    },
+                };
+                let step_inputs = step.resolve(&scope);
+                let missing = if skipped_by_condition {
+                    Vec::new()
+                } else {
+                    action_spec(&step.action)
+                        .map(|spec| step_inputs.missing(spec))
+                        .unwrap_or_default()
+                };
+                if !missing.is_empty() && !step.optional {
+                    let problem = format!(
+                        "step {}: {} requires {}",
+                        step.id,
+                        step.action,
+                        missing.join(" and ")
+                    );
+                    // A failing on_failure step does not change the result.
+                    if on_failure {
+                        ctx.warnings.push(problem);
+                    } else {
+                        ctx.errors.push(problem);
+                    }
+                }
+                let skipped = skipped_by_condition || (!missing.is_empty() && step.optional);
+                if skipped && skipped_reason.is_empty() {
+                    skipped_reason = format!("missing {}", missing.join(" and "));
+                }
+                ran |= !skipped;
+                let status = if skipped { "skipped" } else { "succeeded" };
+                if let Some(target) = target.as_ref() {
+                    conditions
+                        .statuses
+                        .insert(RunState::status_key(&step.id, target), status.into());
+                }
+                planned.push(PlannedStep {
+                    id: step.id.clone(),
+                    action: step.action.clone(),
+                    needs: step.needs.clone(),
+                    wave: wave_index as u32,
+                    target_id: target.map(|value| Id { value }),
+                    inputs: step_inputs
+                        .values
+                        .iter()
+                        .map(|(key, value)| KeyValue {
+                            key: key.clone(),
+                            value: match value {
+                                InputValue::One(value) => value.clone(),
+                                InputValue::List(values) => values.join(","),
+                            },
+                        })
+                        .collect(),
+                    missing_inputs: missing.iter().map(|key| key.to_string()).collect(),
+                    optional: step.optional,
+                    skipped,
+                    condition: condition.unwrap_or_default().to_string(),
+                    skipped_reason,
+                    on_failure,
+                    continue_on_failure: step.continue_on_failure,
+                });
+            }
+            scope.target = None;
+            let status = if ran { "succeeded" } else { "skipped" };
+            conditions.statuses.insert(step.id.clone(), status.into());
+
+            // Skipped steps produce no outputs, so later fallbacks apply.
+            let mut outputs = BTreeMap::new();
+            if ran {
+                for key in referenced.get(step.id.as_str()).into_iter().flatten() {
+                    let known = match (step.action.as_str(), project) {
+                        ("project.open", Some(project)) => match *key {
+                            "project_id" => project.project_id.as_ref().map(|id| id.value.clone()),
+                            "project_path" => Some(project.path.clone()),
+                            "toolchain_set_id" => {
+                                project.toolchain_set_id.as_ref().map(|id| id.value.clone())
+                            }
+                            "default_target_id" => project
+                                .default_target_id
+                                .as_ref()
+                                .map(|id| id.value.clone()),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    let value = known
+                        .filter(|value| !value.is_empty())
+                        .unwrap_or_else(|| format!("<{}.{key}>", step.id));
+                    outputs.insert(key.to_string(), value);
+                }
+            }
+            wave_outputs.push((step.id.clone(), outputs));
+        }
+        scope.steps.extend(wave_outputs);
+    }
+    planned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::pipeline_workflow;
+    use apkw_proto::apkw::v1::WorkflowPipelineOptions;
+
+    #[test]
+    fn plans_fan_out_and_placeholder_outputs() {
+        let req = WorkflowPipelineRequest {
+            project_path: "/work/app".into(),
+            options: Some(WorkflowPipelineOptions {
+                open_project: true,
+                build: true,
+                install_apk: true,
+                launch_app: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let workflow = pipeline_workflow(&req).unwrap();
+        let targets = vec!["emulator-5554".to_string(), "pixel-7".to_string()];
+        let project = Project {
+            project_id: Some(Id {
+                value: "proj-1".into(),
+            }),
+            path: "/work/app".into(),
+            ..Default::default()
+        };
+        let mut ctx = Context {
+            project: Some(project),
+            ..Default::default()
+        };
+        let steps = plan_steps(
+            &workflow,
+            &request_inputs(&req),
+            &targets,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            &mut ctx,
+        );
+        let errors = ctx.errors;
+
+        let input = |step: &PlannedStep, key: &str| {
+            step.inputs
+                .iter()
+                .find(|kv| kv.key == key)
+                .map(|kv| kv.value.clone())
+                .unwrap_or_default()
+        };
+        let ids: Vec<_> = steps.iter().map(|step| step.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "open_project",
+                "build",
+                "install",
+                "install",
+                "launch",
+                "launch"
+            ]
+        );
+        assert_eq!(input(&steps[1], "project"), "proj-1");
+        let install = &steps[3];
+        assert_eq!(install.wave, 2);
+        assert_eq!(install.target_id.as_ref().unwrap().value, "pixel-7");
+        assert_eq!(input(install, "apk_path"), "<build.artifact_path>");
+        assert_eq!(steps[4].missing_inputs, ["application_id"]);
+        assert!(!steps[4].skipped);
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors[0].contains("launch: targets.launch requires application_id"));
+    }
+}
@@ -0,0 +1,286 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use apkw_util::{now_millis, state_file_path, write_json_atomic};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::{artifacts::StepArtifact, definition::WorkflowDef};
+
+const WORKFLOW_RUNS_FILE_NAME: &str = "workflow-runs.json";
+/// Older runs are dropped once more than this many are stored.
+const MAX_STORED_RUNS: usize = 100;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StepStatus {
+    #[default]
+    Pending,
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+impl StepStatus {
+    /// The name step conditions compare against (`steps.ID.status`).
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::Succeeded => "succeeded",
+            StepStatus::Failed => "failed",
+            StepStatus::Skipped => "skipped",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct StepRecord {
+    pub(crate) status: StepStatus,
+    pub(crate) outputs: BTreeMap<String, String>,
+    /// Targets a `for_each: targets` step already succeeded on.
+    pub(crate) done_targets: Vec<String>,
+}
+
+impl StepRecord {
+    /// A succeeded step is reused on resume unless the artifact it produced
+    /// is gone or no longer matches its recorded sha256.
+    pub(crate) async fn reusable(&self) -> bool {
+        if self.status != StepStatus::Succeeded {
+            return false;
+        }
+        match StepArtifact::from_outputs(&self.outputs) {
+            Some(artifact) => artifact.verify().await.is_ok(),
+            None => true,
+        }
+    }
+}
+
+/// Everything needed to resume a pipeline run: the workflow it ran, its
+/// inputs and targets, and the status and outputs of each step.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct RunRecord {
+    pub(crate) run_id: String,
+    pub(crate) correlation_id: String,
+    pub(crate) workflow: WorkflowDef,
+    pub(crate) inputs: BTreeMap<String, String>,
+    pub(crate) targets: Vec<String>,
+    /// What started the run: `manual`, `cron:<trigger_id>` or
+    /// `watch:<trigger_id>`, with `trigger_detail` saying why.
+    pub(crate) trigger: String,
+    pub(crate) trigger_detail: String,
+    /// `running`, `success` or `failed`, matching the observe run record.
+    pub(crate) result: String,
+    pub(crate) steps: BTreeMap<String, StepRecord>,
+    /// The `on_failure` steps of the last failed attempt. Kept apart from
+    /// `steps` so a resume never reuses them.
+    pub(crate) handlers: BTreeMap<String, StepRecord>,
+    pub(crate) job_ids: Vec<String>,
+    pub(crate) target_results: BTreeMap<String, String>,
+    pub(crate) updated_at_unix_millis: i64,
+}
+
+impl RunRecord {
+    /// Ids of the steps a resume reuses instead of running again. This
+    /// hashes recorded artifacts, so a resume asks once.
+    pub(crate) async fn reusable_steps(&self) -> Vec<String> {
+        let mut reusable = Vec::new();
+        for (id, step) in &self.steps {
+            if step.reusable().await {
+                reusable.push(id.clone());
+            }
+        }
+        reusable
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct WorkflowRunsFile {
+    runs: Vec<RunRecord>,
+}
+
+/// Pipeline runs persisted in `workflow-runs.json` so failed runs can be
+/// resumed, plus the runs executing in this process.
+#[derive(Clone)]
+pub(crate) struct RunStore {
+    runs: Arc<Mutex<BTreeMap<String, RunRecord>>>,
+    active: Arc<Mutex<HashSet<String>>>,
+    /// Numbers each snapshot taken by `save`.
+    snapshots: Arc<AtomicU64>,
+    /// The newest snapshot on disk; held while one is written so writes
+    /// land in order.
+    written: Arc<Mutex<u64>>,
+    path: PathBuf,
+}
+
+impl RunStore {
+    pub(crate) fn load() -> Self {
+        Self::at(state_file_path(WORKFLOW_RUNS_FILE_NAME))
+    }
+
+    fn read(path: &Path) -> BTreeMap<String, RunRecord> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return BTreeMap::new(),
+            Err(err) => {
+                warn!("failed to read {}: {err}", path.display());
+                return BTreeMap::new();
+            }
+        };
+        match serde_json::from_slice::<WorkflowRunsFile>(&data) {
+            Ok(file) => file
+                .runs
+                .into_iter()
+                .map(|run| (run.run_id.clone(), run))
+                .collect(),
+            Err(err) => {
+                warn!("ignoring unreadable {}: {err}", path.display());
+                BTreeMap::new()
+            }
+        }
+    }
+
+    /// A store persisted at `path`.
+    pub(crate) fn at(path: PathBuf) -> Self {
+        Self {
+            runs: Arc::new(Mutex::new(Self::read(&path))),
+            active: Arc::new(Mutex::new(HashSet::new())),
+            snapshots: Arc::new(AtomicU64::new(0)),
+            written: Arc::new(Mutex::new(0)),
+            path,
+        }
+    }
+
+    /// Re-reads runs from disk (used by `ReloadState`).
+    pub(crate) async fn reload(&self) -> usize {
+        let runs = Self::read(&self.path);
+        let count = runs.len();
+        *self.runs.lock().await = runs;
+        count
+    }
+
+    pub(crate) async fn get(&self, run_id: &str) -> Option<RunRecord> {
+        self.runs.lock().await.get(run_id).cloned()
+    }
+
+    /// Marks a run as executing here; false when it already is.
+    pub(crate) async fn begin(&self, run_id: &str) -> bool {
+        self.active.lock().await.insert(run_id.to_string())
+    }
+
+    pub(crate) async fn is_active(&self, run_id: &str) -> bool {
+        self.active.lock().await.contains(run_id)
+    }
+
+    pub(crate) async fn end(&self, run_id: &str) {
+        self.active.lock().await.remove(run_id);
+    }
+
+    /// Stores a run snapshot. The file is written off the async runtime
+    /// and without holding the runs lock; a snapshot older than the one
+    /// already written is dropped. Persistence failures are logged: a run
+    /// that cannot be saved still runs, it just cannot be resumed.
+    pub(crate) async fn save(&self, run: &RunRecord) {
+        let (snapshot, file) = {
+            let mut runs = self.runs.lock().await;
+            let mut run = run.clone();
+            run.updated_at_unix_millis = now_millis();
+            runs.insert(run.run_id.clone(), run);
+            while runs.len() > MAX_STORED_RUNS {
+                let Some(oldest) = runs
+                    .values()
+                    .min_by_key(|run| run.updated_at_unix_millis)
+                    .map(|run| run.run_id.clone())
+                else {
+                    break;
+                };
+                runs.remove(&oldest);
+            }
+            let file = WorkflowRunsFile {
+                runs: runs.values().cloned().collect(),
+            };
+            (self.snapshots.fetch_add(1, Ordering::Relaxed) + 1, file)
+        };
+        let mut written = self.written.lock().await;
+        if *written >= snapshot {
+            return;
+        }
+        let path = self.path.clone();
+        match tokio::task::spawn_blocking(move || write_json_atomic(&path, &file)).await {
+            Ok(Ok(())) => *written = snapshot,
+            Ok(Err(err)) => warn!("failed to persist workflow runs: {err}"),
+            Err(err) => warn!("failed to persist workflow runs: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn records_round_trip_and_reuse_succeeded_steps() {
+        let mut run = RunRecord {
+            run_id: "run-1".into(),
+            result: "failed".into(),
+            ..Default::default()
+        };
+        run.steps.insert(
+            "build".into(),
+            StepRecord {
+                status: StepStatus::Succeeded,
+                outputs: BTreeMap::from([("job_id".into(), "job-1".into())]),
+                ..Default::default()
+            },
+        );
+        run.steps.insert(
+            "stale".into(),
+            StepRecord {
+                status: StepStatus::Succeeded,
+                outputs: BTreeMap::from([(
+                    "artifact_path".into(),
+                    "/nonexistent/app-debug.apk".into(),
+                )]),
+                ..Default::default()
+            },
+        );
+        run.steps.insert(
+            "install".into(),
+            StepRecord {
+                status: StepStatus::Failed,
+                done_targets: vec!["emulator-5554".into()],
+                ..Default::default()
+            },
+        );
+
+        let json = serde_json::to_string(&WorkflowRunsFile { runs: vec![run] }).unwrap();
+        assert!(json.contains(r#""status":"succeeded""#));
+        let file: WorkflowRunsFile = serde_json::from_str(&json).unwrap();
+        let run = &file.runs[0];
+        assert_eq!(run.reusable_steps().await, vec!["build"]);
+        assert_eq!(run.steps["install"].done_targets, vec!["emulator-5554"]);
+
+        let path =
+            std::env::temp_dir().join(format!("apkw-workflow-runs-{}.json", uuid::Uuid::new_v4()));
+        let store = RunStore::at(path.clone());
+        let finished = RunRecord {
+            result: "success".into(),
+            ..run.clone()
+        };
+        tokio::join!(store.save(run), store.save(&finished));
+        let saved = store.get("run-1").await.unwrap();
+        assert_eq!(store.reload().await, 1);
+        assert_eq!(store.get("run-1").await.unwrap().result, saved.result);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use apkw_proto::apkw::v1::{ListTargetsRequest, Target, TargetKind, WorkflowPipelineRequest};
+use tonic::Status;
+
+use crate::{connect_targets, WorkflowConfig};
+
+/// A target selector: whitespace/comma separated `key=value` terms that must
+/// all match (`kind`, `provider`, `state`, `api_level`), or `*` for every
+/// online target.
+#[derive(Debug, Default)]
+struct TargetSelector {
+    terms: Vec<(String, String)>,
+}
+
+const SELECTOR_KEYS: &[&str] = &["kind", "provider", "state", "api_level"];
+
+impl TargetSelector {
+    fn parse(raw: &str) -> Result<Self, String> {
+        let mut terms = Vec::new();
+        for token in raw
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+        {
+            if token == "*" {
+                continue;
+            }
+            let Some((key, value)) = token.split_once('=') else {
+                return Err(format!(
+                    "invalid selector term {token:?} (expected key=value)"
+                ));
+            };
+            let key = key.trim().to_ascii_lowercase();
+            if !SELECTOR_KEYS.contains(&key.as_str()) {
+                return Err(format!(
+                    "unknown selector key {key} (expected one of {})",
+                    SELECTOR_KEYS.join(", ")
+                ));
+            }
+            terms.push((key, value.trim().to_ascii_lowercase()));
+        }
+        Ok(Self { terms })
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        self.terms.iter().all(|(key, value)| {
+            let actual = match key.as_str() {
+                "kind" => kind_label(target.kind).to_string(),
+                "provider" => target.provider.to_ascii_lowercase(),
+                "state" => target.state.to_ascii_lowercase(),
+                "api_level" => target.api_level.to_ascii_lowercase(),
+                _ => return false,
+            };
+            actual == *value
+        })
+    }
+}
+
+fn kind_label(kind: i32) -> &'static str {
+    match TargetKind::try_from(kind).unwrap_or(TargetKind::Unspecified) {
+        TargetKind::Device => "device",
+        TargetKind::Emulatorlike => "emulatorlike",
+        TargetKind::Remote => "remote",
+        TargetKind::Unspecified => "unspecified",
+    }
+}
+
+/// The targets a pipeline request fans out to: `target_id`, then
+/// `target_ids`, then the online targets `target_selector` matches, without
+/// duplicates.
+#[allow(clippy::result_large_err)]
+pub(crate) async fn resolve_targets(
+    config: &WorkflowConfig,
+    req: &WorkflowPipelineRequest,
+) -> Result<Vec<String>, Status> {
+    let mut targets: Vec<String> = Vec::new();
+    let mut push = |id: &str| {
+        let id = id.trim();
+        if !id.is_empty() && !targets.iter().any(|known| known == id) {
+            targets.push(id.to_string());
+        }
+    };
+    if let Some(id) = req.target_id.as_ref() {
+        push(&id.value);
+    }
+    for id in &req.target_ids {
+        push(&id.value);
+    }
+
+    let raw = req.target_selector.trim();
+    if !raw.is_empty() {
+        let selector = TargetSelector::parse(raw)
+            .map_err(|err| Status::invalid_argument(format!("target_selector: {err}")))?;
+        let mut client = connect_targets(&config.targets_addr).await?;
+        let listed = client
+            .list_targets(ListTargetsRequest {
+                include_offline: false,
+            })
+            .await?
+            .into_inner()
+            .targets;
+        let mut matched = false;
+        for target in listed.iter().filter(|target| selector.matches(target)) {
+            matched = true;
+            if let Some(id) = target.target_id.as_ref() {
+                push(&id.value);
+            }
+        }
+        if !matched {
+            return Err(Status::failed_precondition(format!(
+                "target selector {raw:?} matched no online targets"
+            )));
+        }
+    }
+    Ok(targets)
+}
+
+/// The kind (`device`, `emulatorlike`, ...) of each of `targets` that
+/// TargetService knows, for `target.kind` in step conditions.
+#[allow(clippy::result_large_err)]
+pub(crate) async fn target_kinds(
+    config: &WorkflowConfig,
+    targets: &[String],
+) -> Result<BTreeMap<String, String>, Status> {
+    let mut client = connect_targets(&config.targets_addr).await?;
+    let listed = client
+        .list_targets(ListTargetsRequest {
+            include_offline: true,
+        })
+        .await?
+        .into_inner()
+        .targets;
+    Ok(listed
+        .iter()
+        .filter_map(|target| {
+            let id = target.target_id.as_ref()?.value.clone();
+            targets
+                .contains(&id)
+                .then(|| (id, kind_label(target.kind).to_string()))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selectors_match_all_terms() {
+        let phone = Target {
+            kind: TargetKind::Device as i32,
+            provider: "adb".into(),
+            state: "device".into(),
+            api_level: "34".into(),
+            ..Default::default()
+        };
+        let cuttlefish = Target {
+            kind: TargetKind::Emulatorlike as i32,
+            provider: "cuttlefish".into(),
+            state: "device".into(),
+            ..Default::default()
+        };
+
+        let all = TargetSelector::parse("*").unwrap();
+        assert!(all.matches(&phone) && all.matches(&cuttlefish));
+
+        let adb_devices = TargetSelector::parse("provider=adb, kind=Device").unwrap();
+        assert!(adb_devices.matches(&phone));
+        assert!(!adb_devices.matches(&cuttlefish));
+
+        let online = TargetSelector::parse("state=device api_level=34").unwrap();
+        assert!(online.matches(&phone));
+        assert!(!online.matches(&cuttlefish));
+
+        assert!(TargetSelector::parse("colour=blue").is_err());
+        assert!(TargetSelector::parse("provider").is_err());
+    }
+}
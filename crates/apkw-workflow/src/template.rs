@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+/// A value a step input can reference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Ref {
+    /// `inputs.NAME`: a workflow input or request field.
+    Input(String),
+    /// `steps.ID.outputs.KEY`: an output of an earlier step.
+    StepOutput { step: String, key: String },
+    /// `run.run_id` or `run.correlation_id`; `on_failure` steps also have
+    /// `run.failed_step` and `run.error`.
+    Run(String),
+    /// `target.id`: the target a `for_each: targets` step instance runs on.
+    Target,
+}
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Text(String),
+    /// Alternatives separated by `||`; the first non-empty one is used.
+    Expr(Vec<Ref>),
+}
+
+/// A step input with `${...}` references, e.g.
+/// `${steps.build.outputs.artifact_path || inputs.apk_path}`.
+#[derive(Clone, Debug)]
+pub(crate) struct Template {
+    segments: Vec<Segment>,
+}
+
+/// Values available to templates while a workflow runs.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Scope {
+    pub(crate) inputs: BTreeMap<String, String>,
+    pub(crate) steps: BTreeMap<String, BTreeMap<String, String>>,
+    pub(crate) run: BTreeMap<String, String>,
+    pub(crate) target: Option<String>,
+}
+
+const RUN_KEYS: &[&str] = &["run_id", "correlation_id", "failed_step", "error"];
+/// Run values set only while `on_failure` steps run.
+pub(crate) const FAILURE_RUN_KEYS: &[&str] = &["failed_step", "error"];
+
+fn is_name(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+}
+
+pub(crate) fn parse_ref(raw: &str) -> Result<Ref, String> {
+    let raw = raw.trim();
+    if let Some(name) = raw.strip_prefix("inputs.") {
+        if is_name(name) {
+            return Ok(Ref::Input(name.to_string()));
+        }
+    } else if let Some(rest) = raw.strip_prefix("steps.") {
+        if let Some((step, key)) = rest.split_once(".outputs.") {
+            if is_name(step) && !key.is_empty() {
+                return Ok(Ref::StepOutput {
+                    step: step.to_string(),
+                    key: key.to_string(),
+                });
+            }
+        }
+    } else if let Some(key) = raw.strip_prefix("run.") {
+        if RUN_KEYS.contains(&key) {
+            return Ok(Ref::Run(key.to_string()));
+        }
+    } else if raw == "target.id" {
+        return Ok(Ref::Target);
+    }
+    Err(format!(
+        "invalid reference `{raw}` (expected inputs.NAME, steps.ID.outputs.KEY, run.KEY or target.id)"
+    ))
+}
+
+impl Template {
+    pub(crate) fn parse(raw: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut rest = raw;
+        while let Some(start) = rest.find("${") {
+            if start > 0 {
+                segments.push(Segment::Text(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unterminated `${{` in `{raw}`"))?;
+            let refs = after[..end]
+                .split("||")
+                .map(parse_ref)
+                .collect::<Result<Vec<_>, _>>()?;
+            segments.push(Segment::Expr(refs));
+            rest = &after[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+        Ok(Self { segments })
+    }
+
+    pub(crate) fn literal(value: &str) -> Self {
+        Self {
+            segments: vec![Segment::Text(value.to_string())],
+        }
+    }
+
+    pub(crate) fn refs(&self) -> impl Iterator<Item = &Ref> {
+        self.segments.iter().flat_map(|segment| match segment {
+            Segment::Text(_) => [].iter(),
+            Segment::Expr(refs) => refs.iter(),
+        })
+    }
+
+    pub(crate) fn render(&self, scope: &Scope) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Expr(refs) => {
+                    if let Some(value) = refs
+                        .iter()
+                        .filter_map(|r| scope.lookup(r))
+                        .find(|value| !value.trim().is_empty())
+                    {
+                        out.push_str(value);
+                    }
+                }
+            }
+        }
+        out.trim().to_string()
+    }
+}
+
+impl Scope {
+    pub(crate) fn lookup(&self, r: &Ref) -> Option<&String> {
+        match r {
+            Ref::Input(name) => self.inputs.get(name),
+            Ref::StepOutput { step, key } => self.steps.get(step).and_then(|out| out.get(key)),
+            Ref::Run(key) => self.run.get(key),
+            Ref::Target => self.target.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_resolve_first_non_empty_alternative() {
+        let template =
+            Template::parse("${steps.build.outputs.artifact_path || inputs.apk_path}").unwrap();
+        assert_eq!(
+            template.refs().cloned().collect::<Vec<_>>(),
+            vec![
+                Ref::StepOutput {
+                    step: "build".into(),
+                    key: "artifact_path".into()
+                },
+                Ref::Input("apk_path".into()),
+            ]
+        );
+
+        let mut scope = Scope::default();
+        scope
+            .inputs
+            .insert("apk_path".into(), "/tmp/app.apk".into());
+        assert_eq!(template.render(&scope), "/tmp/app.apk");
+        scope.steps.insert(
+            "build".into(),
+            BTreeMap::from([("artifact_path".into(), "/out/app-debug.apk".into())]),
+        );
+        assert_eq!(template.render(&scope), "/out/app-debug.apk");
+
+        scope.run.insert("run_id".into(), "run-1".into());
+        let mixed = Template::parse("bundle-${run.run_id}.zip").unwrap();
+        assert_eq!(mixed.render(&scope), "bundle-run-1.zip");
+
+        assert!(Template::parse("${inputs.apk_path").is_err());
+        assert!(Template::parse("${steps.build.artifact_path}").is_err());
+        assert!(Template::parse("${run.job_id}").is_err());
+    }
+}
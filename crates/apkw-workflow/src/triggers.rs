@@ -0,0 +1,732 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use apkw_proto::apkw::v1::{RunId, WorkflowPipelineRequest, WorkflowTrigger};
+use apkw_util::{now_millis, state_file_path, write_json_atomic};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tonic::Status;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{runs::RunStore, start_pipeline, WorkflowConfig};
+
+const WORKFLOW_TRIGGERS_FILE_NAME: &str = "workflow-triggers.json";
+const TICK: Duration = Duration::from_secs(1);
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_DEBOUNCE_MS: u32 = 2_000;
+/// Always ignored by watches, next to the trigger's own patterns; the
+/// project templates' `exclude_dirs`.
+const DEFAULT_IGNORE: &[&str] = &[
+    ".gradle", "build", ".idea", ".git", "target", "out", ".apkw",
+];
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct WorkflowTriggersFile {
+    triggers: Vec<WorkflowTriggerRecord>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct WorkflowTriggerRecord {
+    trigger_id: String,
+    name: String,
+    pipeline: Option<WorkflowPipelineRequest>,
+    cron: String,
+    timezone: String,
+    watch_path: String,
+    ignore: Vec<String>,
+    debounce_ms: u32,
+    disabled: bool,
+    last_fired_unix_millis: i64,
+    last_run_id: String,
+    last_error: String,
+}
+
+impl WorkflowTriggerRecord {
+    fn from_proto(trigger: &WorkflowTrigger) -> Self {
+        Self {
+            trigger_id: trigger.trigger_id.clone(),
+            name: trigger.name.clone(),
+            pipeline: trigger.pipeline.clone(),
+            cron: trigger.cron.clone(),
+            timezone: trigger.timezone.clone(),
+            watch_path: trigger.watch_path.clone(),
+            ignore: trigger.ignore.clone(),
+            debounce_ms: trigger.debounce_ms,
+            disabled: trigger.disabled,
+            last_fired_unix_millis: trigger.last_fired_unix_millis,
+            last_run_id: trigger
+                .last_run_id
+                .as_ref()
+                .map(|id| id.value.clone())
+                .unwrap_or_default(),
+            last_error: trigger.last_error.clone(),
+        }
+    }
+
+    fn into_proto(self) -> WorkflowTrigger {
+        WorkflowTrigger {
+            trigger_id: self.trigger_id,
+            name: self.name,
+            pipeline: self.pipeline,
+            cron: self.cron,
+            timezone: self.timezone,
+            watch_path: self.watch_path,
+            ignore: self.ignore,
+            debounce_ms: self.debounce_ms,
+            disabled: self.disabled,
+            last_fired_unix_millis: self.last_fired_unix_millis,
+            last_run_id: (!self.last_run_id.is_empty()).then_some(RunId {
+                value: self.last_run_id,
+            }),
+            last_error: self.last_error,
+        }
+    }
+}
+
+/// A parsed cron expression; each field is a bit set of allowed values.
+#[derive(Debug)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Day-of-month and day-of-week restrict together only when one is `*`;
+    /// when both are given a day matching either fires, as in crontab.
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn parse_cron_field(field: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step in {name} field {field:?}"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let value = |raw: &str| {
+            raw.parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("{name} value {raw:?} is outside {min}-{max}"))
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (value(start)?, value(end)?)
+        } else if step > 1 {
+            (value(range)?, max)
+        } else {
+            let single = value(range)?;
+            (single, single)
+        };
+        if start > end {
+            return Err(format!("invalid range {range:?} in {name} field"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+impl Cron {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "cron {expr:?} must have 5 fields (minute hour day-of-month month day-of-week)"
+            ));
+        };
+        let mut weekdays = parse_cron_field(weekday, 0, 7, "day-of-week")?;
+        // Both 0 and 7 are Sunday.
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_cron_field(minute, 0, 59, "minute")?,
+            hours: parse_cron_field(hour, 0, 23, "hour")?,
+            days: parse_cron_field(day, 1, 31, "day-of-month")?,
+            months: parse_cron_field(month, 1, 12, "month")?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// Whether the cron fires in `local_minute`, counted from 1970-01-01
+    /// 00:00 in the trigger's timezone.
+    fn matches(&self, local_minute: i64) -> bool {
+        let minute = local_minute.rem_euclid(60);
+        let hour = local_minute.div_euclid(60).rem_euclid(24);
+        let days = local_minute.div_euclid(24 * 60);
+        let (month, day) = month_day(days);
+        let weekday = (days + 4).rem_euclid(7);
+        let day_matches = self.days & (1 << day) != 0;
+        let weekday_matches = self.weekdays & (1 << weekday) != 0;
+        let date_matches = if self.any_day || self.any_weekday {
+            day_matches && weekday_matches
+        } else {
+            day_matches || weekday_matches
+        };
+        self.minutes & (1 << minute) != 0
+            && self.hours & (1 << hour) != 0
+            && self.months & (1 << month) != 0
+            && date_matches
+    }
+}
+
+/// Minutes east of UTC of a trigger `timezone`: `UTC` (or empty) or a fixed
+/// `+HH:MM`/`-HH:MM` offset.
+fn utc_offset_minutes(timezone: &str) -> Result<i64, String> {
+    let timezone = timezone.trim();
+    if timezone.is_empty() || timezone.eq_ignore_ascii_case("utc") {
+        return Ok(0);
+    }
+    let invalid = || format!("timezone {timezone:?} must be UTC or an offset such as +02:00");
+    let (sign, offset) = match timezone.split_at_checked(1) {
+        Some(("+", offset)) => (1, offset),
+        Some(("-", offset)) => (-1, offset),
+        _ => return Err(invalid()),
+    };
+    let (hours, minutes) = offset.split_once(':').ok_or_else(invalid)?;
+    let part = |raw: &str, max: i64| {
+        raw.parse::<i64>()
+            .ok()
+            .filter(|value| raw.len() == 2 && (0..=max).contains(value))
+            .ok_or_else(invalid)
+    };
+    Ok(sign * (part(hours, 14)? * 60 + part(minutes, 59)?))
+}
+
+/// `UTC`, or the offset as `+HH:MM`/`-HH:MM`.
+fn format_utc_offset(minutes: i64) -> String {
+    if minutes == 0 {
+        return "UTC".to_string();
+    }
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = minutes.abs();
+    format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Month (1-12) and day (1-31) of a day counted from 1970-01-01.
+fn month_day(days: i64) -> (i64, i64) {
+    let z = days + 719_468;
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (month, day)
+}
+
+/// Matches `name` against a pattern with `*` (any run) and `?` (one char).
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+type Snapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+/// Modification time and size of every file under `root` not ignored.
+fn snapshot(root: &Path, ignore: &[String]) -> Snapshot {
+    let mut files = Snapshot::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if ignore.iter().any(|pattern| glob_match(pattern, &name)) {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                dirs.push(entry.path());
+            } else {
+                files.insert(entry.path(), (meta.modified().ok(), meta.len()));
+            }
+        }
+    }
+    files
+}
+
+/// Files added, removed or modified between two snapshots.
+fn changed_files(before: &Snapshot, after: &Snapshot) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = after
+        .iter()
+        .filter(|(path, stat)| before.get(*path) != Some(*stat))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(
+        before
+            .keys()
+            .filter(|path| !after.contains_key(*path))
+            .cloned(),
+    );
+    changed
+}
+
+/// Validates a trigger and fills defaults.
+#[allow(clippy::result_large_err)]
+fn normalize_trigger(mut trigger: WorkflowTrigger) -> Result<WorkflowTrigger, Status> {
+    trigger.trigger_id = trigger.trigger_id.trim().to_string();
+    if trigger.trigger_id.is_empty() {
+        trigger.trigger_id = Uuid::new_v4().to_string();
+    }
+    trigger.name = trigger.name.trim().to_string();
+    if trigger.name.is_empty() {
+        trigger.name = trigger.trigger_id.clone();
+    }
+    let Some(pipeline) = trigger.pipeline.as_mut() else {
+        return Err(Status::invalid_argument("a pipeline is required"));
+    };
+    pipeline.run_id = None;
+    pipeline.job_id = None;
+
+    trigger.cron = trigger.cron.trim().to_string();
+    trigger.watch_path = trigger.watch_path.trim().to_string();
+    match (trigger.cron.is_empty(), trigger.watch_path.is_empty()) {
+        (true, true) => return Err(Status::invalid_argument("a cron or watch_path is required")),
+        (false, false) => {
+            return Err(Status::invalid_argument(
+                "cron and watch_path are mutually exclusive",
+            ))
+        }
+        _ => {}
+    }
+    if trigger.cron.is_empty() {
+        trigger.timezone.clear();
+    } else {
+        Cron::parse(&trigger.cron).map_err(Status::invalid_argument)?;
+        let offset = utc_offset_minutes(&trigger.timezone).map_err(Status::invalid_argument)?;
+        trigger.timezone = format_utc_offset(offset);
+    }
+    if !trigger.watch_path.is_empty() && !Path::new(&trigger.watch_path).is_dir() {
+        return Err(Status::invalid_argument(format!(
+            "watch_path {} is not a directory",
+            trigger.watch_path
+        )));
+    }
+    let mut ignore: Vec<String> = if trigger.watch_path.is_empty() {
+        Vec::new()
+    } else {
+        DEFAULT_IGNORE.iter().map(|dir| dir.to_string()).collect()
+    };
+    for pattern in trigger.ignore.iter().map(|pattern| pattern.trim()) {
+        if !pattern.is_empty() && !ignore.iter().any(|known| known == pattern) {
+            ignore.push(pattern.to_string());
+        }
+    }
+    trigger.ignore = ignore;
+    if trigger.debounce_ms == 0 {
+        trigger.debounce_ms = DEFAULT_DEBOUNCE_MS;
+    }
+    Ok(trigger)
+}
+
+/// Workflow triggers persisted in `workflow-triggers.json`.
+#[derive(Clone)]
+pub(crate) struct TriggerRegistry {
+    triggers: Arc<RwLock<BTreeMap<String, WorkflowTrigger>>>,
+    path: PathBuf,
+}
+
+impl TriggerRegistry {
+    pub(crate) fn load() -> Self {
+        let path = state_file_path(WORKFLOW_TRIGGERS_FILE_NAME);
+        Self {
+            triggers: Arc::new(RwLock::new(Self::read(&path))),
+            path,
+        }
+    }
+
+    fn read(path: &Path) -> BTreeMap<String, WorkflowTrigger> {
+        let file = match fs::read(path) {
+            Ok(data) => match serde_json::from_slice::<WorkflowTriggersFile>(&data) {
+                Ok(file) => file,
+                Err(err) => {
+                    warn!("ignoring unreadable {}: {err}", path.display());
+                    return BTreeMap::new();
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return BTreeMap::new(),
+            Err(err) => {
+                warn!("failed to read {}: {err}", path.display());
+                return BTreeMap::new();
+            }
+        };
+        let mut triggers = BTreeMap::new();
+        for record in file.triggers {
+            match normalize_trigger(record.into_proto()) {
+                Ok(trigger) => {
+                    triggers.insert(trigger.trigger_id.clone(), trigger);
+                }
+                Err(err) => warn!("skipping invalid workflow trigger: {}", err.message()),
+            }
+        }
+        triggers
+    }
+
+    /// Re-reads triggers from disk (used by `ReloadState`).
+    pub(crate) async fn reload(&self) -> usize {
+        let triggers = Self::read(&self.path);
+        let count = triggers.len();
+        *self.triggers.write().await = triggers;
+        count
+    }
+
+    pub(crate) async fn list(&self) -> Vec<WorkflowTrigger> {
+        self.triggers.read().await.values().cloned().collect()
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn persist(&self, triggers: &BTreeMap<String, WorkflowTrigger>) -> Result<(), Status> {
+        let file = WorkflowTriggersFile {
+            triggers: triggers
+                .values()
+                .map(WorkflowTriggerRecord::from_proto)
+                .collect(),
+        };
+        write_json_atomic(&self.path, &file)
+            .map_err(|err| Status::internal(format!("failed to persist workflow triggers: {err}")))
+    }
+
+    /// Adds a trigger, or replaces the trigger with the same id (keeping
+    /// what it last fired).
+    #[allow(clippy::result_large_err)]
+    pub(crate) async fn create(&self, trigger: WorkflowTrigger) -> Result<WorkflowTrigger, Status> {
+        let mut trigger = normalize_trigger(trigger)?;
+        let mut triggers = self.triggers.write().await;
+        if let Some(existing) = triggers.get(&trigger.trigger_id) {
+            trigger.last_fired_unix_millis = existing.last_fired_unix_millis;
+            trigger.last_run_id = existing.last_run_id.clone();
+            trigger.last_error = existing.last_error.clone();
+        } else {
+            trigger.last_fired_unix_millis = 0;
+            trigger.last_run_id = None;
+            trigger.last_error.clear();
+        }
+        let mut next = triggers.clone();
+        next.insert(trigger.trigger_id.clone(), trigger.clone());
+        self.persist(&next)?;
+        *triggers = next;
+        Ok(trigger)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) async fn delete(&self, trigger_id: &str) -> Result<bool, Status> {
+        let mut triggers = self.triggers.write().await;
+        if !triggers.contains_key(trigger_id) {
+            return Ok(false);
+        }
+        let mut next = triggers.clone();
+        next.remove(trigger_id);
+        self.persist(&next)?;
+        *triggers = next;
+        Ok(true)
+    }
+
+    /// Records a firing; `run_id` is empty when no run was started.
+    async fn record_fired(&self, trigger_id: &str, run_id: &str, error: &str) {
+        let mut triggers = self.triggers.write().await;
+        let Some(trigger) = triggers.get_mut(trigger_id) else {
+            return;
+        };
+        trigger.last_fired_unix_millis = now_millis();
+        // A firing that started nothing keeps pointing at the last run.
+        if !run_id.is_empty() {
+            trigger.last_run_id = Some(RunId {
+                value: run_id.to_string(),
+            });
+        }
+        trigger.last_error = error.to_string();
+        if let Err(err) = self.persist(&triggers) {
+            warn!("{}", err.message());
+        }
+    }
+}
+
+/// File-watch state of one trigger.
+struct Watch {
+    /// The watch settings the snapshot was taken with; a replaced trigger
+    /// starts over.
+    key: (String, Vec<String>),
+    snapshot: Option<Snapshot>,
+    last_scan: Option<Instant>,
+    /// Changes not yet handed to a run, and when the last one was seen.
+    pending: Vec<PathBuf>,
+    last_change: Option<Instant>,
+}
+
+/// Runs the scheduler: cron triggers fire once in each minute they match in
+/// their timezone, watch triggers once their files have been quiet for the
+/// debounce period.
+/// A trigger does not start a run while its previous run is still going;
+/// a cron firing is skipped, file changes wait for the run to finish.
+pub(crate) fn spawn_scheduler(config: WorkflowConfig, runs: RunStore, registry: TriggerRegistry) {
+    tokio::spawn(async move {
+        let mut watches: HashMap<String, Watch> = HashMap::new();
+        let mut last_minute = now_millis().div_euclid(60_000);
+        let mut interval = tokio::time::interval(TICK);
+        loop {
+            interval.tick().await;
+            let triggers: Vec<WorkflowTrigger> = registry
+                .list()
+                .await
+                .into_iter()
+                .filter(|trigger| !trigger.disabled)
+                .collect();
+            watches.retain(|id, _| {
+                triggers
+                    .iter()
+                    .any(|trigger| &trigger.trigger_id == id && !trigger.watch_path.is_empty())
+            });
+
+            let minute = now_millis().div_euclid(60_000);
+            if minute != last_minute {
+                for trigger in triggers.iter().filter(|trigger| !trigger.cron.is_empty()) {
+                    let Ok(cron) = Cron::parse(&trigger.cron) else {
+                        continue;
+                    };
+                    let offset = utc_offset_minutes(&trigger.timezone).unwrap_or_default();
+                    if (last_minute + 1..=minute).any(|m| cron.matches(m + offset)) {
+                        if last_run_active(&runs, trigger).await {
+                            registry
+                                .record_fired(
+                                    &trigger.trigger_id,
+                                    "",
+                                    "skipped: previous run still running",
+                                )
+                                .await;
+                            continue;
+                        }
+                        fire(&config, &runs, &registry, trigger, "cron", &trigger.cron).await;
+                    }
+                }
+                last_minute = minute;
+            }
+
+            for trigger in triggers
+                .iter()
+                .filter(|trigger| !trigger.watch_path.is_empty())
+            {
+                let key = (trigger.watch_path.clone(), trigger.ignore.clone());
+                let watch = watches
+                    .entry(trigger.trigger_id.clone())
+                    .or_insert_with(|| Watch {
+                        key: key.clone(),
+                        snapshot: None,
+                        last_scan: None,
+                        pending: Vec::new(),
+                        last_change: None,
+                    });
+                if watch.key != key {
+                    *watch = Watch {
+                        key: key.clone(),
+                        snapshot: None,
+                        last_scan: None,
+                        pending: Vec::new(),
+                        last_change: None,
+                    };
+                }
+                if watch
+                    .last_scan
+                    .is_none_or(|at| at.elapsed() >= WATCH_POLL_INTERVAL)
+                {
+                    watch.last_scan = Some(Instant::now());
+                    let (root, ignore) = key;
+                    let scanned =
+                        tokio::task::spawn_blocking(move || snapshot(Path::new(&root), &ignore))
+                            .await
+                            .unwrap_or_default();
+                    if let Some(before) = watch.snapshot.as_ref() {
+                        let changed = changed_files(before, &scanned);
+                        if !changed.is_empty() {
+                            watch.last_change = Some(Instant::now());
+                            for path in changed {
+                                if !watch.pending.contains(&path) {
+                                    watch.pending.push(path);
+                                }
+                            }
+                        }
+                    }
+                    watch.snapshot = Some(scanned);
+                }
+
+                let debounce = Duration::from_millis(u64::from(trigger.debounce_ms));
+                let quiet = watch.last_change.is_some_and(|at| at.elapsed() >= debounce);
+                if !quiet || watch.pending.is_empty() || last_run_active(&runs, trigger).await {
+                    continue;
+                }
+                let root = Path::new(&trigger.watch_path);
+                let first = watch.pending[0]
+                    .strip_prefix(root)
+                    .unwrap_or(&watch.pending[0])
+                    .display()
+                    .to_string();
+                let detail = match watch.pending.len() {
+                    1 => format!("changed {first}"),
+                    n => format!("changed {first} and {} more", n - 1),
+                };
+                watch.pending.clear();
+                watch.last_change = None;
+                fire(&config, &runs, &registry, trigger, "watch", &detail).await;
+            }
+        }
+    });
+}
+
+async fn last_run_active(runs: &RunStore, trigger: &WorkflowTrigger) -> bool {
+    match trigger.last_run_id.as_ref() {
+        Some(run_id) => runs.is_active(&run_id.value).await,
+        None => false,
+    }
+}
+
+async fn fire(
+    config: &WorkflowConfig,
+    runs: &RunStore,
+    registry: &TriggerRegistry,
+    trigger: &WorkflowTrigger,
+    kind: &str,
+    detail: &str,
+) {
+    let req = trigger.pipeline.clone().unwrap_or_default();
+    let source = format!("{kind}:{}", trigger.trigger_id);
+    match start_pipeline(config, runs, req, &source, detail).await {
+        Ok(resp) => {
+            let run_id = resp.run_id.map(|id| id.value).unwrap_or_default();
+            registry
+                .record_fired(&trigger.trigger_id, &run_id, "")
+                .await;
+        }
+        Err(err) => {
+            warn!(
+                "workflow trigger {} failed to start a run: {}",
+                trigger.trigger_id,
+                err.message()
+            );
+            registry
+                .record_fired(&trigger.trigger_id, "", err.message())
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cron_and_ignore_patterns_match() {
+        // 2024-03-05 02:30 UTC, a Tuesday.
+        let minute = 1_709_605_800 / 60;
+        assert!(Cron::parse("30 2 * * *").unwrap().matches(minute));
+        assert!(Cron::parse("*/15 0-6 5 3 2").unwrap().matches(minute));
+        assert!(Cron::parse("30 2 * * 1-5").unwrap().matches(minute));
+        assert!(!Cron::parse("30 2 * * 0,6").unwrap().matches(minute));
+        // Day-of-month or day-of-week when both are restricted.
+        assert!(Cron::parse("30 2 1 * 2").unwrap().matches(minute));
+        assert!(!Cron::parse("@daily").unwrap().matches(minute));
+        assert!(Cron::parse("0 0 * * 7")
+            .unwrap()
+            .matches(1_709_424_000 / 60));
+        assert!(Cron::parse("61 * * * *").is_err());
+        assert!(Cron::parse("* * *").is_err());
+
+        let ignore: Vec<String> = vec!["build".into(), "*.iml".into(), ".?radle".into()];
+        let ignored = |name: &str| ignore.iter().any(|pattern| glob_match(pattern, name));
+        assert!(ignored("build"));
+        assert!(ignored("app.iml"));
+        assert!(ignored(".gradle"));
+        assert!(!ignored("MainActivity.kt"));
+        assert!(!ignored("buildSrc"));
+    }
+
+    #[test]
+    fn triggers_keep_timezones_default_ignores_and_pipeline_fields() {
+        assert_eq!(utc_offset_minutes("").unwrap(), 0);
+        assert_eq!(utc_offset_minutes("+02:00").unwrap(), 120);
+        assert_eq!(utc_offset_minutes("-05:30").unwrap(), -330);
+        assert!(utc_offset_minutes("Europe/Berlin").is_err());
+        assert!(utc_offset_minutes("+2:00").is_err());
+        // 02:30 UTC is 04:30 at +02:00.
+        let minute = 1_709_605_800 / 60;
+        assert!(Cron::parse("30 4 * * *").unwrap().matches(minute + 120));
+
+        let cron = normalize_trigger(WorkflowTrigger {
+            pipeline: Some(WorkflowPipelineRequest {
+                workflow: "nightly".into(),
+                ..Default::default()
+            }),
+            cron: "@daily".into(),
+            timezone: "-00:00".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(cron.timezone, "UTC");
+        let watch = normalize_trigger(WorkflowTrigger {
+            pipeline: Some(WorkflowPipelineRequest::default()),
+            watch_path: std::env::temp_dir().display().to_string(),
+            ignore: vec!["*.iml".into(), " build ".into()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(watch
+            .ignore
+            .starts_with(&[".gradle".to_string(), "build".to_string()]));
+        assert_eq!(watch.ignore.last().unwrap(), "*.iml");
+        assert_eq!(watch.ignore.iter().filter(|p| *p == "build").count(), 1);
+
+        let json = serde_json::to_value(WorkflowTriggerRecord::from_proto(&cron)).unwrap();
+        assert_eq!(json["pipeline"]["workflow"], "nightly");
+        let record: WorkflowTriggerRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(record.into_proto(), cron);
+    }
+}